@@ -0,0 +1,48 @@
+//! Read-only initramfs inspector.
+//!
+//! Lists an initramfs cpio with modes, sizes and resolved symlink targets, or
+//! dumps one file from it, for chasing down preflight "broken symlink" and
+//! "missing binary" failures without unpacking the archive by hand.
+//!
+//! Usage:
+//!   cargo run --bin cpio-inspect -- .artifacts/out/acorn/initramfs-live.cpio.gz
+//!   cargo run --bin cpio-inspect -- initramfs.img --extract /init
+//!   cargo run --bin cpio-inspect -- initramfs.img --extract /usr/lib/libc.so --output /tmp/libc.so
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use install_tests::inspect;
+
+#[derive(Parser)]
+#[command(name = "cpio-inspect")]
+#[command(about = "List or extract files from an initramfs cpio (read-only)")]
+struct Cli {
+    /// Initramfs cpio archive (plain or gzip newc).
+    archive: PathBuf,
+
+    /// Path inside the archive to dump instead of listing (symlinks are followed).
+    #[arg(long, value_name = "PATH")]
+    extract: Option<String>,
+
+    /// Write the extracted file here instead of stdout.
+    #[arg(long, short, value_name = "FILE", requires = "extract")]
+    output: Option<PathBuf>,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: install_tests::color::ColorChoice,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
+    if !cli.archive.is_file() {
+        bail!("archive not found: {}", cli.archive.display());
+    }
+    match cli.extract {
+        Some(inner) => inspect::extract_cpio_file(&cli.archive, &inner, cli.output.as_deref()),
+        None => inspect::print_cpio_listing(&cli.archive),
+    }
+}
@@ -1,29 +1,52 @@
 //! Installation test utility binary.
 //!
 //! Serial wrapper harness execution is intentionally removed.
-//! This binary now only provides step listing metadata.
+//! This binary now only provides step listing metadata, as text or as a
+//! JSON catalog (`list --json`) for docs generation, and compares saved run
+//! reports (`compare --baseline`) as a no-regressions gate.
+//!
+//! `run --attach <SOCKET>` is the one way to execute steps here: it runs them
+//! on the serial console of a VM started by hand with
+//! `-serial unix:<SOCKET>,server=on,wait=off`, without spawning QEMU or
+//! waiting for boot. Handy for iterating on Phase 6 checks against a
+//! long-lived VM. `--report FILE` saves its results for a later `compare`,
+//! and `--baseline FILE` runs that comparison straight away.
 
 use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use install_tests::qemu::{Console, ConsoleAttach, ContextConsole};
+use install_tests::steps::report::{
+    print_slowest_commands, write_checks_json, write_timing_json, RunReport,
+};
 use install_tests::{
-    all_steps_with_experimental, context_for_distro, DistroContext, AVAILABLE_DISTROS,
+    all_steps, all_steps_with_experimental, context_for_distro, is_experimental, parse_step_range,
+    phase_name, run_phase_parallel, run_steps_until_failure, steps_for_phase,
+    steps_for_phase_experimental, steps_in_range, CheckResult, DistroContext, SshExecutor, Step,
+    StepResult, AVAILABLE_DISTROS,
 };
 
 #[derive(Parser)]
 #[command(name = "install-tests")]
-#[command(about = "Installation test utility (list only; serial run removed)")]
+#[command(about = "Installation test utility (list, compare, and run --attach)")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto", global = true)]
+    color: install_tests::color::ColorChoice,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Run installation tests (disabled; legacy serial wrapper removed)
+    /// Run installation tests (only with --attach; legacy serial wrapper removed)
     Run {
-        /// Run only a specific step (1-24)
+        /// Run only a specific step (1-30)
         #[arg(long)]
         step: Option<usize>,
 
@@ -31,9 +54,98 @@ enum Commands {
         #[arg(long)]
         phase: Option<usize>,
 
+        /// Run only steps START through END, inclusive (e.g. 16..18)
+        #[arg(long = "step-range", value_name = "START..END")]
+        step_range: Option<String>,
+
         /// Distro to test (levitate, acorn, iuppiter, ralph)
         #[arg(long, default_value = "levitate")]
         distro: String,
+
+        /// Print the QEMU command lines and resolved paths instead of running
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+
+        /// Include experimental steps (Phase 6 post-reboot verification)
+        #[arg(long)]
+        experimental: bool,
+
+        /// Stop the pre-reboot phases (1-5) at the first failing step (default)
+        #[arg(long = "fail-fast", overrides_with = "no_fail_fast")]
+        fail_fast: bool,
+
+        /// Keep running independent pre-reboot steps after a failure; steps
+        /// depending on a failed one are still skipped. Phase 6 always runs
+        /// every step.
+        #[arg(long = "no-fail-fast", overrides_with = "fail_fast")]
+        no_fail_fast: bool,
+
+        /// Run the selected steps on an already-running VM's serial socket
+        /// (`-serial unix:SOCKET,server=on,wait=off`), skipping spawn, boot
+        /// wait and login. The guest must be at a root shell.
+        #[arg(long, value_name = "SOCKET")]
+        attach: Option<PathBuf>,
+
+        /// With --attach, run parallel-safe steps concurrently, each over its
+        /// own SSH connection to the guest's sshd forwarded to host PORT
+        /// (recshuttle default key). Every step runs; fail-fast does not apply
+        #[arg(long = "parallel-ssh", value_name = "PORT", requires = "attach")]
+        parallel_ssh: Option<u16>,
+
+        /// With --attach, save the run report (the `compare` input) to FILE
+        #[arg(long, value_name = "FILE", requires = "attach")]
+        report: Option<PathBuf>,
+
+        /// With --attach, diff the run against this earlier report and exit
+        /// non-zero on any regression, even if every step passed
+        #[arg(long, value_name = "FILE", requires = "attach")]
+        baseline: Option<PathBuf>,
+
+        /// With --attach, write every command's step, exit code and duration
+        /// to FILE as JSON
+        #[arg(long = "timing-json", value_name = "FILE", requires = "attach")]
+        timing_json: Option<PathBuf>,
+
+        /// With --attach, write every check with its evidence and structured
+        /// data to FILE as JSON
+        #[arg(long = "checks-json", value_name = "FILE", requires = "attach")]
+        checks_json: Option<PathBuf>,
+
+        /// With --baseline, report command timing changes larger than this
+        /// many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timing_threshold: u64,
+
+        /// Echo each guest command to stderr as it runs; repeat (-vv) to
+        /// also print its output and exit status.
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+    },
+
+    /// Describe the preflight checklist for a distro
+    Preflight {
+        /// Distro whose checklist to describe
+        #[arg(long, default_value = "levitate")]
+        distro: String,
+
+        /// List every check preflight would run, without opening any artifact
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Compare a run report against a baseline; exits non-zero on regressions
+    Compare {
+        /// Report of the earlier run to compare against
+        #[arg(long, value_name = "FILE")]
+        baseline: PathBuf,
+
+        /// Report of the run under test
+        #[arg(long, value_name = "FILE")]
+        current: PathBuf,
+
+        /// Report command timing changes larger than this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timing_threshold: u64,
     },
 
     /// List all test steps
@@ -41,47 +153,312 @@ enum Commands {
         /// Distro to list steps for
         #[arg(long, default_value = "levitate")]
         distro: String,
+
+        /// Print the step catalog as JSON instead of formatted text
+        #[arg(long)]
+        json: bool,
+
+        /// Include experimental steps (Phase 6 post-reboot verification)
+        #[arg(long)]
+        experimental: bool,
     },
 }
 
+/// One step in the `list --json` catalog.
+#[derive(Serialize)]
+struct StepEntry<'a> {
+    num: usize,
+    name: &'a str,
+    phase: usize,
+    phase_name: &'static str,
+    ensures: &'a str,
+    experimental: bool,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
 
     match cli.command {
         Commands::Run {
             step,
             phase,
+            step_range,
             distro,
+            dry_run,
+            experimental,
+            fail_fast: _,
+            no_fail_fast,
+            attach,
+            parallel_ssh,
+            report,
+            baseline,
+            timing_threshold,
+            timing_json,
+            checks_json,
+            verbose,
         } => {
+            let ctx = resolve_context(&distro)?;
+            let selected = select_steps(step, phase, step_range.as_deref(), experimental)?;
+            if dry_run {
+                return install_tests::scenarios::print_dry_run(ctx.id());
+            }
             install_tests::enforce_policy_guard("install-tests run")?;
+            if verbose > 0 {
+                std::env::set_var(install_tests::executor::VERBOSE_ENV, verbose.to_string());
+            }
+            if let Some(socket) = attach {
+                let mode = match parallel_ssh {
+                    Some(port) => RunMode::ParallelSsh(port),
+                    None => RunMode::Sequential {
+                        fail_fast: !no_fail_fast,
+                    },
+                };
+                // Only targeted reruns trust `already_satisfied`; a full or
+                // per-phase run always exercises every step.
+                let skip_satisfied = step.is_some() || step_range.is_some();
+                let outputs = RunOutputs {
+                    report,
+                    baseline,
+                    timing_threshold: Duration::from_secs(timing_threshold),
+                    timing_json,
+                    checks_json,
+                };
+                return run_attached(&socket, &selected, &*ctx, mode, skip_satisfied, &outputs);
+            }
             bail!(
                 "Legacy serial wrapper harness is removed for `install-tests run`.\n\
              Use the scenario runner instead (e.g. `cargo xtask scenarios test live-tools <distro>` or `just scenario-test live-tools <distro>`).\n\
-             Received args: step={:?}, phase={:?}, distro={}",
+             Received args: step={:?}, phase={:?}, fail_fast={}, distro={} ({}), selected steps: {}",
                 step,
                 phase,
-                distro
+                !no_fail_fast,
+                ctx.id(),
+                ctx.init_system_name(),
+                selected
+                    .iter()
+                    .map(|step| step_label(&**step))
+                    .collect::<Vec<_>>()
+                    .join(", ")
             )
         }
-        Commands::List { distro } => {
-            let ctx = context_for_distro(&distro).ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Unknown distro '{}'. Available: {}",
-                    distro,
-                    AVAILABLE_DISTROS.join(", ")
-                )
-            })?;
-            list_steps(&*ctx);
+        Commands::Preflight { distro, explain } => {
+            let ctx = resolve_context(&distro)?;
+            if !explain {
+                bail!(
+                    "`install-tests preflight` only supports --explain.\n\
+                 Run checks with `cargo run --bin preflight -- --distro {} --iso-dir <DIR>`.",
+                    ctx.id()
+                );
+            }
+            install_tests::print_preflight_explain(ctx.id());
+            Ok(())
+        }
+        Commands::Compare {
+            baseline,
+            current,
+            timing_threshold,
+        } => RunReport::load(&current)?
+            .check_against(&baseline, Duration::from_secs(timing_threshold)),
+        Commands::List {
+            distro,
+            json,
+            experimental,
+        } => {
+            let ctx = resolve_context(&distro)?;
+            if json {
+                return print_step_catalog_json(experimental);
+            }
+            list_steps(&*ctx, experimental);
             Ok(())
         }
     }
 }
 
-fn list_steps(ctx: &dyn DistroContext) {
+/// How many of the slowest commands `run --attach` lists after the steps.
+const SLOWEST_COMMANDS: usize = 5;
+
+/// How `run --attach` schedules the selected steps.
+enum RunMode {
+    /// One at a time on the serial console.
+    Sequential { fail_fast: bool },
+    /// Parallel-safe steps concurrently over SSH to this host port, the rest
+    /// on the serial console.
+    ParallelSsh(u16),
+}
+
+/// Where `run --attach` writes its results and what it compares them to.
+struct RunOutputs {
+    report: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    timing_threshold: Duration,
+    timing_json: Option<PathBuf>,
+    checks_json: Option<PathBuf>,
+}
+
+/// Run `steps` on the VM behind `socket`, reporting each result.
+///
+/// `skip_satisfied` skips steps whose work is already on the guest.
+fn run_attached(
+    socket: &Path,
+    steps: &[Box<dyn Step>],
+    ctx: &dyn DistroContext,
+    mode: RunMode,
+    skip_satisfied: bool,
+    outputs: &RunOutputs,
+) -> Result<()> {
+    println!("Attaching to {} ({})", socket.display(), ctx.name());
+    let mut console = Console::attach(socket)?;
+    let mut executor = ContextConsole::new(&mut console, ctx);
+    let results = match mode {
+        RunMode::Sequential { fail_fast } => {
+            run_steps_until_failure(steps, &mut executor, ctx, fail_fast, skip_satisfied)
+        }
+        RunMode::ParallelSsh(port) => {
+            run_phase_parallel(steps, &mut executor, ctx, skip_satisfied, || {
+                Ok(SshExecutor::new(port))
+            })
+        }
+    };
+
+    let mut failed = 0;
+    for (step, result) in steps.iter().zip(&results) {
+        match result {
+            Ok(result) if result.passed => println!(
+                "{} {} ({:.1}s)",
+                "PASS".green(),
+                step_label(&**step),
+                result.duration.as_secs_f64()
+            ),
+            Ok(result) => {
+                failed += 1;
+                println!("{} {}", "FAIL".red(), step_label(&**step));
+                for (check, outcome) in &result.checks {
+                    if let CheckResult::Fail { expected, actual } = outcome {
+                        println!("    {}: expected {}, got {}", check, expected, actual);
+                    }
+                }
+                if let Some(fix) = &result.fix_suggestion {
+                    println!("    fix: {}", fix);
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                println!("{} {}: {:#}", "ERROR".red(), step_label(&**step), err);
+            }
+        }
+    }
+    if results.len() < steps.len() {
+        println!(
+            "{}",
+            format!(
+                "Stopped after the first failure; {} step(s) not run (--no-fail-fast runs them)",
+                steps.len() - results.len()
+            )
+            .yellow()
+        );
+    }
+
+    let ran = results.len();
+    let report = RunReport::from_run(steps, &results);
+    let completed: Vec<StepResult> = results.into_iter().filter_map(Result::ok).collect();
+    print_slowest_commands(&completed, SLOWEST_COMMANDS);
+    if let Some(path) = &outputs.timing_json {
+        write_timing_json(&completed, path)?;
+    }
+    if let Some(path) = &outputs.checks_json {
+        write_checks_json(&completed, path)?;
+    }
+    if let Some(path) = &outputs.report {
+        report.save(path)?;
+        println!("Run report saved to {}", path.display());
+    }
+    if let Some(baseline) = &outputs.baseline {
+        report.check_against(baseline, outputs.timing_threshold)?;
+    }
+    if failed > 0 {
+        bail!("{} of {} step(s) failed", failed, ran);
+    }
+    Ok(())
+}
+
+fn resolve_context(distro: &str) -> Result<Box<dyn DistroContext>> {
+    context_for_distro(distro).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unknown distro '{}'. Available: {}",
+            distro,
+            AVAILABLE_DISTROS.join(", ")
+        )
+    })
+}
+
+fn catalog(experimental: bool) -> Vec<Box<dyn Step>> {
+    if experimental {
+        all_steps_with_experimental()
+    } else {
+        all_steps()
+    }
+}
+
+/// Steps a `run` with these filters would execute.
+fn select_steps(
+    step: Option<usize>,
+    phase: Option<usize>,
+    range: Option<&str>,
+    experimental: bool,
+) -> Result<Vec<Box<dyn Step>>> {
+    let selected: Vec<Box<dyn Step>> = match (step, phase, range) {
+        (Some(_), _, Some(_)) | (_, Some(_), Some(_)) => {
+            bail!("--step-range cannot be combined with --step or --phase")
+        }
+        (None, None, Some(range)) => steps_in_range(parse_step_range(range)?)?,
+        (Some(num), _, None) => catalog(true)
+            .into_iter()
+            .filter(|s| s.num() == num)
+            .collect(),
+        (None, Some(phase), None) if experimental => steps_for_phase_experimental(phase),
+        (None, Some(phase), None) => steps_for_phase(phase),
+        (None, None, None) => catalog(experimental),
+    };
+    if let Some(experimental_step) = selected
+        .iter()
+        .find(|s| !experimental && is_experimental(&***s))
+    {
+        bail!(
+            "Step {} is experimental; pass --experimental to run it",
+            experimental_step.num()
+        );
+    }
+    if selected.is_empty() {
+        match (step, phase) {
+            (_, Some(6)) if !experimental => {
+                bail!("Phase 6 steps are experimental; pass --experimental to run them")
+            }
+            _ => bail!("No steps match step={:?}, phase={:?}", step, phase),
+        }
+    }
+    Ok(selected)
+}
+
+/// `"25. Verify Essential Commands"`, tagged when experimental.
+fn step_label(step: &dyn Step) -> String {
+    if is_experimental(step) {
+        format!("{}. {} [experimental]", step.num(), step.name())
+    } else {
+        format!("{}. {}", step.num(), step.name())
+    }
+}
+
+fn list_steps(ctx: &dyn DistroContext, experimental: bool) {
     println!(
         "{}",
         format!("{} Installation Test Steps", ctx.name()).bold()
     );
+    println!(
+        "Init system: {} (boot target: {})",
+        ctx.init_system_name(),
+        ctx.boot_target_name()
+    );
     println!();
     println!("Each step has an 'ensures' statement describing what it guarantees.");
     println!();
@@ -92,7 +469,7 @@ fn list_steps(ctx: &dyn DistroContext) {
     );
     println!();
 
-    let steps = all_steps_with_experimental();
+    let steps = catalog(experimental);
     let mut current_phase = 0;
 
     for step in steps {
@@ -100,18 +477,56 @@ fn list_steps(ctx: &dyn DistroContext) {
             current_phase = step.phase();
             println!();
             let phase_desc = match current_phase {
-                1 => "Phase 1 (Boot Verification)",
-                2 => "Phase 2 (Disk Setup)",
-                3 => "Phase 3 (Base System)",
-                4 => "Phase 4 (Configuration)",
-                5 => "Phase 5 (Bootloader)",
-                6 => "Phase 6 (Post-Reboot Verification) <- REBOOTS INTO INSTALLED SYSTEM",
-                _ => "Unknown Phase",
+                1..=5 => format!("Phase {} ({})", current_phase, phase_name(current_phase)),
+                6 => format!(
+                    "Phase 6 ({}, {}) <- REBOOTS INTO INSTALLED SYSTEM",
+                    phase_name(6),
+                    ctx.init_system_name()
+                ),
+                _ => phase_name(current_phase).to_string(),
             };
             println!("{}", phase_desc.blue().bold());
         }
-        println!("  {:2}. {}", step.num(), step.name());
+        if is_experimental(&*step) {
+            println!(
+                "  {:2}. {} {}",
+                step.num(),
+                step.name(),
+                "[experimental]".yellow()
+            );
+        } else {
+            println!("  {:2}. {}", step.num(), step.name());
+        }
         println!("      ensures: {}", step.ensures());
+        if !step.depends_on().is_empty() {
+            let deps: Vec<String> = step.depends_on().iter().map(usize::to_string).collect();
+            println!("      depends on: {}", deps.join(", "));
+        }
+    }
+    if !experimental {
+        println!();
+        println!(
+            "{}",
+            "Experimental Phase 6 steps hidden; pass --experimental to include them.".dimmed()
+        );
     }
     println!();
 }
+
+fn print_step_catalog_json(experimental: bool) -> Result<()> {
+    let steps = catalog(experimental);
+    let entries: Vec<StepEntry> = steps.iter().map(|step| step_entry(&**step)).collect();
+    println!("{}", serde_json::to_string_pretty(&entries)?);
+    Ok(())
+}
+
+fn step_entry(step: &dyn Step) -> StepEntry<'_> {
+    StepEntry {
+        num: step.num(),
+        name: step.name(),
+        phase: step.phase(),
+        phase_name: phase_name(step.phase()),
+        ensures: step.ensures(),
+        experimental: is_experimental(step),
+    }
+}
@@ -0,0 +1,47 @@
+//! Read-only ISO inspector.
+//!
+//! Lists what is actually on a built ISO, or copies one file out of it, for
+//! chasing down preflight "missing X" failures.
+//!
+//! Usage:
+//!   cargo run --bin iso-inspect -- .artifacts/out/acorn/acorn.iso
+//!   cargo run --bin iso-inspect -- acorn.iso --extract /boot/vmlinuz --output /tmp/vmlinuz
+//!   cargo run --bin iso-inspect -- acorn.iso --extract /live/filesystem.erofs | file -
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use install_tests::inspect;
+
+#[derive(Parser)]
+#[command(name = "iso-inspect")]
+#[command(about = "List or extract files from a built ISO (read-only)")]
+struct Cli {
+    /// ISO image to inspect.
+    iso: PathBuf,
+
+    /// Path inside the ISO to copy out instead of listing.
+    #[arg(long, value_name = "PATH")]
+    extract: Option<String>,
+
+    /// Write the extracted file here instead of stdout.
+    #[arg(long, short, value_name = "FILE", requires = "extract")]
+    output: Option<PathBuf>,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: install_tests::color::ColorChoice,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
+    if !cli.iso.is_file() {
+        bail!("ISO not found: {}", cli.iso.display());
+    }
+    match cli.extract {
+        Some(inner) => inspect::extract_iso_file(&cli.iso, &inner, cli.output.as_deref()),
+        None => inspect::print_iso_listing(&cli.iso),
+    }
+}
@@ -0,0 +1,71 @@
+//! Standalone preflight CLI for CI gating.
+//!
+//! Verifies built ISO artifacts without starting QEMU. Exits 0 when every
+//! check passes and 1 otherwise.
+//!
+//! Usage:
+//!   cargo run --bin preflight -- --distro acorn --iso-dir .artifacts/out/acorn
+//!   cargo run --bin preflight -- --distro acorn --iso-dir .artifacts/out/acorn --iso acorn.iso
+//!   cargo run --bin preflight -- --distro acorn --explain
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+
+use install_tests::{context_for_distro, AVAILABLE_DISTROS};
+
+#[derive(Parser)]
+#[command(name = "preflight")]
+#[command(about = "Verify built ISO artifacts before running any scenario")]
+struct Cli {
+    /// Distro to verify (levitate, acorn, iuppiter, ralph)
+    #[arg(long)]
+    distro: String,
+
+    /// Directory containing the ISO artifacts.
+    #[arg(
+        long = "iso-dir",
+        value_name = "DIR",
+        required_unless_present = "explain"
+    )]
+    iso_dir: Option<PathBuf>,
+
+    /// Specific ISO filename inside --iso-dir (defaults to the first .iso found).
+    #[arg(long, value_name = "FILENAME")]
+    iso: Option<String>,
+
+    /// List every check preflight would run, without opening any artifact.
+    #[arg(long)]
+    explain: bool,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: install_tests::color::ColorChoice,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
+    let Some(ctx) = context_for_distro(&cli.distro) else {
+        bail!(
+            "Unknown distro '{}'. Available: {}",
+            cli.distro,
+            AVAILABLE_DISTROS.join(", ")
+        );
+    };
+
+    if cli.explain {
+        install_tests::print_preflight_explain(ctx.id());
+        return Ok(());
+    }
+
+    let iso_dir = cli
+        .iso_dir
+        .expect("clap requires --iso-dir without --explain");
+    if !iso_dir.is_dir() {
+        bail!("ISO directory not found: {}", iso_dir.display());
+    }
+    let result =
+        install_tests::run_preflight_with_iso_distro(&iso_dir, cli.iso.as_deref(), ctx.id())?;
+    std::process::exit(if result.overall_pass { 0 } else { 1 });
+}
@@ -15,9 +15,8 @@ use std::path::Path;
 use std::time::Duration;
 
 use install_tests::qemu::qmp::QmpClient;
-use install_tests::{
-    create_disk, find_ovmf, find_ovmf_vars, kill_stale_qemu_processes, QemuBuilder,
-};
+use install_tests::qemu::session;
+use install_tests::{create_disk, kill_stale_qemu_processes, QemuBuilder};
 
 #[derive(Parser)]
 #[command(name = "install-tests-qmp")]
@@ -25,6 +24,10 @@ use install_tests::{
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto", global = true)]
+    color: install_tests::color::ColorChoice,
 }
 
 #[derive(Subcommand)]
@@ -38,15 +41,25 @@ enum Commands {
         /// VNC display number for live viewing
         #[arg(long, default_value = "0")]
         vnc: u16,
+
+        /// Directory for the scratch disk and OVMF vars (default: system temp dir)
+        #[arg(long = "work-dir", value_name = "PATH")]
+        work_dir: Option<std::path::PathBuf>,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
     install_tests::enforce_policy_guard("install-tests qmp")?;
 
     match cli.command {
-        Commands::Smoke { iso, vnc } => smoke_test(&iso, vnc),
+        Commands::Smoke { iso, vnc, work_dir } => {
+            if let Some(dir) = work_dir {
+                std::env::set_var(session::WORK_DIR_ENV, dir);
+            }
+            smoke_test(&iso, vnc)
+        }
     }
 }
 
@@ -61,22 +74,17 @@ fn smoke_test(iso_path: &Path, vnc_display: u16) -> Result<()> {
 
     kill_stale_qemu_processes();
 
-    let ovmf = find_ovmf().context("OVMF not found - UEFI boot required for installation tests")?;
-    let ovmf_vars_template =
-        find_ovmf_vars().context("OVMF_VARS not found - needed for EFI variable storage")?;
-    let ovmf_vars_path = std::env::temp_dir().join("leviso-qmp-smoke-vars.fd");
-    if ovmf_vars_path.exists() {
-        std::fs::remove_file(&ovmf_vars_path)?;
-    }
-    std::fs::copy(&ovmf_vars_template, &ovmf_vars_path)?;
+    let mut scratch = session::ScratchFiles::new();
+    let (ovmf, ovmf_vars_path) =
+        session::setup_ovmf_vars_at(&scratch.path("leviso-qmp-smoke-vars", "fd")?)
+            .context("UEFI firmware is required for the QMP smoke test")?;
 
-    let disk_path = std::env::temp_dir().join("leviso-qmp-smoke.qcow2");
-    if disk_path.exists() {
-        std::fs::remove_file(&disk_path)?;
-    }
+    let disk_path = scratch.path("leviso-qmp-smoke", "qcow2")?;
     create_disk(&disk_path, "10G")?;
 
-    let qmp_socket = std::env::temp_dir().join("leviso-qmp-smoke.sock");
+    // Sockets stay in the temp dir: their paths are length-limited.
+    let qmp_socket =
+        std::env::temp_dir().join(format!("leviso-qmp-smoke-{}.sock", std::process::id()));
     if qmp_socket.exists() {
         std::fs::remove_file(&qmp_socket)?;
     }
@@ -96,27 +104,21 @@ fn smoke_test(iso_path: &Path, vnc_display: u16) -> Result<()> {
         .qmp_socket(qmp_socket.clone())
         .vnc_display(vnc_display)
         .no_reboot()
-        .build_qmp();
+        .build_qmp()?;
 
     let mut child = cmd.spawn().context("Failed to spawn QEMU")?;
     println!("{}", "QEMU started!".green());
 
-    println!("{}", "Waiting for QMP socket...".cyan());
-    for _ in 0..50 {
-        if qmp_socket.exists() {
-            break;
-        }
-        std::thread::sleep(Duration::from_millis(100));
-    }
-
-    if !qmp_socket.exists() {
-        let _ = child.kill();
-        let _ = child.wait();
-        bail!("QMP socket not created after 5 seconds");
-    }
-
     println!("{}", "Connecting to QMP...".cyan());
-    let mut qmp = QmpClient::connect(&qmp_socket)?;
+    // connect() retries until QEMU has created the socket.
+    let mut qmp = match QmpClient::connect(&qmp_socket) {
+        Ok(qmp) => qmp,
+        Err(err) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+    };
     println!("{}", "QMP connected!".green());
     println!();
 
@@ -148,10 +150,8 @@ fn smoke_test(iso_path: &Path, vnc_display: u16) -> Result<()> {
 
     println!();
     println!("{}", "Shutting down...".cyan());
-    let _ = child.kill();
-    let _ = child.wait();
-    let _ = std::fs::remove_file(&disk_path);
-    let _ = std::fs::remove_file(&ovmf_vars_path);
+    session::shutdown_qmp(&mut child, &mut qmp, session::SHUTDOWN_TIMEOUT);
+    drop(scratch);
     let _ = std::fs::remove_file(&qmp_socket);
 
     println!();
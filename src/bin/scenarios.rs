@@ -3,11 +3,26 @@
 //! Lightweight, incremental scenario runner for verifying OS builds.
 //!
 //! Usage:
+//!   cargo run --bin scenarios -- --distro acorn --smoke
 //!   cargo run --bin scenarios -- --distro acorn --scenario live-boot
 //!   cargo run --bin scenarios -- --distro acorn --scenario live-tools
 //!   cargo run --bin scenarios -- --distro acorn --up-to-scenario install
+//!   cargo run --bin scenarios -- --distro acorn --from-scenario install --up-to-scenario runtime
 //!   cargo run --bin scenarios -- --distro acorn --status
+//!   cargo run --bin scenarios -- --distro acorn --status --json
+//!   cargo run --bin scenarios -- --distro acorn --scenario install --explain
 //!   cargo run --bin scenarios -- --distro acorn --reset
+//!   cargo run --bin scenarios -- --reset-all
+//!   cargo run --bin scenarios -- --distro acorn --dry-run
+//!   cargo run --bin scenarios -- --distro acorn --up-to-scenario install --offline
+//!   cargo run --bin scenarios -- --distro acorn --scenario live-boot --display vnc:1
+//!   cargo run --bin scenarios -- --distro acorn --scenario install --disk-cache unsafe
+//!   cargo run --bin scenarios -- --distro acorn --scenario live-boot --screenshot-on-boot
+//!   cargo run --bin scenarios -- --distro acorn --scenario install -vv
+//!   cargo run --bin scenarios -- --distro acorn --up-to-scenario runtime --max-run-time 3600
+//!   cargo run --bin scenarios -- --distro acorn --disk other-pipeline.qcow2
+//!   cargo run --bin scenarios -- --distro acorn --up-to-scenario runtime --report run.json
+//!   cargo run --bin scenarios -- --distro acorn --scenario runtime --dump-journal --journal-lines 500
 
 use anyhow::{bail, Result};
 use clap::Parser;
@@ -20,25 +35,38 @@ use install_tests::scenarios;
 #[command(about = "Scenario runner for LevitateOS variants")]
 struct Cli {
     /// Distro to test (levitate, acorn, iuppiter, ralph)
-    #[arg(long)]
-    distro: String,
+    #[arg(long, required_unless_present = "reset_all")]
+    distro: Option<String>,
 
     /// Run a specific canonical scenario.
     #[arg(long, value_name = "NAME")]
     scenario: Option<String>,
 
     /// Run all scenarios up to the named canonical scenario.
-    #[arg(long = "up-to-scenario", value_name = "NAME")]
+    #[arg(long = "up-to-scenario", alias = "to-scenario", value_name = "NAME")]
     up_to_scenario: Option<String>,
 
+    /// Re-run from the named scenario (its predecessor must have passed),
+    /// through --up-to-scenario or the last scenario.
+    #[arg(long = "from-scenario", value_name = "NAME")]
+    from_scenario: Option<String>,
+
     /// Show scenario status.
     #[arg(long)]
     status: bool,
 
+    /// With --status, print machine-readable JSON instead of the table.
+    #[arg(long, requires = "status")]
+    json: bool,
+
     /// Reset scenario state (forces re-run).
     #[arg(long)]
     reset: bool,
 
+    /// Reset scenario state for every distro.
+    #[arg(long = "reset-all")]
+    reset_all: bool,
+
     /// Boot-inject key/value pairs (comma-separated KEY=VALUE entries).
     #[arg(long, value_name = "KEY=VALUE[,KEY=VALUE...]")]
     inject: Option<String>,
@@ -47,51 +75,348 @@ struct Cli {
     #[arg(long, value_name = "PATH")]
     inject_file: Option<PathBuf>,
 
+    /// Extra kernel command line arguments (e.g. "rd.break"). Only direct
+    /// kernel boots can apply them; UEFI VMs refuse to start with them set.
+    #[arg(
+        long = "kernel-append",
+        value_name = "ARGS",
+        allow_hyphen_values = true
+    )]
+    kernel_append: Option<String>,
+
+    /// With --scenario, explain why it is blocked instead of running it.
+    #[arg(long, requires = "scenario")]
+    explain: bool,
+
+    /// Only check that the live ISO's kernel loads (fast smoke test).
+    #[arg(long)]
+    smoke: bool,
+
     /// Re-run the requested scenario even if it is already cached as passed.
     #[arg(long)]
     force: bool,
+
+    /// After install, kexec into the installed kernel instead of powering off.
+    /// Bypasses UEFI; never a substitute for the installed-boot scenario.
+    #[arg(long = "fast-reboot")]
+    fast_reboot: bool,
+
+    /// Boot every VM without outbound networking (`-nic none`) to prove the
+    /// install never fetches from the internet. Phase 6 networking is skipped.
+    #[arg(long)]
+    offline: bool,
+
+    /// Show the guest screen while the serial console drives the run:
+    /// none (default), gtk, or vnc:N (connect to port 5900+N).
+    #[arg(long)]
+    display: Option<install_tests::qemu::Display>,
+
+    /// Save `boot.png` when boot is detected and `login.png` after login
+    /// (live-boot and automated-login) via a QMP screendump.
+    #[arg(long = "screenshot-on-boot")]
+    screenshot_on_boot: bool,
+
+    /// Host cache mode for VM disks: none, writeback, or unsafe (fastest;
+    /// fine for throwaway install disks). Defaults to QEMU's own.
+    #[arg(long = "disk-cache", value_name = "MODE")]
+    disk_cache: Option<install_tests::qemu::DiskCache>,
+
+    /// Bus VM disks attach to: virtio (default), virtio-scsi, or ide.
+    #[arg(long = "disk-bus", value_name = "BUS")]
+    disk_bus: Option<install_tests::qemu::DiskBus>,
+
+    /// QEMU accelerator: auto (default; KVM when usable), kvm, or tcg.
+    #[arg(long, value_name = "ACCEL")]
+    accel: Option<install_tests::qemu::Accel>,
+
+    /// QEMU machine type, e.g. q35 (the UEFI default), pc, or pc-q35-8.2.
+    #[arg(long, value_name = "TYPE")]
+    machine: Option<String>,
+
+    /// QEMU CPU model, e.g. host (KVM only) or Skylake-Client.
+    #[arg(long, value_name = "MODEL")]
+    cpu: Option<String>,
+
+    /// Print elapsed time and boot stage every few seconds during boot waits
+    /// (on by default when stderr is a terminal).
+    #[arg(long)]
+    progress: bool,
+
+    /// Echo each guest command to stderr as it runs; repeat (-vv) to also
+    /// print its output and exit status.
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Abort the whole run after SECS seconds: print the last serial lines of
+    /// each VM, kill QEMU and exit 124. Independent of the per-command and
+    /// stall timeouts.
+    #[arg(long = "max-run-time", value_name = "SECS")]
+    max_run_time: Option<u64>,
+
+    /// Verify a pre-built disk image: boot it directly and run only the
+    /// post-install scenarios (all of them, or those picked with --scenario /
+    /// --up-to-scenario). Nothing is recorded in scenario state. qcow2 and
+    /// raw images are both accepted.
+    #[arg(long, value_name = "IMAGE")]
+    disk: Option<PathBuf>,
+
+    /// With --disk, the disk's own OVMF vars (a fresh copy is used otherwise).
+    #[arg(long = "ovmf-vars", value_name = "PATH", requires = "disk")]
+    ovmf_vars: Option<PathBuf>,
+
+    /// Directory for scratch files such as fresh OVMF vars copies, instead of
+    /// the system temp dir (also `LEVITATE_WORK_DIR`). Created if missing.
+    #[arg(long = "work-dir", value_name = "PATH")]
+    work_dir: Option<PathBuf>,
+
+    /// When a post-install scenario fails after login, save the installed
+    /// system's boot log (`journalctl -b`, or the init system's equivalent) to
+    /// the artifact dir (`--dump-journal=-` prints it instead).
+    #[arg(
+        long = "dump-journal",
+        value_name = "DEST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "artifacts"
+    )]
+    dump_journal: Option<String>,
+
+    /// With --dump-journal, keep only the last N journal entries.
+    #[arg(long = "journal-lines", value_name = "N", requires = "dump_journal")]
+    journal_lines: Option<usize>,
+
+    /// If the install scenario fails, move its disk image and OVMF vars to
+    /// `.artifacts/out/<distro>/preserved/` so they survive run pruning and
+    /// can be booted again with --disk.
+    #[arg(long = "preserve-on-failure")]
+    preserve_on_failure: bool,
+
+    /// Install PKG into the live overlay before the live scenarios run their
+    /// checks, for debugging without rebuilding the ISO. Repeatable; the
+    /// packages are gone on the next boot and passes are not reused by runs
+    /// without them.
+    #[arg(long = "extra-pkg", value_name = "PKG")]
+    extra_pkg: Vec<String>,
+
+    /// Serve DIR to the guest as the package mirror for --extra-pkg, in place
+    /// of the distro's repositories. Supported on acorn and iuppiter, where
+    /// DIR is an apk repository (`<arch>/APKINDEX.tar.gz` plus packages).
+    #[arg(long = "extra-pkg-mirror", value_name = "DIR", requires = "extra_pkg")]
+    extra_pkg_mirror: Option<PathBuf>,
+
+    /// After the run, save the distro's scenario results as a run report
+    /// (one step per scenario) for `install-tests compare`.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["disk", "smoke"])]
+    report: Option<PathBuf>,
+
+    /// Print the QEMU command lines and resolved paths instead of spawning.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// Colour output: auto (terminal and no NO_COLOR), always, or never.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: install_tests::color::ColorChoice,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    install_tests::color::apply(cli.color);
+    if let Some(secs) = cli.max_run_time {
+        install_tests::qemu::watchdog::start(std::time::Duration::from_secs(secs))?;
+    }
     apply_boot_injection_env(&cli)?;
-    let requires_guard = cli.scenario.is_some() || cli.up_to_scenario.is_some();
+    if cli.fast_reboot {
+        std::env::set_var(scenarios::FAST_REBOOT_ENV, "1");
+    }
+    if cli.progress {
+        std::env::set_var(install_tests::qemu::session::PROGRESS_ENV, "1");
+    }
+    if let Some(display) = cli.display {
+        std::env::set_var(
+            install_tests::qemu::session::DISPLAY_ENV,
+            display.to_string(),
+        );
+    }
+    if let Some(cache) = cli.disk_cache {
+        std::env::set_var(
+            install_tests::qemu::session::DISK_CACHE_ENV,
+            cache.to_string(),
+        );
+    }
+    if let Some(bus) = cli.disk_bus {
+        std::env::set_var(install_tests::qemu::session::DISK_BUS_ENV, bus.to_string());
+    }
+    if let Some(accel) = cli.accel {
+        std::env::set_var(install_tests::qemu::session::ACCEL_ENV, accel.to_string());
+    }
+    if let Some(machine) = &cli.machine {
+        std::env::set_var(install_tests::qemu::session::MACHINE_ENV, machine);
+    }
+    if let Some(cpu) = &cli.cpu {
+        std::env::set_var(install_tests::qemu::session::CPU_ENV, cpu);
+    }
+    if cli.offline {
+        std::env::set_var(install_tests::qemu::session::OFFLINE_ENV, "1");
+    }
+    if cli.verbose > 0 {
+        std::env::set_var(
+            install_tests::executor::VERBOSE_ENV,
+            cli.verbose.to_string(),
+        );
+    }
+    if cli.screenshot_on_boot {
+        std::env::set_var(install_tests::qemu::session::SCREENSHOT_ON_BOOT_ENV, "1");
+    }
+    if let Some(dir) = &cli.work_dir {
+        std::env::set_var(install_tests::qemu::session::WORK_DIR_ENV, dir);
+    }
+    if let Some(destination) = &cli.dump_journal {
+        std::env::set_var(scenarios::DUMP_JOURNAL_ENV, destination);
+    }
+    if let Some(lines) = cli.journal_lines {
+        std::env::set_var(scenarios::JOURNAL_LINES_ENV, lines.to_string());
+    }
+    if cli.preserve_on_failure {
+        std::env::set_var(scenarios::PRESERVE_ON_FAILURE_ENV, "1");
+    }
+    if !cli.extra_pkg.is_empty() {
+        std::env::set_var(scenarios::EXTRA_PACKAGES_ENV, cli.extra_pkg.join(" "));
+    }
+    if let Some(dir) = &cli.extra_pkg_mirror {
+        std::env::set_var(scenarios::EXTRA_PACKAGES_MIRROR_ENV, dir);
+    }
+    if cli.reset_all {
+        scenarios::reset_all()?;
+        return Ok(());
+    }
+    let distro = cli.distro.as_deref().unwrap_or_default();
+
+    if cli.dry_run {
+        return scenarios::print_dry_run(distro);
+    }
+
+    if cli.explain {
+        let scenario = scenarios::parse_scenario_name(cli.scenario.as_deref().unwrap_or_default())?;
+        return scenarios::explain_block(distro, scenario);
+    }
+
+    let spawns_vms = !(cli.reset || cli.status)
+        && (cli.smoke
+            || cli.disk.is_some()
+            || cli.scenario.is_some()
+            || cli.up_to_scenario.is_some()
+            || cli.from_scenario.is_some());
+    if spawns_vms {
+        install_tests::check_host_environment()?;
+    }
+
+    if cli.smoke {
+        install_tests::enforce_policy_guard("install-tests scenarios")?;
+        let passed = scenarios::run_smoke_boot(distro)?;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
+    let requires_guard =
+        cli.scenario.is_some() || cli.up_to_scenario.is_some() || cli.from_scenario.is_some();
     if requires_guard {
         install_tests::enforce_policy_guard("install-tests scenarios")?;
     }
 
     if cli.reset {
-        return scenarios::reset_state(&cli.distro);
+        return scenarios::reset_state(distro);
     }
 
     if cli.status {
-        return scenarios::print_status(&cli.distro);
+        if cli.json {
+            return scenarios::print_status_json(distro);
+        }
+        return scenarios::print_status(distro);
+    }
+
+    if let Some(disk) = cli.disk.as_deref() {
+        let selected: Vec<scenarios::ScenarioId> =
+            match (cli.scenario.as_deref(), cli.up_to_scenario.as_deref()) {
+                (Some(name), _) => vec![scenarios::parse_scenario_name(name)?],
+                (None, Some(target)) => {
+                    let target = scenarios::parse_scenario_name(target)?;
+                    scenarios::ScenarioId::ALL
+                        .into_iter()
+                        .filter(|s| {
+                            s.ordinal() >= scenarios::ScenarioId::InstalledBoot.ordinal()
+                                && s.ordinal() <= target.ordinal()
+                        })
+                        .collect()
+                }
+                (None, None) => scenarios::ScenarioId::ALL
+                    .into_iter()
+                    .filter(|s| s.ordinal() >= scenarios::ScenarioId::InstalledBoot.ordinal())
+                    .collect(),
+            };
+        install_tests::enforce_policy_guard("install-tests scenarios")?;
+        let passed = scenarios::run_with_disk(distro, disk, cli.ovmf_vars.as_deref(), &selected)?;
+        std::process::exit(if passed { 0 } else { 1 });
     }
 
     if cli.force && cli.scenario.is_none() {
         bail!("--force requires --scenario NAME");
     }
 
+    if let Some(from) = cli.from_scenario.as_deref() {
+        if cli.scenario.is_some() {
+            bail!("--from-scenario cannot be combined with --scenario");
+        }
+        let from = scenarios::parse_scenario_name(from)?;
+        let to = match cli.up_to_scenario.as_deref() {
+            Some(target) => scenarios::parse_scenario_name(target)?,
+            None => *scenarios::ScenarioId::ALL
+                .last()
+                .expect("at least one scenario"),
+        };
+        let passed = scenarios::run_scenario_range(distro, from, to)?;
+        finish(&cli, distro, passed);
+    }
+
     if let Some(scenario_name) = cli.scenario.as_deref() {
         let scenario = scenarios::parse_scenario_name(scenario_name)?;
         let passed = if cli.force {
-            scenarios::run_scenario_forced(&cli.distro, scenario)?
+            scenarios::run_scenario_forced(distro, scenario)?
         } else {
-            scenarios::run_scenario(&cli.distro, scenario)?
+            scenarios::run_scenario(distro, scenario)?
         };
-        std::process::exit(if passed { 0 } else { 1 });
+        finish(&cli, distro, passed);
     }
 
     if let Some(target) = cli.up_to_scenario.as_deref() {
         let scenario = scenarios::parse_scenario_name(target)?;
-        let passed = scenarios::run_up_to_scenario(&cli.distro, scenario)?;
-        std::process::exit(if passed { 0 } else { 1 });
+        let passed = scenarios::run_up_to_scenario(distro, scenario)?;
+        finish(&cli, distro, passed);
     }
 
     bail!("Specify --scenario NAME, --up-to-scenario NAME, --status, or --reset");
 }
 
+/// Save the `--report` if one was asked for, then exit with the run's status.
+fn finish(cli: &Cli, distro: &str, passed: bool) -> ! {
+    if let Some(path) = &cli.report {
+        let saved = scenarios::run_report(distro).and_then(|report| report.save(path));
+        match saved {
+            Ok(()) => println!("Run report saved to {}", path.display()),
+            Err(err) => {
+                eprintln!("Error: {:#}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    std::process::exit(if passed { 0 } else { 1 });
+}
+
 fn apply_boot_injection_env(cli: &Cli) -> Result<()> {
+    if let Some(args) = &cli.kernel_append {
+        if args.trim().is_empty() {
+            bail!("--kernel-append cannot be empty");
+        }
+        std::env::set_var(install_tests::boot_injection::ENV_KERNEL_APPEND, args);
+    }
     if let Some(path) = &cli.inject_file {
         if !path.is_file() {
             bail!("--inject-file is not a readable file: {}", path.display());
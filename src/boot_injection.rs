@@ -1,4 +1,22 @@
-use anyhow::{anyhow, Context, Result};
+//! Boot injection: hand a `KEY=VALUE` payload to the guest before userspace.
+//!
+//! The payload reaches the guest two ways:
+//!
+//! - As the fw_cfg entry [`FW_CFG_NAME`]. With the `qemu_fw_cfg` module loaded,
+//!   the initramfs reads it from
+//!   `/sys/firmware/qemu_fw_cfg/by_name/opt/levitate/boot-injection/raw`.
+//! - As `boot-injection.env` on an extra CD-ROM labelled `LEVITATE_INJECT`,
+//!   for guests whose kernel lacks fw_cfg support.
+//!
+//! The payload is shell-sourceable: one `KEY=VALUE` per line.
+//!
+//! Kernel arguments (the presets' `quiet`, `loglevel=` and so on) are not part
+//! of the payload, since they must be in place before the kernel starts.
+//! [`BootInjection::apply`] passes them to QEMU's `-append`, which only a
+//! direct kernel boot honours; applying them to a UEFI boot is an error.
+
+use crate::qemu::QemuBuilder;
+use anyhow::{anyhow, bail, Context, Result};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -9,11 +27,174 @@ pub const FW_CFG_NAME: &str = "opt/levitate/boot-injection";
 const ENV_INJECT_FILE: &str = "LEVITATE_BOOT_INJECTION_FILE";
 const ENV_INJECT_KV: &str = "LEVITATE_BOOT_INJECTION_KV";
 
+/// Env var holding extra kernel command line arguments (`--kernel-append`).
+pub const ENV_KERNEL_APPEND: &str = "LEVITATE_KERNEL_APPEND";
+
 #[derive(Debug, Clone)]
 pub struct BootInjection {
     pub fw_cfg_name: String,
     pub payload_file: PathBuf,
     pub media_iso_file: Option<PathBuf>,
+    /// Kernel command line arguments, applied through `-append`.
+    pub kernel_args: Vec<String>,
+}
+
+impl BootInjection {
+    /// Start an empty injection.
+    pub fn builder() -> BootInjectionBuilder {
+        BootInjectionBuilder::default()
+    }
+
+    /// Maximum kernel and init logging, for diagnosing a failing boot.
+    pub fn debug_verbose() -> BootInjectionBuilder {
+        Self::builder()
+            .kernel_arg("debug")
+            .kernel_arg("loglevel=7")
+            .kernel_arg("systemd.log_level=debug")
+    }
+
+    /// Minimal console output, for boot timing runs.
+    pub fn quiet() -> BootInjectionBuilder {
+        Self::builder().kernel_arg("quiet").kernel_arg("loglevel=3")
+    }
+
+    /// Boot straight into the emergency shell instead of the default target.
+    pub fn emergency_shell() -> BootInjectionBuilder {
+        Self::builder().kernel_arg("emergency")
+    }
+
+    /// Attach this injection to a QEMU builder (fw_cfg entry + media CD-ROM),
+    /// appending its kernel args to the `-append` line.
+    ///
+    /// Fails if the builder cannot apply kernel args, see
+    /// [`require_kernel_cmdline_applied`].
+    pub fn apply(self, builder: QemuBuilder) -> Result<QemuBuilder> {
+        let mut configured = builder.fw_cfg_file(&self.fw_cfg_name, self.payload_file);
+        if let Some(media_iso_file) = self.media_iso_file {
+            configured = configured.extra_cdrom(media_iso_file);
+        }
+        if !self.kernel_args.is_empty() {
+            configured = configured.append_kernel_cmdline(&self.kernel_args.join(" "));
+        }
+        require_kernel_cmdline_applied(&configured)?;
+        Ok(configured)
+    }
+}
+
+/// Fail if `builder` holds kernel args QEMU cannot pass to the guest.
+///
+/// UEFI ISO and disk boots take their command line from the boot entry, and
+/// no guest reads extra arguments from elsewhere, so they would be dropped.
+pub fn require_kernel_cmdline_applied(builder: &QemuBuilder) -> Result<()> {
+    let pending = builder.pending_kernel_cmdline();
+    if !pending.is_empty() {
+        bail!(
+            "kernel args '{}' need a direct kernel boot (-kernel); this VM boots through UEFI, \
+which takes its command line from the boot entry",
+            pending.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Programmatic boot injection, e.g. `BootInjection::quiet().set("FOO", "1").build()?`.
+#[derive(Debug, Clone, Default)]
+pub struct BootInjectionBuilder {
+    entries: Vec<(String, String)>,
+    kernel_args: Vec<String>,
+}
+
+impl BootInjectionBuilder {
+    /// Add a payload entry.
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        self.entries.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Append one argument to the kernel command line.
+    pub fn kernel_arg(mut self, arg: &str) -> Self {
+        self.kernel_args.push(arg.to_string());
+        self
+    }
+
+    /// Reject payloads the guest would misread or that contradict themselves.
+    ///
+    /// Checks key syntax, single-line values, duplicate keys with different
+    /// values, `quiet` combined with `debug`, and conflicting `loglevel=`.
+    pub fn validate(&self) -> Result<()> {
+        if self.entries.is_empty() && self.kernel_args.is_empty() {
+            bail!("boot injection is empty");
+        }
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            let valid_key = key
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key {
+                bail!(
+                    "invalid boot injection key '{}' (expected [A-Za-z_][A-Za-z0-9_]*)",
+                    key
+                );
+            }
+            if value.contains('\n') {
+                bail!("boot injection value for '{}' must be a single line", key);
+            }
+            if let Some((_, other)) = self.entries[..i].iter().find(|(k, _)| k == key) {
+                if other != value {
+                    bail!(
+                        "conflicting boot injection values for '{}': '{}' vs '{}'",
+                        key,
+                        other,
+                        value
+                    );
+                }
+            }
+        }
+
+        let has = |arg: &str| self.kernel_args.iter().any(|a| a == arg);
+        if has("quiet") && has("debug") {
+            bail!("conflicting kernel args: 'quiet' and 'debug'");
+        }
+        let mut loglevels: Vec<&str> = self
+            .kernel_args
+            .iter()
+            .filter_map(|a| a.strip_prefix("loglevel="))
+            .collect();
+        loglevels.sort_unstable();
+        loglevels.dedup();
+        if loglevels.len() > 1 {
+            bail!(
+                "conflicting kernel args: loglevel={}",
+                loglevels.join(" vs loglevel=")
+            );
+        }
+        Ok(())
+    }
+
+    /// Payload lines in the order they will be written.
+    fn payload_entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            if !entries.contains(entry) {
+                entries.push(entry.clone());
+            }
+        }
+        entries
+    }
+
+    /// Validate, then write the payload file and its media ISO.
+    pub fn build(self) -> Result<BootInjection> {
+        self.validate()?;
+        let payload = write_env_payload_file(&self.payload_entries())?;
+        let media_iso = create_boot_injection_iso(&payload)?;
+        Ok(BootInjection {
+            fw_cfg_name: FW_CFG_NAME.to_string(),
+            payload_file: payload,
+            media_iso_file: Some(media_iso),
+            kernel_args: self.kernel_args,
+        })
+    }
 }
 
 /// Parse a boot injection spec from environment variables.
@@ -37,6 +218,7 @@ pub fn boot_injection_from_env() -> Result<Option<BootInjection>> {
             fw_cfg_name: FW_CFG_NAME.to_string(),
             payload_file: payload,
             media_iso_file: Some(media_iso),
+            kernel_args: Vec::new(),
         }));
     }
 
@@ -45,14 +227,14 @@ pub fn boot_injection_from_env() -> Result<Option<BootInjection>> {
         _ => return Ok(None),
     };
 
-    let entries = parse_kv_csv(&raw)?;
-    let payload = write_env_payload_file(&entries)?;
-    let media_iso = create_boot_injection_iso(&payload)?;
-    Ok(Some(BootInjection {
-        fw_cfg_name: FW_CFG_NAME.to_string(),
-        payload_file: payload,
-        media_iso_file: Some(media_iso),
-    }))
+    parse_kv_csv(&raw)?
+        .iter()
+        .fold(BootInjection::builder(), |builder, (k, v)| {
+            builder.set(k, v)
+        })
+        .build()
+        .with_context(|| format!("invalid {}", ENV_INJECT_KV))
+        .map(Some)
 }
 
 fn parse_kv_csv(raw: &str) -> Result<Vec<(String, String)>> {
@@ -170,4 +352,56 @@ mod tests {
         assert_eq!(pairs[0], ("A".to_string(), "1".to_string()));
         assert_eq!(pairs[1], ("B".to_string(), "two words".to_string()));
     }
+
+    #[test]
+    fn presets_validate_and_conflicts_are_rejected() {
+        BootInjection::debug_verbose()
+            .validate()
+            .expect("debug preset");
+        BootInjection::quiet().validate().expect("quiet preset");
+        BootInjection::emergency_shell()
+            .set("FOO", "1")
+            .validate()
+            .expect("emergency preset");
+
+        let mixed = BootInjection::quiet().kernel_arg("debug");
+        assert!(mixed.validate().unwrap_err().to_string().contains("quiet"));
+        assert!(BootInjection::builder()
+            .set("A", "1")
+            .set("A", "2")
+            .validate()
+            .is_err());
+        assert!(BootInjection::builder()
+            .set("1BAD", "x")
+            .validate()
+            .is_err());
+        assert!(BootInjection::builder().validate().is_err());
+    }
+
+    #[test]
+    fn kernel_args_go_to_append_not_the_payload() {
+        let builder = BootInjection::quiet().set("FOO", "bar").set("FOO", "bar");
+        assert_eq!(
+            builder.payload_entries(),
+            vec![("FOO".to_string(), "bar".to_string())]
+        );
+
+        let injection = BootInjection {
+            fw_cfg_name: FW_CFG_NAME.to_string(),
+            payload_file: PathBuf::from("/tmp/payload.env"),
+            media_iso_file: None,
+            kernel_args: vec!["quiet".to_string(), "loglevel=3".to_string()],
+        };
+        let direct = injection
+            .clone()
+            .apply(QemuBuilder::new().kernel(PathBuf::from("/tmp/vmlinuz")))
+            .expect("direct kernel boot applies kernel args");
+        let args = direct.render_args().unwrap();
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-append", "quiet loglevel=3"]));
+
+        let uefi = injection.apply(QemuBuilder::new().uefi(PathBuf::from("/tmp/OVMF_CODE.fd")));
+        assert!(uefi.unwrap_err().to_string().contains("direct kernel boot"));
+    }
 }
@@ -0,0 +1,72 @@
+//! `--color` handling shared by every binary.
+//!
+//! `colored` only looks at `NO_COLOR`/`CLICOLOR*`, not at whether stdout is
+//! a terminal, so piping a report into a log file keeps the ANSI codes.
+//! [`apply`] settles the choice once at startup.
+
+use anyhow::{bail, Result};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+
+/// When to colour terminal output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colour when stdout is a terminal and `NO_COLOR` is unset or empty.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => bail!(
+                "unknown color mode '{}' (expected auto, always, or never)",
+                other
+            ),
+        }
+    }
+}
+
+/// Force `colored` on or off for the rest of the process.
+pub fn apply(choice: ColorChoice) {
+    let enabled = should_colorize(
+        choice,
+        std::env::var_os("NO_COLOR"),
+        std::io::stdout().is_terminal(),
+    );
+    colored::control::set_override(enabled);
+}
+
+fn should_colorize(choice: ColorChoice, no_color: Option<OsString>, stdout_tty: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        // https://no-color.org: any non-empty value disables colour.
+        ColorChoice::Auto => stdout_tty && no_color.is_none_or(|value| value.is_empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_needs_a_terminal_and_no_no_color() {
+        use ColorChoice::*;
+        assert!(should_colorize(Auto, None, true));
+        assert!(!should_colorize(Auto, None, false));
+        assert!(!should_colorize(Auto, Some("1".into()), true));
+        assert!(should_colorize(Auto, Some("".into()), true));
+        assert!(should_colorize(Always, Some("1".into()), false));
+        assert!(!should_colorize(Never, None, true));
+        assert_eq!("never".parse::<ColorChoice>().unwrap(), Never);
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Structured failure diagnostics collected from a running guest.
+//!
+//! Failure paths used to build ad-hoc text dumps inline. This module runs the
+//! same kind of probes through any [`Executor`] and returns a
+//! [`DiagnosticReport`], which renders to the familiar text form and also
+//! serializes for machine-readable reports.
+
+use crate::distro::DistroContext;
+use crate::executor::Executor;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Timeout for a single diagnostic probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Timeout for a full boot journal, which is slow to drain over serial.
+const JOURNAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// systemd unit suffixes recognised in `systemctl --failed` output.
+const UNIT_SUFFIXES: &[&str] = &[
+    ".service",
+    ".socket",
+    ".mount",
+    ".target",
+    ".timer",
+    ".path",
+    ".swap",
+    ".device",
+    ".scope",
+    ".slice",
+    ".automount",
+];
+
+/// Output of one diagnostic command.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandCapture {
+    pub title: String,
+    pub command: String,
+    pub output: String,
+    /// Set when the command could not be run at all.
+    pub error: Option<String>,
+}
+
+/// Whether a file related to a failed service exists in the guest.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCheck {
+    pub path: String,
+    pub exists: bool,
+}
+
+/// Everything collected for one failed service.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceDiagnostics {
+    pub service: String,
+    pub status: CommandCapture,
+    pub journal_tail: CommandCapture,
+    pub related_files: Vec<FileCheck>,
+}
+
+/// Structured diagnostics for a failure.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiagnosticReport {
+    /// Failed unit/service names, as reported or as discovered in the guest.
+    pub failed_units: Vec<String>,
+    pub services: Vec<ServiceDiagnostics>,
+    /// Free-form probes (see [`collect_checks`]).
+    pub checks: Vec<CommandCapture>,
+}
+
+impl DiagnosticReport {
+    /// Text rendering for error messages, starting with `header`.
+    pub fn render(&self, header: &str) -> String {
+        let mut report = format!("{}\n", header);
+        for check in &self.checks {
+            render_capture(&mut report, check);
+        }
+        if !self.failed_units.is_empty() {
+            report.push_str(&format!(
+                "\nFailed services: {}\n",
+                self.failed_units.join(", ")
+            ));
+        }
+        for service in &self.services {
+            render_capture(&mut report, &service.status);
+            render_capture(&mut report, &service.journal_tail);
+            for file in &service.related_files {
+                let state = if file.exists { "present" } else { "MISSING" };
+                report.push_str(&format!("{}: {}\n", file.path, state));
+            }
+        }
+        report
+    }
+}
+
+fn render_capture(report: &mut String, capture: &CommandCapture) {
+    report.push_str(&format!(
+        "\n--- {} ---\n$ {}\n",
+        capture.title, capture.command
+    ));
+    if let Some(err) = &capture.error {
+        report.push_str(&format!("(failed to collect: {})\n", err));
+    } else if capture.output.is_empty() {
+        report.push_str("(no output)\n");
+    } else {
+        report.push_str(&capture.output);
+        report.push('\n');
+    }
+}
+
+/// Run one probe, capturing its trimmed output or the error.
+pub fn capture(executor: &mut dyn Executor, title: &str, command: &str) -> CommandCapture {
+    capture_within(executor, title, command, PROBE_TIMEOUT)
+}
+
+fn capture_within(
+    executor: &mut dyn Executor,
+    title: &str,
+    command: &str,
+    timeout: Duration,
+) -> CommandCapture {
+    let (output, error) = match executor.exec(command, timeout) {
+        Ok(result) => (result.output.trim().to_string(), None),
+        Err(err) => (String::new(), Some(format!("{:#}", err))),
+    };
+    CommandCapture {
+        title: title.to_string(),
+        command: command.to_string(),
+        output,
+        error,
+    }
+}
+
+/// Run a list of `(title, command)` probes.
+pub fn collect_checks(executor: &mut dyn Executor, checks: &[(&str, &str)]) -> DiagnosticReport {
+    DiagnosticReport {
+        checks: checks
+            .iter()
+            .map(|(title, command)| capture(executor, title, command))
+            .collect(),
+        ..DiagnosticReport::default()
+    }
+}
+
+/// Collect status, log tail and related file checks for failed services.
+///
+/// When `failed` is empty (e.g. the boot wait did not track failures), the
+/// guest is asked via `ctx.list_failed_services_cmd()`.
+pub fn collect_service_failures(
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    failed: &[String],
+) -> DiagnosticReport {
+    let mut report = DiagnosticReport::default();
+    let listing = capture(executor, "Failed services", &ctx.list_failed_services_cmd());
+    report.failed_units = if failed.is_empty() {
+        parse_failed_units(&listing.output)
+    } else {
+        failed.to_vec()
+    };
+    report.checks.push(listing);
+
+    for service in &report.failed_units {
+        let related_files = related_paths(service)
+            .iter()
+            .map(|path| FileCheck {
+                path: path.to_string(),
+                exists: executor
+                    .exec(&format!("test -e {}", path), PROBE_TIMEOUT)
+                    .is_ok_and(|result| result.success()),
+            })
+            .collect();
+        report.services.push(ServiceDiagnostics {
+            service: service.clone(),
+            status: capture(
+                executor,
+                &format!("{} status", service),
+                &format!("{} 2>&1 || true", ctx.check_service_status_cmd(service)),
+            ),
+            journal_tail: capture(
+                executor,
+                &format!("{} log", service),
+                &format!("{} 2>&1 || true", ctx.service_log_cmd(service)),
+            ),
+            related_files,
+        });
+    }
+    report
+}
+
+/// Capture the current boot's system log, optionally just the last `lines`
+/// lines, with the distro's `boot_log_cmd`.
+pub fn collect_journal(
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    lines: Option<usize>,
+) -> CommandCapture {
+    capture_within(
+        executor,
+        "Boot journal",
+        &format!("{} 2>&1 || true", ctx.boot_log_cmd(lines)),
+        JOURNAL_TIMEOUT,
+    )
+}
+
+/// Extract failed unit names from `systemctl --failed` or `rc-status` output.
+pub fn parse_failed_units(output: &str) -> Vec<String> {
+    let mut units = Vec::new();
+    for line in output.lines() {
+        let line = line.trim().trim_start_matches(['●', '*']).trim();
+        let mut tokens = line.split_whitespace();
+        let Some(first) = tokens.next() else {
+            continue;
+        };
+        let is_unit = UNIT_SUFFIXES.iter().any(|suffix| first.ends_with(suffix));
+        // OpenRC: bare names from `rc-status --crashed`, or `name [ crashed ]`.
+        let is_openrc = (tokens.next().is_none() || line.contains('['))
+            && first
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+            && first.chars().any(|c| c.is_ascii_lowercase());
+        if (is_unit || is_openrc) && !units.iter().any(|u| u == first) {
+            units.push(first.to_string());
+        }
+    }
+    units
+}
+
+/// Files whose absence commonly explains a service failure.
+fn related_paths(service: &str) -> &'static [&'static str] {
+    if service.contains("ssh") {
+        &[
+            "/run/sshd",
+            "/etc/ssh/sshd_config",
+            "/etc/ssh/ssh_host_ed25519_key",
+            "/etc/ssh/ssh_host_rsa_key",
+        ]
+    } else if service.contains("tmpfiles") {
+        &["/etc/tmpfiles.d", "/usr/lib/tmpfiles.d"]
+    } else if service.contains("network") || service.contains("dhcpcd") {
+        &["/etc/resolv.conf"]
+    } else {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FakeExecutor, Reply};
+
+    const SYSTEMCTL_FAILED: &str = "  UNIT                 LOAD   ACTIVE SUB    DESCRIPTION
+● sshd.service         loaded failed failed OpenSSH Daemon
+● systemd-tmpfiles-setup.service loaded failed failed Create Volatile Files
+
+LOAD   = Reflects whether the unit definition was properly loaded.
+2 loaded units listed.";
+
+    #[test]
+    fn parses_systemd_and_openrc_failed_units() {
+        assert_eq!(
+            parse_failed_units(SYSTEMCTL_FAILED),
+            ["sshd.service", "systemd-tmpfiles-setup.service"]
+        );
+        assert_eq!(
+            parse_failed_units(" sshd     [ crashed ]\n dhcpcd\nRunlevel: default\n"),
+            ["sshd", "dhcpcd"]
+        );
+        assert!(parse_failed_units("0 loaded units listed.").is_empty());
+    }
+
+    #[test]
+    fn collects_per_service_status_log_and_files() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let mut executor = FakeExecutor::new()
+            .on("systemctl --failed", SYSTEMCTL_FAILED)
+            .on("systemctl is-active sshd.service", "failed")
+            .on("journalctl", "sshd: no hostkeys available -- exiting.")
+            .on("test -e /etc/ssh/sshd_config", "")
+            .otherwise(Reply::Exit(1, String::new()));
+
+        let report = collect_service_failures(&mut executor, &*ctx, &[]);
+
+        assert_eq!(report.failed_units.len(), 2);
+        let sshd = &report.services[0];
+        assert_eq!(sshd.status.output, "failed");
+        assert!(sshd.journal_tail.output.contains("no hostkeys"));
+        let missing: Vec<&str> = sshd
+            .related_files
+            .iter()
+            .filter(|f| !f.exists)
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                "/run/sshd",
+                "/etc/ssh/ssh_host_ed25519_key",
+                "/etc/ssh/ssh_host_rsa_key"
+            ]
+        );
+        assert!(report
+            .render("diag")
+            .contains("/etc/ssh/ssh_host_rsa_key: MISSING"));
+    }
+
+    #[test]
+    fn journal_is_bounded_only_when_asked() {
+        let systemd = crate::distro::context_for_distro("levitate").unwrap();
+        let openrc = crate::distro::context_for_distro("acorn").unwrap();
+        assert_eq!(systemd.boot_log_cmd(None), "journalctl -b --no-pager");
+        let mut executor = FakeExecutor::new()
+            .on("journalctl -b", "-- Boot 1f2e --\nkernel: Linux version")
+            .on("{ cat /var/log/rc.log", "* Starting sshd ... [ ok ]")
+            .otherwise(Reply::Exit(1, String::new()));
+
+        let journal = collect_journal(&mut executor, systemd.as_ref(), Some(200));
+        assert!(journal.output.contains("Linux version"));
+        assert!(journal.error.is_none());
+        let rc_log = collect_journal(&mut executor, openrc.as_ref(), Some(50));
+        assert!(rc_log.output.contains("Starting sshd"));
+
+        assert_eq!(
+            executor.commands,
+            [
+                "journalctl -b --no-pager -n 200 2>&1 || true",
+                "{ cat /var/log/rc.log /var/log/messages 2>/dev/null; dmesg; } | tail -n 50 2>&1 || true"
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Init system command generation shared by every distro context.
+//!
+//! Service, target and PID 1 commands depend only on the init system, so they
+//! live here once instead of in each `DistroContext`. A distro returns its
+//! [`InitSystem`] and inherits the commands through the trait's defaults,
+//! overriding only what differs.
+
+use anyhow::{bail, Result};
+
+/// Init systems the install tests know how to drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitSystem {
+    Systemd,
+    OpenRc,
+    /// Void-style runit: services in `/etc/sv`, enabled via the default runsvdir.
+    Runit,
+    /// s6-rc with s6-linux-init: services in `/etc/s6/sv`, `default` bundle.
+    S6,
+}
+
+impl InitSystem {
+    /// Display name (e.g. "systemd", "OpenRC").
+    pub fn name(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "systemd",
+            InitSystem::OpenRc => "OpenRC",
+            InitSystem::Runit => "runit",
+            InitSystem::S6 => "s6",
+        }
+    }
+
+    /// What "booted" means, for summaries.
+    pub fn boot_target_name(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "multi-user.target",
+            InitSystem::OpenRc => "default runlevel",
+            InitSystem::Runit => "default runsvdir",
+            InitSystem::S6 => "default bundle",
+        }
+    }
+
+    /// Command enabling `service` in `target` (ignored by systemd, which
+    /// uses the unit's `[Install]` section).
+    pub fn enable_service_cmd(self, service: &str, target: &str) -> String {
+        match self {
+            InitSystem::Systemd => format!("systemctl enable {}", service),
+            InitSystem::OpenRc => format!("rc-update add {} {}", service, target),
+            // /var/service points into /run, which only exists once booted.
+            InitSystem::Runit => format!(
+                "ln -sf /etc/sv/{} /etc/runit/runsvdir/default/{}",
+                service, service
+            ),
+            InitSystem::S6 => format!("s6-service add {} {}", target, service),
+        }
+    }
+
+    /// Command printing `service` if its definition is installed.
+    pub fn check_service_exists_cmd(self, service: &str) -> String {
+        match self {
+            InitSystem::Systemd => format!(
+                "test -f /usr/lib/systemd/system/{}.service && echo {}",
+                service, service
+            ),
+            InitSystem::OpenRc => format!("test -f /etc/init.d/{} && echo {}", service, service),
+            InitSystem::Runit => format!("test -d /etc/sv/{} && echo {}", service, service),
+            InitSystem::S6 => format!("test -d /etc/s6/sv/{} && echo {}", service, service),
+        }
+    }
+
+    /// Command reporting whether `service` is running.
+    pub fn check_service_status_cmd(self, service: &str) -> String {
+        match self {
+            InitSystem::Systemd => format!("systemctl is-active {}", service),
+            InitSystem::OpenRc => format!("rc-service {} status", service),
+            InitSystem::Runit => format!("sv status {}", service),
+            InitSystem::S6 => format!("s6-svstat /run/service/{}", service),
+        }
+    }
+
+    /// Command listing failed (or stopped) services.
+    pub fn list_failed_services_cmd(self) -> String {
+        match self {
+            InitSystem::Systemd => "systemctl --failed --no-pager".to_string(),
+            InitSystem::OpenRc => {
+                "rc-status --crashed 2>/dev/null || rc-status -a | grep -E 'stopped|crashed'"
+                    .to_string()
+            }
+            InitSystem::Runit => {
+                "sv status /var/service/* 2>/dev/null | grep -v '^run:'".to_string()
+            }
+            InitSystem::S6 => "s6-rc -da list 2>/dev/null".to_string(),
+        }
+    }
+
+    /// Command printing the recent log of one service.
+    pub fn service_log_cmd(self, service: &str) -> String {
+        match self {
+            InitSystem::Systemd => format!("journalctl -b -u {} --no-pager -n 50", service),
+            InitSystem::OpenRc => format!(
+                "grep -i {} /var/log/messages /var/log/rc.log 2>/dev/null | tail -n 50",
+                service
+            ),
+            InitSystem::Runit | InitSystem::S6 => {
+                format!("tail -n 50 /var/log/{}/current 2>/dev/null", service)
+            }
+        }
+    }
+
+    /// Command printing the current boot's system log, optionally just the
+    /// last `lines` lines.
+    pub fn boot_log_cmd(self, lines: Option<usize>) -> String {
+        let log = match self {
+            InitSystem::Systemd => "journalctl -b --no-pager",
+            // OpenRC logs service starts to rc.log; everything else goes
+            // through syslog, if one is running.
+            InitSystem::OpenRc => "cat /var/log/rc.log /var/log/messages 2>/dev/null; dmesg",
+            // No central log: services log under /var/log/<service>/.
+            InitSystem::Runit | InitSystem::S6 => "dmesg",
+        };
+        match (self, lines) {
+            (InitSystem::Systemd, Some(lines)) => format!("{} -n {}", log, lines),
+            (_, Some(lines)) => format!("{{ {}; }} | tail -n {}", log, lines),
+            (_, None) => log.to_string(),
+        }
+    }
+
+    /// Command enabling a getty on the serial console.
+    pub fn enable_serial_getty_cmd(self) -> String {
+        match self {
+            InitSystem::Systemd => "systemctl enable serial-getty@ttyS0.service".to_string(),
+            InitSystem::OpenRc => "grep -q 'ttyS0' /etc/inittab || echo 'ttyS0::respawn:/sbin/getty -L 115200 ttyS0 vt100' >> /etc/inittab".to_string(),
+            InitSystem::Runit => {
+                "ln -sf /etc/sv/agetty-ttyS0 /etc/runit/runsvdir/default/agetty-ttyS0".to_string()
+            }
+            InitSystem::S6 => "s6-service add default agetty-ttyS0".to_string(),
+        }
+    }
+
+    /// Name of PID 1 once booted.
+    pub fn expected_pid1_name(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "systemd",
+            InitSystem::OpenRc => "init",
+            InitSystem::Runit => "runit",
+            InitSystem::S6 => "s6-svscan",
+        }
+    }
+
+    /// Command printing [`target_reached_expected`](Self::target_reached_expected)
+    /// once the boot target is reached.
+    pub fn check_target_reached_cmd(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "systemctl is-active multi-user.target",
+            InitSystem::OpenRc => {
+                "rc-status default 2>/dev/null | grep -q started && echo 'default_reached'"
+            }
+            InitSystem::Runit => {
+                "sv check /var/service/* >/dev/null 2>&1 && echo 'default_reached'"
+            }
+            InitSystem::S6 => "test -z \"$(s6-rc -da list 2>/dev/null)\" && echo 'default_reached'",
+        }
+    }
+
+    /// Output of `check_target_reached_cmd` on success.
+    pub fn target_reached_expected(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "active",
+            InitSystem::OpenRc | InitSystem::Runit | InitSystem::S6 => "default_reached",
+        }
+    }
+
+    /// Command printing the number of failed services.
+    pub fn count_failed_services_cmd(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => "systemctl --failed --no-legend | wc -l",
+            InitSystem::OpenRc => "rc-status --crashed 2>/dev/null | wc -l || echo 0",
+            InitSystem::Runit => "sv status /var/service/* 2>/dev/null | grep -vc '^run:'",
+            InitSystem::S6 => "s6-rc -da list 2>/dev/null | wc -l",
+        }
+    }
+
+    /// Command printing `active` when the network service is up.
+    pub fn check_network_service_cmd(self) -> &'static str {
+        match self {
+            InitSystem::Systemd => {
+                "systemctl is-active systemd-networkd || systemctl is-active NetworkManager"
+            }
+            InitSystem::OpenRc => {
+                "rc-service networking status 2>/dev/null | grep -q started && echo 'active'"
+            }
+            InitSystem::Runit => "sv status dhcpcd 2>/dev/null | grep -q '^run:' && echo 'active'",
+            InitSystem::S6 => {
+                "s6-svstat /run/service/dhcpcd 2>/dev/null | grep -q '^up' && echo 'active'"
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for InitSystem {
+    type Err = anyhow::Error;
+
+    /// Parse a display name, case-insensitively.
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "systemd" => Ok(InitSystem::Systemd),
+            "openrc" => Ok(InitSystem::OpenRc),
+            "runit" => Ok(InitSystem::Runit),
+            "s6" => Ok(InitSystem::S6),
+            other => bail!(
+                "unsupported init system '{}' (expected systemd, OpenRC, runit, or s6)",
+                other
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip_through_from_str() {
+        for init in [
+            InitSystem::Systemd,
+            InitSystem::OpenRc,
+            InitSystem::Runit,
+            InitSystem::S6,
+        ] {
+            assert_eq!(init.name().parse::<InitSystem>().unwrap(), init);
+        }
+        assert!("dinit".parse::<InitSystem>().is_err());
+    }
+
+    #[test]
+    fn service_commands_follow_the_init_system() {
+        assert_eq!(
+            InitSystem::OpenRc.enable_service_cmd("sshd", "default"),
+            "rc-update add sshd default"
+        );
+        assert_eq!(
+            InitSystem::Runit.enable_service_cmd("sshd", "default"),
+            "ln -sf /etc/sv/sshd /etc/runit/runsvdir/default/sshd"
+        );
+        assert_eq!(
+            InitSystem::Systemd.enable_service_cmd("sshd", "multi-user.target"),
+            "systemctl enable sshd"
+        );
+    }
+}
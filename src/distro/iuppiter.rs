@@ -8,7 +8,7 @@
 //! - Serial console only (headless appliance)
 
 use super::openrc_base::OpenRcBase;
-use super::DistroContext;
+use super::{DistroContext, InitSystem};
 
 /// IuppiterOS context for OpenRC-based testing on headless appliance.
 pub struct IuppiterContext;
@@ -24,6 +24,10 @@ impl DistroContext for IuppiterContext {
         "iuppiter"
     }
 
+    fn init_system(&self) -> InitSystem {
+        InitSystem::OpenRc
+    }
+
     fn live_boot_success_patterns(&self) -> &[&str] {
         &["___SHELL_READY___"]
     }
@@ -45,20 +49,12 @@ impl DistroContext for IuppiterContext {
         BASE.live_boot_stall_timeout_secs()
     }
 
-    fn enable_service_cmd(&self, service: &str, runlevel: &str) -> String {
-        BASE.enable_service_cmd(service, runlevel)
-    }
-
-    fn check_service_exists_cmd(&self, service: &str) -> String {
-        BASE.check_service_exists_cmd(service)
-    }
-
-    fn check_service_status_cmd(&self, service: &str) -> String {
-        BASE.check_service_status_cmd(service)
+    fn live_boot_timeout_secs(&self) -> u64 {
+        BASE.live_boot_timeout_secs()
     }
 
-    fn list_failed_services_cmd(&self) -> String {
-        BASE.list_failed_services_cmd()
+    fn installed_boot_timeout_secs(&self) -> u64 {
+        BASE.installed_boot_timeout_secs()
     }
 
     fn enabled_services(&self) -> Vec<(&str, &str, bool)> {
@@ -70,28 +66,8 @@ impl DistroContext for IuppiterContext {
         ]
     }
 
-    fn enable_serial_getty_cmd(&self) -> String {
-        BASE.enable_serial_getty_cmd()
-    }
-
-    fn expected_pid1_name(&self) -> &str {
-        BASE.expected_pid1_name()
-    }
-
-    fn check_target_reached_cmd(&self) -> &str {
-        BASE.check_target_reached_cmd()
-    }
-
-    fn target_reached_expected(&self) -> &str {
-        BASE.target_reached_expected()
-    }
-
-    fn count_failed_services_cmd(&self) -> &str {
-        BASE.count_failed_services_cmd()
-    }
-
-    fn check_network_service_cmd(&self) -> &str {
-        BASE.check_network_service_cmd()
+    fn essential_commands(&self) -> &[(&str, &str)] {
+        BASE.essential_commands()
     }
 
     fn install_bootloader_cmd(&self) -> &str {
@@ -106,6 +82,14 @@ impl DistroContext for IuppiterContext {
         BASE.chroot_shell()
     }
 
+    fn package_mirror_cmd(&self, mirror_url: &str) -> Option<String> {
+        BASE.package_mirror_cmd(mirror_url)
+    }
+
+    fn install_packages_cmd(&self, packages: &[&str]) -> Option<String> {
+        BASE.install_packages_cmd(packages)
+    }
+
     fn default_hostname(&self) -> &str {
         "iuppiter"
     }
@@ -120,14 +104,6 @@ impl DistroContext for IuppiterContext {
         )
     }
 
-    fn init_system_name(&self) -> &str {
-        BASE.init_system_name()
-    }
-
-    fn boot_target_name(&self) -> &str {
-        BASE.boot_target_name()
-    }
-
     fn live_tools(&self) -> &[&str] {
         &[
             // === Core Installation Tools ===
@@ -6,7 +6,7 @@
 //! - glibc + GNU coreutils
 //! - bash shell
 
-use super::DistroContext;
+use super::{DistroContext, InitSystem};
 /// LevitateOS context for systemd-based testing.
 pub struct LevitateContext;
 
@@ -23,6 +23,10 @@ impl DistroContext for LevitateContext {
         "levitate"
     }
 
+    fn init_system(&self) -> InitSystem {
+        InitSystem::Systemd
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // Boot Detection Patterns
     // ═══════════════════════════════════════════════════════════════════════════
@@ -122,26 +126,6 @@ impl DistroContext for LevitateContext {
     // Service Management
     // ═══════════════════════════════════════════════════════════════════════════
 
-    fn enable_service_cmd(&self, service: &str, _target: &str) -> String {
-        // systemd ignores target for enable (uses [Install] section)
-        format!("systemctl enable {}", service)
-    }
-
-    fn check_service_exists_cmd(&self, service: &str) -> String {
-        format!(
-            "test -f /usr/lib/systemd/system/{}.service && echo {}",
-            service, service
-        )
-    }
-
-    fn check_service_status_cmd(&self, service: &str) -> String {
-        format!("systemctl is-active {}", service)
-    }
-
-    fn list_failed_services_cmd(&self) -> String {
-        "systemctl --failed --no-pager".to_string()
-    }
-
     fn enabled_services(&self) -> Vec<(&str, &str, bool)> {
         // (service_name, target, is_required)
         // Note: Rocky 10 uses NetworkManager (not systemd-networkd) and chronyd
@@ -152,34 +136,6 @@ impl DistroContext for LevitateContext {
         ]
     }
 
-    fn enable_serial_getty_cmd(&self) -> String {
-        "systemctl enable serial-getty@ttyS0.service".to_string()
-    }
-
-    // ═══════════════════════════════════════════════════════════════════════════
-    // Init Verification (Phase 6)
-    // ═══════════════════════════════════════════════════════════════════════════
-
-    fn expected_pid1_name(&self) -> &str {
-        "systemd"
-    }
-
-    fn check_target_reached_cmd(&self) -> &str {
-        "systemctl is-active multi-user.target"
-    }
-
-    fn target_reached_expected(&self) -> &str {
-        "active"
-    }
-
-    fn count_failed_services_cmd(&self) -> &str {
-        "systemctl --failed --no-legend | wc -l"
-    }
-
-    fn check_network_service_cmd(&self) -> &str {
-        "systemctl is-active systemd-networkd || systemctl is-active NetworkManager"
-    }
-
     // ═══════════════════════════════════════════════════════════════════════════
     // Bootloader
     // ═══════════════════════════════════════════════════════════════════════════
@@ -203,6 +159,13 @@ impl DistroContext for LevitateContext {
         "/bin/bash"
     }
 
+    fn install_packages_cmd(&self, packages: &[&str]) -> Option<String> {
+        Some(format!(
+            "dnf install -y {}",
+            super::quoted_packages(packages)
+        ))
+    }
+
     fn default_hostname(&self) -> &str {
         "levitateos"
     }
@@ -219,14 +182,6 @@ impl DistroContext for LevitateContext {
     // Summary Display
     // ═══════════════════════════════════════════════════════════════════════════
 
-    fn init_system_name(&self) -> &str {
-        "systemd"
-    }
-
-    fn boot_target_name(&self) -> &str {
-        "multi-user.target"
-    }
-
     fn live_tools(&self) -> &[&str] {
         &[
             // === Core Installation Tools ===
@@ -2,20 +2,52 @@
 //!
 //! The DistroContext trait enables the same test infrastructure to work with
 //! LevitateOS/RalphOS (systemd) and AcornOS/IuppiterOS (OpenRC) by abstracting
-//! init system and bootloader differences.
+//! init system and bootloader differences. Init commands come from
+//! [`InitSystem`], so a new init system is added there once, not per distro.
 
 use anyhow::{Context, Result};
 use distro_contract::{
     load_variant_contract_for_distro_from, AutomatedLoginCheckpoint, BootCheckpoint,
     InstallExperience, RuntimePolicyCheckpoint, ToolsCheckpoint,
 };
-use std::path::PathBuf;
+use recshuttle::{InstallLayout, InstallPlanSpec};
+use std::path::{Path, PathBuf};
 
 pub mod acorn;
+mod init_system;
 pub mod iuppiter;
 pub mod levitate;
 mod openrc_base;
+mod partition;
 pub mod ralph;
+mod toml_context;
+
+pub use init_system::InitSystem;
+pub use partition::{
+    default_partition_layout, device_mounted_at, partition_devices, partition_mounts,
+    sfdisk_script, validate_partition_layout, Filesystem, PartitionSpec, PartitionType,
+};
+pub use toml_context::TomlContext;
+
+/// Default for [`DistroContext::live_overlay_lowerdir`]: the writable live
+/// overlay stacked on the read-only rootfs.
+const LIVE_OVERLAY_LOWERDIR: &str = "/live-overlay:/rootfs";
+
+/// Default for [`DistroContext::essential_commands`]: a GNU userland on systemd.
+const SYSTEMD_ESSENTIAL_COMMANDS: &[(&str, &str)] = &[
+    ("ls --version", "coreutils"),
+    ("cat --version", "coreutils"),
+    ("grep --version", "grep"),
+    ("find --version", "findutils"),
+    ("tar --version", "tar"),
+    ("systemctl --version", "systemd"),
+    ("journalctl --version", "systemd"),
+    ("ip --version", "iproute2"),
+    ("bash --version", "bash"),
+];
+
+/// One labelled installer command: (description, shell command).
+pub type InstallCommand = (&'static str, String);
 
 /// Context for distro-specific test behavior.
 ///
@@ -32,6 +64,10 @@ pub trait DistroContext: Send + Sync {
     /// Short identifier (e.g., "levitate", "acorn").
     fn id(&self) -> &str;
 
+    /// Init system; the service and init verification commands below
+    /// default to its commands.
+    fn init_system(&self) -> InitSystem;
+
     // ═══════════════════════════════════════════════════════════════════════════
     // Boot Detection Patterns
     // ═══════════════════════════════════════════════════════════════════════════
@@ -61,6 +97,27 @@ pub trait DistroContext: Send + Sync {
         60
     }
 
+    /// Total time allowed for the live ISO to become ready (boot + SSH).
+    ///
+    /// Distinct from the stall window above. Scaled by
+    /// `LEVITATE_BOOT_TIMEOUT_SCALE` at the wait call sites.
+    fn live_boot_timeout_secs(&self) -> u64 {
+        300
+    }
+
+    /// Time allowed for the installed system to reach its boot success patterns.
+    fn installed_boot_timeout_secs(&self) -> u64 {
+        90
+    }
+
+    /// `lowerdir=` chain of the live ISO's root overlay mount, or `None` if
+    /// the live root is not an overlay.
+    ///
+    /// Defaults to `/live-overlay:/rootfs`.
+    fn live_overlay_lowerdir(&self) -> Option<&str> {
+        Some(LIVE_OVERLAY_LOWERDIR)
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // Service Management
     // ═══════════════════════════════════════════════════════════════════════════
@@ -69,17 +126,35 @@ pub trait DistroContext: Send + Sync {
     ///
     /// For systemd: `systemctl enable <service>`
     /// For OpenRC: `rc-update add <service> <runlevel>`
-    fn enable_service_cmd(&self, service: &str, target: &str) -> String;
+    fn enable_service_cmd(&self, service: &str, target: &str) -> String {
+        self.init_system().enable_service_cmd(service, target)
+    }
 
     /// Command to check if a service exists (unit file present).
-    fn check_service_exists_cmd(&self, service: &str) -> String;
+    fn check_service_exists_cmd(&self, service: &str) -> String {
+        self.init_system().check_service_exists_cmd(service)
+    }
 
     /// Command to check service status.
     #[allow(dead_code)]
-    fn check_service_status_cmd(&self, service: &str) -> String;
+    fn check_service_status_cmd(&self, service: &str) -> String {
+        self.init_system().check_service_status_cmd(service)
+    }
 
     /// Command to list failed services.
-    fn list_failed_services_cmd(&self) -> String;
+    fn list_failed_services_cmd(&self) -> String {
+        self.init_system().list_failed_services_cmd()
+    }
+
+    /// Command printing the recent log of one service, for failure diagnostics.
+    fn service_log_cmd(&self, service: &str) -> String {
+        self.init_system().service_log_cmd(service)
+    }
+
+    /// Command printing the current boot's system log, for failure diagnostics.
+    fn boot_log_cmd(&self, lines: Option<usize>) -> String {
+        self.init_system().boot_log_cmd(lines)
+    }
 
     /// Services that should be enabled during installation.
     ///
@@ -87,7 +162,9 @@ pub trait DistroContext: Send + Sync {
     fn enabled_services(&self) -> Vec<(&str, &str, bool)>;
 
     /// Command to enable serial console getty for testing.
-    fn enable_serial_getty_cmd(&self) -> String;
+    fn enable_serial_getty_cmd(&self) -> String {
+        self.init_system().enable_serial_getty_cmd()
+    }
 
     // ═══════════════════════════════════════════════════════════════════════════
     // Init Verification (Phase 6)
@@ -97,22 +174,40 @@ pub trait DistroContext: Send + Sync {
     ///
     /// For systemd: "systemd"
     /// For OpenRC: "init"
-    fn expected_pid1_name(&self) -> &str;
+    fn expected_pid1_name(&self) -> &str {
+        self.init_system().expected_pid1_name()
+    }
 
     /// Command to check if system reached boot target.
     ///
     /// For systemd: `systemctl is-active multi-user.target`
     /// For OpenRC: `rc-status default | grep -q started`
-    fn check_target_reached_cmd(&self) -> &str;
+    fn check_target_reached_cmd(&self) -> &str {
+        self.init_system().check_target_reached_cmd()
+    }
 
     /// Expected output indicating target reached.
-    fn target_reached_expected(&self) -> &str;
+    fn target_reached_expected(&self) -> &str {
+        self.init_system().target_reached_expected()
+    }
 
     /// Command to count failed units/services.
-    fn count_failed_services_cmd(&self) -> &str;
+    fn count_failed_services_cmd(&self) -> &str {
+        self.init_system().count_failed_services_cmd()
+    }
 
     /// Command to get network service status.
-    fn check_network_service_cmd(&self) -> &str;
+    fn check_network_service_cmd(&self) -> &str {
+        self.init_system().check_network_service_cmd()
+    }
+
+    /// Commands that must succeed on the installed system, as
+    /// (command, package providing it). Every one must pass.
+    ///
+    /// Defaults to a GNU userland with systemd tools.
+    fn essential_commands(&self) -> &[(&str, &str)] {
+        SYSTEMD_ESSENTIAL_COMMANDS
+    }
 
     // ═══════════════════════════════════════════════════════════════════════════
     // Bootloader
@@ -125,6 +220,92 @@ pub trait DistroContext: Send + Sync {
     /// EFI entry label for efibootmgr.
     fn efi_entry_label(&self) -> &str;
 
+    // ═══════════════════════════════════════════════════════════════════════════
+    // Installation
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Whether the install copies an initramfs to the ESP.
+    fn install_includes_initramfs(&self) -> bool {
+        self.init_system() != InitSystem::OpenRc
+    }
+
+    /// Command pointing the live system's package manager at `mirror_url`,
+    /// e.g. a [`crate::fixtures::serve_dir`] mirror, so install steps fetch
+    /// from it instead of the internet.
+    ///
+    /// `None` (the default) when the distro has no mirror configuration.
+    fn package_mirror_cmd(&self, _mirror_url: &str) -> Option<String> {
+        None
+    }
+
+    /// Command installing `packages` into the running live system, used by
+    /// `--extra-pkg` to add debugging tools without rebuilding the ISO.
+    ///
+    /// `None` (the default) when the harness does not know the distro's
+    /// package manager.
+    fn install_packages_cmd(&self, _packages: &[&str]) -> Option<String> {
+        None
+    }
+
+    /// Inputs for the shared recshuttle install flow.
+    fn install_plan_spec(&self) -> Result<InstallPlanSpec> {
+        Ok(self.install_plan_spec_with_password(self.default_password()?))
+    }
+
+    /// [`install_plan_spec`](Self::install_plan_spec) with the password
+    /// given, so the plan can be built without the variant contract.
+    fn install_plan_spec_with_password(&self, default_password: String) -> InstallPlanSpec {
+        InstallPlanSpec {
+            distro_id: self.id().to_string(),
+            os_name: self.name().to_string(),
+            default_hostname: self.default_hostname().to_string(),
+            default_password,
+            install_bootloader_cmd: self.install_bootloader_cmd().to_string(),
+            enable_serial_getty_cmd: self.enable_serial_getty_cmd(),
+            include_initramfs: self.install_includes_initramfs(),
+        }
+    }
+
+    /// Partitions of the install disk, consumed by the Phase 2 steps.
+    ///
+    /// Defaults to [`default_partition_layout`] (distro-spec's ESP + ext4 root).
+    fn partition_layout(&self) -> Vec<PartitionSpec> {
+        default_partition_layout()
+    }
+
+    /// Commands the remote installer runs to install onto `install_disk`.
+    ///
+    /// Builds the [`install_plan_spec`](Self::install_plan_spec) and hands
+    /// it to [`install_recipe_for`](Self::install_recipe_for).
+    fn install_recipe(
+        &self,
+        install_disk: &str,
+        layout: InstallLayout,
+    ) -> Result<Vec<InstallCommand>> {
+        self.install_recipe_for(&self.install_plan_spec()?, install_disk, layout)
+    }
+
+    /// Commands installing `plan` onto `install_disk`.
+    ///
+    /// Defaults to the shared recshuttle flow, which partitions with the
+    /// default layout only; a distro with its own `partition_layout` must
+    /// override this too. Override to give a distro its own install sequence.
+    fn install_recipe_for(
+        &self,
+        plan: &InstallPlanSpec,
+        install_disk: &str,
+        layout: InstallLayout,
+    ) -> Result<Vec<InstallCommand>> {
+        if self.partition_layout() != default_partition_layout() {
+            anyhow::bail!(
+                "'{}' has a custom partition_layout, but recshuttle's install recipe only \
+                 creates the default ESP + root layout; override install_recipe_for as well",
+                self.id()
+            );
+        }
+        Ok(recshuttle::install_commands_for(plan, install_disk, layout))
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // Paths
     // ═══════════════════════════════════════════════════════════════════════════
@@ -141,15 +322,53 @@ pub trait DistroContext: Send + Sync {
     /// Path to test instrumentation script to copy to installed system.
     fn test_instrumentation_source(&self) -> &str;
 
+    // ═══════════════════════════════════════════════════════════════════════════
+    // Credentials
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Default non-root user created during installation.
+    ///
+    /// Defaults to the canonical automated-login checkpoint of the variant contract.
+    fn default_username(&self) -> Result<String> {
+        load_installed_scenario_facts(self.id())?
+            .automated_login
+            .default_username
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "missing canonical automated-login default_username for '{}'",
+                    self.id()
+                )
+            })
+    }
+
+    /// Default password for root and the default user.
+    ///
+    /// Defaults to the canonical automated-login checkpoint of the variant contract.
+    fn default_password(&self) -> Result<String> {
+        load_installed_scenario_facts(self.id())?
+            .automated_login
+            .default_password
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "missing canonical automated-login default_password for '{}'",
+                    self.id()
+                )
+            })
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // Summary Display
     // ═══════════════════════════════════════════════════════════════════════════
 
     /// Init system name for display (e.g., "systemd", "OpenRC").
-    fn init_system_name(&self) -> &str;
+    fn init_system_name(&self) -> &str {
+        self.init_system().name()
+    }
 
     /// Boot target name for display (e.g., "multi-user.target", "default runlevel").
-    fn boot_target_name(&self) -> &str;
+    fn boot_target_name(&self) -> &str {
+        self.init_system().boot_target_name()
+    }
 
     /// Tools expected to be present in the live ISO environment.
     fn live_tools(&self) -> &[&str];
@@ -166,6 +385,13 @@ pub fn context_for_distro(id: &str) -> Option<Box<dyn DistroContext>> {
     }
 }
 
+/// Create a DistroContext from a `distro.toml` spec file.
+///
+/// Used for downstream derivatives that are not built into this crate.
+pub fn context_for_distro_from_file(path: &Path) -> Result<Box<dyn DistroContext>> {
+    Ok(Box::new(TomlContext::from_file(path)?))
+}
+
 /// Available distro IDs for CLI help.
 pub const AVAILABLE_DISTROS: &[&str] = &["levitate", "acorn", "iuppiter", "ralph"];
 
@@ -198,6 +424,46 @@ pub fn load_installed_scenario_facts(distro_id: &str) -> Result<InstalledScenari
     })
 }
 
+/// `packages` as shell words for an `install_packages_cmd`.
+fn quoted_packages(packages: &[&str]) -> String {
+    packages
+        .iter()
+        .map(|package| crate::executor::shell_quote(package))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn workspace_root() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_builtin_distro_has_an_install_recipe() {
+        for id in AVAILABLE_DISTROS {
+            let ctx = context_for_distro(id).unwrap();
+            // A stand-in password keeps the host's variant contracts out of it.
+            let plan = ctx.install_plan_spec_with_password("test-password".to_string());
+            let recipe = ctx
+                .install_recipe_for(&plan, "/dev/vda", InstallLayout::MutableSingleRoot)
+                .unwrap();
+            assert!(!recipe.is_empty(), "{} has an empty install recipe", id);
+        }
+    }
+
+    #[test]
+    fn openrc_distros_point_apk_at_the_package_mirror() {
+        for id in ["acorn", "iuppiter"] {
+            let ctx = context_for_distro(id).unwrap();
+            assert_eq!(
+                ctx.package_mirror_cmd("http://10.0.2.2:8080").as_deref(),
+                Some("printf '%s\\n' 'http://10.0.2.2:8080' > /etc/apk/repositories && apk update"),
+                "{}",
+                id
+            );
+        }
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Both distros use OpenRC + systemd-boot + musl + busybox + ash.
 //! This struct provides all identical methods; variant structs delegate here.
+//! Init commands come from [`InitSystem::OpenRc`](super::InitSystem) instead.
 
 /// Shared OpenRC methods. Compose into AcornContext/IuppiterContext.
 pub struct OpenRcBase;
@@ -11,6 +12,14 @@ impl OpenRcBase {
         180
     }
 
+    pub fn live_boot_timeout_secs(&self) -> u64 {
+        600
+    }
+
+    pub fn installed_boot_timeout_secs(&self) -> u64 {
+        180
+    }
+
     pub fn boot_error_patterns(&self) -> &[&str] {
         &[
             // === UEFI PHASE ===
@@ -84,44 +93,18 @@ impl OpenRcBase {
         &["ERROR: cannot start", "* ERROR:", "crashed"]
     }
 
-    pub fn enable_service_cmd(&self, service: &str, runlevel: &str) -> String {
-        format!("rc-update add {} {}", service, runlevel)
-    }
-
-    pub fn check_service_exists_cmd(&self, service: &str) -> String {
-        format!("test -f /etc/init.d/{} && echo {}", service, service)
-    }
-
-    pub fn check_service_status_cmd(&self, service: &str) -> String {
-        format!("rc-service {} status", service)
-    }
-
-    pub fn list_failed_services_cmd(&self) -> String {
-        "rc-status --crashed 2>/dev/null || rc-status -a | grep -E 'stopped|crashed'".to_string()
-    }
-
-    pub fn enable_serial_getty_cmd(&self) -> String {
-        "grep -q 'ttyS0' /etc/inittab || echo 'ttyS0::respawn:/sbin/getty -L 115200 ttyS0 vt100' >> /etc/inittab".to_string()
-    }
-
-    pub fn expected_pid1_name(&self) -> &str {
-        "init"
-    }
-
-    pub fn check_target_reached_cmd(&self) -> &str {
-        "rc-status default 2>/dev/null | grep -q started && echo 'default_reached'"
-    }
-
-    pub fn target_reached_expected(&self) -> &str {
-        "default_reached"
-    }
-
-    pub fn count_failed_services_cmd(&self) -> &str {
-        "rc-status --crashed 2>/dev/null | wc -l || echo 0"
-    }
-
-    pub fn check_network_service_cmd(&self) -> &str {
-        "rc-service networking status 2>/dev/null | grep -q started && echo 'active'"
+    /// Busybox applets stand in for coreutils/findutils, so no `--version`.
+    pub fn essential_commands(&self) -> &[(&str, &str)] {
+        &[
+            ("ls /", "busybox"),
+            ("cat /proc/version", "busybox"),
+            ("echo ok | grep -q ok", "busybox"),
+            ("find / -maxdepth 0", "busybox"),
+            ("ash -c true", "busybox"),
+            ("openrc --version", "openrc"),
+            ("rc-status --version", "openrc"),
+            ("ip --version", "iproute2"),
+        ]
     }
 
     pub fn install_bootloader_cmd(&self) -> &str {
@@ -132,11 +115,16 @@ impl OpenRcBase {
         "/bin/ash"
     }
 
-    pub fn init_system_name(&self) -> &str {
-        "OpenRC"
+    /// Replaces every configured repository, so `apk add` only sees the
+    /// mirror's `APKINDEX`.
+    pub fn package_mirror_cmd(&self, mirror_url: &str) -> Option<String> {
+        Some(format!(
+            "printf '%s\\n' {} > /etc/apk/repositories && apk update",
+            crate::executor::shell_quote(mirror_url)
+        ))
     }
 
-    pub fn boot_target_name(&self) -> &str {
-        "default runlevel"
+    pub fn install_packages_cmd(&self, packages: &[&str]) -> Option<String> {
+        Some(format!("apk add {}", super::quoted_packages(packages)))
     }
 }
@@ -0,0 +1,396 @@
+//! Install disk partition layouts.
+//!
+//! [`DistroContext::partition_layout`](super::DistroContext::partition_layout)
+//! describes the target disk as a list of [`PartitionSpec`]s. The Phase 2
+//! steps turn it into the `sfdisk` script, the `mkfs` calls and the mounts,
+//! so a separate `/home`, a swap partition or a larger ESP is a context
+//! change rather than a step change.
+
+use crate::executor::partition_device;
+use anyhow::{bail, Result};
+use distro_spec::PartitionLayout;
+
+/// GPT partition type, written as an `sfdisk` type alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionType {
+    /// EFI System Partition (`U`).
+    Esp,
+    /// Linux filesystem (`L`).
+    Linux,
+    /// Linux home (`H`), for a separate `/home`.
+    Home,
+    /// Linux swap (`S`).
+    Swap,
+}
+
+impl PartitionType {
+    fn sfdisk_alias(self) -> &'static str {
+        match self {
+            PartitionType::Esp => "U",
+            PartitionType::Linux => "L",
+            PartitionType::Home => "H",
+            PartitionType::Swap => "S",
+        }
+    }
+}
+
+/// Filesystem a partition is formatted with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filesystem {
+    Vfat,
+    Ext4,
+    Swap,
+}
+
+impl Filesystem {
+    /// `lsblk FSTYPE` of a partition formatted this way.
+    pub fn lsblk_fstype(self) -> &'static str {
+        match self {
+            Filesystem::Vfat => "vfat",
+            Filesystem::Ext4 => "ext4",
+            Filesystem::Swap => "swap",
+        }
+    }
+
+    /// Read-only consistency check, prefixed to the device; `None` for swap.
+    pub fn fsck_command(self) -> Option<&'static str> {
+        match self {
+            Filesystem::Vfat => Some("fsck.fat -n"),
+            Filesystem::Ext4 => Some("e2fsck -fn"),
+            Filesystem::Swap => None,
+        }
+    }
+
+    /// Command formatting `device`.
+    pub fn mkfs_command(self, device: &str) -> String {
+        match self {
+            Filesystem::Vfat => format!("mkfs.fat -F32 {}", device),
+            Filesystem::Ext4 => format!("mkfs.ext4 -F {}", device),
+            Filesystem::Swap => format!("mkswap {}", device),
+        }
+    }
+}
+
+/// One partition of the install disk, in on-disk order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionSpec {
+    /// Size in MiB; `None` takes the rest of the disk (last partition only).
+    pub size_mib: Option<u64>,
+    pub part_type: PartitionType,
+    pub filesystem: Filesystem,
+    /// Mountpoint in the installed system (`/`, `/boot`, `/home`); `None`
+    /// for swap.
+    pub mountpoint: Option<String>,
+}
+
+impl PartitionSpec {
+    pub fn new(
+        size_mib: Option<u64>,
+        part_type: PartitionType,
+        filesystem: Filesystem,
+        mountpoint: Option<&str>,
+    ) -> Self {
+        Self {
+            size_mib,
+            part_type,
+            filesystem,
+            mountpoint: mountpoint.map(str::to_string),
+        }
+    }
+}
+
+/// The layout every distro installs with unless it overrides
+/// `partition_layout`: distro-spec's default GPT layout, an ESP at `/boot`
+/// and an ext4 root filling the rest of the disk.
+pub fn default_partition_layout() -> Vec<PartitionSpec> {
+    layout_from_sfdisk_script(&PartitionLayout::default().to_sfdisk_script())
+        .expect("distro-spec's default partition layout is a valid sfdisk script")
+}
+
+/// Read an `sfdisk` script back into a layout.
+///
+/// Partition lines are `key=value` lists; only `size` and `type` are used.
+/// Types may be sfdisk aliases, names or GPT type GUIDs. The first Linux
+/// partition becomes `/` (ext4), a Home partition `/home` (ext4), the ESP
+/// `/boot` (vfat), and swap stays unmounted.
+pub fn layout_from_sfdisk_script(script: &str) -> Result<Vec<PartitionSpec>> {
+    let mut layout = Vec::new();
+    for line in script.lines().map(str::trim) {
+        // `sfdisk --dump` prefixes partition lines with `<device> :`.
+        let fields = match line.split_once(" : ") {
+            Some((_, fields)) => fields,
+            None if line.is_empty() || line.starts_with('#') => continue,
+            None if line.contains(':') && !line.contains('=') => continue,
+            None => line,
+        };
+        let field = |key: &str| {
+            fields
+                .split(',')
+                .filter_map(|field| field.trim().split_once('='))
+                .find(|(k, _)| k.trim() == key)
+                .map(|(_, v)| v.trim().trim_matches('"'))
+        };
+        let size_mib = field("size").map(parse_sfdisk_size_mib).transpose()?;
+        let part_type = match field("type") {
+            Some(value) => parse_sfdisk_type(value)?,
+            None => PartitionType::Linux,
+        };
+        let has_root = layout
+            .iter()
+            .any(|spec: &PartitionSpec| spec.mountpoint.as_deref() == Some("/"));
+        let (filesystem, mountpoint) = match part_type {
+            PartitionType::Esp => (Filesystem::Vfat, Some("/boot")),
+            PartitionType::Linux if !has_root => (Filesystem::Ext4, Some("/")),
+            PartitionType::Linux => bail!("sfdisk script has more than one Linux partition"),
+            PartitionType::Home => (Filesystem::Ext4, Some("/home")),
+            PartitionType::Swap => (Filesystem::Swap, None),
+        };
+        layout.push(PartitionSpec::new(
+            size_mib, part_type, filesystem, mountpoint,
+        ));
+    }
+    Ok(layout)
+}
+
+/// An `sfdisk` size (`512MiB`, `1G`, `+2048K`, or bare 512-byte sectors) in MiB.
+fn parse_sfdisk_size_mib(value: &str) -> Result<u64> {
+    let value = value.trim_start_matches('+');
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid sfdisk size '{}'", value))?;
+    Ok(match unit.trim_end_matches("iB").trim_end_matches('B') {
+        "" => number * 512 / (1024 * 1024),
+        "K" => number / 1024,
+        "M" => number,
+        "G" => number * 1024,
+        "T" => number * 1024 * 1024,
+        _ => bail!("invalid sfdisk size unit in '{}'", value),
+    })
+}
+
+/// An `sfdisk` partition type alias, name or GPT type GUID.
+fn parse_sfdisk_type(value: &str) -> Result<PartitionType> {
+    Ok(match value.to_ascii_uppercase().as_str() {
+        "U" | "UEFI" | "EFI SYSTEM" | "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => PartitionType::Esp,
+        "L" | "LINUX" | "LINUX FILESYSTEM"
+        | "0FC63DAF-8483-4772-8E79-3D69D8477DE4"
+        // Discoverable root partition types (x86-64, aarch64).
+        | "4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709"
+        | "B921B045-1DF0-41C3-AF44-4C6F280D3FAE" => PartitionType::Linux,
+        "H" | "HOME" | "LINUX HOME" | "933AC7E1-2EB4-4F13-B844-0E14E2AEF915" => {
+            PartitionType::Home
+        }
+        "S" | "SWAP" | "LINUX SWAP" | "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F" => {
+            PartitionType::Swap
+        }
+        _ => bail!("unsupported sfdisk partition type '{}'", value),
+    })
+}
+
+/// Reject layouts the install and boot steps cannot work with.
+///
+/// There must be exactly one root and one ESP mounted at `/boot` (where
+/// systemd-boot reads the kernel), only the last partition may fill the
+/// disk, and swap is never mounted.
+pub fn validate_partition_layout(layout: &[PartitionSpec]) -> Result<()> {
+    let mounted_at = |target: &str| {
+        layout
+            .iter()
+            .filter(|spec| spec.mountpoint.as_deref() == Some(target))
+            .count()
+    };
+    if mounted_at("/") != 1 {
+        bail!("partition layout needs exactly one partition mounted at /");
+    }
+    let esps: Vec<&PartitionSpec> = layout
+        .iter()
+        .filter(|spec| spec.part_type == PartitionType::Esp)
+        .collect();
+    if esps.len() != 1
+        || esps[0].filesystem != Filesystem::Vfat
+        || esps[0].mountpoint.as_deref() != Some("/boot")
+    {
+        bail!("partition layout needs exactly one vfat ESP mounted at /boot");
+    }
+    if let Some(position) = layout
+        .iter()
+        .position(|spec| spec.size_mib.is_none())
+        .filter(|&position| position + 1 != layout.len())
+    {
+        bail!(
+            "only the last partition may fill the disk (partition {} has no size)",
+            position + 1
+        );
+    }
+    for (index, spec) in layout.iter().enumerate() {
+        let is_swap = spec.filesystem == Filesystem::Swap;
+        if is_swap != (spec.part_type == PartitionType::Swap)
+            || (is_swap && spec.mountpoint.is_some())
+        {
+            bail!(
+                "partition {}: swap needs type Swap, filesystem Swap and no mountpoint",
+                index + 1
+            );
+        }
+        if let Some(mountpoint) = &spec.mountpoint {
+            if !mountpoint.starts_with('/') || mounted_at(mountpoint) != 1 {
+                bail!(
+                    "partition {}: mountpoint '{}' must be absolute and unique",
+                    index + 1,
+                    mountpoint
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `sfdisk` input creating `layout` as a GPT table.
+pub fn sfdisk_script(layout: &[PartitionSpec]) -> String {
+    let mut script = String::from("label: gpt\n");
+    for spec in layout {
+        match spec.size_mib {
+            Some(size) => script.push_str(&format!(
+                "size={}MiB, type={}\n",
+                size,
+                spec.part_type.sfdisk_alias()
+            )),
+            None => script.push_str(&format!("type={}\n", spec.part_type.sfdisk_alias())),
+        }
+    }
+    script
+}
+
+/// Each partition of `layout` paired with its device node on `disk`.
+pub fn partition_devices<'a>(
+    disk: &str,
+    layout: &'a [PartitionSpec],
+) -> Vec<(String, &'a PartitionSpec)> {
+    layout
+        .iter()
+        .enumerate()
+        .map(|(index, spec)| (partition_device(disk, index as u32 + 1), spec))
+        .collect()
+}
+
+/// Device of the partition `layout` mounts at `mountpoint` on `disk`.
+pub fn device_mounted_at(disk: &str, layout: &[PartitionSpec], mountpoint: &str) -> Option<String> {
+    partition_devices(disk, layout)
+        .into_iter()
+        .find(|(_, spec)| spec.mountpoint.as_deref() == Some(mountpoint))
+        .map(|(device, _)| device)
+}
+
+/// `(device, target)` mounts for `layout` under `root` (e.g. `/mnt`),
+/// parents before children so `/home` lands on the mounted root.
+pub fn partition_mounts(disk: &str, layout: &[PartitionSpec], root: &str) -> Vec<(String, String)> {
+    let mut mounts: Vec<(String, &str)> = partition_devices(disk, layout)
+        .into_iter()
+        .filter_map(|(device, spec)| spec.mountpoint.as_deref().map(|target| (device, target)))
+        .collect();
+    mounts.sort_by_key(|(_, target)| target.trim_end_matches('/').matches('/').count());
+    mounts
+        .into_iter()
+        .map(|(device, target)| {
+            let target = match target {
+                "/" => root.to_string(),
+                _ => format!("{}{}", root, target),
+            };
+            (device, target)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sfdisk_script_round_trips_the_layout() {
+        let layout = vec![
+            PartitionSpec::new(
+                Some(1024),
+                PartitionType::Esp,
+                Filesystem::Vfat,
+                Some("/boot"),
+            ),
+            PartitionSpec::new(Some(2048), PartitionType::Swap, Filesystem::Swap, None),
+            PartitionSpec::new(
+                Some(8192),
+                PartitionType::Linux,
+                Filesystem::Ext4,
+                Some("/"),
+            ),
+            PartitionSpec::new(None, PartitionType::Home, Filesystem::Ext4, Some("/home")),
+        ];
+        validate_partition_layout(&layout).unwrap();
+
+        let script = sfdisk_script(&layout);
+        assert!(script.starts_with("label: gpt\n"), "{}", script);
+        assert_eq!(layout_from_sfdisk_script(&script).unwrap(), layout);
+
+        assert_eq!(
+            device_mounted_at("/dev/nvme0n1", &layout, "/").as_deref(),
+            Some("/dev/nvme0n1p3")
+        );
+        assert_eq!(
+            partition_mounts("/dev/nvme0n1", &layout, "/mnt"),
+            [
+                ("/dev/nvme0n1p3".to_string(), "/mnt".to_string()),
+                ("/dev/nvme0n1p1".to_string(), "/mnt/boot".to_string()),
+                ("/dev/nvme0n1p4".to_string(), "/mnt/home".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sfdisk_scripts_in_other_spellings_are_read() {
+        let script = "label: gpt\nunit: sectors\n\n\
+            /dev/vda1 : start=2048, size=1048576, type=C12A7328-F81F-11D2-BA4B-00A0C93EC93B\n\
+            size=+4G, type=\"Linux swap\"\n\
+            type=linux\n";
+        assert_eq!(
+            layout_from_sfdisk_script(script).unwrap(),
+            [
+                PartitionSpec::new(
+                    Some(512),
+                    PartitionType::Esp,
+                    Filesystem::Vfat,
+                    Some("/boot")
+                ),
+                PartitionSpec::new(Some(4096), PartitionType::Swap, Filesystem::Swap, None),
+                PartitionSpec::new(None, PartitionType::Linux, Filesystem::Ext4, Some("/")),
+            ]
+        );
+        assert!(layout_from_sfdisk_script("type=L\ntype=L\n").is_err());
+        assert!(layout_from_sfdisk_script("size=10X, type=L\n").is_err());
+        assert!(layout_from_sfdisk_script("type=Z\n").is_err());
+    }
+
+    #[test]
+    fn layouts_without_a_bootable_root_are_rejected() {
+        validate_partition_layout(&default_partition_layout()).unwrap();
+
+        let mut no_esp = default_partition_layout();
+        no_esp.remove(0);
+        assert!(validate_partition_layout(&no_esp).is_err());
+
+        let mut fill_first = default_partition_layout();
+        fill_first[0].size_mib = None;
+        assert!(validate_partition_layout(&fill_first).is_err());
+
+        let mut mounted_swap = default_partition_layout();
+        mounted_swap.push(PartitionSpec::new(
+            None,
+            PartitionType::Swap,
+            Filesystem::Swap,
+            Some("/swap"),
+        ));
+        mounted_swap[1].size_mib = Some(4096);
+        assert!(validate_partition_layout(&mounted_swap).is_err());
+    }
+}
@@ -3,7 +3,7 @@
 //! RalphOS currently follows the same systemd/systemd-boot test contract shape
 //! as LevitateOS for install-test staging.
 
-use super::DistroContext;
+use super::{DistroContext, InitSystem};
 /// RalphOS context for systemd-based testing.
 pub struct RalphContext;
 
@@ -16,6 +16,10 @@ impl DistroContext for RalphContext {
         "ralph"
     }
 
+    fn init_system(&self) -> InitSystem {
+        InitSystem::Systemd
+    }
+
     fn live_boot_success_patterns(&self) -> &[&str] {
         &["___SHELL_READY___"]
     }
@@ -92,25 +96,6 @@ impl DistroContext for RalphContext {
         &["Failed to start", "[FAILED]", "Dependency failed"]
     }
 
-    fn enable_service_cmd(&self, service: &str, _target: &str) -> String {
-        format!("systemctl enable {}", service)
-    }
-
-    fn check_service_exists_cmd(&self, service: &str) -> String {
-        format!(
-            "test -f /usr/lib/systemd/system/{}.service && echo {}",
-            service, service
-        )
-    }
-
-    fn check_service_status_cmd(&self, service: &str) -> String {
-        format!("systemctl is-active {}", service)
-    }
-
-    fn list_failed_services_cmd(&self) -> String {
-        "systemctl --failed --no-pager".to_string()
-    }
-
     fn enabled_services(&self) -> Vec<(&str, &str, bool)> {
         vec![
             ("NetworkManager", "multi-user.target", true),
@@ -119,30 +104,6 @@ impl DistroContext for RalphContext {
         ]
     }
 
-    fn enable_serial_getty_cmd(&self) -> String {
-        "systemctl enable serial-getty@ttyS0.service".to_string()
-    }
-
-    fn expected_pid1_name(&self) -> &str {
-        "systemd"
-    }
-
-    fn check_target_reached_cmd(&self) -> &str {
-        "systemctl is-active multi-user.target"
-    }
-
-    fn target_reached_expected(&self) -> &str {
-        "active"
-    }
-
-    fn count_failed_services_cmd(&self) -> &str {
-        "systemctl --failed --no-legend | wc -l"
-    }
-
-    fn check_network_service_cmd(&self) -> &str {
-        "systemctl is-active systemd-networkd || systemctl is-active NetworkManager"
-    }
-
     fn install_bootloader_cmd(&self) -> &str {
         "bootctl install --esp-path=/boot --no-variables"
     }
@@ -167,14 +128,6 @@ impl DistroContext for RalphContext {
         include_str!("../../../../distro-spec/src/shared/auth/files/00-levitate-test.sh")
     }
 
-    fn init_system_name(&self) -> &str {
-        "systemd"
-    }
-
-    fn boot_target_name(&self) -> &str {
-        "multi-user.target"
-    }
-
     fn live_tools(&self) -> &[&str] {
         &[
             "recstrap",
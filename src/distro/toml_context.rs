@@ -0,0 +1,734 @@
+//! Distro context loaded from a `distro.toml` spec file.
+//!
+//! Lets downstream forks test their own LevitateOS derivatives without
+//! adding a hand-written `impl DistroContext` to this crate.
+//!
+//! # Spec format
+//!
+//! ```toml
+//! [identity]
+//! name = "MyOS"
+//! id = "myos"
+//!
+//! [boot]
+//! live_success_patterns = ["___SHELL_READY___"]
+//! error_patterns = ["Kernel panic", "emergency shell"]
+//! critical_errors = ["Kernel panic"]
+//! service_failure_patterns = ["Failed to start"]
+//! live_stall_timeout_secs = 60 # optional
+//! live_timeout_secs = 300 # optional
+//! installed_timeout_secs = 90 # optional
+//! live_overlay_lowerdir = "/live-overlay:/rootfs" # optional; "" if the live root is not an overlay
+//!
+//! [services]
+//! enabled = [{ name = "sshd", target = "multi-user.target", required = false }]
+//! # optional; each defaults to the init system's command
+//! enable_cmd = "systemctl enable {service}"
+//! check_exists_cmd = "test -f /usr/lib/systemd/system/{service}.service && echo {service}"
+//! check_status_cmd = "systemctl is-active {service}"
+//! list_failed_cmd = "systemctl --failed --no-pager"
+//! enable_serial_getty_cmd = "systemctl enable serial-getty@ttyS0.service"
+//!
+//! [init]
+//! system_name = "systemd" # systemd, OpenRC, runit or s6
+//! # optional; each defaults to the init system's value
+//! boot_target_name = "multi-user.target"
+//! expected_pid1 = "systemd"
+//! check_target_reached_cmd = "systemctl is-active multi-user.target"
+//! target_reached_expected = "active"
+//! count_failed_services_cmd = "systemctl --failed --no-legend | wc -l"
+//! check_network_service_cmd = "systemctl is-active NetworkManager"
+//!
+//! [bootloader]
+//! install_cmd = "bootctl install --esp-path=/boot --no-variables"
+//! efi_entry_label = "MyOS"
+//!
+//! [system]
+//! chroot_shell = "/bin/bash"
+//! default_hostname = "myos"
+//! hostname_check_pattern = "myos"
+//! test_instrumentation = "files/00-myos-test.sh" # relative to distro.toml
+//! package_mirror_cmd = "echo {url}/repo > /etc/apk/repositories" # optional
+//! package_install_cmd = "apk add {packages}" # optional, for --extra-pkg
+//!
+//! [tools]
+//! live = ["recstrap", "recfstab", "recchroot"]
+//! # optional for systemd and OpenRC, which have defaults; `[command, package]`
+//! essential = [["ls --version", "coreutils"], ["systemctl --version", "systemd"]]
+//!
+//! [credentials] # optional
+//! username = "myos"
+//! password = "myos"
+//! ```
+//!
+//! `{service}` and `{target}` placeholders in service commands are replaced
+//! with the service name and target/runlevel; `{url}` in
+//! `package_mirror_cmd` with the mirror's base URL; `{packages}` in
+//! `package_install_cmd` with the quoted package names.
+
+use super::openrc_base::OpenRcBase;
+use super::{DistroContext, InitSystem, LIVE_OVERLAY_LOWERDIR, SYSTEMD_ESSENTIAL_COMMANDS};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Keys that must be present in every distro spec. Service and init keys not
+/// listed here default to the spec's [`InitSystem`].
+const REQUIRED_KEYS: &[&str] = &[
+    "identity.name",
+    "identity.id",
+    "boot.live_success_patterns",
+    "boot.error_patterns",
+    "boot.critical_errors",
+    "boot.service_failure_patterns",
+    "services.enabled",
+    "init.system_name",
+    "bootloader.install_cmd",
+    "bootloader.efi_entry_label",
+    "system.chroot_shell",
+    "system.default_hostname",
+    "system.hostname_check_pattern",
+    "system.test_instrumentation",
+    "tools.live",
+];
+
+#[derive(Debug, Deserialize)]
+struct DistroSpec {
+    identity: IdentitySpec,
+    boot: BootSpec,
+    services: ServicesSpec,
+    init: InitSpec,
+    bootloader: BootloaderSpec,
+    system: SystemSpec,
+    tools: ToolsSpec,
+    #[serde(default)]
+    credentials: Option<CredentialsSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdentitySpec {
+    name: String,
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BootSpec {
+    live_success_patterns: Vec<String>,
+    error_patterns: Vec<String>,
+    critical_errors: Vec<String>,
+    service_failure_patterns: Vec<String>,
+    #[serde(default)]
+    live_stall_timeout_secs: Option<u64>,
+    #[serde(default)]
+    live_timeout_secs: Option<u64>,
+    #[serde(default)]
+    installed_timeout_secs: Option<u64>,
+    #[serde(default)]
+    live_overlay_lowerdir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServicesSpec {
+    #[serde(default)]
+    enable_cmd: Option<String>,
+    #[serde(default)]
+    check_exists_cmd: Option<String>,
+    #[serde(default)]
+    check_status_cmd: Option<String>,
+    #[serde(default)]
+    list_failed_cmd: Option<String>,
+    #[serde(default)]
+    enable_serial_getty_cmd: Option<String>,
+    enabled: Vec<EnabledServiceSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnabledServiceSpec {
+    name: String,
+    target: String,
+    #[serde(default)]
+    required: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitSpec {
+    system_name: String,
+    #[serde(default)]
+    boot_target_name: Option<String>,
+    #[serde(default)]
+    expected_pid1: Option<String>,
+    #[serde(default)]
+    check_target_reached_cmd: Option<String>,
+    #[serde(default)]
+    target_reached_expected: Option<String>,
+    #[serde(default)]
+    count_failed_services_cmd: Option<String>,
+    #[serde(default)]
+    check_network_service_cmd: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BootloaderSpec {
+    install_cmd: String,
+    efi_entry_label: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SystemSpec {
+    chroot_shell: String,
+    default_hostname: String,
+    hostname_check_pattern: String,
+    test_instrumentation: String,
+    #[serde(default)]
+    package_mirror_cmd: Option<String>,
+    #[serde(default)]
+    package_install_cmd: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolsSpec {
+    live: Vec<String>,
+    #[serde(default)]
+    essential: Option<Vec<(String, String)>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CredentialsSpec {
+    username: String,
+    password: String,
+}
+
+/// Distro context defined entirely by a `distro.toml` spec file.
+pub struct TomlContext {
+    name: String,
+    id: String,
+    init_system: InitSystem,
+    // Pattern/tool lists are leaked once at load time so they can be handed out
+    // as `&[&str]` like the built-in contexts. Specs are loaded once per process.
+    live_boot_success_patterns: &'static [&'static str],
+    boot_error_patterns: &'static [&'static str],
+    critical_boot_errors: &'static [&'static str],
+    service_failure_patterns: &'static [&'static str],
+    live_boot_stall_timeout_secs: Option<u64>,
+    live_boot_timeout_secs: Option<u64>,
+    installed_boot_timeout_secs: Option<u64>,
+    live_overlay_lowerdir: Option<String>,
+    // Service and init overrides; `None` falls back to `init_system`.
+    enable_service_cmd: Option<String>,
+    check_service_exists_cmd: Option<String>,
+    check_service_status_cmd: Option<String>,
+    list_failed_services_cmd: Option<String>,
+    enable_serial_getty_cmd: Option<String>,
+    enabled_services: Vec<EnabledServiceSpec>,
+    boot_target_name: Option<String>,
+    expected_pid1_name: Option<String>,
+    check_target_reached_cmd: Option<String>,
+    target_reached_expected: Option<String>,
+    count_failed_services_cmd: Option<String>,
+    check_network_service_cmd: Option<String>,
+    install_bootloader_cmd: String,
+    efi_entry_label: String,
+    chroot_shell: String,
+    default_hostname: String,
+    hostname_check_pattern: String,
+    test_instrumentation_source: String,
+    package_mirror_cmd: Option<String>,
+    package_install_cmd: Option<String>,
+    live_tools: &'static [&'static str],
+    /// `None` falls back to the init system's default set.
+    essential_commands: Option<&'static [(&'static str, &'static str)]>,
+    credentials: Option<CredentialsSpec>,
+}
+
+impl TomlContext {
+    /// Load a distro context from a `distro.toml` file.
+    ///
+    /// Relative paths in the spec are resolved against the spec's directory.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading distro spec '{}'", path.display()))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_toml_str(&raw, base_dir)
+            .with_context(|| format!("loading distro spec '{}'", path.display()))
+    }
+
+    /// Load a distro context from TOML source, resolving relative paths against `base_dir`.
+    pub fn from_toml_str(raw: &str, base_dir: &Path) -> Result<Self> {
+        let value: toml::Value = toml::from_str(raw).context("parsing distro spec TOML")?;
+
+        let missing: Vec<&str> = REQUIRED_KEYS
+            .iter()
+            .copied()
+            .filter(|key| lookup_key(&value, key).is_none())
+            .collect();
+        if !missing.is_empty() {
+            bail!(
+                "distro spec is missing required keys: {}",
+                missing.join(", ")
+            );
+        }
+
+        let spec: DistroSpec = value.try_into().context("invalid distro spec")?;
+
+        let instrumentation_path = base_dir.join(&spec.system.test_instrumentation);
+        let test_instrumentation_source = std::fs::read_to_string(&instrumentation_path)
+            .with_context(|| {
+                format!(
+                    "reading system.test_instrumentation '{}'",
+                    instrumentation_path.display()
+                )
+            })?;
+
+        let init_system = spec
+            .init
+            .system_name
+            .parse::<InitSystem>()
+            .context("invalid init.system_name")?;
+        if spec.tools.essential.is_none()
+            && !matches!(init_system, InitSystem::Systemd | InitSystem::OpenRc)
+        {
+            bail!(
+                "tools.essential is required for {} (no default set)",
+                init_system.name()
+            );
+        }
+
+        Ok(Self {
+            name: spec.identity.name,
+            id: spec.identity.id,
+            init_system,
+            live_boot_success_patterns: leak_str_list(spec.boot.live_success_patterns),
+            boot_error_patterns: leak_str_list(spec.boot.error_patterns),
+            critical_boot_errors: leak_str_list(spec.boot.critical_errors),
+            service_failure_patterns: leak_str_list(spec.boot.service_failure_patterns),
+            live_boot_stall_timeout_secs: spec.boot.live_stall_timeout_secs,
+            live_boot_timeout_secs: spec.boot.live_timeout_secs,
+            installed_boot_timeout_secs: spec.boot.installed_timeout_secs,
+            live_overlay_lowerdir: spec.boot.live_overlay_lowerdir,
+            enable_service_cmd: spec.services.enable_cmd,
+            check_service_exists_cmd: spec.services.check_exists_cmd,
+            check_service_status_cmd: spec.services.check_status_cmd,
+            list_failed_services_cmd: spec.services.list_failed_cmd,
+            enable_serial_getty_cmd: spec.services.enable_serial_getty_cmd,
+            enabled_services: spec.services.enabled,
+            boot_target_name: spec.init.boot_target_name,
+            expected_pid1_name: spec.init.expected_pid1,
+            check_target_reached_cmd: spec.init.check_target_reached_cmd,
+            target_reached_expected: spec.init.target_reached_expected,
+            count_failed_services_cmd: spec.init.count_failed_services_cmd,
+            check_network_service_cmd: spec.init.check_network_service_cmd,
+            install_bootloader_cmd: spec.bootloader.install_cmd,
+            efi_entry_label: spec.bootloader.efi_entry_label,
+            chroot_shell: spec.system.chroot_shell,
+            default_hostname: spec.system.default_hostname,
+            hostname_check_pattern: spec.system.hostname_check_pattern,
+            test_instrumentation_source,
+            package_mirror_cmd: spec.system.package_mirror_cmd,
+            package_install_cmd: spec.system.package_install_cmd,
+            live_tools: leak_str_list(spec.tools.live),
+            essential_commands: spec.tools.essential.map(leak_command_list),
+            credentials: spec.credentials,
+        })
+    }
+}
+
+fn lookup_key<'a>(value: &'a toml::Value, dotted: &str) -> Option<&'a toml::Value> {
+    dotted
+        .split('.')
+        .try_fold(value, |current, part| current.get(part))
+}
+
+fn leak_str_list(values: Vec<String>) -> &'static [&'static str] {
+    let leaked: Vec<&'static str> = values
+        .into_iter()
+        .map(|v| &*Box::leak(v.into_boxed_str()))
+        .collect();
+    Box::leak(leaked.into_boxed_slice())
+}
+
+fn leak_command_list(values: Vec<(String, String)>) -> &'static [(&'static str, &'static str)] {
+    let leaked: Vec<(&'static str, &'static str)> = values
+        .into_iter()
+        .map(|(cmd, package)| {
+            (
+                &*Box::leak(cmd.into_boxed_str()),
+                &*Box::leak(package.into_boxed_str()),
+            )
+        })
+        .collect();
+    Box::leak(leaked.into_boxed_slice())
+}
+
+fn render_service_cmd(template: &str, service: &str, target: &str) -> String {
+    template
+        .replace("{service}", service)
+        .replace("{target}", target)
+}
+
+impl DistroContext for TomlContext {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn init_system(&self) -> InitSystem {
+        self.init_system
+    }
+
+    fn live_boot_success_patterns(&self) -> &[&str] {
+        self.live_boot_success_patterns
+    }
+
+    fn boot_error_patterns(&self) -> &[&str] {
+        self.boot_error_patterns
+    }
+
+    fn critical_boot_errors(&self) -> &[&str] {
+        self.critical_boot_errors
+    }
+
+    fn service_failure_patterns(&self) -> &[&str] {
+        self.service_failure_patterns
+    }
+
+    fn live_boot_stall_timeout_secs(&self) -> u64 {
+        self.live_boot_stall_timeout_secs.unwrap_or(60)
+    }
+
+    fn live_boot_timeout_secs(&self) -> u64 {
+        self.live_boot_timeout_secs.unwrap_or(300)
+    }
+
+    fn installed_boot_timeout_secs(&self) -> u64 {
+        self.installed_boot_timeout_secs.unwrap_or(90)
+    }
+
+    fn live_overlay_lowerdir(&self) -> Option<&str> {
+        match self.live_overlay_lowerdir.as_deref() {
+            None => Some(LIVE_OVERLAY_LOWERDIR),
+            Some("") => None,
+            Some(chain) => Some(chain),
+        }
+    }
+
+    fn enable_service_cmd(&self, service: &str, target: &str) -> String {
+        match &self.enable_service_cmd {
+            Some(template) => render_service_cmd(template, service, target),
+            None => self.init_system.enable_service_cmd(service, target),
+        }
+    }
+
+    fn check_service_exists_cmd(&self, service: &str) -> String {
+        match &self.check_service_exists_cmd {
+            Some(template) => render_service_cmd(template, service, ""),
+            None => self.init_system.check_service_exists_cmd(service),
+        }
+    }
+
+    fn check_service_status_cmd(&self, service: &str) -> String {
+        match &self.check_service_status_cmd {
+            Some(template) => render_service_cmd(template, service, ""),
+            None => self.init_system.check_service_status_cmd(service),
+        }
+    }
+
+    fn list_failed_services_cmd(&self) -> String {
+        self.list_failed_services_cmd
+            .clone()
+            .unwrap_or_else(|| self.init_system.list_failed_services_cmd())
+    }
+
+    fn enabled_services(&self) -> Vec<(&str, &str, bool)> {
+        self.enabled_services
+            .iter()
+            .map(|s| (s.name.as_str(), s.target.as_str(), s.required))
+            .collect()
+    }
+
+    fn enable_serial_getty_cmd(&self) -> String {
+        self.enable_serial_getty_cmd
+            .clone()
+            .unwrap_or_else(|| self.init_system.enable_serial_getty_cmd())
+    }
+
+    fn expected_pid1_name(&self) -> &str {
+        self.expected_pid1_name
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.expected_pid1_name())
+    }
+
+    fn check_target_reached_cmd(&self) -> &str {
+        self.check_target_reached_cmd
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.check_target_reached_cmd())
+    }
+
+    fn target_reached_expected(&self) -> &str {
+        self.target_reached_expected
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.target_reached_expected())
+    }
+
+    fn count_failed_services_cmd(&self) -> &str {
+        self.count_failed_services_cmd
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.count_failed_services_cmd())
+    }
+
+    fn check_network_service_cmd(&self) -> &str {
+        self.check_network_service_cmd
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.check_network_service_cmd())
+    }
+
+    fn install_bootloader_cmd(&self) -> &str {
+        &self.install_bootloader_cmd
+    }
+
+    fn efi_entry_label(&self) -> &str {
+        &self.efi_entry_label
+    }
+
+    fn chroot_shell(&self) -> &str {
+        &self.chroot_shell
+    }
+
+    fn default_hostname(&self) -> &str {
+        &self.default_hostname
+    }
+
+    fn hostname_check_pattern(&self) -> &str {
+        &self.hostname_check_pattern
+    }
+
+    fn test_instrumentation_source(&self) -> &str {
+        &self.test_instrumentation_source
+    }
+
+    fn package_mirror_cmd(&self, mirror_url: &str) -> Option<String> {
+        self.package_mirror_cmd
+            .as_ref()
+            .map(|cmd| cmd.replace("{url}", mirror_url))
+    }
+
+    fn install_packages_cmd(&self, packages: &[&str]) -> Option<String> {
+        self.package_install_cmd
+            .as_ref()
+            .map(|cmd| cmd.replace("{packages}", &super::quoted_packages(packages)))
+    }
+
+    fn default_username(&self) -> Result<String> {
+        self.credentials
+            .as_ref()
+            .map(|c| c.username.clone())
+            .ok_or_else(|| anyhow!("distro spec for '{}' has no [credentials] table", self.id))
+    }
+
+    fn default_password(&self) -> Result<String> {
+        self.credentials
+            .as_ref()
+            .map(|c| c.password.clone())
+            .ok_or_else(|| anyhow!("distro spec for '{}' has no [credentials] table", self.id))
+    }
+
+    fn init_system_name(&self) -> &str {
+        self.init_system.name()
+    }
+
+    fn boot_target_name(&self) -> &str {
+        self.boot_target_name
+            .as_deref()
+            .unwrap_or_else(|| self.init_system.boot_target_name())
+    }
+
+    fn live_tools(&self) -> &[&str] {
+        self.live_tools
+    }
+
+    fn essential_commands(&self) -> &[(&str, &str)] {
+        match self.essential_commands {
+            Some(commands) => commands,
+            None if self.init_system == InitSystem::OpenRc => OpenRcBase.essential_commands(),
+            None => SYSTEMD_ESSENTIAL_COMMANDS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const SPEC: &str = r#"
+[identity]
+name = "ForkOS"
+id = "fork"
+
+[boot]
+live_success_patterns = ["___SHELL_READY___"]
+error_patterns = ["Kernel panic"]
+critical_errors = ["Kernel panic"]
+service_failure_patterns = ["Failed to start"]
+
+[services]
+enable_cmd = "rc-update add {service} {target}"
+check_exists_cmd = "test -f /etc/init.d/{service} && echo {service}"
+check_status_cmd = "rc-service {service} status"
+list_failed_cmd = "rc-status --crashed"
+enable_serial_getty_cmd = "true"
+enabled = [{ name = "sshd", target = "default", required = true }]
+
+[init]
+system_name = "OpenRC"
+boot_target_name = "default runlevel"
+expected_pid1 = "init"
+check_target_reached_cmd = "rc-status default"
+target_reached_expected = "started"
+count_failed_services_cmd = "rc-status --crashed | wc -l"
+check_network_service_cmd = "rc-service networking status"
+
+[bootloader]
+install_cmd = "true"
+efi_entry_label = "ForkOS"
+
+[system]
+chroot_shell = "/bin/ash"
+default_hostname = "forkos"
+hostname_check_pattern = "fork"
+test_instrumentation = "00-fork-test.sh"
+
+[tools]
+live = ["recstrap", "sfdisk"]
+"#;
+
+    #[test]
+    fn loads_full_spec_and_renders_service_commands() {
+        let dir = temp_dir("toml-context-full");
+        std::fs::write(dir.join("00-fork-test.sh"), "echo ready\n").expect("write script");
+
+        let ctx = TomlContext::from_toml_str(SPEC, &dir).expect("load spec");
+        assert_eq!(ctx.id(), "fork");
+        assert_eq!(ctx.init_system(), InitSystem::OpenRc);
+        assert_eq!(ctx.live_tools(), &["recstrap", "sfdisk"]);
+        assert!(ctx
+            .essential_commands()
+            .iter()
+            .any(|(_, package)| *package == "openrc"));
+        assert_eq!(ctx.live_boot_stall_timeout_secs(), 60);
+        assert_eq!(ctx.live_overlay_lowerdir(), Some("/live-overlay:/rootfs"));
+        assert_eq!(
+            ctx.enable_service_cmd("sshd", "default"),
+            "rc-update add sshd default"
+        );
+        assert_eq!(ctx.enabled_services(), vec![("sshd", "default", true)]);
+        assert_eq!(ctx.test_instrumentation_source(), "echo ready\n");
+        assert!(ctx.default_username().is_err());
+        assert_eq!(ctx.package_mirror_cmd("http://10.0.2.2:8080"), None);
+
+        std::fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn declared_essential_commands_replace_the_default() {
+        let dir = temp_dir("toml-context-essential");
+        std::fs::write(dir.join("00-fork-test.sh"), "echo ready\n").expect("write script");
+        let raw = SPEC
+            .replace("system_name = \"OpenRC\"", "system_name = \"runit\"")
+            .replace(
+                "live = [\"recstrap\", \"sfdisk\"]",
+                "live = [\"recstrap\", \"sfdisk\"]\nessential = [[\"doas -V\", \"doas\"]]",
+            );
+
+        let ctx = TomlContext::from_toml_str(&raw, &dir).expect("load spec");
+        assert_eq!(ctx.essential_commands(), &[("doas -V", "doas")]);
+
+        let mirrored = SPEC.replace(
+            "test_instrumentation = \"00-fork-test.sh\"",
+            "test_instrumentation = \"00-fork-test.sh\"\npackage_mirror_cmd = \"echo {url}/main > /etc/apk/repositories\"\npackage_install_cmd = \"apk add {packages}\"",
+        );
+        let ctx = TomlContext::from_toml_str(&mirrored, &dir).expect("load spec");
+        assert_eq!(
+            ctx.package_mirror_cmd("http://10.0.2.2:8080").as_deref(),
+            Some("echo http://10.0.2.2:8080/main > /etc/apk/repositories")
+        );
+        assert_eq!(
+            ctx.install_packages_cmd(&["strace", "gdb"]).as_deref(),
+            Some("apk add 'strace' 'gdb'")
+        );
+
+        let undeclared = SPEC.replace("system_name = \"OpenRC\"", "system_name = \"runit\"");
+        let err = TomlContext::from_toml_str(&undeclared, &dir)
+            .err()
+            .expect("runit has no default essentials");
+        assert!(format!("{:#}", err).contains("tools.essential"));
+
+        std::fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn omitted_init_keys_default_to_the_init_system() {
+        let dir = temp_dir("toml-context-defaults");
+        std::fs::write(dir.join("00-fork-test.sh"), "echo ready\n").expect("write script");
+        let defaulted = [
+            "enable_cmd",
+            "check_exists_cmd",
+            "check_status_cmd",
+            "list_failed_cmd",
+            "enable_serial_getty_cmd",
+            "boot_target_name",
+            "expected_pid1",
+            "check_target_reached_cmd",
+            "target_reached_expected",
+            "count_failed_services_cmd",
+            "check_network_service_cmd",
+        ];
+        let raw = SPEC
+            .lines()
+            .filter(|line| !defaulted.iter().any(|key| line.starts_with(key)))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .replace("system_name = \"OpenRC\"", "system_name = \"openrc\"");
+
+        let ctx = TomlContext::from_toml_str(&raw, &dir).expect("load spec");
+        let init = InitSystem::OpenRc;
+        assert_eq!(ctx.init_system_name(), "OpenRC");
+        assert_eq!(ctx.boot_target_name(), init.boot_target_name());
+        assert_eq!(ctx.expected_pid1_name(), init.expected_pid1_name());
+        assert_eq!(
+            ctx.check_target_reached_cmd(),
+            init.check_target_reached_cmd()
+        );
+        assert_eq!(
+            ctx.target_reached_expected(),
+            init.target_reached_expected()
+        );
+        assert_eq!(
+            ctx.enable_service_cmd("sshd", "default"),
+            init.enable_service_cmd("sshd", "default")
+        );
+        assert_eq!(
+            ctx.check_service_status_cmd("sshd"),
+            init.check_service_status_cmd("sshd")
+        );
+        assert_eq!(ctx.install_bootloader_cmd(), "true");
+
+        std::fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn reports_every_missing_required_key() {
+        let raw = "[identity]\nname = \"ForkOS\"\n";
+        let err = TomlContext::from_toml_str(raw, Path::new("."))
+            .err()
+            .expect("missing keys must fail");
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("identity.id"), "{}", msg);
+        assert!(msg.contains("tools.live"), "{}", msg);
+        assert!(!msg.contains("identity.name"), "{}", msg);
+        assert!(msg.contains("init.system_name"), "{}", msg);
+        assert!(!msg.contains("services.enable_cmd"), "{}", msg);
+    }
+}
@@ -4,7 +4,10 @@
 //! Each backend implements command execution, text input, and output waiting.
 
 use anyhow::Result;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Hard cap for [`Executor::exec_long`] on backends without output streaming.
+pub const LONG_EXEC_CEILING: Duration = Duration::from_secs(30 * 60);
 
 /// Result of executing a command through an executor.
 #[derive(Debug, Clone)]
@@ -19,12 +22,110 @@ pub struct ExecResult {
     pub aborted_on_error: bool,
     /// Whether execution was aborted due to stall (no output).
     pub stalled: bool,
+    /// Whether the connection to the guest dropped before the command
+    /// finished (SSH exit 255).
+    pub disconnected: bool,
+    /// The abort pattern that stopped the command, when the backend knows it.
+    pub matched_pattern: Option<String>,
+}
+
+/// Why a command ended, as classified by [`ExecResult::outcome`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    NonZeroExit(i32),
+    /// Ran past its timeout.
+    Timeout,
+    /// Stopped printing output for longer than the stall timeout.
+    Stalled,
+    /// Aborted on a fatal console pattern. `pattern` is the one that
+    /// matched, when known; `line` is the output line it matched in.
+    FatalPattern {
+        pattern: Option<String>,
+        line: String,
+    },
+    Disconnected,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Ok => f.write_str("ok"),
+            Outcome::NonZeroExit(code) => write!(f, "exit {}", code),
+            Outcome::Timeout => f.write_str("timed out"),
+            Outcome::Stalled => f.write_str("stalled"),
+            Outcome::FatalPattern {
+                pattern: Some(pattern),
+                line,
+            } => write!(f, "fatal pattern '{}' in: {}", pattern, line),
+            Outcome::FatalPattern {
+                pattern: None,
+                line,
+            } => write!(f, "fatal pattern in: {}", line),
+            Outcome::Disconnected => f.write_str("disconnected"),
+        }
+    }
 }
 
 impl ExecResult {
     /// Check if the command succeeded.
     pub fn success(&self) -> bool {
-        self.completed && self.exit_code == 0 && !self.aborted_on_error && !self.stalled
+        self.completed
+            && self.exit_code == 0
+            && !self.aborted_on_error
+            && !self.stalled
+            && !self.disconnected
+    }
+
+    /// Classify how the command ended from the result flags.
+    pub fn outcome(&self) -> Outcome {
+        if self.disconnected {
+            Outcome::Disconnected
+        } else if self.aborted_on_error {
+            self.fatal_pattern()
+        } else if self.stalled {
+            Outcome::Stalled
+        } else if !self.completed {
+            Outcome::Timeout
+        } else if self.exit_code != 0 {
+            Outcome::NonZeroExit(self.exit_code)
+        } else {
+            Outcome::Ok
+        }
+    }
+
+    /// The abort pattern and the line it matched, searching from the end
+    /// since the console stops reading at the match. Without a recorded or
+    /// built-in pattern to look for, the last non-empty line is reported.
+    fn fatal_pattern(&self) -> Outcome {
+        use crate::qemu::patterns::{EMERGENCY_SHELL_PATTERNS, FATAL_ERROR_PATTERNS};
+        let mut lines = self.output.lines().rev().map(str::trim);
+        let found = match &self.matched_pattern {
+            Some(pattern) => lines
+                .clone()
+                .find(|line| line.contains(pattern.as_str()))
+                .map(|line| (pattern.clone(), line)),
+            None => lines.clone().find_map(|line| {
+                FATAL_ERROR_PATTERNS
+                    .iter()
+                    .chain(EMERGENCY_SHELL_PATTERNS)
+                    .find(|pattern| line.contains(*pattern))
+                    .map(|pattern| (pattern.to_string(), line))
+            }),
+        };
+        match found {
+            Some((pattern, line)) => Outcome::FatalPattern {
+                pattern: Some(pattern),
+                line: line.to_string(),
+            },
+            None => Outcome::FatalPattern {
+                pattern: self.matched_pattern.clone(),
+                line: lines
+                    .find(|line| !line.is_empty())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+        }
     }
 }
 
@@ -50,8 +151,8 @@ pub trait Executor {
         let result = self.exec(cmd, timeout)?;
         if !result.success() {
             anyhow::bail!(
-                "Command failed (exit {}): {}\nOutput: {}",
-                result.exit_code,
+                "Command failed ({}): {}\nOutput: {}",
+                result.outcome(),
                 cmd,
                 result.output
             );
@@ -59,6 +160,205 @@ pub trait Executor {
         Ok(result.output)
     }
 
+    /// Execute a command with `stdin_lines` piped to its standard input.
+    ///
+    /// This is a shell pipe, not a prompt responder: the lines are all
+    /// available up front, and programs that read prompts from the tty
+    /// (`passwd`, `cryptsetup` without `--batch-mode`/`--key-file=-`) never
+    /// see them. The command runs through `exec`, so completion and exit
+    /// code capture work as usual.
+    fn exec_with_piped_stdin(
+        &mut self,
+        cmd: &str,
+        stdin_lines: &[&str],
+        timeout: Duration,
+    ) -> Result<ExecResult> {
+        self.exec(&piped_stdin_command(cmd, stdin_lines), timeout)
+    }
+
+    /// Run several commands in one round trip, returning a result per command.
+    ///
+    /// All of `cmds` go into a single `sh -c` script, each in its own subshell
+    /// followed by a delimiter line carrying its index and exit code, so the
+    /// backend's sync overhead is paid once. The batch gets `timeout_each`
+    /// per command as its overall budget. Commands whose delimiter never
+    /// arrived (timeout, stall, fatal error) come back with `completed: false`.
+    fn exec_many(&mut self, cmds: &[&str], timeout_each: Duration) -> Result<Vec<ExecResult>> {
+        if cmds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let budget = timeout_each * cmds.len() as u32;
+        let batch = self.exec(&exec_many_script(cmds), budget)?;
+        Ok(split_exec_many_output(&batch, cmds.len()))
+    }
+
+    /// Run `cmds` one `exec` at a time, optionally stopping at the first failure.
+    ///
+    /// With `stop_on_failure`, the returned list ends at the failing command,
+    /// so `results.len() - 1` indexes the command that broke and the rest are
+    /// never run. Without it every command runs and all results are returned.
+    fn exec_chain(
+        &mut self,
+        cmds: &[&str],
+        timeout_each: Duration,
+        stop_on_failure: bool,
+    ) -> Result<Vec<ExecResult>> {
+        let mut results = Vec::with_capacity(cmds.len());
+        for cmd in cmds {
+            let result = self.exec(cmd, timeout_each)?;
+            let failed = !result.success();
+            results.push(result);
+            if failed && stop_on_failure {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Re-run `cmd` until `done` accepts its result, for checks that only
+    /// become true eventually (udev settling, device nodes appearing).
+    ///
+    /// Sleeps `interval` between attempts and gives up after `max_attempts`,
+    /// returning the last result either way so callers decide whether a
+    /// never-satisfied check is fatal.
+    fn exec_until(
+        &mut self,
+        cmd: &str,
+        timeout: Duration,
+        done: &dyn Fn(&ExecResult) -> bool,
+        interval: Duration,
+        max_attempts: usize,
+    ) -> Result<ExecResult> {
+        let mut attempt = 1;
+        loop {
+            let result = self.exec(cmd, timeout)?;
+            if done(&result) || attempt >= max_attempts {
+                return Ok(result);
+            }
+            attempt += 1;
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Run a long command (rootfs extraction, large copies) that should be
+    /// judged by progress rather than a guessed deadline.
+    ///
+    /// Backends that stream output fail the command only once it has printed
+    /// nothing for `stall_timeout`, or as soon as a line matches one of
+    /// `error_patterns`. The default is the best effort for backends that
+    /// only see output at the end: one `exec` capped at
+    /// [`LONG_EXEC_CEILING`], with `error_patterns` checked afterwards.
+    fn exec_long(
+        &mut self,
+        cmd: &str,
+        stall_timeout: Duration,
+        error_patterns: &[&str],
+    ) -> Result<ExecResult> {
+        let mut result = self.exec(cmd, LONG_EXEC_CEILING.max(stall_timeout))?;
+        if let Some(pattern) = error_patterns
+            .iter()
+            .find(|pattern| result.output.contains(*pattern))
+        {
+            result.aborted_on_error = true;
+            result.matched_pattern = Some(pattern.to_string());
+        }
+        Ok(result)
+    }
+
+    /// Read a guest file byte-exact.
+    ///
+    /// The file travels as one base64 line between delimiter lines, so prompts,
+    /// command echo and console mangling never leak into the contents. Fails
+    /// if the file is unreadable or is not UTF-8.
+    fn read_file(&mut self, path: &str) -> Result<String> {
+        let result = self.exec(&read_file_command(path), Duration::from_secs(10))?;
+        let encoded = read_file_payload(&result.output)
+            .ok_or_else(|| anyhow::anyhow!("reading {}: {}", path, result.output.trim()))?;
+        let bytes = decode_base64(&encoded)
+            .ok_or_else(|| anyhow::anyhow!("reading {}: guest sent invalid base64", path))?;
+        String::from_utf8(bytes).map_err(|_| anyhow::anyhow!("reading {}: file is not UTF-8", path))
+    }
+
+    /// Confirm the logged-in shell runs commands, not just that a prompt showed.
+    ///
+    /// Sends a probe whose marker only appears if the shell executes it
+    /// (the echoed command line never contains it). A boot that reaches a
+    /// prompt but cannot run commands fails here with the output seen.
+    fn wait_for_shell_ready(&mut self, timeout: Duration) -> Result<()> {
+        let result = self.exec(SHELL_READY_PROBE, timeout)?;
+        if result.output.contains(SHELL_READY_MARKER) {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Booted but shell not functional: expected {} from `{}`, got: {}",
+            SHELL_READY_MARKER,
+            SHELL_READY_PROBE,
+            result.output.trim()
+        )
+    }
+
+    /// Pick the disk to install to: the largest non-removable, writable disk.
+    ///
+    /// Works for virtio (`/dev/vda`), SCSI (`/dev/sda`) and NVMe alike, so
+    /// install steps never hardcode a device name. Name partitions on it
+    /// with [`partition_device`].
+    fn detect_install_disk(&mut self) -> Result<String> {
+        let listing = self.exec_ok(INSTALL_DISK_PROBE, Duration::from_secs(5))?;
+        pick_install_disk(&listing).ok_or_else(|| {
+            anyhow::anyhow!(
+                "no writable, non-removable disk found. lsblk output:\n{}",
+                listing.trim()
+            )
+        })
+    }
+
+    /// Boot straight into another kernel with `kexec`, skipping firmware.
+    ///
+    /// Loads `kernel` (and `initrd`, if any) with `kexec -l`, executes it with
+    /// `kexec -e`, then waits for the installed system via
+    /// `wait_for_installed_boot`. This bypasses the UEFI boot chain entirely,
+    /// so it must never stand in for a real installed-boot verification.
+    fn kexec_into(
+        &mut self,
+        kernel: &str,
+        initrd: Option<&str>,
+        cmdline: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        let mut load = format!("kexec -l {}", shell_quote(kernel));
+        if let Some(initrd) = initrd {
+            load.push_str(&format!(" --initrd={}", shell_quote(initrd)));
+        }
+        load.push_str(&format!(" --append={}", shell_quote(cmdline)));
+        self.exec_ok(&load, Duration::from_secs(30))?;
+
+        // The running kernel is replaced, so the completion marker never
+        // arrives: only a timeout or a dropped connection means it worked.
+        let exec = self.exec("sync; kexec -e", Duration::from_secs(5))?;
+        match exec.outcome() {
+            Outcome::Timeout | Outcome::Stalled | Outcome::Disconnected => {}
+            outcome => anyhow::bail!(
+                "kexec -e did not replace the kernel ({}): {}",
+                outcome,
+                exec.output.trim()
+            ),
+        }
+        self.wait_for_installed_boot(timeout)
+    }
+
+    /// Mount a host directory shared via `QemuBuilder::share_dir` at `dest`.
+    fn mount_shared(&mut self, mount_tag: &str, dest: &str) -> Result<()> {
+        self.exec_ok(
+            &format!(
+                "mkdir -p {dest} && mount -t 9p -o trans=virtio,version=9p2000.L {tag} {dest}",
+                dest = shell_quote(dest),
+                tag = shell_quote(mount_tag),
+            ),
+            Duration::from_secs(30),
+        )?;
+        Ok(())
+    }
+
     /// Execute a command in a chroot environment.
     ///
     /// Uses recchroot (like arch-chroot) to handle bind mounts automatically.
@@ -87,3 +387,564 @@ pub trait Executor {
     /// Get any services that failed during boot.
     fn failed_services(&self) -> &[String];
 }
+
+/// Env var holding the `-v` count: 1 echoes each command, 2 also its output.
+pub const VERBOSE_ENV: &str = "LEVITATE_VERBOSE";
+
+/// Verbosity requested via `LEVITATE_VERBOSE`; 0 when unset or unparsable.
+pub fn verbosity() -> u8 {
+    std::env::var(VERBOSE_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Run `exec`, echoing `cmd` to stderr first at `-v` and its output once it
+/// returns at `-vv`.
+///
+/// Backends wrap their command execution in this so a step running for
+/// minutes shows progress as it goes, not only in the post-step log.
+pub(crate) fn traced(cmd: &str, exec: impl FnOnce() -> Result<ExecResult>) -> Result<ExecResult> {
+    let level = verbosity();
+    if level >= 1 {
+        eprintln!("{}", trace_command(cmd));
+    }
+    let started = Instant::now();
+    let result = exec();
+    if level >= 2 {
+        match &result {
+            Ok(result) => eprintln!("{}", trace_output(result, started.elapsed())),
+            Err(err) => eprintln!("  ! {:#}", err),
+        }
+    }
+    result
+}
+
+/// `  $ cmd`, with continuation lines indented under it.
+fn trace_command(cmd: &str) -> String {
+    format!("  $ {}", cmd.trim_end().replace('\n', "\n    "))
+}
+
+/// The output as `  | ` lines, then exit status and duration.
+fn trace_output(result: &ExecResult, elapsed: Duration) -> String {
+    let mut out = String::new();
+    for line in result.output.lines() {
+        out.push_str("  | ");
+        out.push_str(line);
+        out.push('\n');
+    }
+    let status = if !result.completed {
+        "incomplete".to_string()
+    } else {
+        format!("exit {}", result.exit_code)
+    };
+    out.push_str(&format!("  [{}, {:.1}s]", status, elapsed.as_secs_f64()));
+    out
+}
+
+/// Whole-disk listing used by `detect_install_disk` (sizes in bytes).
+const INSTALL_DISK_PROBE: &str = "lsblk -dnbP -o NAME,SIZE,TYPE,RM,RO";
+
+/// Device path of partition `number` on `disk`.
+///
+/// Disks whose name ends in a digit (`/dev/nvme0n1`, `/dev/mmcblk0`) take a
+/// `p` separator: `/dev/nvme0n1p2`, but `/dev/vda2`.
+pub fn partition_device(disk: &str, number: u32) -> String {
+    if disk.ends_with(|c: char| c.is_ascii_digit()) {
+        format!("{}p{}", disk, number)
+    } else {
+        format!("{}{}", disk, number)
+    }
+}
+
+/// Largest `TYPE="disk"` with `RM="0"` and `RO="0"` in `lsblk -dnbP` output.
+fn pick_install_disk(output: &str) -> Option<String> {
+    let field = |line: &str, key: &str| {
+        line.split_once(&format!("{}=\"", key))
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(value, _)| value.to_string())
+            .unwrap_or_default()
+    };
+    output
+        .lines()
+        .filter(|line| {
+            field(line, "TYPE") == "disk" && field(line, "RM") == "0" && field(line, "RO") == "0"
+        })
+        .filter_map(|line| {
+            let size: u64 = field(line, "SIZE").parse().ok()?;
+            let name = field(line, "NAME");
+            (size > 0 && !name.is_empty()).then_some((size, name))
+        })
+        // max_by_key keeps the last maximum; reverse so the first listed wins ties.
+        .rev()
+        .max_by_key(|(size, _)| *size)
+        .map(|(_, name)| format!("/dev/{}", name))
+}
+
+/// Single-quote `value` for the guest shell.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Printed by [`Executor::wait_for_shell_ready`]'s probe once the shell runs it.
+const SHELL_READY_MARKER: &str = "SHELL_READY_OK";
+
+/// Assembles [`SHELL_READY_MARKER`] with `printf` so command echo can't match.
+const SHELL_READY_PROBE: &str = "printf 'SHELL_%s\\n' READY_OK";
+
+/// Prefix of the delimiter lines around `read_file`'s base64 payload.
+const READ_FILE_MARKER: &str = "__READ_FILE_";
+
+/// Guest command printing `path` as base64 between delimiter lines. The
+/// markers are assembled by `printf`, so the command echo never contains them.
+fn read_file_command(path: &str) -> String {
+    format!(
+        "printf '{m}%s__\\n' START; base64 -w0 {path} && printf '\\n{m}%s__\\n' END",
+        m = READ_FILE_MARKER,
+        path = shell_quote(path),
+    )
+}
+
+/// The base64 text between the `read_file` delimiters, whitespace removed.
+fn read_file_payload(output: &str) -> Option<String> {
+    let start = format!("{}START__", READ_FILE_MARKER);
+    let end = format!("{}END__", READ_FILE_MARKER);
+    let mut lines = output.lines().map(str::trim);
+    lines.find(|line| *line == start)?;
+    let mut payload = String::new();
+    for line in lines {
+        if line == end {
+            return Some(payload);
+        }
+        payload.extend(line.chars().filter(|c| !c.is_whitespace()));
+    }
+    None
+}
+
+/// Decode standard (RFC 4648) base64 with optional `=` padding.
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let trimmed = encoded.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(trimmed.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in trimmed.bytes() {
+        buffer = (buffer << 6) | u32::from(value(c)?);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Prefix of the per-command delimiter lines emitted by `exec_many`.
+const EXEC_MANY_MARKER: &str = "__EXEC_MANY_";
+
+/// One `sh -c` script running `cmds` in order, each followed by a delimiter.
+fn exec_many_script(cmds: &[&str]) -> String {
+    let body: String = cmds
+        .iter()
+        .enumerate()
+        .map(|(index, cmd)| {
+            format!(
+                "( {} ); printf '\\n{}%s_%s__\\n' {} \"$?\"; ",
+                cmd, EXEC_MANY_MARKER, index
+            )
+        })
+        .collect();
+    format!("sh -c {}", shell_quote(&body))
+}
+
+/// Parse `__EXEC_MANY_<index>_<exit>__` into (index, exit code).
+fn parse_exec_many_marker(line: &str) -> Option<(usize, i32)> {
+    let (index, code) = line
+        .trim()
+        .strip_prefix(EXEC_MANY_MARKER)?
+        .strip_suffix("__")?
+        .split_once('_')?;
+    Some((index.parse().ok()?, code.parse().ok()?))
+}
+
+/// Split a batch result from `exec_many` into `count` per-command results.
+fn split_exec_many_output(batch: &ExecResult, count: usize) -> Vec<ExecResult> {
+    let mut results = Vec::with_capacity(count);
+    let mut segment: Vec<&str> = Vec::new();
+    for line in batch.output.lines() {
+        match parse_exec_many_marker(line) {
+            Some((index, exit_code)) if index == results.len() => {
+                results.push(ExecResult {
+                    completed: true,
+                    exit_code,
+                    output: segment.join("\n").trim().to_string(),
+                    aborted_on_error: false,
+                    stalled: false,
+                    disconnected: false,
+                    matched_pattern: None,
+                });
+                segment.clear();
+            }
+            _ => segment.push(line),
+        }
+    }
+    // The first command without a delimiter was the one interrupted; it keeps
+    // the trailing output. The rest never ran.
+    while results.len() < count {
+        let output = segment.join("\n").trim().to_string();
+        segment.clear();
+        results.push(ExecResult {
+            completed: false,
+            exit_code: -1,
+            output,
+            aborted_on_error: batch.aborted_on_error,
+            stalled: batch.stalled,
+            disconnected: batch.disconnected,
+            matched_pattern: batch.matched_pattern.clone(),
+        });
+    }
+    results
+}
+
+/// Wrap `cmd` so `stdin_lines` arrive on its stdin through a pipe.
+fn piped_stdin_command(cmd: &str, stdin_lines: &[&str]) -> String {
+    if stdin_lines.is_empty() {
+        return cmd.to_string();
+    }
+    let quoted: Vec<String> = stdin_lines.iter().map(|line| shell_quote(line)).collect();
+    format!("printf '%s\\n' {} | {}", quoted.join(" "), cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FakeExecutor, Reply};
+
+    #[test]
+    fn trace_lines_echo_command_then_output_and_status() {
+        assert_eq!(
+            trace_command("cat <<EOF\nhello\nEOF\n"),
+            "  $ cat <<EOF\n    hello\n    EOF"
+        );
+        let result = ExecResult {
+            completed: true,
+            exit_code: 1,
+            output: "a\nb\n".to_string(),
+            aborted_on_error: false,
+            stalled: false,
+            disconnected: false,
+            matched_pattern: None,
+        };
+        assert_eq!(
+            trace_output(&result, Duration::from_millis(1500)),
+            "  | a\n  | b\n  [exit 1, 1.5s]"
+        );
+    }
+
+    #[test]
+    fn outcome_names_why_a_command_failed() {
+        let result = |completed, exit_code, aborted_on_error, stalled, disconnected| ExecResult {
+            completed,
+            exit_code,
+            output: "mounting /sysroot\n[  OK  ] Reached target emergency.target.\n#".to_string(),
+            aborted_on_error,
+            stalled,
+            disconnected,
+            matched_pattern: None,
+        };
+        assert_eq!(result(true, 0, false, false, false).outcome(), Outcome::Ok);
+        assert_eq!(
+            result(true, 2, false, false, false).outcome(),
+            Outcome::NonZeroExit(2)
+        );
+        assert_eq!(
+            result(false, -1, false, false, false).outcome(),
+            Outcome::Timeout
+        );
+        assert_eq!(
+            result(false, -1, false, true, false).outcome(),
+            Outcome::Stalled
+        );
+        assert_eq!(
+            result(false, 255, false, false, true).outcome(),
+            Outcome::Disconnected
+        );
+        assert_eq!(
+            result(false, -1, true, false, false).outcome(),
+            Outcome::FatalPattern {
+                pattern: Some("Reached target emergency.target".to_string()),
+                line: "[  OK  ] Reached target emergency.target.".to_string(),
+            }
+        );
+
+        let mut custom = result(false, -1, true, false, false);
+        custom.output = "step 1\nrecstrap: checksum mismatch on base.tar\n#".to_string();
+        custom.matched_pattern = Some("checksum mismatch".to_string());
+        assert_eq!(
+            custom.outcome().to_string(),
+            "fatal pattern 'checksum mismatch' in: recstrap: checksum mismatch on base.tar"
+        );
+        custom.matched_pattern = None;
+        assert_eq!(custom.outcome().to_string(), "fatal pattern in: #");
+    }
+
+    #[test]
+    fn piped_stdin_command_pipes_every_line() {
+        assert_eq!(
+            piped_stdin_command(
+                "cryptsetup luksFormat --batch-mode /dev/vda2",
+                &["it's secret"]
+            ),
+            "printf '%s\\n' 'it'\\''s secret' | cryptsetup luksFormat --batch-mode /dev/vda2"
+        );
+        assert_eq!(
+            piped_stdin_command("passwd --stdin root", &["a", "b"]),
+            "printf '%s\\n' 'a' 'b' | passwd --stdin root"
+        );
+        assert_eq!(piped_stdin_command("true", &[]), "true");
+    }
+
+    #[test]
+    fn shell_ready_probe_needs_a_working_shell() {
+        assert!(!SHELL_READY_PROBE.contains(SHELL_READY_MARKER));
+        let mut shell = FakeExecutor::host_shell();
+        shell
+            .wait_for_shell_ready(Duration::from_secs(5))
+            .expect("host sh runs the probe");
+    }
+
+    #[test]
+    fn exec_many_returns_per_command_output_and_exit_codes() {
+        let mut shell = FakeExecutor::host_shell();
+        let results = shell
+            .exec_many(
+                &["echo one", "echo 'two'; exit 3", "printf 'a\\nb'", "true"],
+                Duration::from_secs(5),
+            )
+            .unwrap();
+
+        assert_eq!(shell.commands.len(), 1);
+        let summary: Vec<(i32, &str)> = results
+            .iter()
+            .map(|r| (r.exit_code, r.output.as_str()))
+            .collect();
+        assert_eq!(summary, [(0, "one"), (3, "two"), (0, "a\nb"), (0, "")]);
+        assert!(results.iter().all(|r| r.completed));
+    }
+
+    #[test]
+    fn exec_many_marks_commands_after_an_interruption_incomplete() {
+        let batch = ExecResult {
+            completed: false,
+            exit_code: -1,
+            output: "ok\n__EXEC_MANY_0_0__\npartial".to_string(),
+            aborted_on_error: false,
+            stalled: true,
+            disconnected: false,
+            matched_pattern: None,
+        };
+        let results = split_exec_many_output(&batch, 3);
+        assert!(results[0].success());
+        assert_eq!(results[1].output, "partial");
+        assert!(!results[1].completed && results[1].stalled);
+        assert!(results[2].output.is_empty() && !results[2].completed);
+    }
+
+    #[test]
+    fn exec_chain_stops_at_first_failure_when_requested() {
+        let mut shell = FakeExecutor::host_shell();
+        let cmds = ["true", "exit 4", "echo never"];
+
+        let stopped = shell
+            .exec_chain(&cmds, Duration::from_secs(5), true)
+            .unwrap();
+        assert_eq!(stopped.len(), 2);
+        assert_eq!(stopped[1].exit_code, 4);
+        assert_eq!(shell.commands.len(), 2);
+
+        let all = shell
+            .exec_chain(&cmds, Duration::from_secs(5), false)
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].output.trim(), "never");
+    }
+
+    #[test]
+    fn exec_long_fallback_flags_error_patterns_in_output() {
+        let mut shell = FakeExecutor::host_shell();
+        let patterns = ["No space left on device"];
+
+        let clean = shell
+            .exec_long("echo extracting", Duration::from_secs(60), &patterns)
+            .unwrap();
+        assert!(clean.success());
+
+        let full = shell
+            .exec_long(
+                "echo 'write /mnt/usr: No space left on device'",
+                Duration::from_secs(60),
+                &patterns,
+            )
+            .unwrap();
+        assert_eq!(full.exit_code, 0);
+        assert!(full.aborted_on_error && !full.success());
+    }
+
+    #[test]
+    fn read_file_round_trips_contents_byte_exact() {
+        let mut shell = FakeExecutor::host_shell();
+        let path = std::env::temp_dir().join(format!(
+            "install-tests-read-file-{}.conf",
+            std::process::id()
+        ));
+        let contents = "LANG=en_US.UTF-8\n  indented 'quoted' line\n\nno trailing newline";
+        std::fs::write(&path, contents).unwrap();
+
+        let read = shell.read_file(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(read.unwrap(), contents);
+        assert!(shell.read_file("/nonexistent/locale.conf").is_err());
+    }
+
+    #[test]
+    fn read_file_payload_ignores_echo_and_prompt_noise() {
+        let output = "printf '__READ_FILE_%s__\\n' START; base64 -w0 '/etc/hostname'\r\n\
+                      __READ_FILE_START__\r\n\
+                      bGV2aXRhdGUK\r\n\
+                      __READ_FILE_END__\r\n\
+                      [root@levitate ~]# ";
+        let payload = read_file_payload(output).unwrap();
+        assert_eq!(decode_base64(&payload).unwrap(), b"levitate\n");
+        assert_eq!(decode_base64("YQ==").unwrap(), b"a");
+        assert!(decode_base64("not base64!").is_none());
+    }
+
+    #[test]
+    fn exec_until_polls_until_the_predicate_holds() {
+        let mut exec = FakeExecutor::new()
+            .once("", Reply::Exit(1, String::new()))
+            .once("", Reply::Exit(1, String::new()));
+        let result = exec
+            .exec_until(
+                "test -b /dev/vda2",
+                Duration::from_secs(1),
+                &ExecResult::success,
+                Duration::ZERO,
+                10,
+            )
+            .unwrap();
+        assert!(result.success());
+        assert_eq!(exec.commands.len(), 3);
+    }
+
+    #[test]
+    fn exec_until_returns_last_result_when_attempts_run_out() {
+        let mut exec = (1..=4).fold(FakeExecutor::new(), |exec, n| {
+            exec.once("", Reply::Exit(1, format!("call {n}")))
+        });
+        let result = exec
+            .exec_until(
+                "false",
+                Duration::from_secs(1),
+                &ExecResult::success,
+                Duration::ZERO,
+                4,
+            )
+            .unwrap();
+        assert!(!result.success());
+        assert_eq!(result.output, "call 4");
+        assert_eq!(exec.commands.len(), 4);
+    }
+
+    #[test]
+    fn kexec_into_fails_unless_the_console_is_lost() {
+        let kexec = |reply| {
+            FakeExecutor::new()
+                .reply("sync; kexec -e", reply)
+                .kexec_into(
+                    "/mnt/boot/vmlinuz",
+                    Some("/mnt/boot/initramfs.img"),
+                    "root=/dev/vda2",
+                    Duration::from_secs(60),
+                )
+        };
+        assert!(kexec(Reply::Timeout).is_ok());
+
+        let unloaded = kexec(Reply::Exit(1, "Nothing has been loaded!".to_string()));
+        let message = unloaded.unwrap_err().to_string();
+        assert!(message.contains("exit 1") && message.contains("Nothing has been loaded!"));
+        assert!(kexec(Reply::ok("")).is_err());
+        assert!(kexec(Reply::Error("serial console closed")).is_err());
+    }
+
+    #[test]
+    fn kexec_into_surfaces_the_boot_wait_error() {
+        // Like a plain Console, whose boot waits refuse without a distro.
+        let mut console = FakeExecutor::new()
+            .reply("sync; kexec -e", Reply::Timeout)
+            .boot_fails("Console boot waits need a distro context");
+        let err = console
+            .kexec_into("/mnt/boot/vmlinuz", None, "", Duration::from_secs(60))
+            .unwrap_err();
+        assert!(err.to_string().contains("need a distro context"));
+        assert_eq!(
+            console.commands,
+            ["kexec -l '/mnt/boot/vmlinuz' --append=''", "sync; kexec -e"]
+        );
+    }
+
+    #[test]
+    fn pick_install_disk_prefers_largest_writable_fixed_disk() {
+        let lsblk = "NAME=\"sda\" SIZE=\"8589934592\" TYPE=\"disk\" RM=\"0\" RO=\"0\"\n\
+                     NAME=\"sr0\" SIZE=\"1073741824\" TYPE=\"rom\" RM=\"1\" RO=\"0\"\n\
+                     NAME=\"vda\" SIZE=\"21474836480\" TYPE=\"disk\" RM=\"0\" RO=\"0\"\n\
+                     NAME=\"sdb\" SIZE=\"64424509440\" TYPE=\"disk\" RM=\"1\" RO=\"0\"\n\
+                     NAME=\"vdb\" SIZE=\"42949672960\" TYPE=\"disk\" RM=\"0\" RO=\"1\"\n";
+        assert_eq!(pick_install_disk(lsblk).as_deref(), Some("/dev/vda"));
+        assert_eq!(
+            pick_install_disk("NAME=\"sr0\" SIZE=\"1\" TYPE=\"rom\" RM=\"1\" RO=\"1\"\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn partition_device_inserts_p_after_trailing_digit() {
+        assert_eq!(partition_device("/dev/vda", 2), "/dev/vda2");
+        assert_eq!(partition_device("/dev/sda", 1), "/dev/sda1");
+        assert_eq!(partition_device("/dev/nvme0n1", 1), "/dev/nvme0n1p1");
+    }
+
+    /// `cargo test exec_many_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore = "timing benchmark"]
+    fn exec_many_benchmark() {
+        let cmds: Vec<String> = (0..30).map(|i| format!("echo step-{}", i)).collect();
+        let cmds: Vec<&str> = cmds.iter().map(String::as_str).collect();
+        let mut shell = FakeExecutor::host_shell().latency(Duration::from_millis(50));
+
+        let start = std::time::Instant::now();
+        for cmd in &cmds {
+            shell.exec(cmd, Duration::from_secs(5)).unwrap();
+        }
+        let sequential = start.elapsed();
+
+        let start = std::time::Instant::now();
+        shell.exec_many(&cmds, Duration::from_secs(5)).unwrap();
+        let batched = start.elapsed();
+
+        println!(
+            "{} commands: sequential exec {:?}, exec_many {:?}",
+            cmds.len(),
+            sequential,
+            batched
+        );
+        assert!(batched < sequential);
+    }
+}
@@ -0,0 +1,170 @@
+//! Host-side fixtures the guest can reach over QEMU user networking.
+//!
+//! [`serve_dir`] starts a static HTTP server on the host loopback. A guest
+//! on the user `-netdev` reaches it at [`GUEST_HOST_ADDR`] without any port
+//! forwarding, so install steps can fetch packages from a controlled mirror
+//! instead of the internet. A `no_network()` guest (`restrict=on`) cannot
+//! reach the host this way.
+//!
+//! Only `GET` and `HEAD` of regular files are served; there are no
+//! directory listings, ranges or keep-alive.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// The host as seen from a QEMU user-networking guest.
+pub const GUEST_HOST_ADDR: &str = "10.0.2.2";
+
+/// A running [`serve_dir`] server; stops accepting when dropped.
+pub struct ServerHandle {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Base URL of the served directory from inside the guest.
+    pub fn guest_url(&self) -> String {
+        format!("http://{}:{}", GUEST_HOST_ADDR, self.addr.port())
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept so the thread sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Serve the files under `root` over HTTP on an ephemeral loopback port.
+///
+/// Returns the handle and the port. Each connection is answered on its own
+/// thread, so one slow download does not stall the next request.
+pub fn serve_dir(root: &Path) -> Result<(ServerHandle, u16)> {
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("resolving mirror root '{}'", root.display()))?;
+    let listener = TcpListener::bind("127.0.0.1:0").context("binding mirror server")?;
+    let addr = listener.local_addr()?;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let thread = {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let root = root.clone();
+                std::thread::spawn(move || {
+                    let _ = handle_request(stream, &root);
+                });
+            }
+        })
+    };
+
+    Ok((
+        ServerHandle {
+            addr,
+            stop,
+            thread: Some(thread),
+        },
+        addr.port(),
+    ))
+}
+
+fn handle_request(mut stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let response = match (method, resolve_target(root, target)) {
+        ("GET" | "HEAD", Some(path)) if path.is_file() => {
+            let body = std::fs::read(&path)?;
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )?;
+            if method == "GET" {
+                stream.write_all(&body)?;
+            }
+            None
+        }
+        ("GET" | "HEAD", _) => Some("404 Not Found"),
+        _ => Some("405 Method Not Allowed"),
+    };
+    if let Some(status) = response {
+        write!(
+            stream,
+            "HTTP/1.0 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        )?;
+    }
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)
+}
+
+/// Map a request target to a path under `root`, rejecting anything that
+/// would escape it.
+fn resolve_target(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next()?.strip_prefix('/')?;
+    let mut resolved = root.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::io::Read;
+
+    fn get(port: u16, target: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(stream, "GET {} HTTP/1.0\r\n\r\n", target).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_files_and_nothing_outside_root() {
+        let root = temp_dir("mirror");
+        std::fs::create_dir_all(root.join("repo")).unwrap();
+        std::fs::write(root.join("repo/pkg.txt"), "package bytes").unwrap();
+
+        let (server, port) = serve_dir(&root).unwrap();
+        assert_eq!(server.guest_url(), format!("http://10.0.2.2:{}", port));
+
+        let ok = get(port, "/repo/pkg.txt");
+        assert!(ok.starts_with("HTTP/1.0 200 OK"), "{}", ok);
+        assert!(ok.ends_with("\r\n\r\npackage bytes"), "{}", ok);
+        assert!(get(port, "/../etc/passwd").starts_with("HTTP/1.0 404"));
+        assert!(get(port, "/missing").starts_with("HTTP/1.0 404"));
+
+        drop(server);
+        assert!(
+            TcpStream::connect(("127.0.0.1", port)).is_err(),
+            "server still accepting after drop"
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,670 @@
+//! Read-only inspection of built artifacts (ISO images, initramfs archives).
+//!
+//! Reads the same ISO images and initramfs archives preflight verifies, so
+//! when a checklist reports "missing X" or "broken symlink" you can see what
+//! the image really contains. Parsing is done here, without host tools.
+//! Nothing here modifies an artifact; extraction copies out of it.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// ISO 9660 logical sector size.
+const ISO_SECTOR: u64 = 2048;
+
+/// Sector holding the primary volume descriptor.
+const PRIMARY_VOLUME_DESCRIPTOR_SECTOR: u64 = 16;
+
+/// Read the volume label (ISO 9660 volume identifier) of `iso`.
+pub fn iso_volume_label(iso: &Path) -> Result<String> {
+    let mut file = File::open(iso).with_context(|| format!("opening {}", iso.display()))?;
+    let mut descriptor = [0u8; ISO_SECTOR as usize];
+    file.seek(SeekFrom::Start(
+        PRIMARY_VOLUME_DESCRIPTOR_SECTOR * ISO_SECTOR,
+    ))
+    .and_then(|_| file.read_exact(&mut descriptor))
+    .with_context(|| format!("reading volume descriptor of {}", iso.display()))?;
+    parse_volume_label(&descriptor)
+        .with_context(|| format!("{} is not an ISO 9660 image", iso.display()))
+}
+
+/// Volume identifier from a primary volume descriptor sector.
+fn parse_volume_label(descriptor: &[u8]) -> Result<String> {
+    if descriptor.len() < 72 || &descriptor[..6] != b"\x01CD001" {
+        bail!("no primary volume descriptor");
+    }
+    Ok(String::from_utf8_lossy(&descriptor[40..72])
+        .trim_end()
+        .to_string())
+}
+
+/// One file or directory on an ISO.
+#[derive(Debug, Clone, PartialEq)]
+struct IsoEntry {
+    /// Absolute path, using Rock Ridge names when present.
+    path: String,
+    is_dir: bool,
+    size: u64,
+    /// First sector of the file's data.
+    extent: u64,
+}
+
+/// Directory nesting followed before the image counts as corrupt.
+const MAX_ISO_DEPTH: usize = 64;
+
+/// Every entry on `iso`, walking the directory tree from the primary volume
+/// descriptor's root record. Reads the image directly; no host tools.
+fn read_iso_entries(iso: &Path) -> Result<Vec<IsoEntry>> {
+    let mut file = File::open(iso).with_context(|| format!("opening {}", iso.display()))?;
+    let mut descriptor = [0u8; ISO_SECTOR as usize];
+    file.seek(SeekFrom::Start(
+        PRIMARY_VOLUME_DESCRIPTOR_SECTOR * ISO_SECTOR,
+    ))
+    .and_then(|_| file.read_exact(&mut descriptor))
+    .with_context(|| format!("reading volume descriptor of {}", iso.display()))?;
+    parse_volume_label(&descriptor)
+        .with_context(|| format!("{} is not an ISO 9660 image", iso.display()))?;
+    let root = parse_directory_record(&descriptor[156..190])
+        .with_context(|| format!("{} has no root directory record", iso.display()))?;
+
+    let mut entries = Vec::new();
+    let mut pending = vec![(String::new(), root.extent, root.size, 0)];
+    while let Some((dir, extent, size, depth)) = pending.pop() {
+        if depth > MAX_ISO_DEPTH {
+            bail!("{} nests directories deeper than {}", dir, MAX_ISO_DEPTH);
+        }
+        let mut data = vec![0u8; size as usize];
+        file.seek(SeekFrom::Start(extent * ISO_SECTOR))
+            .and_then(|_| file.read_exact(&mut data))
+            .with_context(|| format!("reading directory {}/ of {}", dir, iso.display()))?;
+        for record in directory_records(&data) {
+            let path = format!("{}/{}", dir, record.name);
+            if record.is_dir {
+                pending.push((path.clone(), record.extent, record.size, depth + 1));
+            }
+            entries.push(IsoEntry {
+                path,
+                is_dir: record.is_dir,
+                size: record.size,
+                extent: record.extent,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// A parsed ISO 9660 directory record.
+#[derive(Debug, PartialEq)]
+struct DirectoryRecord {
+    name: String,
+    is_dir: bool,
+    extent: u64,
+    size: u64,
+}
+
+/// Records of a directory extent, without `.` and `..`.
+///
+/// Records never cross a sector boundary; a zero length byte means the rest
+/// of the sector is padding.
+fn directory_records(data: &[u8]) -> Vec<DirectoryRecord> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let len = data[offset] as usize;
+        if len == 0 {
+            offset = (offset / ISO_SECTOR as usize + 1) * ISO_SECTOR as usize;
+            continue;
+        }
+        let Some(raw) = data.get(offset..offset + len) else {
+            break;
+        };
+        if let Some(record) = parse_directory_record(raw) {
+            if record.name != "." && record.name != ".." {
+                records.push(record);
+            }
+        }
+        offset += len;
+    }
+    records
+}
+
+/// Parse one directory record, preferring its Rock Ridge `NM` name.
+fn parse_directory_record(raw: &[u8]) -> Option<DirectoryRecord> {
+    if raw.len() < 34 {
+        return None;
+    }
+    let le_u32 = |at: usize| u32::from_le_bytes(raw[at..at + 4].try_into().unwrap());
+    let name_len = raw[32] as usize;
+    let id = raw.get(33..33 + name_len)?;
+    let iso_name = match id {
+        [0] => ".".to_string(),
+        [1] => "..".to_string(),
+        _ => {
+            let name = String::from_utf8_lossy(id);
+            let name = name.split(';').next().unwrap_or_default();
+            name.strip_suffix('.').unwrap_or(name).to_string()
+        }
+    };
+    // System use area: after the name, padded to an even offset.
+    let system_use = raw
+        .get(33 + name_len + (name_len + 1) % 2..)
+        .unwrap_or_default();
+    Some(DirectoryRecord {
+        name: rock_ridge_name(system_use).unwrap_or(iso_name),
+        is_dir: raw[25] & 0x02 != 0,
+        extent: u64::from(le_u32(2)),
+        size: u64::from(le_u32(10)),
+    })
+}
+
+/// The Rock Ridge alternate name (`NM` entries, possibly continued) in a
+/// directory record's system use area.
+fn rock_ridge_name(mut system_use: &[u8]) -> Option<String> {
+    let mut name: Option<Vec<u8>> = None;
+    while system_use.len() >= 4 {
+        let len = system_use[2] as usize;
+        if len < 4 || len > system_use.len() {
+            break;
+        }
+        if &system_use[..2] == b"NM" && len >= 5 {
+            name.get_or_insert_with(Vec::new)
+                .extend_from_slice(&system_use[5..len]);
+        }
+        system_use = &system_use[len..];
+    }
+    name.map(|name| String::from_utf8_lossy(&name).into_owned())
+}
+
+/// Print the volume label, image size, and every entry with its size.
+pub fn print_iso_listing(iso: &Path) -> Result<()> {
+    let label = iso_volume_label(iso)?;
+    let image_size = std::fs::metadata(iso)
+        .with_context(|| format!("reading {}", iso.display()))?
+        .len();
+    let mut entries = read_iso_entries(iso)?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let content: u64 = entries.iter().filter(|e| !e.is_dir).map(|e| e.size).sum();
+
+    println!("ISO:     {}", iso.display());
+    println!("Label:   {}", label);
+    println!(
+        "Size:    {} ({} in {} files)",
+        format_size(image_size),
+        format_size(content),
+        entries.iter().filter(|e| !e.is_dir).count()
+    );
+    println!();
+    for entry in entries {
+        if entry.is_dir {
+            println!("{:>10}  {}/", "-", entry.path);
+        } else {
+            println!("{:>10}  {}", format_size(entry.size), entry.path);
+        }
+    }
+    Ok(())
+}
+
+/// Copy `inner_path` out of `iso` to `dest`, or to stdout when `dest` is `None`.
+pub fn extract_iso_file(iso: &Path, inner_path: &str, dest: Option<&Path>) -> Result<()> {
+    let inner_path = format!("/{}", inner_path.trim_matches('/'));
+    let entry = match read_iso_entries(iso)?
+        .into_iter()
+        .find(|e| e.path == inner_path)
+    {
+        Some(entry) if entry.is_dir => bail!("{} is a directory", inner_path),
+        Some(entry) => entry,
+        None => bail!("{} is not on {}", inner_path, iso.display()),
+    };
+
+    let mut file = File::open(iso).with_context(|| format!("opening {}", iso.display()))?;
+    file.seek(SeekFrom::Start(entry.extent * ISO_SECTOR))
+        .with_context(|| format!("seeking to {} in {}", inner_path, iso.display()))?;
+    let mut data = file.take(entry.size);
+    match dest {
+        Some(dest) => {
+            let mut out =
+                File::create(dest).with_context(|| format!("creating {}", dest.display()))?;
+            std::io::copy(&mut data, &mut out)
+                .with_context(|| format!("writing {} to {}", inner_path, dest.display()))?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            std::io::copy(&mut data, &mut stdout)
+                .with_context(|| format!("writing {} to stdout", inner_path))?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Symlink hops followed before a path is reported as a loop (as Linux).
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Archive paths mapped to their symlink target (`None` for non-links).
+type ArchiveIndex = BTreeMap<String, Option<String>>;
+
+/// One entry of a newc cpio archive.
+#[derive(Debug, Clone, PartialEq)]
+struct CpioEntry {
+    path: String,
+    mode: u32,
+    size: u64,
+    /// Target of a symlink (its data), `None` otherwise.
+    link_target: Option<String>,
+    data: Vec<u8>,
+}
+
+/// Length of a newc header: magic plus thirteen 8-digit hex fields.
+const NEWC_HEADER_LEN: usize = 110;
+
+/// Every entry of the initramfs at `archive`, decompressed in memory.
+///
+/// Handles concatenated archives (e.g. early microcode before the main
+/// one); entries from later archives follow earlier ones.
+fn read_cpio_entries(archive: &Path) -> Result<Vec<CpioEntry>> {
+    let raw = std::fs::read(archive).with_context(|| format!("reading {}", archive.display()))?;
+    let bytes = match raw.as_slice() {
+        [0x1f, 0x8b, ..] => {
+            let mut out = Vec::new();
+            flate2::read::MultiGzDecoder::new(raw.as_slice())
+                .read_to_end(&mut out)
+                .with_context(|| format!("decompressing {}", archive.display()))?;
+            out
+        }
+        _ => match compression_name(&raw) {
+            Some(format) => bail!(
+                "{} is {}-compressed; only plain and gzip archives are read here \
+                 (decompress it first, e.g. `{} -dc {} > initramfs.cpio`)",
+                archive.display(),
+                format,
+                format,
+                archive.display()
+            ),
+            None => raw,
+        },
+    };
+    parse_newc(&bytes).with_context(|| format!("parsing {}", archive.display()))
+}
+
+/// Compression other than gzip recognised from magic bytes.
+fn compression_name(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some("zstd"),
+        [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some("xz"),
+        [0x02, 0x21, 0x4c, 0x18, ..] => Some("lz4"),
+        _ => None,
+    }
+}
+
+/// Parse newc (`070701`/`070702`) archives back to back in `bytes`.
+fn parse_newc(bytes: &[u8]) -> Result<Vec<CpioEntry>> {
+    let align = |offset: usize| (offset + 3) & !3;
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    loop {
+        // Zero padding may separate concatenated archives.
+        while bytes.get(offset) == Some(&0) {
+            offset += 1;
+        }
+        if offset >= bytes.len() {
+            return Ok(entries);
+        }
+        let header = bytes
+            .get(offset..offset + NEWC_HEADER_LEN)
+            .context("truncated cpio header")?;
+        if &header[..6] != b"070701" && &header[..6] != b"070702" {
+            bail!("unsupported cpio header at byte {} (only newc)", offset);
+        }
+        let field = |index: usize| -> Result<usize> {
+            let hex = std::str::from_utf8(&header[6 + index * 8..14 + index * 8])?;
+            Ok(usize::from_str_radix(hex, 16)?)
+        };
+        let (mode, size, name_size) = (field(1)? as u32, field(6)?, field(11)?);
+        let name_start = offset + NEWC_HEADER_LEN;
+        let name = bytes
+            .get(name_start..name_start + name_size.saturating_sub(1))
+            .context("truncated cpio name")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        let data_start = align(name_start + name_size);
+        let data = bytes
+            .get(data_start..data_start + size)
+            .with_context(|| format!("truncated data of {}", name))?;
+        offset = align(data_start + size);
+        if name == "TRAILER!!!" {
+            continue;
+        }
+        entries.push(CpioEntry {
+            link_target: (mode & 0o170000 == 0o120000)
+                .then(|| String::from_utf8_lossy(data).into_owned()),
+            path: name,
+            mode,
+            size: size as u64,
+            data: data.to_vec(),
+        });
+    }
+}
+
+/// Print every entry of the initramfs cpio at `archive` with its mode, size
+/// and symlink target, flagging symlinks that do not resolve inside it.
+pub fn print_cpio_listing(archive: &Path) -> Result<()> {
+    let mut entries = read_cpio_entries(archive)?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let index: ArchiveIndex = entries
+        .iter()
+        .map(|e| (normalize_archive_path(&e.path), e.link_target.clone()))
+        .collect();
+
+    let mut broken = 0;
+    let mut lines = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let path = normalize_archive_path(&entry.path);
+        let mut line = format!(
+            "{} {:>10}  /{}",
+            mode_string(entry.mode),
+            format_size(entry.size),
+            path
+        );
+        if let Some(target) = &entry.link_target {
+            line.push_str(&format!(" -> {}", target));
+            match resolve_archive_path(&index, &path) {
+                Ok(resolved) => line.push_str(&format!("  (/{})", resolved)),
+                Err(reason) => {
+                    broken += 1;
+                    line.push_str(&format!("  [BROKEN: {}]", reason));
+                }
+            }
+        }
+        lines.push(line);
+    }
+
+    println!("Archive: {}", archive.display());
+    println!("Entries: {} ({} broken symlinks)", entries.len(), broken);
+    println!();
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Write `inner_path` from the initramfs cpio at `archive` to `dest`, or to
+/// stdout when `dest` is `None`. Symlinks are followed inside the archive.
+pub fn extract_cpio_file(archive: &Path, inner_path: &str, dest: Option<&Path>) -> Result<()> {
+    let entries = read_cpio_entries(archive)?;
+    let index: ArchiveIndex = entries
+        .iter()
+        .map(|e| (normalize_archive_path(&e.path), e.link_target.clone()))
+        .collect();
+    let resolved = resolve_archive_path(&index, &normalize_archive_path(inner_path))
+        .map_err(|reason| anyhow::anyhow!("{} in {}: {}", inner_path, archive.display(), reason))?;
+    // A later archive overrides an earlier one, as when the kernel unpacks them.
+    let entry = entries
+        .iter()
+        .rev()
+        .find(|e| normalize_archive_path(&e.path) == resolved)
+        .with_context(|| format!("/{} is not in {}", resolved, archive.display()))?;
+    if entry.mode & 0o170000 == 0o040000 {
+        bail!("/{} is a directory", resolved);
+    }
+
+    match dest {
+        Some(dest) => std::fs::write(dest, &entry.data)
+            .with_context(|| format!("writing {}", dest.display()))?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&entry.data)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Archive path without leading `/` or `./`.
+fn normalize_archive_path(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Resolve `path` against the archive, following symlinks in every component
+/// the way the kernel would once the initramfs is unpacked as `/`.
+fn resolve_archive_path(index: &ArchiveIndex, path: &str) -> std::result::Result<String, String> {
+    let mut pending: Vec<String> = path
+        .split('/')
+        .rev()
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+        .collect();
+    let mut resolved: Vec<String> = Vec::new();
+    let mut hops = 0;
+
+    while let Some(component) = pending.pop() {
+        match component.as_str() {
+            "." => continue,
+            ".." => {
+                resolved.pop();
+                continue;
+            }
+            _ => resolved.push(component),
+        }
+        let current = resolved.join("/");
+        match index.get(&current) {
+            None => return Err(format!("/{} does not exist", current)),
+            Some(None) => {}
+            Some(Some(target)) => {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(format!("symlink loop at /{}", current));
+                }
+                resolved.pop();
+                if target.starts_with('/') {
+                    resolved.clear();
+                }
+                pending.extend(
+                    target
+                        .split('/')
+                        .rev()
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string),
+                );
+            }
+        }
+    }
+    Ok(resolved.join("/"))
+}
+
+/// `ls -l` style mode string (`drwxr-xr-x`, `lrwxrwxrwx`, ...).
+fn mode_string(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    out
+}
+
+/// Human-readable byte count (`512 B`, `4.0 KiB`, `1.5 GiB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_volume_label_reads_padded_identifier() {
+        let mut descriptor = vec![0u8; ISO_SECTOR as usize];
+        descriptor[..6].copy_from_slice(b"\x01CD001");
+        descriptor[40..72].fill(b' ');
+        descriptor[40..48].copy_from_slice(b"ACORNISO");
+        assert_eq!(parse_volume_label(&descriptor).unwrap(), "ACORNISO");
+
+        descriptor[1] = b'X';
+        assert!(parse_volume_label(&descriptor).is_err());
+    }
+
+    /// A directory record for `name` with an optional Rock Ridge name.
+    fn record(name: &[u8], rock_ridge: Option<&str>, is_dir: bool, extent: u32) -> Vec<u8> {
+        let mut raw = vec![0u8; 33];
+        raw[2..6].copy_from_slice(&extent.to_le_bytes());
+        raw[10..14].copy_from_slice(&1234u32.to_le_bytes());
+        raw[25] = if is_dir { 2 } else { 0 };
+        raw[32] = name.len() as u8;
+        raw.extend_from_slice(name);
+        if name.len() % 2 == 0 {
+            raw.push(0);
+        }
+        if let Some(nm) = rock_ridge {
+            raw.extend_from_slice(&[b'N', b'M', 5 + nm.len() as u8, 1, 0]);
+            raw.extend_from_slice(nm.as_bytes());
+        }
+        raw[0] = raw.len() as u8;
+        raw
+    }
+
+    #[test]
+    fn directory_records_prefer_rock_ridge_names() {
+        let mut extent = Vec::new();
+        extent.extend(record(&[0], None, true, 20));
+        extent.extend(record(&[1], None, true, 18));
+        extent.extend(record(b"VMLINUZ.;1", Some("vmlinuz"), false, 30));
+        extent.extend(record(b"LIVE", None, true, 40));
+        extent.resize(ISO_SECTOR as usize, 0);
+
+        assert_eq!(
+            directory_records(&extent),
+            [
+                DirectoryRecord {
+                    name: "vmlinuz".to_string(),
+                    is_dir: false,
+                    extent: 30,
+                    size: 1234,
+                },
+                DirectoryRecord {
+                    name: "LIVE".to_string(),
+                    is_dir: true,
+                    extent: 40,
+                    size: 1234,
+                },
+            ]
+        );
+    }
+
+    fn newc_entry(out: &mut Vec<u8>, name: &str, mode: u32, data: &[u8]) {
+        out.extend(
+            format!(
+                "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+                0, mode, 0, 0, 1, 0, data.len(), 0, 0, 0, 0, name.len() + 1, 0
+            )
+            .bytes(),
+        );
+        out.extend(name.bytes());
+        out.push(0);
+        out.resize((out.len() + 3) & !3, 0);
+        out.extend(data);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+
+    #[test]
+    fn parse_newc_reads_concatenated_archives() {
+        let mut bytes = Vec::new();
+        newc_entry(&mut bytes, "kernel", 0o040755, b"");
+        newc_entry(&mut bytes, "TRAILER!!!", 0, b"");
+        bytes.resize(bytes.len() + 512, 0);
+        newc_entry(&mut bytes, "./init", 0o100755, b"#!/bin/sh\n");
+        newc_entry(&mut bytes, "sbin", 0o120777, b"usr/bin");
+        newc_entry(&mut bytes, "TRAILER!!!", 0, b"");
+
+        let entries = parse_newc(&bytes).unwrap();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.mode, e.size, e.link_target.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("kernel", 0o040755, 0, None),
+                ("./init", 0o100755, 10, None),
+                ("sbin", 0o120777, 7, Some("usr/bin")),
+            ]
+        );
+        assert_eq!(entries[1].data, b"#!/bin/sh\n");
+        assert!(parse_newc(b"0707070000").is_err());
+    }
+
+    fn index(entries: &[(&str, Option<&str>)]) -> ArchiveIndex {
+        entries
+            .iter()
+            .map(|(path, target)| (path.to_string(), target.map(str::to_string)))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_archive_path_follows_relative_absolute_and_directory_links() {
+        let index = index(&[
+            ("lib64", Some("usr/lib")),
+            ("usr", None),
+            ("usr/lib", None),
+            ("usr/lib/libc.so.6", None),
+            ("usr/lib/libc.so", Some("libc.so.6")),
+            ("usr/lib/libm.so", Some("../../lib64/libc.so")),
+            ("sbin", Some("/nonexistent")),
+            ("loop", Some("loop")),
+        ]);
+        assert_eq!(
+            resolve_archive_path(&index, "lib64/libc.so").unwrap(),
+            "usr/lib/libc.so.6"
+        );
+        assert_eq!(
+            resolve_archive_path(&index, "usr/lib/libm.so").unwrap(),
+            "usr/lib/libc.so.6"
+        );
+        assert_eq!(
+            resolve_archive_path(&index, "sbin").unwrap_err(),
+            "/nonexistent does not exist"
+        );
+        assert!(resolve_archive_path(&index, "loop")
+            .unwrap_err()
+            .contains("symlink loop"));
+    }
+
+    #[test]
+    fn mode_string_matches_ls() {
+        assert_eq!(mode_string(0o100755), "-rwxr-xr-x");
+        assert_eq!(mode_string(0o040700), "drwx------");
+        assert_eq!(mode_string(0o120777), "lrwxrwxrwx");
+        assert_eq!(normalize_archive_path("./usr/bin/"), "usr/bin");
+    }
+
+    #[test]
+    fn format_size_picks_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(4096), "4.0 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
+}
@@ -16,31 +16,42 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub mod boot_injection;
+pub mod color;
+pub mod diagnostics;
 pub mod distro;
 pub mod executor;
+pub mod fixtures;
+pub mod inspect;
 pub mod preflight;
 pub mod qemu;
 pub mod scenarios;
 pub mod steps;
+#[cfg(test)]
+mod test_support;
 
 // Re-export commonly used items
 pub use boot_injection::{
-    boot_injection_from_env, BootInjection, FW_CFG_NAME as BOOT_INJECTION_FW_CFG_NAME,
+    boot_injection_from_env, BootInjection, BootInjectionBuilder,
+    FW_CFG_NAME as BOOT_INJECTION_FW_CFG_NAME,
 };
-pub use distro::{context_for_distro, DistroContext, AVAILABLE_DISTROS};
-pub use executor::{ExecResult, Executor};
+pub use distro::{
+    context_for_distro, context_for_distro_from_file, DistroContext, TomlContext, AVAILABLE_DISTROS,
+};
+pub use executor::{ExecResult, Executor, Outcome};
 pub use preflight::{
-    require_preflight, require_preflight_for_distro, require_preflight_with_iso_for_distro,
-    run_preflight, run_preflight_for_distro, run_preflight_with_iso, run_preflight_with_iso_distro,
-    PreflightCheck, PreflightResult,
+    check_host_environment, describe_preflight, print_preflight_explain, require_preflight,
+    require_preflight_for_distro, require_preflight_with_iso_for_distro, run_preflight,
+    run_preflight_for_distro, run_preflight_with_iso, run_preflight_with_iso_distro,
+    ChecklistDescription, PreflightCheck, PreflightResult,
 };
 pub use qemu::{
-    acquire_test_lock, create_disk, find_ovmf, find_ovmf_vars, kill_stale_qemu_processes, Console,
-    QemuBuilder, SerialExecutorExt,
+    acquire_test_lock, create_disk, find_ovmf, find_ovmf_vars, kill_stale_qemu_processes,
+    BootTimeline, Console, QemuBuilder, SerialExecutorExt, SerialTap, SshExecutor,
 };
 pub use steps::{
-    all_steps, all_steps_with_experimental, steps_for_phase, steps_for_phase_experimental,
-    CheckResult, CommandLog, Step, StepResult,
+    all_steps, all_steps_with_experimental, is_experimental, parse_step_range, phase_name,
+    run_phase_parallel, run_steps_until_failure, steps_for_phase, steps_for_phase_experimental,
+    steps_in_range, CheckResult, CommandLog, Step, StepResult,
 };
 
 pub fn enforce_policy_guard(entrypoint: &str) -> Result<()> {
@@ -14,6 +14,9 @@
 //! - Missing udev rules (critical for device discovery)
 //!
 //! If preflight fails, we know the ISO is broken WITHOUT waiting for QEMU.
+//!
+//! [`check_host_environment`] does the same for the host: QEMU, OVMF and
+//! `ssh` must be installed before any scenario spawns a VM.
 
 use anyhow::{Context, Result};
 use colored::Colorize;
@@ -30,9 +33,11 @@ use fsdbg::checklist::{ChecklistType, VerificationReport};
 use fsdbg::cpio::CpioReader;
 use fsdbg::iso::IsoReader;
 use leviso_cheat_guard::cheat_bail;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
 
 #[derive(Debug, Deserialize)]
 struct RunManifest {
@@ -61,11 +66,13 @@ pub struct PreflightResult {
     pub live_initramfs: Option<PreflightCheck>,
     pub install_initramfs: Option<PreflightCheck>,
     pub iso: Option<PreflightCheck>,
+    /// Pre-built disk image (`*.qcow2` in the artifact dir), if one ships.
+    pub disk: Option<PreflightCheck>,
     pub overall_pass: bool,
 }
 
 /// Result of a single preflight check
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreflightCheck {
     pub name: String,
     pub passed: bool,
@@ -338,6 +345,24 @@ fn release_product_root_dir_for_distro(distro_id: &str, product_name: &str) -> P
         .join(product_name)
 }
 
+/// Kernel release (`uname -r`) the distro's ISO ships, per its contract.
+///
+/// Read from `build.kernel.release_path` in the kernel build output, so it
+/// is only available where that kernel was built.
+pub fn expected_kernel_release(distro_id: &str) -> Result<String> {
+    let bundle = load_variant_contract_bundle_for_distro_from(&workspace_root(), distro_id)
+        .with_context(|| format!("loading variant contract for '{}'", distro_id))?;
+    let path =
+        kernel_output_dir_for_distro(distro_id).join(&bundle.contract.build.kernel.release_path);
+    let release = fs::read_to_string(&path)
+        .with_context(|| format!("reading kernel release '{}'", path.display()))?;
+    let release = release.trim();
+    if release.is_empty() {
+        anyhow::bail!("kernel release file '{}' is empty", path.display());
+    }
+    Ok(release.to_string())
+}
+
 fn kernel_output_dir_for_distro(distro_id: &str) -> PathBuf {
     workspace_root()
         .join(".artifacts/kernel")
@@ -345,6 +370,15 @@ fn kernel_output_dir_for_distro(distro_id: &str) -> PathBuf {
         .join("current")
 }
 
+/// A successful release run that produced an ISO.
+#[derive(Debug)]
+struct RunCandidate {
+    /// `finished_at_utc`, falling back to `created_at_utc`.
+    sort_key: String,
+    run_dir: PathBuf,
+    iso_path: PathBuf,
+}
+
 fn resolve_latest_successful_run_dir(
     run_root: &Path,
     iso_filename: &str,
@@ -353,7 +387,7 @@ fn resolve_latest_successful_run_dir(
         return Ok(None);
     }
 
-    let mut candidates: Vec<(String, PathBuf)> = Vec::new();
+    let mut candidates = Vec::new();
     for entry in fs::read_dir(run_root)
         .with_context(|| format!("reading run output directory '{}'", run_root.display()))?
     {
@@ -388,11 +422,84 @@ fn resolve_latest_successful_run_dir(
         if !iso_candidate.is_file() {
             continue;
         }
-        candidates.push((sort_key, run_dir));
+        candidates.push(RunCandidate {
+            sort_key,
+            run_dir,
+            iso_path: iso_candidate,
+        });
     }
 
-    candidates.sort_by(|a, b| b.0.cmp(&a.0));
-    Ok(candidates.into_iter().next().map(|(_, run_dir)| run_dir))
+    sort_run_candidates(&mut candidates);
+    for warning in run_candidate_warnings(&candidates) {
+        eprintln!("  {} {}", "warning:".yellow(), warning);
+    }
+    let Some(chosen) = candidates.into_iter().next() else {
+        return Ok(None);
+    };
+    if let Some(warning) = stale_legacy_iso_warning(&run_root.join(iso_filename), &chosen.iso_path)
+    {
+        eprintln!("  {} {}", "warning:".yellow(), warning);
+    }
+    Ok(Some(chosen.run_dir))
+}
+
+/// Newest first; runs finishing in the same second are ordered by run
+/// directory name (also newest first), so the pick never depends on
+/// `read_dir` order.
+fn sort_run_candidates(candidates: &mut [RunCandidate]) {
+    candidates.sort_by(|a, b| {
+        b.sort_key
+            .cmp(&a.sort_key)
+            .then_with(|| b.run_dir.file_name().cmp(&a.run_dir.file_name()))
+    });
+}
+
+/// Say which of several sorted candidates is used, and flag timestamp ties.
+fn run_candidate_warnings(candidates: &[RunCandidate]) -> Vec<String> {
+    let [chosen, runner_up, ..] = candidates else {
+        return Vec::new();
+    };
+    let mut warnings = vec![format!(
+        "{} successful runs with an ISO; using '{}' ({})",
+        candidates.len(),
+        chosen.iso_path.display(),
+        chosen.sort_key
+    )];
+    if chosen.sort_key == runner_up.sort_key {
+        warnings.push(format!(
+            "runs '{}' and '{}' both finished at {}; picked the first by run directory name",
+            chosen.run_dir.display(),
+            runner_up.run_dir.display(),
+            chosen.sort_key
+        ));
+    }
+    warnings
+}
+
+/// Warn when a legacy flat-path ISO sits beside the run history with a
+/// different mtime than the ISO about to be tested: usually a rebuild
+/// that wrote one path while the other is what gets booted.
+pub(crate) fn stale_legacy_iso_warning(legacy_iso: &Path, chosen_iso: &Path) -> Option<String> {
+    if legacy_iso == chosen_iso {
+        return None;
+    }
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let (legacy, chosen) = (modified(legacy_iso)?, modified(chosen_iso)?);
+    if legacy == chosen {
+        return None;
+    }
+    let newer = if legacy > chosen {
+        "the legacy ISO is newer"
+    } else {
+        "the run ISO is newer"
+    };
+    Some(format!(
+        "legacy ISO '{}' and run ISO '{}' differ in mtime ({}); testing '{}'",
+        legacy_iso.display(),
+        chosen_iso.display(),
+        newer,
+        chosen_iso.display()
+    ))
 }
 
 fn load_run_manifest(run_dir: &Path) -> Result<Option<RunManifest>> {
@@ -457,6 +564,7 @@ fn run_preflight_with_iso_distro_layout(
         live_initramfs: None,
         install_initramfs: None,
         iso: None,
+        disk: None,
         overall_pass: true,
     };
     let resolved_iso_path = iso_filename
@@ -480,6 +588,40 @@ fn run_preflight_with_iso_distro_layout(
     }
 
     let live_path = runtime_artifacts.initramfs_live.clone();
+    let disk_path = find_qcow2_file(iso_dir);
+    let cache_dir = preflight_cache_dir();
+    let cache_key = resolved_iso_path
+        .as_deref()
+        .zip(checks_fingerprint())
+        .map(|(iso, checks)| {
+            artifact_fingerprint(
+                distro_id,
+                checks,
+                &[
+                    Some(live_path.as_path()),
+                    runtime_artifacts.initramfs_installed.as_deref(),
+                    Some(iso),
+                    disk_path.as_deref(),
+                ],
+            )
+        });
+    if let Some(cached) = cache_key
+        .as_deref()
+        .and_then(|key| load_cached_artifact_checks(&cache_dir, distro_id, key))
+    {
+        println!(
+            "  {} preflight (cached): artifacts unchanged since last pass",
+            "✓".green()
+        );
+        result.live_initramfs = cached.live_initramfs;
+        result.install_initramfs = cached.install_initramfs;
+        result.iso = cached.iso;
+        result.disk = cached.disk;
+        println!();
+        print_summary(&result);
+        return Ok(result);
+    }
+
     if live_path.exists() {
         result.live_initramfs = Some(verify_artifact(&live_path, ChecklistType::LiveInitramfs)?);
         if !result.live_initramfs.as_ref().unwrap().passed {
@@ -547,11 +689,138 @@ fn run_preflight_with_iso_distro_layout(
         result.overall_pass = false;
     }
 
+    // Disk images are optional; only verify one if the build shipped it.
+    if let Some(disk_path) = disk_path {
+        result.disk = Some(verify_artifact(&disk_path, ChecklistType::Qcow2)?);
+        if !result.disk.as_ref().unwrap().passed {
+            result.overall_pass = false;
+        }
+    }
+
+    // Only passing results are cached so failures always re-verify and re-print.
+    if let (true, Some(key)) = (result.overall_pass, cache_key) {
+        let cached = CachedArtifactChecks {
+            fingerprint: key,
+            live_initramfs: result.live_initramfs.clone(),
+            install_initramfs: result.install_initramfs.clone(),
+            iso: result.iso.clone(),
+            disk: result.disk.clone(),
+        };
+        if let Err(e) = store_cached_artifact_checks(&cache_dir, distro_id, &cached) {
+            println!(
+                "  {} Could not write preflight cache: {:#}",
+                "WARN".yellow(),
+                e
+            );
+        }
+    }
+
     println!();
     print_summary(&result);
     Ok(result)
 }
 
+/// fsdbg artifact results persisted under `.artifacts/preflight-cache/`.
+///
+/// The contract conformance check is cheap and depends on more than the
+/// artifacts, so it always runs fresh; only the fsdbg passes are cached.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedArtifactChecks {
+    fingerprint: String,
+    live_initramfs: Option<PreflightCheck>,
+    install_initramfs: Option<PreflightCheck>,
+    iso: Option<PreflightCheck>,
+    disk: Option<PreflightCheck>,
+}
+
+fn preflight_cache_dir() -> PathBuf {
+    workspace_root().join(".artifacts/preflight-cache")
+}
+
+fn preflight_cache_path(cache_dir: &Path, distro_id: &str) -> PathBuf {
+    cache_dir.join(format!("{}.json", distro_id))
+}
+
+/// Identity of the checks themselves: this crate's version (which owns the
+/// qcow2 checks) and a hash of every fsdbg checklist's items.
+///
+/// A newer fsdbg with added or renamed items changes it, so artifacts that
+/// passed the old checklist are verified again. The hash is a CRC-32, stable
+/// across Rust releases, and is computed once per process. `None` when the
+/// items cannot be listed: nothing is cached then.
+fn checks_fingerprint() -> Option<&'static str> {
+    static FINGERPRINT: OnceLock<Option<String>> = OnceLock::new();
+    FINGERPRINT
+        .get_or_init(|| {
+            let mut crc = flate2::Crc::new();
+            for checklist in [
+                ChecklistType::LiveInitramfs,
+                ChecklistType::InstallInitramfs,
+                ChecklistType::Iso,
+            ] {
+                crc.update(checklist.name().as_bytes());
+                for item in try_checklist_items(checklist).ok()? {
+                    crc.update(b"\0");
+                    crc.update(item.as_bytes());
+                }
+                crc.update(b"\n");
+            }
+            Some(format!("{}+{:08x}", env!("CARGO_PKG_VERSION"), crc.sum()))
+        })
+        .as_deref()
+}
+
+/// Identity of the verified artifacts (path, size and mtime of each) and of
+/// the `checks` run on them.
+///
+/// Same idea as the scenario input fingerprint: any rebuild touches the
+/// mtime, so a changed artifact never matches a stale cache entry.
+fn artifact_fingerprint(distro_id: &str, checks: &str, paths: &[Option<&Path>]) -> String {
+    let mut parts = vec![
+        format!("distro:{}", distro_id),
+        format!("checks:{}", checks),
+    ];
+    for path in paths.iter().flatten() {
+        let identity = fs::metadata(path).ok().and_then(|meta| {
+            let mtime = meta
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?;
+            Some(format!("{}:{}", meta.len(), mtime.as_nanos()))
+        });
+        parts.push(format!(
+            "{}:{}",
+            path.display(),
+            identity.as_deref().unwrap_or("missing")
+        ));
+    }
+    parts.join("|")
+}
+
+/// Load cached artifact checks if present, parseable, and for `fingerprint`.
+fn load_cached_artifact_checks(
+    cache_dir: &Path,
+    distro_id: &str,
+    fingerprint: &str,
+) -> Option<CachedArtifactChecks> {
+    let raw = fs::read(preflight_cache_path(cache_dir, distro_id)).ok()?;
+    let cached: CachedArtifactChecks = serde_json::from_slice(&raw).ok()?;
+    (cached.fingerprint == fingerprint).then_some(cached)
+}
+
+fn store_cached_artifact_checks(
+    cache_dir: &Path,
+    distro_id: &str,
+    cached: &CachedArtifactChecks,
+) -> Result<()> {
+    fs::create_dir_all(cache_dir).with_context(|| format!("Creating {}", cache_dir.display()))?;
+    let path = preflight_cache_path(cache_dir, distro_id);
+    let json = serde_json::to_string_pretty(cached)?;
+    fs::write(&path, json).with_context(|| format!("Writing {}", path.display()))?;
+    Ok(())
+}
+
 struct CanonicalRuntimeArtifactNames {
     rootfs_image: String,
     initramfs_live: String,
@@ -715,9 +984,251 @@ fn find_iso_file(dir: &Path) -> Option<std::path::PathBuf> {
     }
 }
 
+/// Find any .qcow2 file in the given directory.
+fn find_qcow2_file(dir: &Path) -> Option<PathBuf> {
+    let mut images: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("qcow2"))
+        .collect();
+    images.sort();
+    images.into_iter().next()
+}
+
+/// qcow2 header magic: `QFI\xfb`.
+const QCOW2_MAGIC: [u8; 4] = *b"QFI\xfb";
+
+/// Kernel path checked inside the image (the ESP is mounted at /boot).
+const DISK_KERNEL_PATH: &str = "/boot/vmlinuz";
+
+/// Bootloader paths checked inside the image; any one is enough.
+const DISK_BOOTLOADER_PATHS: &[&str] = &[
+    "/boot/EFI/BOOT/BOOTX64.EFI",
+    "/boot/EFI/systemd/systemd-bootx64.efi",
+];
+
+/// Filesystem types accepted as the root filesystem.
+const DISK_ROOT_FS_TYPES: &[&str] = &["ext4", "xfs", "btrfs"];
+
+/// Names of the disk image checks, as reported by `verify_qcow2` and listed
+/// by `describe_preflight`.
+const DISK_CHECK_HEADER: &str = "qcow2 header";
+const DISK_CHECK_METADATA: &str = "qcow2 metadata";
+const DISK_CHECK_ESP: &str = "EFI system partition";
+const DISK_CHECK_ROOT: &str = "root filesystem";
+const DISK_CHECK_BOOTLOADER: &str = "bootloader";
+
+/// Validate the qcow2 header and return the format version.
+fn check_qcow2_header(path: &Path) -> Result<u32> {
+    use std::io::Read;
+
+    let mut header = [0u8; 8];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .with_context(|| format!("Failed to read qcow2 header: {}", path.display()))?;
+    if header[..4] != QCOW2_MAGIC {
+        anyhow::bail!("bad magic {:02x?} (not a qcow2 image)", &header[..4]);
+    }
+    let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    if version != 2 && version != 3 {
+        anyhow::bail!("unsupported qcow2 version {}", version);
+    }
+    Ok(version)
+}
+
+/// Parse `guestfish list-filesystems` output into `(device, fstype)` pairs.
+fn parse_guestfish_filesystems(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(device, fstype)| (device.trim().to_string(), fstype.trim().to_string()))
+        .collect()
+}
+
+/// Run a read-only guestfish command against a qcow2 image.
+fn guestfish(path: &Path, args: &[&str]) -> Result<String> {
+    let output = std::process::Command::new("guestfish")
+        .arg("--ro")
+        .arg("--format=qcow2")
+        .arg("-a")
+        .arg(path)
+        .args(args)
+        .output()
+        .context("Failed to run guestfish (is libguestfs installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "guestfish failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Env var opting in to the guestfish partition and boot file checks.
+const ENV_PREFLIGHT_GUESTFISH: &str = "LEVITATE_PREFLIGHT_GUESTFISH";
+
+/// Run `qemu-img check` on `path`, accepting leaked clusters.
+///
+/// Exit 3 means only leaks (wasted space, harmless); 2 is corruption and
+/// 1 means the check itself could not run.
+fn qemu_img_check(path: &Path) -> std::result::Result<(), String> {
+    let output = std::process::Command::new("qemu-img")
+        .args(["check", "-f", "qcow2"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("running qemu-img: {}", e))?;
+    match output.status.code() {
+        Some(0) | Some(3) => Ok(()),
+        _ => Err(format!(
+            "qemu-img check failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .chain(String::from_utf8_lossy(&output.stderr).lines())
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or("no output")
+                .trim()
+        )),
+    }
+}
+
+/// Verify a pre-built qcow2 disk image.
+///
+/// The header is checked natively and the image's metadata with
+/// `qemu-img check`, which preflight already requires. Partition and boot
+/// file checks need libguestfs and only run with
+/// `LEVITATE_PREFLIGHT_GUESTFISH=1`.
+fn verify_qcow2(path: &Path) -> Result<PreflightCheck> {
+    let name = ChecklistType::Qcow2.name();
+    let mut checks: Vec<(String, std::result::Result<(), String>)> = Vec::new();
+
+    match check_qcow2_header(path) {
+        Ok(_) => checks.push((DISK_CHECK_HEADER.to_string(), Ok(()))),
+        Err(e) => {
+            checks.push((DISK_CHECK_HEADER.to_string(), Err(format!("{:#}", e))));
+            return Ok(print_disk_check(name, checks));
+        }
+    }
+    checks.push((DISK_CHECK_METADATA.to_string(), qemu_img_check(path)));
+    if std::env::var(ENV_PREFLIGHT_GUESTFISH).as_deref() != Ok("1") {
+        return Ok(print_disk_check(name, checks));
+    }
+
+    let filesystems = match guestfish(path, &["run", ":", "list-filesystems"]) {
+        Ok(out) => parse_guestfish_filesystems(&out),
+        Err(e) => {
+            checks.push(("partition table".to_string(), Err(format!("{:#}", e))));
+            return Ok(print_disk_check(name, checks));
+        }
+    };
+    let esp = filesystems.iter().find(|(_, fstype)| fstype == "vfat");
+    let root = filesystems
+        .iter()
+        .find(|(_, fstype)| DISK_ROOT_FS_TYPES.contains(&fstype.as_str()));
+    checks.push((
+        DISK_CHECK_ESP.to_string(),
+        esp.map(|_| ())
+            .ok_or_else(|| "no vfat filesystem".to_string()),
+    ));
+    checks.push((
+        DISK_CHECK_ROOT.to_string(),
+        root.map(|_| ()).ok_or_else(|| {
+            format!(
+                "none of {} found in {:?}",
+                DISK_ROOT_FS_TYPES.join("/"),
+                filesystems
+            )
+        }),
+    ));
+    let (Some((esp_dev, _)), Some((root_dev, _))) = (esp, root) else {
+        return Ok(print_disk_check(name, checks));
+    };
+
+    let root_mount = root_dev.clone();
+    let esp_mount = format!("{}:/boot", esp_dev);
+    let mut args = vec![
+        "-m",
+        &root_mount,
+        "-m",
+        &esp_mount,
+        "exists",
+        DISK_KERNEL_PATH,
+    ];
+    for bootloader in DISK_BOOTLOADER_PATHS {
+        args.extend([":", "exists", bootloader]);
+    }
+    match guestfish(path, &args) {
+        Ok(out) => {
+            let found: Vec<bool> = out.lines().map(|line| line.trim() == "true").collect();
+            checks.push((
+                DISK_KERNEL_PATH.to_string(),
+                if found.first() == Some(&true) {
+                    Ok(())
+                } else {
+                    Err("Missing".to_string())
+                },
+            ));
+            checks.push((
+                DISK_CHECK_BOOTLOADER.to_string(),
+                if found.iter().skip(1).any(|&f| f) {
+                    Ok(())
+                } else {
+                    Err(format!("none of {}", DISK_BOOTLOADER_PATHS.join(", ")))
+                },
+            ));
+        }
+        Err(e) => checks.push(("mount filesystems".to_string(), Err(format!("{:#}", e)))),
+    }
+
+    Ok(print_disk_check(name, checks))
+}
+
+/// Build and print a disk image check from individual results.
+fn print_disk_check(
+    name: &str,
+    checks: Vec<(String, std::result::Result<(), String>)>,
+) -> PreflightCheck {
+    let details: Vec<String> = checks
+        .iter()
+        .filter_map(|(item, outcome)| outcome.as_ref().err().map(|msg| (item, msg)))
+        .map(|(item, msg)| format!("FAIL: {} - {}", item, msg))
+        .collect();
+    let check = PreflightCheck {
+        name: name.to_string(),
+        passed: details.is_empty(),
+        total_checks: checks.len(),
+        passed_checks: checks.len() - details.len(),
+        failures: details.len(),
+        details,
+    };
+
+    if check.passed {
+        println!(
+            "{} ({}/{} checks)",
+            "PASS".green(),
+            check.passed_checks,
+            check.total_checks
+        );
+    } else {
+        println!(
+            "{} ({}/{} checks, {} failed)",
+            "FAIL".red().bold(),
+            check.passed_checks,
+            check.total_checks,
+            check.failures
+        );
+        for detail in &check.details {
+            println!("    {}", detail.red());
+        }
+    }
+    check
+}
+
 /// Verify an artifact against its checklist.
 ///
-/// Handles CPIO (initramfs) and ISO formats based on the checklist type.
+/// Handles CPIO (initramfs), ISO and qcow2 formats based on the checklist type.
 fn verify_artifact(path: &Path, checklist_type: ChecklistType) -> Result<PreflightCheck> {
     let name = checklist_type.name();
     print!("  Checking {}... ", name);
@@ -756,8 +1267,9 @@ fn verify_artifact(path: &Path, checklist_type: ChecklistType) -> Result<Preflig
             };
             fsdbg::checklist::iso::verify(&reader)
         }
-        ChecklistType::AuthAudit | ChecklistType::Qcow2 => {
-            // These checklist types are not used in preflight verification
+        ChecklistType::Qcow2 => return verify_qcow2(path),
+        ChecklistType::AuthAudit => {
+            // This checklist type is not used in preflight verification
             return Ok(PreflightCheck {
                 name: name.to_string(),
                 passed: true,
@@ -812,6 +1324,246 @@ fn verify_artifact(path: &Path, checklist_type: ChecklistType) -> Result<Preflig
     Ok(check)
 }
 
+/// One group of checks preflight applies, as listed by `--explain`.
+#[derive(Debug, Clone)]
+pub struct ChecklistDescription {
+    pub name: String,
+    pub items: Vec<String>,
+}
+
+/// Every check preflight would run for `distro_id`, without opening artifacts.
+///
+/// Groups follow the order of `run_preflight_with_iso_distro`. The disk image
+/// group only applies when a `.qcow2` ships next to the ISO.
+pub fn describe_preflight(distro_id: &str) -> Vec<ChecklistDescription> {
+    fn group<I, T>(name: &str, items: I) -> ChecklistDescription
+    where
+        I: IntoIterator<Item = T>,
+        T: ToString,
+    {
+        ChecklistDescription {
+            name: name.to_string(),
+            items: items.into_iter().map(|item| item.to_string()).collect(),
+        }
+    }
+
+    let mut groups = vec![
+        group(
+            "Contract conformance",
+            [
+                "variant contract declaration is valid".to_string(),
+                "build runtime: rootfs image, live initramfs and overlay image match the contract"
+                    .to_string(),
+                "live boot runtime: live overlay dir and rootfs source pointer (live-boot runs only)"
+                    .to_string(),
+                format!(
+                    "kernel recipe installed in {}",
+                    kernel_output_dir_for_distro(distro_id).display()
+                ),
+                "build evidence script passes".to_string(),
+            ],
+        ),
+        group(
+            ChecklistType::LiveInitramfs.name(),
+            checklist_items(ChecklistType::LiveInitramfs),
+        ),
+    ];
+    if distro_id == "levitate" {
+        groups.push(group(
+            ChecklistType::InstallInitramfs.name(),
+            checklist_items(ChecklistType::InstallInitramfs),
+        ));
+    }
+    groups.push(group(
+        ChecklistType::Iso.name(),
+        checklist_items(ChecklistType::Iso),
+    ));
+
+    let guestfish_only = format!("(only with {}=1)", ENV_PREFLIGHT_GUESTFISH);
+    let disk_items = vec![
+        format!("{} (magic + version 2/3)", DISK_CHECK_HEADER),
+        format!("{} (qemu-img check)", DISK_CHECK_METADATA),
+        format!("{} (vfat) {}", DISK_CHECK_ESP, guestfish_only),
+        format!(
+            "{} ({}) {}",
+            DISK_CHECK_ROOT,
+            DISK_ROOT_FS_TYPES.join("/"),
+            guestfish_only
+        ),
+        format!("{} {}", DISK_KERNEL_PATH, guestfish_only),
+        format!(
+            "{}: one of {} {}",
+            DISK_CHECK_BOOTLOADER,
+            DISK_BOOTLOADER_PATHS.join(", "),
+            guestfish_only
+        ),
+    ];
+    groups.push(group(
+        &format!(
+            "{} (only if a .qcow2 is present)",
+            ChecklistType::Qcow2.name()
+        ),
+        disk_items,
+    ));
+    groups
+}
+
+/// Items of an fsdbg checklist, in checklist order.
+///
+/// fsdbg only exposes `verify`, so the checklist runs against an empty
+/// archive of the right format written to a temp file: every item fails
+/// and is named in the report. If that fails, the single item says why.
+fn checklist_items(checklist_type: ChecklistType) -> Vec<String> {
+    try_checklist_items(checklist_type)
+        .unwrap_or_else(|err| vec![format!("(items unavailable: {:#})", err)])
+}
+
+/// [`checklist_items`], failing instead of describing the failure.
+fn try_checklist_items(checklist_type: ChecklistType) -> Result<Vec<String>> {
+    // Unique per call: parallel callers in one process must not share it.
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "install-tests-explain-{}-{}-{}",
+        checklist_type.name().replace(' ', "-"),
+        std::process::id(),
+        CALLS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let report = (|| -> Result<VerificationReport> {
+        Ok(match checklist_type {
+            ChecklistType::Iso => {
+                fs::write(&path, empty_iso())?;
+                fsdbg::checklist::iso::verify(&IsoReader::open(&path)?)
+            }
+            ChecklistType::InstallInitramfs => {
+                fs::write(&path, empty_cpio())?;
+                fsdbg::checklist::install_initramfs::verify(&CpioReader::open(&path)?)
+            }
+            ChecklistType::LiveInitramfs => {
+                fs::write(&path, empty_cpio())?;
+                fsdbg::checklist::live_initramfs::verify(&CpioReader::open(&path)?)
+            }
+            other => anyhow::bail!("preflight does not run the {} checklist", other.name()),
+        })
+    })();
+    let _ = fs::remove_file(&path);
+    Ok(report?
+        .results
+        .iter()
+        .map(|result| result.item.to_string())
+        .collect())
+}
+
+/// A newc cpio archive holding only its trailer.
+fn empty_cpio() -> Vec<u8> {
+    let name = "TRAILER!!!";
+    let mut archive = format!(
+        "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{}\0",
+        0,
+        0,
+        0,
+        0,
+        1,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        name.len() + 1,
+        0,
+        name
+    )
+    .into_bytes();
+    archive.resize(archive.len().div_ceil(4) * 4, 0);
+    archive
+}
+
+/// ISO 9660 logical sector size.
+const ISO_SECTOR: usize = 2048;
+
+/// An ISO 9660 image with an empty root directory: system area, primary
+/// volume descriptor (sector 16), terminator, both path tables and the
+/// root directory extent (sector 20).
+fn empty_iso() -> Vec<u8> {
+    const ROOT: u32 = 20;
+    const SECTORS: u32 = 21;
+    fn both_u32(out: &mut [u8], value: u32) {
+        out[..4].copy_from_slice(&value.to_le_bytes());
+        out[4..8].copy_from_slice(&value.to_be_bytes());
+    }
+    fn both_u16(out: &mut [u8], value: u16) {
+        out[..2].copy_from_slice(&value.to_le_bytes());
+        out[2..4].copy_from_slice(&value.to_be_bytes());
+    }
+    fn root_record(out: &mut [u8], id: u8) {
+        out[0] = 34;
+        both_u32(&mut out[2..10], ROOT);
+        both_u32(&mut out[10..18], ISO_SECTOR as u32);
+        out[25] = 2; // directory
+        both_u16(&mut out[28..32], 1);
+        out[32] = 1;
+        out[33] = id;
+    }
+
+    let mut image = vec![0u8; SECTORS as usize * ISO_SECTOR];
+    let sector = |n: usize| n * ISO_SECTOR;
+
+    let pvd = &mut image[sector(16)..sector(17)];
+    pvd[0] = 1;
+    pvd[1..6].copy_from_slice(b"CD001");
+    pvd[6] = 1;
+    pvd[8..72].fill(b' ');
+    both_u32(&mut pvd[80..88], SECTORS);
+    both_u16(&mut pvd[120..124], 1);
+    both_u16(&mut pvd[124..128], 1);
+    both_u16(&mut pvd[128..132], ISO_SECTOR as u16);
+    both_u32(&mut pvd[132..140], 10);
+    pvd[140..144].copy_from_slice(&18u32.to_le_bytes());
+    pvd[148..152].copy_from_slice(&19u32.to_be_bytes());
+    root_record(&mut pvd[156..190], 0);
+    pvd[881] = 1;
+
+    let terminator = &mut image[sector(17)..sector(18)];
+    terminator[0] = 255;
+    terminator[1..6].copy_from_slice(b"CD001");
+    terminator[6] = 1;
+
+    for (table, big_endian) in [(18, false), (19, true)] {
+        let entry = &mut image[sector(table)..sector(table) + 10];
+        entry[0] = 1;
+        let (extent, parent) = if big_endian {
+            (ROOT.to_be_bytes(), 1u16.to_be_bytes())
+        } else {
+            (ROOT.to_le_bytes(), 1u16.to_le_bytes())
+        };
+        entry[2..6].copy_from_slice(&extent);
+        entry[6..8].copy_from_slice(&parent);
+    }
+
+    let root = sector(ROOT as usize);
+    root_record(&mut image[root..root + 34], 0);
+    root_record(&mut image[root + 34..root + 68], 1);
+    image
+}
+
+/// Print `describe_preflight` grouped by checklist.
+pub fn print_preflight_explain(distro_id: &str) {
+    println!(
+        "{}",
+        format!("=== PREFLIGHT CHECKLIST ({}) ===", distro_id)
+            .cyan()
+            .bold()
+    );
+    for group in describe_preflight(distro_id) {
+        println!();
+        println!("{} ({} checks)", group.name.bold(), group.items.len());
+        for item in &group.items {
+            println!("  - {}", item);
+        }
+    }
+    println!();
+}
+
 /// Print the overall summary.
 fn print_summary(result: &PreflightResult) {
     println!("{}", "--- Preflight Summary ---".bold());
@@ -880,6 +1632,11 @@ pub fn require_preflight_with_iso_for_distro(
                 all_failures.extend(check.details.iter().cloned());
             }
         }
+        if let Some(ref check) = result.disk {
+            if !check.passed {
+                all_failures.extend(check.details.iter().cloned());
+            }
+        }
 
         cheat_bail!(
             protects = "Installation tests verify REAL artifacts, not broken/incomplete ones",
@@ -904,23 +1661,162 @@ pub fn require_preflight_with_iso_for_distro(
     Ok(())
 }
 
+/// A host program the scenarios run, looked up on `PATH`.
+struct HostTool {
+    binary: &'static str,
+    version_args: &'static [&'static str],
+    /// What to install when it is missing.
+    install: &'static str,
+    required: bool,
+}
+
+const HOST_TOOLS: &[HostTool] = &[
+    HostTool {
+        binary: "qemu-system-x86_64",
+        version_args: &["--version"],
+        install: "install qemu (qemu-system-x86 on Debian/Ubuntu)",
+        required: true,
+    },
+    HostTool {
+        binary: "qemu-img",
+        version_args: &["--version"],
+        install: "install qemu-img (qemu-utils on Debian/Ubuntu)",
+        required: true,
+    },
+    HostTool {
+        binary: "ssh",
+        version_args: &["-V"],
+        install: "install openssh (openssh-client on Debian/Ubuntu) for the SSH scenarios",
+        required: true,
+    },
+    HostTool {
+        binary: "swtpm",
+        version_args: &["--version"],
+        install: "optional; install swtpm to give VMs an emulated TPM",
+        required: false,
+    },
+    HostTool {
+        binary: "tesseract",
+        version_args: &["--version"],
+        install: "optional; install tesseract to OCR screenshots",
+        required: false,
+    },
+];
+
+/// One host requirement and what was found for it.
+struct HostRequirement {
+    name: String,
+    required: bool,
+    hint: String,
+    /// Version line (or path) when present.
+    found: Option<String>,
+}
+
+/// Check the host has what the scenarios need before any VM is spawned.
+///
+/// Looks for QEMU, `qemu-img`, the OVMF code and vars images and `ssh`,
+/// printing the version (or path) of each, and warns about missing optional
+/// tools (`swtpm`, `tesseract`). Fails with one line per missing requirement
+/// and what to install, instead of a spawn error halfway through a run.
+///
+/// Returns the printed lines.
+pub fn check_host_environment() -> Result<Vec<String>> {
+    let path = std::env::var_os("PATH");
+    let mut requirements: Vec<HostRequirement> = HOST_TOOLS
+        .iter()
+        .map(|tool| HostRequirement {
+            name: tool.binary.to_string(),
+            required: tool.required,
+            hint: tool.install.to_string(),
+            found: crate::qemu::find_in_path(tool.binary, path.clone())
+                .map(|binary| tool_version(&binary, tool.version_args)),
+        })
+        .collect();
+    for (name, found) in [
+        ("OVMF code", crate::qemu::find_ovmf()),
+        ("OVMF vars", crate::qemu::find_ovmf_vars()),
+    ] {
+        let (found, hint) = match found {
+            Ok(path) => (Some(path.display().to_string()), String::new()),
+            Err(err) => (
+                None,
+                format!("{:#}; install ovmf (edk2-ovmf on Fedora)", err),
+            ),
+        };
+        requirements.push(HostRequirement {
+            name: name.to_string(),
+            required: true,
+            hint,
+            found,
+        });
+    }
+
+    println!("{}", "--- Host environment ---".bold());
+    let lines = summarize_host_requirements(&requirements)?;
+    for line in &lines {
+        if line.starts_with("warning:") {
+            println!("  {}", line.yellow());
+        } else {
+            println!("  {}", line);
+        }
+    }
+    Ok(lines)
+}
+
+/// First output line of `binary version_args`, else the binary's path.
+///
+/// `ssh -V` prints to stderr, so stderr is read when stdout is empty.
+fn tool_version(binary: &Path, version_args: &[&str]) -> String {
+    std::process::Command::new(binary)
+        .args(version_args)
+        .output()
+        .ok()
+        .and_then(|output| {
+            [output.stdout, output.stderr]
+                .into_iter()
+                .find_map(|stream| {
+                    String::from_utf8_lossy(&stream)
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty())
+                        .map(str::to_string)
+                })
+        })
+        .unwrap_or_else(|| binary.display().to_string())
+}
+
+/// Report lines for `requirements`, or an error listing every missing
+/// required one.
+fn summarize_host_requirements(requirements: &[HostRequirement]) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    let mut missing = Vec::new();
+    for requirement in requirements {
+        match (&requirement.found, requirement.required) {
+            (Some(version), _) => lines.push(format!("{}: {}", requirement.name, version)),
+            (None, false) => lines.push(format!(
+                "warning: {} not found ({})",
+                requirement.name, requirement.hint
+            )),
+            (None, true) => missing.push(format!(
+                "  - {} not found; {}",
+                requirement.name, requirement.hint
+            )),
+        }
+    }
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Host environment is missing required tools:\n{}",
+            missing.join("\n")
+        );
+    }
+    Ok(lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    fn temp_dir(test_name: &str) -> PathBuf {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("clock before epoch")
-            .as_nanos();
-        let path = std::env::temp_dir().join(format!(
-            "install-tests-preflight-{test_name}-{}-{nanos}",
-            std::process::id()
-        ));
-        fs::create_dir_all(&path).expect("create temp dir");
-        path
-    }
+    use crate::test_support::temp_dir;
+    use std::time::UNIX_EPOCH;
 
     fn write_file(path: &Path, content: &str) {
         if let Some(parent) = path.parent() {
@@ -929,9 +1825,54 @@ mod tests {
         fs::write(path, content).expect("write file");
     }
 
+    #[test]
+    fn run_ties_break_by_directory_name_and_stale_legacy_isos_are_flagged() {
+        let dir = temp_dir("preflight-run-ties");
+        let candidate = |name: &str, key: &str| RunCandidate {
+            sort_key: key.to_string(),
+            run_dir: dir.join(name),
+            iso_path: dir.join(name).join("levitate.iso"),
+        };
+        let mut candidates = vec![
+            candidate("20260313T120000Z-a", "20260313T120100Z"),
+            candidate("20260313T120000Z-b", "20260313T120100Z"),
+            candidate("20260312T090000Z", "20260312T091500Z"),
+        ];
+        sort_run_candidates(&mut candidates);
+        assert_eq!(candidates[0].run_dir, dir.join("20260313T120000Z-b"));
+        let warnings = run_candidate_warnings(&candidates);
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[1].contains("both finished at 20260313T120100Z"));
+        assert!(run_candidate_warnings(&candidates[2..]).is_empty());
+
+        let legacy = dir.join("levitate.iso");
+        let run_iso = dir.join("run/levitate.iso");
+        write_file(&legacy, "old build");
+        write_file(&run_iso, "new build");
+        let set_mtime = |path: &Path, secs: u64| {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        set_mtime(&legacy, 2_000_000_000);
+        set_mtime(&run_iso, 1_000_000_000);
+        let warning = stale_legacy_iso_warning(&legacy, &run_iso).expect("mtimes differ");
+        assert!(warning.contains("the legacy ISO is newer"), "{}", warning);
+        set_mtime(&legacy, 1_000_000_000);
+        assert_eq!(stale_legacy_iso_warning(&legacy, &run_iso), None);
+        assert_eq!(
+            stale_legacy_iso_warning(&dir.join("missing.iso"), &run_iso),
+            None
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn resolve_runtime_artifacts_prefers_product_native_names() {
-        let dir = temp_dir("product-native");
+        let dir = temp_dir("preflight-product-native");
         write_file(&dir.join("filesystem.erofs"), "rootfs");
         write_file(&dir.join("initramfs-live.cpio.gz"), "initramfs");
         write_file(&dir.join("initramfs-installed.img"), "install-initramfs");
@@ -959,7 +1900,7 @@ mod tests {
 
     #[test]
     fn live_boot_runtime_scope_uses_release_product_metadata() {
-        let dir = temp_dir("scope");
+        let dir = temp_dir("preflight-scope");
         let manifest = RunManifest {
             status: "success".to_string(),
             created_at_utc: "20260313T120000Z".to_string(),
@@ -978,4 +1919,159 @@ mod tests {
 
         fs::remove_dir_all(dir).expect("cleanup temp dir");
     }
+
+    #[test]
+    fn qcow2_header_accepts_v3_and_rejects_raw_images() {
+        let dir = temp_dir("preflight-qcow2-header");
+        let good = dir.join("disk.qcow2");
+        fs::write(&good, [b'Q', b'F', b'I', 0xfb, 0, 0, 0, 3]).expect("write qcow2");
+        assert_eq!(check_qcow2_header(&good).expect("valid header"), 3);
+
+        let raw = dir.join("raw.qcow2");
+        fs::write(&raw, [0u8; 512]).expect("write raw");
+        let err = check_qcow2_header(&raw).expect_err("raw image must fail");
+        assert!(err.to_string().contains("not a qcow2 image"));
+
+        assert_eq!(find_qcow2_file(&dir), Some(good));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn parse_guestfish_filesystems_splits_device_and_type() {
+        let parsed = parse_guestfish_filesystems("/dev/sda1: vfat\n/dev/sda2: ext4\n");
+        assert_eq!(
+            parsed,
+            vec![
+                ("/dev/sda1".to_string(), "vfat".to_string()),
+                ("/dev/sda2".to_string(), "ext4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn preflight_cache_hits_only_for_unchanged_artifacts() {
+        let dir = temp_dir("preflight-cache");
+        let iso = dir.join("levitate.iso");
+        write_file(&iso, "iso-v1");
+        let key = artifact_fingerprint("levitate", "checks-v1", &[Some(iso.as_path())]);
+        let cached = CachedArtifactChecks {
+            fingerprint: key.clone(),
+            live_initramfs: None,
+            install_initramfs: None,
+            iso: Some(PreflightCheck {
+                name: "Live ISO".to_string(),
+                passed: true,
+                total_checks: 3,
+                passed_checks: 3,
+                failures: 0,
+                details: Vec::new(),
+            }),
+            disk: None,
+        };
+        let cache_dir = dir.join("cache");
+        store_cached_artifact_checks(&cache_dir, "levitate", &cached).expect("store cache");
+
+        let hit = load_cached_artifact_checks(&cache_dir, "levitate", &key).expect("cache hit");
+        assert_eq!(hit.iso.expect("iso check").passed_checks, 3);
+
+        let newer_checks = artifact_fingerprint("levitate", "checks-v2", &[Some(iso.as_path())]);
+        assert_ne!(newer_checks, key);
+        assert!(load_cached_artifact_checks(&cache_dir, "levitate", &newer_checks).is_none());
+
+        write_file(&iso, "iso-v2-rebuilt");
+        let rebuilt = artifact_fingerprint("levitate", "checks-v1", &[Some(iso.as_path())]);
+        assert_ne!(rebuilt, key);
+        assert!(load_cached_artifact_checks(&cache_dir, "levitate", &rebuilt).is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn explain_lists_checklist_items_from_fsdbg() {
+        for checklist in [
+            ChecklistType::LiveInitramfs,
+            ChecklistType::InstallInitramfs,
+            ChecklistType::Iso,
+        ] {
+            let name = checklist.name().to_string();
+            let items = checklist_items(checklist);
+            assert!(!items.is_empty(), "{}", name);
+            assert!(
+                !items[0].starts_with("(items unavailable"),
+                "{}: {}",
+                name,
+                items[0]
+            );
+        }
+    }
+
+    #[test]
+    fn empty_archives_are_well_formed() {
+        let cpio = empty_cpio();
+        assert!(cpio.starts_with(b"070701"));
+        assert_eq!(cpio.len() % 4, 0);
+        assert_eq!(&cpio[110..120], b"TRAILER!!!");
+
+        let iso = empty_iso();
+        assert_eq!(iso.len(), 21 * ISO_SECTOR);
+        assert_eq!(&iso[16 * ISO_SECTOR + 1..16 * ISO_SECTOR + 6], b"CD001");
+        assert_eq!(iso[17 * ISO_SECTOR], 255);
+    }
+
+    #[test]
+    fn describe_preflight_skips_install_initramfs_outside_levitate() {
+        let has_install = |distro: &str| {
+            describe_preflight(distro)
+                .iter()
+                .any(|group| group.name == ChecklistType::InstallInitramfs.name())
+        };
+        assert!(has_install("levitate"));
+        assert!(!has_install("acorn"));
+
+        let groups = describe_preflight("acorn");
+        let disk = groups.last().expect("disk group");
+        assert!(disk.items[1].starts_with(DISK_CHECK_METADATA));
+        let kernel = disk
+            .items
+            .iter()
+            .find(|item| item.starts_with(DISK_KERNEL_PATH))
+            .expect("kernel item");
+        assert!(kernel.ends_with("(only with LEVITATE_PREFLIGHT_GUESTFISH=1)"));
+    }
+
+    #[test]
+    fn missing_required_host_tools_are_listed_with_install_hints() {
+        let requirement = |name: &str, required: bool, found: Option<&str>| HostRequirement {
+            name: name.to_string(),
+            required,
+            hint: format!("install {}", name),
+            found: found.map(str::to_string),
+        };
+        let lines = summarize_host_requirements(&[
+            requirement("qemu-img", true, Some("qemu-img version 8.2.2")),
+            requirement("swtpm", false, None),
+        ])
+        .unwrap();
+        assert_eq!(
+            lines,
+            [
+                "qemu-img: qemu-img version 8.2.2",
+                "warning: swtpm not found (install swtpm)"
+            ]
+        );
+
+        let err = summarize_host_requirements(&[
+            requirement("qemu-system-x86_64", true, None),
+            requirement("ssh", true, Some("OpenSSH_9.6p1")),
+            requirement("OVMF code", true, None),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("  - qemu-system-x86_64 not found; install qemu-system-x86_64"),
+            "{}",
+            err
+        );
+        assert!(err.contains("  - OVMF code not found"), "{}", err);
+        assert!(!err.contains("ssh"), "{}", err);
+    }
 }
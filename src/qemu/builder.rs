@@ -2,19 +2,461 @@
 //!
 //! Re-exports from `recqemu` and extends with testing-specific features:
 //! - Anti-cheat protections (detect UEFI bypass)
+//! - Host-to-guest TCP port forwarding (`forward_port`)
+//! - Host directory sharing over virtio-9p (`share_dir`)
+//! - KVM/TCG accelerator selection with fallback (`accel`)
+//! - Graphical displays for interactive debugging (`display`)
+//! - Disk cache mode and bus selection (`disk_cache`, `disk_bus`)
+//! - Machine type and CPU model selection (`machine`, `cpu`)
+//! - Guest architecture selection (`arch`): x86_64 (default) or aarch64
+//! - OVMF lookup that honors `LEVITATE_OVMF_CODE` / `LEVITATE_OVMF_VARS`, and
+//!   AAVMF lookup that honors `LEVITATE_AAVMF_CODE` / `LEVITATE_AAVMF_VARS`
 //!
 //! Process utilities (kill_stale_qemu_processes, acquire_test_lock) are
 //! provided by recqemu::process.
 
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 // Re-export basics from recqemu
-pub use recqemu::{create_disk, find_ovmf, find_ovmf_vars};
+pub use recqemu::create_disk;
+
+/// Env var naming the OVMF code image; takes precedence over the probe list.
+pub const OVMF_CODE_ENV: &str = "LEVITATE_OVMF_CODE";
+
+/// Env var naming the OVMF vars template; takes precedence over the probe list.
+pub const OVMF_VARS_ENV: &str = "LEVITATE_OVMF_VARS";
+
+/// Locate the OVMF code image.
+///
+/// `LEVITATE_OVMF_CODE` wins when set (and must name an existing file);
+/// otherwise recqemu's standard install locations are probed.
+pub fn find_ovmf() -> Result<PathBuf> {
+    firmware_path(
+        OVMF_CODE_ENV,
+        std::env::var_os(OVMF_CODE_ENV),
+        recqemu::find_ovmf,
+    )
+}
+
+/// Locate the OVMF vars template, as [`find_ovmf`] with `LEVITATE_OVMF_VARS`.
+pub fn find_ovmf_vars() -> Result<PathBuf> {
+    firmware_path(
+        OVMF_VARS_ENV,
+        std::env::var_os(OVMF_VARS_ENV),
+        recqemu::find_ovmf_vars,
+    )
+}
+
+/// Env var naming the AAVMF (aarch64 UEFI) code image.
+pub const AAVMF_CODE_ENV: &str = "LEVITATE_AAVMF_CODE";
+
+/// Env var naming the AAVMF vars template.
+pub const AAVMF_VARS_ENV: &str = "LEVITATE_AAVMF_VARS";
+
+/// Standard install locations of the AAVMF code image (Debian, Fedora, QEMU).
+const AAVMF_CODE_PATHS: &[&str] = &[
+    "/usr/share/AAVMF/AAVMF_CODE.fd",
+    "/usr/share/edk2/aarch64/QEMU_EFI-pflash.raw",
+    "/usr/share/qemu/edk2-aarch64-code.fd",
+];
+
+/// Standard install locations of the AAVMF vars template.
+const AAVMF_VARS_PATHS: &[&str] = &[
+    "/usr/share/AAVMF/AAVMF_VARS.fd",
+    "/usr/share/edk2/aarch64/vars-template-pflash.raw",
+    "/usr/share/qemu/edk2-arm-vars.fd",
+];
+
+/// Locate the AAVMF code image, as [`find_ovmf`] for aarch64 guests.
+pub fn find_aavmf() -> Result<PathBuf> {
+    firmware_path(AAVMF_CODE_ENV, std::env::var_os(AAVMF_CODE_ENV), || {
+        first_existing(AAVMF_CODE_PATHS)
+    })
+}
+
+/// Locate the AAVMF vars template, as [`find_aavmf`] with `LEVITATE_AAVMF_VARS`.
+pub fn find_aavmf_vars() -> Result<PathBuf> {
+    firmware_path(AAVMF_VARS_ENV, std::env::var_os(AAVMF_VARS_ENV), || {
+        first_existing(AAVMF_VARS_PATHS)
+    })
+}
+
+fn first_existing(paths: &[&str]) -> Option<PathBuf> {
+    paths.iter().map(PathBuf::from).find(|path| path.is_file())
+}
+
+/// Firmware from the `env` override `value` if set, else from `probe`.
+fn firmware_path(
+    env: &str,
+    value: Option<OsString>,
+    probe: impl FnOnce() -> Option<PathBuf>,
+) -> Result<PathBuf> {
+    match value.filter(|value| !value.is_empty()) {
+        Some(value) => {
+            let path = PathBuf::from(value);
+            if !path.is_file() {
+                anyhow::bail!("{}={} is not an existing file", env, path.display());
+            }
+            Ok(path)
+        }
+        None => probe().ok_or_else(|| {
+            anyhow::anyhow!("not in any standard location; set {} to its path", env)
+        }),
+    }
+}
 
 // Re-export process utilities from recqemu
 pub use recqemu::process::{acquire_test_lock, kill_stale_qemu_processes};
 
+/// Device node QEMU needs read/write access to for KVM acceleration.
+const KVM_DEVICE: &str = "/dev/kvm";
+
+/// True if `/dev/kvm` exists and is readable and writable by this process.
+pub fn kvm_available() -> bool {
+    Path::new(KVM_DEVICE).exists()
+        && std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(KVM_DEVICE)
+            .is_ok()
+}
+
+/// Guest CPU architecture, which picks the QEMU binary and machine defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arch {
+    #[default]
+    X86_64,
+    /// `-machine virt`, UEFI from AAVMF, serial on the PL011 (`ttyAMA0`).
+    Aarch64,
+}
+
+impl Arch {
+    /// Spelling accepted by `FromStr`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+        }
+    }
+
+    /// QEMU system emulator for this architecture.
+    pub fn qemu_binary(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "qemu-system-x86_64",
+            Arch::Aarch64 => "qemu-system-aarch64",
+        }
+    }
+
+    /// Distro package providing [`qemu_binary`](Self::qemu_binary) (Debian naming).
+    fn qemu_package(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "qemu-system-x86",
+            Arch::Aarch64 => "qemu-system-arm",
+        }
+    }
+
+    /// Machine type used when none is selected.
+    pub fn default_machine(self) -> &'static str {
+        match self {
+            Arch::X86_64 => DEFAULT_UEFI_MACHINE,
+            Arch::Aarch64 => "virt",
+        }
+    }
+
+    /// Guest device name of the serial console.
+    pub fn serial_console(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "ttyS0",
+            Arch::Aarch64 => "ttyAMA0",
+        }
+    }
+
+    /// Whether this is the host's architecture, so KVM can run it.
+    pub fn is_host(self) -> bool {
+        self.as_str() == std::env::consts::ARCH
+    }
+}
+
+/// First `binary` on a `PATH`-style list of directories.
+pub(crate) fn find_in_path(binary: &str, path: Option<OsString>) -> Option<PathBuf> {
+    std::env::split_paths(&path?)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+impl std::fmt::Display for Arch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Arch {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim() {
+            "x86_64" | "amd64" => Ok(Arch::X86_64),
+            "aarch64" | "arm64" => Ok(Arch::Aarch64),
+            other => anyhow::bail!(
+                "unknown architecture '{}' (expected x86_64 or aarch64)",
+                other
+            ),
+        }
+    }
+}
+
+/// QEMU accelerator selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accel {
+    /// Hardware virtualization; falls back to TCG if `/dev/kvm` is unusable.
+    Kvm,
+    /// Software emulation.
+    Tcg,
+    /// KVM when available, otherwise TCG.
+    Auto,
+}
+
+impl Accel {
+    /// Value passed to QEMU's `-accel`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Accel::Kvm => "kvm",
+            Accel::Tcg => "tcg",
+            Accel::Auto => "auto",
+        }
+    }
+}
+
+impl std::fmt::Display for Accel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Accel {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim() {
+            "kvm" => Ok(Accel::Kvm),
+            "tcg" => Ok(Accel::Tcg),
+            "auto" => Ok(Accel::Auto),
+            other => anyhow::bail!(
+                "unknown accelerator '{}' (expected kvm, tcg, or auto)",
+                other
+            ),
+        }
+    }
+}
+
+/// Where the guest's screen goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Display {
+    /// No screen; serial only (`-nographic` as configured). The test default.
+    #[default]
+    None,
+    /// VNC server on display `:N` (TCP port 5900 + N).
+    Vnc(u16),
+    /// Local GTK window.
+    Gtk,
+}
+
+impl Display {
+    /// Whether this display renders a screen (and so replaces `-nographic`).
+    pub fn is_graphical(self) -> bool {
+        !matches!(self, Display::None)
+    }
+
+    /// `vnc://` address a viewer can connect to, for VNC displays.
+    pub fn vnc_address(self) -> Option<String> {
+        match self {
+            Display::Vnc(display) => Some(format!("vnc://127.0.0.1:{}", 5900 + u32::from(display))),
+            _ => None,
+        }
+    }
+
+    /// QEMU arguments selecting this display.
+    fn args(self) -> Vec<String> {
+        match self {
+            Display::None => Vec::new(),
+            Display::Vnc(display) => vec!["-vnc".to_string(), format!(":{}", display)],
+            Display::Gtk => vec!["-display".to_string(), "gtk".to_string()],
+        }
+    }
+}
+
+impl std::fmt::Display for Display {
+    /// Same spelling `FromStr` accepts.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Display::None => f.write_str("none"),
+            Display::Vnc(display) => write!(f, "vnc:{}", display),
+            Display::Gtk => f.write_str("gtk"),
+        }
+    }
+}
+
+impl std::str::FromStr for Display {
+    type Err = anyhow::Error;
+
+    /// Parse `none`, `gtk`, or `vnc[:N]` (display 0 when `N` is omitted).
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim() {
+            "none" => Ok(Display::None),
+            "gtk" => Ok(Display::Gtk),
+            "vnc" => Ok(Display::Vnc(0)),
+            other => match other.strip_prefix("vnc:") {
+                Some(display) => display
+                    .parse()
+                    .map(Display::Vnc)
+                    .with_context(|| format!("invalid VNC display number '{}'", display)),
+                None => anyhow::bail!("unknown display '{}' (expected none, gtk, or vnc:N)", other),
+            },
+        }
+    }
+}
+
+/// Host page cache mode for attached disks (`-drive cache=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskCache {
+    /// Bypass the host page cache (O_DIRECT); steadiest timings on slow storage.
+    None,
+    /// QEMU's default: host page cache, flushes honored.
+    Writeback,
+    /// Host page cache, flushes ignored. Fastest; a host crash loses data,
+    /// which only matters for disks worth keeping.
+    Unsafe,
+}
+
+impl DiskCache {
+    /// Value passed to `-drive cache=`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiskCache::None => "none",
+            DiskCache::Writeback => "writeback",
+            DiskCache::Unsafe => "unsafe",
+        }
+    }
+}
+
+impl std::fmt::Display for DiskCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DiskCache {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim() {
+            "none" => Ok(DiskCache::None),
+            "writeback" => Ok(DiskCache::Writeback),
+            "unsafe" => Ok(DiskCache::Unsafe),
+            other => anyhow::bail!(
+                "unknown disk cache '{}' (expected none, writeback, or unsafe)",
+                other
+            ),
+        }
+    }
+}
+
+/// Controller attached disks hang off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskBus {
+    /// virtio-blk (`/dev/vdX`).
+    Virtio,
+    /// A virtio-scsi controller with `scsi-hd` disks (`/dev/sdX`).
+    VirtioScsi,
+    /// Emulated IDE/AHCI (`/dev/sdX`); slow, but what every firmware knows.
+    Ide,
+}
+
+impl DiskBus {
+    /// Spelling accepted by `FromStr`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiskBus::Virtio => "virtio",
+            DiskBus::VirtioScsi => "virtio-scsi",
+            DiskBus::Ide => "ide",
+        }
+    }
+
+    /// `-device` for the disk backed by drive `id`.
+    fn device(self, id: &str) -> String {
+        match self {
+            DiskBus::Virtio => format!("virtio-blk-pci,drive={}", id),
+            DiskBus::VirtioScsi => format!("scsi-hd,drive={},bus={}.0", id, DISK_SCSI_CONTROLLER),
+            DiskBus::Ide => format!("ide-hd,drive={}", id),
+        }
+    }
+}
+
+impl std::fmt::Display for DiskBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DiskBus {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim() {
+            "virtio" => Ok(DiskBus::Virtio),
+            "virtio-scsi" => Ok(DiskBus::VirtioScsi),
+            "ide" => Ok(DiskBus::Ide),
+            other => anyhow::bail!(
+                "unknown disk bus '{}' (expected virtio, virtio-scsi, or ide)",
+                other
+            ),
+        }
+    }
+}
+
+/// Machine type used for UEFI boots when none is selected.
+pub const DEFAULT_UEFI_MACHINE: &str = "q35";
+
+/// Whether `machine` (a `-machine` value, options allowed) has the pflash
+/// flash devices OVMF/AAVMF firmware is loaded into.
+pub fn machine_supports_pflash(machine: &str) -> bool {
+    let name = machine.split(',').next().unwrap_or_default().trim();
+    matches!(name, "q35" | "pc" | "virt")
+        || name.starts_with("pc-q35-")
+        || name.starts_with("pc-i440fx-")
+        || name.starts_with("virt-")
+}
+
+/// Id of the virtio-scsi controller added for [`DiskBus::VirtioScsi`] disks.
+const DISK_SCSI_CONTROLLER: &str = "disk-scsi";
+
+/// First bytes of every qcow2 image.
+const QCOW2_MAGIC: &[u8; 4] = b"QFI\xfb";
+
+/// `-drive format=` for a disk image: qcow2 when its header says so, raw
+/// otherwise. An unreadable image is assumed to be qcow2, like every disk this
+/// crate creates; QEMU reports the real problem when it opens it.
+fn disk_format(path: &Path) -> &'static str {
+    use std::io::Read;
+    let mut magic = [0u8; 4];
+    match std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut magic)) {
+        Ok(()) if &magic == QCOW2_MAGIC => "qcow2",
+        Ok(()) => "raw",
+        Err(_) => "qcow2",
+    }
+}
+
+/// A host->guest TCP forward on the user-mode network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortForward {
+    pub host_port: u16,
+    pub guest_port: u16,
+}
+
 /// Builder for QEMU commands - extends recqemu with testing features.
 ///
 /// Adds anti-cheat protections that panic if you try to bypass UEFI boot.
@@ -24,6 +466,21 @@ pub struct QemuBuilder {
     // Testing-specific fields
     has_uefi: bool,
     has_kernel: bool,
+    has_user_network: bool,
+    no_network: bool,
+    cmdline: Option<String>,
+    cmdline_append: Vec<String>,
+    port_forwards: Vec<PortForward>,
+    shared_dirs: Vec<(PathBuf, String)>,
+    accel: Option<Accel>,
+    display: Display,
+    qmp_socket: Option<PathBuf>,
+    disks: Vec<PathBuf>,
+    disk_cache: Option<DiskCache>,
+    disk_bus: Option<DiskBus>,
+    machine: Option<String>,
+    cpu: Option<String>,
+    arch: Arch,
 }
 
 impl QemuBuilder {
@@ -32,6 +489,21 @@ impl QemuBuilder {
             inner: recqemu::QemuBuilder::new().nodefaults(),
             has_uefi: false,
             has_kernel: false,
+            has_user_network: false,
+            no_network: false,
+            cmdline: None,
+            cmdline_append: Vec::new(),
+            port_forwards: Vec::new(),
+            shared_dirs: Vec::new(),
+            accel: None,
+            display: Display::None,
+            qmp_socket: None,
+            disks: Vec::new(),
+            disk_cache: None,
+            disk_bus: None,
+            machine: None,
+            cpu: None,
+            arch: Arch::X86_64,
         }
     }
 
@@ -50,10 +522,32 @@ impl QemuBuilder {
 
     /// Set kernel command line arguments.
     pub fn append(mut self, args: &str) -> Self {
-        self.inner = self.inner.append(args);
+        self.cmdline = Some(args.to_string());
         self
     }
 
+    /// Add extra kernel command line arguments (e.g. `rd.break`).
+    ///
+    /// With `.kernel()` these are appended to the `-append` line. UEFI ISO and
+    /// disk boots take their command line from the boot entry, which QEMU
+    /// cannot override; there the arguments are only recorded, and `session`
+    /// refuses to spawn the VM (`boot_injection::require_kernel_cmdline_applied`).
+    pub fn append_kernel_cmdline(mut self, args: &str) -> Self {
+        self.cmdline_append
+            .extend(args.split_whitespace().map(str::to_string));
+        self
+    }
+
+    /// Arguments from `append_kernel_cmdline` that QEMU cannot apply itself,
+    /// i.e. every one of them unless booting with `.kernel()`.
+    pub fn pending_kernel_cmdline(&self) -> &[String] {
+        if self.has_kernel {
+            &[]
+        } else {
+            &self.cmdline_append
+        }
+    }
+
     /// Set ISO for CD-ROM (exposed as /dev/sr0 via virtio-scsi).
     pub fn cdrom(mut self, path: PathBuf) -> Self {
         self.inner = self.inner.cdrom(path);
@@ -66,9 +560,54 @@ impl QemuBuilder {
         self
     }
 
-    /// Add virtio disk.
+    /// Add a disk (virtio unless `disk_bus` says otherwise).
     pub fn disk(mut self, path: PathBuf) -> Self {
-        self.inner = self.inner.disk(path);
+        self.disks.push(path);
+        self
+    }
+
+    /// Set the host cache mode for every disk. Unset keeps QEMU's default.
+    pub fn disk_cache(mut self, cache: DiskCache) -> Self {
+        self.disk_cache = Some(cache);
+        self
+    }
+
+    /// Set the bus every disk is attached to. Unset keeps virtio-blk.
+    pub fn disk_bus(mut self, bus: DiskBus) -> Self {
+        self.disk_bus = Some(bus);
+        self
+    }
+
+    /// Select the machine type (`-machine`, e.g. `q35`, `pc`, `pc-q35-8.2`).
+    ///
+    /// Unset, UEFI boots use [`DEFAULT_UEFI_MACHINE`] unless recqemu already
+    /// picked one. With `.uefi()` the machine must support pflash (see
+    /// [`machine_supports_pflash`]); building panics otherwise.
+    pub fn machine(mut self, machine: &str) -> Self {
+        self.machine = Some(machine.to_string());
+        self
+    }
+
+    /// Select the guest architecture. Defaults to [`Arch::X86_64`].
+    ///
+    /// [`Arch::Aarch64`] runs `qemu-system-aarch64` on `-machine virt` with
+    /// `-cpu max` unless `machine`/`cpu` say otherwise; pass AAVMF from
+    /// [`find_aavmf`] to `.uefi()`. A guest that is not the host's
+    /// architecture always runs under TCG. Building fails if the binary is
+    /// not on `PATH`.
+    pub fn arch(mut self, arch: Arch) -> Self {
+        self.arch = arch;
+        self
+    }
+
+    /// Architecture selected via [`arch`](Self::arch).
+    pub fn selected_arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Select the CPU model (`-cpu`, e.g. `host` under KVM or `Skylake-Client`).
+    pub fn cpu(mut self, cpu: &str) -> Self {
+        self.cpu = Some(cpu.to_string());
         self
     }
 
@@ -93,16 +632,97 @@ impl QemuBuilder {
 
     /// Enable QEMU user-mode networking (provides DHCP, DNS, NAT to guest).
     pub fn with_user_network(mut self) -> Self {
+        self.has_user_network = true;
         self.inner = self.inner.user_network();
         self
     }
 
     /// Enable user networking and host->guest TCP forwarding.
     pub fn with_user_network_hostfwd(mut self, host_port: u16, guest_port: u16) -> Self {
+        self.has_user_network = true;
         self.inner = self.inner.user_network_with_hostfwd(host_port, guest_port);
         self
     }
 
+    /// Give the guest no network at all (emits `-nic none`).
+    ///
+    /// States offline intent explicitly rather than relying on
+    /// `with_user_network()` simply not being called. Ports registered via
+    /// `forward_port` still reach the guest, through a `restrict=on` user
+    /// netdev that has no route out.
+    ///
+    /// Conflicts with `with_user_network()`; building a command with both panics.
+    pub fn no_network(mut self) -> Self {
+        self.no_network = true;
+        self
+    }
+
+    /// Whether `no_network()` was requested.
+    pub fn is_offline(&self) -> bool {
+        self.no_network
+    }
+
+    /// Forward host tcp/`host_port` to guest tcp/`guest_port`.
+    ///
+    /// Entries accumulate as `hostfwd=tcp::H-:G` on the user `-netdev`, which
+    /// is enabled implicitly if `with_user_network()` was not called. A
+    /// `host_port` of 0 allocates a free ephemeral port; read it back with
+    /// [`Self::forwarded_host_port`].
+    pub fn forward_port(mut self, host_port: u16, guest_port: u16) -> Result<Self> {
+        let host_port = if host_port == 0 {
+            allocate_local_port()?
+        } else {
+            host_port
+        };
+        self.port_forwards.push(PortForward {
+            host_port,
+            guest_port,
+        });
+        Ok(self)
+    }
+
+    /// Host port forwarded to `guest_port` via `forward_port`, if any.
+    pub fn forwarded_host_port(&self, guest_port: u16) -> Option<u16> {
+        self.port_forwards
+            .iter()
+            .find(|forward| forward.guest_port == guest_port)
+            .map(|forward| forward.host_port)
+    }
+
+    /// All forwards registered via `forward_port`.
+    pub fn port_forwards(&self) -> &[PortForward] {
+        &self.port_forwards
+    }
+
+    /// Share a host directory with the guest over virtio-9p.
+    ///
+    /// Emits `-virtfs local,path=...,mount_tag=...,security_model=none`. Mount it
+    /// in the guest with `Executor::mount_shared(mount_tag, dest)`.
+    pub fn share_dir(mut self, host_path: PathBuf, mount_tag: &str) -> Self {
+        self.shared_dirs.push((host_path, mount_tag.to_string()));
+        self
+    }
+
+    /// Select the QEMU accelerator (emits `-accel kvm` or `-accel tcg`).
+    ///
+    /// Without this call no `-accel` is emitted and QEMU uses its default (TCG).
+    pub fn accel(mut self, accel: Accel) -> Self {
+        self.accel = Some(accel);
+        self
+    }
+
+    /// Concrete accelerator the built command will use, if one was selected.
+    ///
+    /// `Auto` and `Kvm` resolve to `Tcg` when `/dev/kvm` is unavailable or
+    /// the guest architecture is not the host's.
+    pub fn resolved_accel(&self) -> Option<Accel> {
+        self.accel.map(|accel| match accel {
+            Accel::Tcg => Accel::Tcg,
+            Accel::Kvm | Accel::Auto if self.arch.is_host() && kvm_available() => Accel::Kvm,
+            Accel::Kvm | Accel::Auto => Accel::Tcg,
+        })
+    }
+
     /// Disable graphics, use serial console.
     pub fn nographic(mut self) -> Self {
         self.inner = self.inner.nographic();
@@ -121,12 +741,22 @@ impl QemuBuilder {
         self
     }
 
-    /// Set QMP Unix socket path for QMP control mode.
+    /// Expose a QMP Unix socket (`-qmp unix:<path>,server,nowait`).
+    ///
+    /// Emitted by every build mode, so a `build_piped()` VM can be driven over
+    /// serial while QMP takes screenshots or snapshots. QEMU creates the
+    /// socket after it starts; `QmpClient::connect` retries until it exists.
+    /// Remove a stale socket at `path` before spawning.
     pub fn qmp_socket(mut self, path: PathBuf) -> Self {
-        self.inner = self.inner.qmp_socket(path);
+        self.qmp_socket = Some(path);
         self
     }
 
+    /// Socket path set via [`qmp_socket`](Self::qmp_socket).
+    pub fn qmp_socket_path(&self) -> Option<&Path> {
+        self.qmp_socket.as_deref()
+    }
+
     /// Attach a QEMU fw_cfg payload file for early-boot guest consumption.
     pub fn fw_cfg_file(mut self, name: &str, path: PathBuf) -> Self {
         self.inner = self.inner.fw_cfg_file(name, path);
@@ -134,46 +764,100 @@ impl QemuBuilder {
     }
 
     /// Set VNC display number for optional live viewing.
-    pub fn vnc_display(mut self, display: u16) -> Self {
-        self.inner = self.inner.vnc_display(display);
+    ///
+    /// Shorthand for `.display(Display::Vnc(display))`; the later of the two
+    /// calls wins, so only one `-vnc` is ever emitted.
+    pub fn vnc_display(self, display: u16) -> Self {
+        self.display(Display::Vnc(display))
+    }
+
+    /// Show the guest screen for interactive debugging.
+    ///
+    /// A graphical display drops `-nographic` (adding a VGA device when none
+    /// is configured) while leaving `-serial stdio` in place, so the serial
+    /// console keeps driving the test. [`Display::None`] is the default.
+    pub fn display(mut self, display: Display) -> Self {
+        self.display = display;
         self
     }
 
+    /// Display selected via [`display`](Self::display).
+    pub fn selected_display(&self) -> Display {
+        self.display
+    }
+
     /// Build the QEMU command (piped for console control).
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if both `.uefi()` and `.kernel()` are set - this combination
-    /// bypasses UEFI firmware while appearing to use it (architectural cheating).
-    pub fn build_piped(self) -> Command {
-        self.check_anti_cheat();
+    /// Fails if both `.uefi()` and `.kernel()` are set - this combination
+    /// bypasses UEFI firmware while appearing to use it (architectural
+    /// cheating) - or on the other combinations `check_anti_cheat` rejects.
+    pub fn build_piped(self) -> Result<Command> {
+        self.check_anti_cheat()?;
 
-        let mut cmd = self.inner.build();
+        let mut cmd = self.build_inner(std::env::var_os("PATH"))?;
         cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit());
-        cmd
+        Ok(cmd)
+    }
+
+    /// Like [`build_piped`](Self::build_piped), also returning the QMP socket
+    /// path so serial and QMP can be used on the same VM.
+    ///
+    /// # Errors
+    ///
+    /// Same anti-cheat rules as `build_piped()`.
+    pub fn build_piped_with_qmp(self) -> Result<(Command, Option<PathBuf>)> {
+        let socket = self.qmp_socket.clone();
+        Ok((self.build_piped()?, socket))
+    }
+
+    /// Render the argv (program first) that `build_piped()` would spawn.
+    ///
+    /// Used by `--dry-run` so a failing run can be reproduced by hand.
+    ///
+    /// # Errors
+    ///
+    /// Same anti-cheat rules as `build_piped()`.
+    pub fn render_args(&self) -> Result<Vec<String>> {
+        self.check_anti_cheat()?;
+
+        let cmd = self.build_inner(std::env::var_os("PATH"))?;
+        Ok(std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect())
     }
 
     /// Build the QEMU command for QMP control mode.
-    pub fn build_qmp(self) -> Command {
-        self.check_anti_cheat();
+    ///
+    /// # Errors
+    ///
+    /// Same anti-cheat rules as `build_piped()`.
+    pub fn build_qmp(self) -> Result<Command> {
+        self.check_anti_cheat()?;
 
-        let mut cmd = self.inner.build();
+        let mut cmd = self.build_inner(std::env::var_os("PATH"))?;
         cmd.stdin(Stdio::null())
             .stdout(Stdio::null())
             .stderr(Stdio::inherit());
-        cmd
+        Ok(cmd)
     }
 
     /// Build QEMU command for direct kernel boot debugging (bypasses UEFI/UKI).
     ///
     /// This is for debugging initramfs issues in isolation.
     ///
+    /// # Errors
+    ///
+    /// Fails if the guest architecture's QEMU binary is not installed.
+    ///
     /// # Panics
     ///
     /// Panics if `.uefi()` is set (this method is for non-UEFI debug only).
-    pub fn build_direct_boot_debug(self) -> Command {
+    pub fn build_direct_boot_debug(self) -> Result<Command> {
         if self.has_uefi {
             panic!(
                 "Direct boot debug cannot use .uefi() - it bypasses UEFI entirely.\n\
@@ -181,17 +865,239 @@ impl QemuBuilder {
             );
         }
 
-        let mut cmd = self.inner.build();
+        let mut cmd = self.build_inner(std::env::var_os("PATH"))?;
         cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit());
-        cmd
+        Ok(cmd)
+    }
+
+    /// Build the recqemu command with accumulated port forwards and shares applied.
+    ///
+    /// `path` is the `PATH` searched for a non-x86_64 guest's QEMU binary.
+    fn build_inner(&self, path: Option<OsString>) -> Result<Command> {
+        let mut inner = self.inner.clone();
+        let mut cmdline: Vec<&str> = self.cmdline.iter().map(String::as_str).collect();
+        if self.has_kernel {
+            cmdline.extend(self.cmdline_append.iter().map(String::as_str));
+        }
+        let aarch64_console = format!("console={}", Arch::Aarch64.serial_console());
+        if self.arch == Arch::Aarch64
+            && self.has_kernel
+            && !cmdline.iter().any(|arg| arg.contains("console="))
+        {
+            cmdline.push(&aarch64_console);
+        }
+        if !cmdline.is_empty() {
+            inner = inner.append(&cmdline.join(" "));
+        }
+        if !self.port_forwards.is_empty() && !self.has_user_network {
+            inner = inner.user_network();
+        }
+        if !self.own_disk_args() {
+            for disk in &self.disks {
+                inner = inner.disk(disk.clone());
+            }
+        }
+        let cmd = inner.build();
+        let mut args: Vec<OsString> = cmd.get_args().map(|arg| arg.to_os_string()).collect();
+
+        if self.port_forwards.is_empty() {
+            if self.no_network {
+                args.extend(["-nic", "none"].map(OsString::from));
+            }
+        } else {
+            let mut hostfwd: String = self
+                .port_forwards
+                .iter()
+                .map(|forward| {
+                    format!(
+                        ",hostfwd=tcp::{}-:{}",
+                        forward.host_port, forward.guest_port
+                    )
+                })
+                .collect();
+            if self.no_network {
+                hostfwd.push_str(",restrict=on");
+            }
+            let netdev = (1..args.len())
+                .find(|&i| {
+                    (args[i - 1] == "-netdev" || args[i - 1] == "-nic")
+                        && args[i].to_string_lossy().starts_with("user")
+                })
+                .context("recqemu user networking did not emit a `-netdev user` argument")?;
+            args[netdev].push(hostfwd);
+        }
+        if self.display.is_graphical() {
+            args.retain(|arg| arg != "-nographic");
+            // nodefaults() removes the default VGA adapter; virt has no VGA.
+            match self.arch {
+                Arch::X86_64 if !args.iter().any(|arg| arg == "-vga") => {
+                    args.extend(["-vga", "std"].map(OsString::from));
+                }
+                Arch::Aarch64 => args.extend(["-device", "virtio-gpu-pci"].map(OsString::from)),
+                Arch::X86_64 => {}
+            }
+        }
+        if let Some(machine) = &self.machine {
+            remove_option(&mut args, &["-machine", "-M"]);
+            args.extend(["-machine", machine.as_str()].map(OsString::from));
+        } else if self.arch != Arch::X86_64 {
+            // recqemu's defaults are PC machine types.
+            remove_option(&mut args, &["-machine", "-M"]);
+            args.extend(["-machine", self.arch.default_machine()].map(OsString::from));
+        } else if self.has_uefi && !args.iter().any(|arg| arg == "-machine" || arg == "-M") {
+            args.extend(["-machine", DEFAULT_UEFI_MACHINE].map(OsString::from));
+        }
+        let cpu = match (&self.cpu, self.arch) {
+            (Some(cpu), _) => Some(cpu.as_str()),
+            // virt's default CPU is 32-bit; `max` works under both KVM and TCG.
+            (None, Arch::Aarch64) => Some("max"),
+            (None, Arch::X86_64) => None,
+        };
+        if let Some(cpu) = cpu {
+            remove_option(&mut args, &["-cpu"]);
+            args.extend(["-cpu", cpu].map(OsString::from));
+        }
+        args.extend(self.extra_args().into_iter().map(OsString::from));
+
+        let mut rebuilt = match self.arch {
+            Arch::X86_64 => Command::new(cmd.get_program()),
+            arch => {
+                if find_in_path(arch.qemu_binary(), path).is_none() {
+                    anyhow::bail!(
+                        "{} not found in PATH; install {} to test {} builds",
+                        arch.qemu_binary(),
+                        arch.qemu_package(),
+                        arch
+                    );
+                }
+                Command::new(arch.qemu_binary())
+            }
+        };
+        rebuilt.args(args);
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => rebuilt.env(key, value),
+                None => rebuilt.env_remove(key),
+            };
+        }
+        if let Some(dir) = cmd.get_current_dir() {
+            rebuilt.current_dir(dir);
+        }
+        Ok(rebuilt)
+    }
+
+    /// Arguments appended after recqemu's own: disks with a custom cache or
+    /// bus, QMP socket, display, accelerator, then shared dirs.
+    fn extra_args(&self) -> Vec<String> {
+        let mut args = self.disk_args();
+        if let Some(socket) = &self.qmp_socket {
+            args.push("-qmp".to_string());
+            args.push(format!("unix:{},server,nowait", socket.display()));
+        }
+        args.extend(self.display.args());
+        if let Some(resolved) = self.resolved_accel() {
+            if self.accel == Some(Accel::Kvm) && resolved == Accel::Tcg {
+                if self.arch.is_host() {
+                    eprintln!(
+                        "warning: KVM requested but {} is unavailable; falling back to TCG",
+                        KVM_DEVICE
+                    );
+                } else {
+                    eprintln!(
+                        "warning: KVM cannot run a {} guest on a {} host; falling back to TCG",
+                        self.arch,
+                        std::env::consts::ARCH
+                    );
+                }
+            }
+            args.push("-accel".to_string());
+            args.push(resolved.as_str().to_string());
+        }
+        args.extend(self.virtfs_args());
+        args
+    }
+
+    /// Whether disks need this builder's `-drive` arguments. recqemu's own
+    /// are only used for qcow2 images on virtio with the default cache.
+    fn own_disk_args(&self) -> bool {
+        self.disk_cache.is_some()
+            || self.disk_bus.is_some()
+            || self.disks.iter().any(|disk| disk_format(disk) != "qcow2")
+    }
+
+    /// `-drive`/`-device` pairs for disks when a cache mode or bus is set or
+    /// an image is not qcow2.
+    ///
+    /// Empty otherwise: recqemu's own virtio disk arguments are used then.
+    fn disk_args(&self) -> Vec<String> {
+        if !self.own_disk_args() {
+            return Vec::new();
+        }
+        let bus = self.disk_bus.unwrap_or(DiskBus::Virtio);
+        let mut args = Vec::new();
+        if bus == DiskBus::VirtioScsi && !self.disks.is_empty() {
+            args.push("-device".to_string());
+            args.push(format!("virtio-scsi-pci,id={}", DISK_SCSI_CONTROLLER));
+        }
+        for (index, disk) in self.disks.iter().enumerate() {
+            let id = format!("disk{}", index);
+            // QEMU option values escape a literal comma as ",,".
+            let mut drive = format!(
+                "file={},format={},if=none,id={}",
+                disk.display().to_string().replace(',', ",,"),
+                disk_format(disk),
+                id
+            );
+            if let Some(cache) = self.disk_cache {
+                drive.push_str(&format!(",cache={}", cache));
+            }
+            args.push("-drive".to_string());
+            args.push(drive);
+            args.push("-device".to_string());
+            args.push(bus.device(&id));
+        }
+        args
     }
 
-    /// Check for architectural anti-cheat violations.
-    fn check_anti_cheat(&self) {
+    /// `-virtfs` arguments for directories registered via `share_dir`.
+    fn virtfs_args(&self) -> Vec<String> {
+        // QEMU option values escape a literal comma as ",,".
+        self.shared_dirs
+            .iter()
+            .flat_map(|(path, tag)| {
+                [
+                    "-virtfs".to_string(),
+                    format!(
+                        "local,path={},mount_tag={},security_model=none",
+                        path.display().to_string().replace(',', ",,"),
+                        tag.replace(',', ",,")
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    /// Check for architectural anti-cheat violations and impossible configs.
+    fn check_anti_cheat(&self) -> Result<()> {
+        if let Some(machine) = self.machine.as_deref().filter(|_| self.has_uefi) {
+            if !machine_supports_pflash(machine) {
+                anyhow::bail!(
+                    "machine type '{}' has no pflash, so UEFI firmware cannot load; \
+                     use q35, pc, or virt",
+                    machine
+                );
+            }
+        }
+        if self.no_network && self.has_user_network {
+            anyhow::bail!(
+                ".no_network() cannot be combined with .with_user_network(): \
+                 an offline run must not quietly regain internet access"
+            );
+        }
         if self.has_uefi && self.has_kernel {
-            panic!(
+            anyhow::bail!(
                 "\n{border}\n\
                 ARCHITECTURAL CHEAT BLOCKED\n\
                 {border}\n\n\
@@ -208,5 +1114,450 @@ impl QemuBuilder {
                 border = "!".repeat(60)
             );
         }
+        Ok(())
+    }
+}
+
+/// Drop every `flag value` pair whose flag is one of `flags`.
+fn remove_option(args: &mut Vec<OsString>, flags: &[&str]) {
+    let mut index = 0;
+    while index < args.len() {
+        if flags.iter().any(|flag| args[index] == *flag) {
+            let end = (index + 2).min(args.len());
+            args.drain(index..end);
+        } else {
+            index += 1;
+        }
+    }
+}
+
+/// Reserve a free local TCP port by binding port 0 and releasing it.
+pub(crate) fn allocate_local_port() -> Result<u16> {
+    let listener =
+        TcpListener::bind("127.0.0.1:0").context("allocating local tcp port for forward")?;
+    let port = listener
+        .local_addr()
+        .context("reading allocated local tcp port")?
+        .port();
+    drop(listener);
+    Ok(port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_args_matches_built_command() {
+        let builder = QemuBuilder::new()
+            .cdrom(PathBuf::from("/tmp/test-live.iso"))
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .nographic()
+            .serial_stdio();
+
+        let args = builder.render_args().unwrap();
+        let cmd = builder.build_piped().unwrap();
+        assert_eq!(args[0], cmd.get_program().to_string_lossy());
+        assert_eq!(args.len(), cmd.get_args().count() + 1);
+        assert!(args.iter().any(|arg| arg.contains("/tmp/test-live.iso")));
+        assert!(args.iter().any(|arg| arg.contains("/tmp/OVMF_CODE.fd")));
+    }
+
+    #[test]
+    fn render_args_enforces_anti_cheat() {
+        let err = QemuBuilder::new()
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .kernel(PathBuf::from("/tmp/vmlinuz"))
+            .render_args()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("ARCHITECTURAL CHEAT BLOCKED"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn forward_port_appends_hostfwd_and_enables_user_network() {
+        let args = QemuBuilder::new()
+            .forward_port(8080, 80)
+            .unwrap()
+            .forward_port(2222, 22)
+            .unwrap()
+            .render_args()
+            .unwrap();
+
+        let netdev = args
+            .iter()
+            .find(|arg| arg.starts_with("user"))
+            .expect("user networking enabled implicitly");
+        assert!(netdev.ends_with(",hostfwd=tcp::8080-:80,hostfwd=tcp::2222-:22"));
+    }
+
+    #[test]
+    fn forward_port_zero_allocates_ephemeral_port() {
+        let builder = QemuBuilder::new()
+            .with_user_network()
+            .forward_port(0, 22)
+            .unwrap();
+
+        let host_port = builder.forwarded_host_port(22).unwrap();
+        assert_ne!(host_port, 0);
+        let expected = format!("hostfwd=tcp::{}-:22", host_port);
+        assert!(builder
+            .render_args()
+            .unwrap()
+            .iter()
+            .any(|arg| arg.contains(&expected)));
+        assert_eq!(builder.forwarded_host_port(80), None);
+    }
+
+    #[test]
+    fn append_kernel_cmdline_extends_direct_kernel_boot() {
+        let builder = QemuBuilder::new()
+            .kernel(PathBuf::from("/tmp/vmlinuz"))
+            .append("console=ttyS0")
+            .append_kernel_cmdline("rd.break systemd.unit=rescue.target");
+        assert!(builder.pending_kernel_cmdline().is_empty());
+        let args = builder.render_args().unwrap();
+        let append = args
+            .windows(2)
+            .find(|pair| pair[0] == "-append")
+            .map(|pair| pair[1].clone());
+        assert_eq!(
+            append.as_deref(),
+            Some("console=ttyS0 rd.break systemd.unit=rescue.target")
+        );
+    }
+
+    #[test]
+    fn append_kernel_cmdline_is_pending_for_uefi_boots() {
+        let builder = QemuBuilder::new()
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .append_kernel_cmdline("rd.break");
+        assert_eq!(builder.pending_kernel_cmdline(), ["rd.break"]);
+        assert!(!builder
+            .render_args()
+            .unwrap()
+            .iter()
+            .any(|arg| arg == "-append"));
+    }
+
+    #[test]
+    fn no_network_emits_nic_none() {
+        let args = QemuBuilder::new().no_network().render_args().unwrap();
+        assert!(args.windows(2).any(|pair| pair == ["-nic", "none"]));
+    }
+
+    #[test]
+    fn no_network_restricts_forwarded_user_netdev() {
+        let args = QemuBuilder::new()
+            .no_network()
+            .forward_port(2222, 22)
+            .unwrap()
+            .render_args()
+            .unwrap();
+        let netdev = args
+            .iter()
+            .find(|arg| arg.starts_with("user"))
+            .expect("forward keeps a user netdev");
+        assert!(netdev.contains("hostfwd=tcp::2222-:22"));
+        assert!(netdev.ends_with(",restrict=on"));
+        assert!(!args.iter().any(|arg| arg == "none"));
+    }
+
+    #[test]
+    fn no_network_conflicts_with_user_network() {
+        let err = QemuBuilder::new()
+            .no_network()
+            .with_user_network()
+            .render_args()
+            .unwrap_err();
+        assert!(err.to_string().contains("no_network"), "{}", err);
+    }
+
+    #[test]
+    fn share_dir_emits_virtfs() {
+        let args = QemuBuilder::new()
+            .share_dir(PathBuf::from("/srv/fixtures,v2"), "fixtures")
+            .render_args()
+            .unwrap();
+
+        let pos = args.iter().position(|arg| arg == "-virtfs").unwrap();
+        assert_eq!(
+            args[pos + 1],
+            "local,path=/srv/fixtures,,v2,mount_tag=fixtures,security_model=none"
+        );
+    }
+
+    #[test]
+    fn accel_tcg_is_emitted_verbatim() {
+        let args = QemuBuilder::new().accel(Accel::Tcg).render_args().unwrap();
+        let pos = args.iter().position(|arg| arg == "-accel").unwrap();
+        assert_eq!(args[pos + 1], "tcg");
+    }
+
+    #[test]
+    fn accel_auto_follows_kvm_availability() {
+        let builder = QemuBuilder::new().accel(Accel::Auto);
+        let expected = if kvm_available() {
+            Accel::Kvm
+        } else {
+            Accel::Tcg
+        };
+        assert_eq!(builder.resolved_accel(), Some(expected));
+        assert!(builder
+            .render_args()
+            .unwrap()
+            .contains(&expected.as_str().to_string()));
+        assert_eq!(QemuBuilder::new().resolved_accel(), None);
+    }
+
+    #[test]
+    fn foreign_arch_never_uses_kvm() {
+        let foreign = if Arch::X86_64.is_host() {
+            Arch::Aarch64
+        } else {
+            Arch::X86_64
+        };
+        for accel in [Accel::Kvm, Accel::Auto] {
+            let builder = QemuBuilder::new().arch(foreign).accel(accel);
+            assert_eq!(builder.resolved_accel(), Some(Accel::Tcg));
+        }
+    }
+
+    #[test]
+    fn vnc_display_replaces_nographic() {
+        let args = QemuBuilder::new()
+            .nographic()
+            .serial_stdio()
+            .display(Display::Vnc(3))
+            .render_args()
+            .unwrap();
+        assert!(!args.iter().any(|arg| arg == "-nographic"));
+        let pos = args.iter().position(|arg| arg == "-vnc").unwrap();
+        assert_eq!(args[pos + 1], ":3");
+        assert!(args.iter().any(|arg| arg == "-vga"));
+        assert_eq!(
+            Display::Vnc(3).vnc_address().as_deref(),
+            Some("vnc://127.0.0.1:5903")
+        );
+    }
+
+    #[test]
+    fn vnc_display_and_display_emit_one_vnc() {
+        let args = QemuBuilder::new()
+            .vnc_display(1)
+            .display(Display::Vnc(3))
+            .render_args()
+            .unwrap();
+        assert_eq!(args.iter().filter(|arg| *arg == "-vnc").count(), 1);
+        assert_eq!(
+            QemuBuilder::new()
+                .display(Display::Gtk)
+                .vnc_display(2)
+                .selected_display(),
+            Display::Vnc(2)
+        );
+    }
+
+    #[test]
+    fn default_display_keeps_nographic() {
+        let args = QemuBuilder::new().nographic().render_args().unwrap();
+        assert!(args.iter().any(|arg| arg == "-nographic"));
+        assert!(!args.iter().any(|arg| arg == "-vnc" || arg == "-display"));
+    }
+
+    #[test]
+    fn display_parses_cli_values() {
+        assert_eq!("vnc:2".parse::<Display>().unwrap(), Display::Vnc(2));
+        assert_eq!("vnc".parse::<Display>().unwrap(), Display::Vnc(0));
+        assert_eq!("gtk".parse::<Display>().unwrap(), Display::Gtk);
+        assert_eq!("none".parse::<Display>().unwrap(), Display::None);
+        assert!("sdl".parse::<Display>().is_err());
+        assert_eq!(Display::Vnc(2).to_string(), "vnc:2");
+    }
+
+    #[test]
+    fn qmp_socket_is_emitted_for_piped_builds() {
+        let (cmd, socket) = QemuBuilder::new()
+            .nographic()
+            .serial_stdio()
+            .qmp_socket(PathBuf::from("/tmp/test-qmp.sock"))
+            .build_piped_with_qmp()
+            .unwrap();
+        assert_eq!(socket, Some(PathBuf::from("/tmp/test-qmp.sock")));
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let pos = args.iter().position(|arg| arg == "-qmp").unwrap();
+        assert_eq!(args[pos + 1], "unix:/tmp/test-qmp.sock,server,nowait");
+        assert!(args.iter().any(|arg| arg == "-nographic"));
+    }
+
+    #[test]
+    fn disk_cache_and_bus_compose_drive_and_device() {
+        let args = QemuBuilder::new()
+            .disk(PathBuf::from("/tmp/disk.qcow2"))
+            .disk_cache(DiskCache::Unsafe)
+            .disk_bus(DiskBus::VirtioScsi)
+            .render_args()
+            .unwrap();
+
+        let pairs: Vec<&str> = args
+            .iter()
+            .skip_while(|arg| !arg.contains("virtio-scsi-pci"))
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                "virtio-scsi-pci,id=disk-scsi",
+                "-drive",
+                "file=/tmp/disk.qcow2,format=qcow2,if=none,id=disk0,cache=unsafe",
+                "-device",
+                "scsi-hd,drive=disk0,bus=disk-scsi.0"
+            ]
+        );
+        assert_eq!("ide".parse::<DiskBus>().unwrap(), DiskBus::Ide);
+        assert!("writethrough".parse::<DiskCache>().is_err());
+        assert_eq!("tcg".parse::<Accel>().unwrap(), Accel::Tcg);
+        assert!("hvf".parse::<Accel>().is_err());
+    }
+
+    #[test]
+    fn disk_format_follows_the_image_header() {
+        let dir = crate::test_support::temp_dir("disk-format");
+        let qcow2 = dir.join("disk.img");
+        std::fs::write(&qcow2, b"QFI\xfb\0\0\0\x03").unwrap();
+        let raw = dir.join("disk.qcow2");
+        std::fs::write(&raw, [0u8; 512]).unwrap();
+
+        let args = QemuBuilder::new()
+            .disk(qcow2)
+            .disk(raw)
+            .render_args()
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let formats: Vec<&str> = args
+            .iter()
+            .filter_map(|arg| arg.split(",format=").nth(1))
+            .map(|rest| rest.split(',').next().unwrap())
+            .collect();
+        assert_eq!(formats, ["qcow2", "raw"]);
+    }
+
+    #[test]
+    fn default_disk_options_leave_recqemu_disk_alone() {
+        let args = QemuBuilder::new()
+            .disk(PathBuf::from("/tmp/disk.qcow2"))
+            .render_args()
+            .unwrap();
+        assert!(args.iter().any(|arg| arg.contains("/tmp/disk.qcow2")));
+        assert!(!args.iter().any(|arg| arg.contains("if=none")));
+    }
+
+    #[test]
+    fn machine_and_cpu_replace_any_recqemu_choice() {
+        let args = QemuBuilder::new()
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .machine("pc")
+            .cpu("host")
+            .render_args()
+            .unwrap();
+        let values = |flag: &str| -> Vec<&str> {
+            args.windows(2)
+                .filter(|pair| pair[0] == flag)
+                .map(|pair| pair[1].as_str())
+                .collect()
+        };
+        assert_eq!(values("-machine"), ["pc"]);
+        assert_eq!(values("-cpu"), ["host"]);
+
+        let default = QemuBuilder::new()
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .render_args()
+            .unwrap();
+        assert_eq!(default.iter().filter(|arg| *arg == "-machine").count(), 1);
+    }
+
+    #[test]
+    fn uefi_rejects_machines_without_pflash() {
+        let err = QemuBuilder::new()
+            .uefi(PathBuf::from("/tmp/OVMF_CODE.fd"))
+            .machine("microvm")
+            .render_args()
+            .unwrap_err();
+        assert!(err.to_string().contains("has no pflash"), "{}", err);
+    }
+
+    #[test]
+    fn aarch64_uses_its_own_binary_machine_and_console() {
+        let bin = crate::test_support::temp_dir("aarch64-qemu");
+        std::fs::write(bin.join("qemu-system-aarch64"), "").unwrap();
+        let builder = QemuBuilder::new()
+            .arch(Arch::Aarch64)
+            .kernel(PathBuf::from("/tmp/Image"))
+            .append("root=/dev/vda2");
+        let cmd = builder.build_inner(Some(bin.clone().into_os_string()));
+        let _ = std::fs::remove_dir_all(&bin);
+        let cmd = cmd.unwrap();
+        let args: Vec<String> = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let value = |flag: &str| {
+            args.windows(2)
+                .find(|pair| pair[0] == flag)
+                .map(|pair| pair[1].clone())
+        };
+        assert_eq!(args[0], "qemu-system-aarch64");
+        assert_eq!(value("-machine").as_deref(), Some("virt"));
+        assert_eq!(value("-cpu").as_deref(), Some("max"));
+        assert_eq!(
+            value("-append").as_deref(),
+            Some("root=/dev/vda2 console=ttyAMA0")
+        );
+        assert_eq!("arm64".parse::<Arch>().unwrap(), Arch::Aarch64);
+    }
+
+    #[test]
+    fn missing_arch_qemu_binary_is_a_clear_error() {
+        let err = QemuBuilder::new()
+            .arch(Arch::Aarch64)
+            .build_inner(Some("/nonexistent".into()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "qemu-system-aarch64 not found in PATH; install qemu-system-arm to test aarch64 builds"
+        );
+    }
+
+    #[test]
+    fn firmware_env_override_takes_precedence_and_is_validated() {
+        let existing = std::env::current_exe().unwrap();
+        let probed = || Some(PathBuf::from("/usr/share/OVMF/OVMF_CODE.fd"));
+
+        let path = firmware_path(
+            OVMF_CODE_ENV,
+            Some(existing.clone().into_os_string()),
+            probed,
+        )
+        .unwrap();
+        assert_eq!(path, existing);
+
+        let err = firmware_path(OVMF_CODE_ENV, Some("/nonexistent/OVMF.fd".into()), probed)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("LEVITATE_OVMF_CODE=/nonexistent/OVMF.fd"),
+            "{}",
+            err
+        );
+
+        let path = firmware_path(OVMF_CODE_ENV, Some("".into()), probed).unwrap();
+        assert_eq!(path, PathBuf::from("/usr/share/OVMF/OVMF_CODE.fd"));
+        assert!(firmware_path(OVMF_CODE_ENV, None, || None).is_err());
     }
 }
@@ -11,15 +11,26 @@
 //! - `patterns` - Re-export from recqemu (boot/error patterns)
 //! - `qmp` - Local QMP backend for visual testing
 //! - `serial` - Executor trait adapter for Console
+//! - `ssh` - Executor over a forwarded SSH port (concurrent-safe)
+//! - `watchdog` - Overall run timeout that reports and kills every VM
 
 mod builder;
 pub mod patterns;
 pub mod qmp;
 pub mod serial;
 pub mod session;
+pub mod ssh;
+pub mod watchdog;
 
+pub(crate) use builder::find_in_path;
 pub use builder::{
-    acquire_test_lock, create_disk, find_ovmf, find_ovmf_vars, kill_stale_qemu_processes,
-    QemuBuilder,
+    acquire_test_lock, create_disk, find_aavmf, find_aavmf_vars, find_ovmf, find_ovmf_vars,
+    kill_stale_qemu_processes, kvm_available, machine_supports_pflash, Accel, Arch, DiskBus,
+    DiskCache, Display, PortForward, QemuBuilder, AAVMF_CODE_ENV, AAVMF_VARS_ENV,
+    DEFAULT_UEFI_MACHINE, OVMF_CODE_ENV, OVMF_VARS_ENV,
 };
-pub use serial::{Console, SerialExecutorExt};
+pub use serial::{
+    AttachedConsole, BootAttempt, BootTimeline, Console, ConsoleAttach, ContextConsole,
+    EnvExecutor, RecoveringExecutor, SerialExecutorExt, SerialTap,
+};
+pub use ssh::SshExecutor;
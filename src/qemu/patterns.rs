@@ -1,5 +1,49 @@
 //! Error pattern constants for QEMU console monitoring.
 //!
-//! Re-exports from `recqemu::patterns` as the canonical source.
+//! Re-exports from `recqemu::patterns` as the canonical source, plus the
+//! emergency-shell patterns checked while a command runs.
 
 pub use recqemu::patterns::*;
+
+/// Console output meaning the guest dropped into an emergency/rescue shell.
+///
+/// A command that triggers one never prints its end marker, so without these
+/// it would sit silently until its timeout.
+pub const EMERGENCY_SHELL_PATTERNS: &[&str] = &[
+    "Starting Dracut Emergency Shell",
+    "You are in emergency mode",
+    "Reached target emergency.target",
+    "Reached target rescue.target",
+    "(or press Control-D",
+];
+
+/// True if `output` shows the guest dropped into an emergency shell.
+pub fn dropped_to_emergency_shell(output: &str) -> bool {
+    EMERGENCY_SHELL_PATTERNS
+        .iter()
+        .any(|pattern| output.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_dracut_and_systemd_emergency_prompts() {
+        assert!(dropped_to_emergency_shell(
+            "Starting Dracut Emergency Shell...\nWarning: /dev/vda2 does not exist"
+        ));
+        assert!(dropped_to_emergency_shell(
+            "Give root password for maintenance\n(or press Control-D to continue):"
+        ));
+        assert!(dropped_to_emergency_shell(
+            "You are in emergency mode. After logging in, type \"journalctl -xb\""
+        ));
+        assert!(!dropped_to_emergency_shell(
+            "rescue.target\nemergency.target"
+        ));
+        assert!(!dropped_to_emergency_shell(
+            "emergency.service loaded inactive dead Emergency Shell"
+        ));
+    }
+}
@@ -1,14 +1,16 @@
 //! Screen capture via QMP screendump.
 //!
-//! Captures screenshots from QEMU for visual verification.
+//! Captures screenshots from QEMU for visual verification, and compares them
+//! against stored baselines for visual regression testing.
 
 use crate::qemu::qmp::QmpClient;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
 
 /// Capture a screenshot and save to file.
 ///
-/// Screenshots are saved in PPM format, which can be converted
-/// to PNG using ImageMagick: `convert screen.ppm screen.png`
+/// Screenshots are saved in PPM format; [`ppm_to_png`] converts them
+/// for viewing.
 ///
 /// # Arguments
 /// * `client` - QMP client connection
@@ -16,3 +18,320 @@ use anyhow::Result;
 pub fn screendump(client: &mut QmpClient, filename: &str) -> Result<()> {
     client.screendump(filename)
 }
+
+/// Convert a screendump PPM to an RGB PNG.
+pub fn ppm_to_png(ppm: &Path, png_path: &Path) -> Result<()> {
+    let data =
+        std::fs::read(ppm).with_context(|| format!("Failed to read image {}", ppm.display()))?;
+    let image =
+        parse_ppm(&data).with_context(|| format!("Failed to decode image {}", ppm.display()))?;
+    let file = std::fs::File::create(png_path)
+        .with_context(|| format!("Failed to create {}", png_path.display()))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image.rgb))
+        .with_context(|| format!("Failed to write {}", png_path.display()))
+}
+
+/// Largest per-channel difference at which two pixels still count as equal.
+///
+/// Absorbs cursor blink and scaler dithering without hiding real changes.
+const PIXEL_TOLERANCE: u8 = 16;
+
+/// Outcome of comparing a screenshot against its baseline.
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    /// Percentage of pixels (0.0-100.0) outside `PIXEL_TOLERANCE`.
+    pub mismatch_percent: f32,
+    pub mismatched_pixels: usize,
+    pub total_pixels: usize,
+    /// Diff image (PPM): mismatches in red over a dimmed copy of `actual`.
+    pub diff_path: PathBuf,
+    /// `mismatch_percent` is within the requested tolerance.
+    pub passed: bool,
+}
+
+/// Compare a screendump against a baseline image.
+///
+/// `baseline_png` may be a PNG or a PPM (detected by magic bytes), so a
+/// screendump can be promoted to a baseline as-is. `tolerance` is the
+/// percentage of mismatched pixels allowed before `passed` is false.
+/// The diff image is written next to `actual_ppm` as `<name>.diff.ppm`.
+pub fn compare(actual_ppm: &Path, baseline_png: &Path, tolerance: f32) -> Result<DiffResult> {
+    let actual = read_image(actual_ppm)?;
+    let baseline = read_image(baseline_png)?;
+    if (actual.width, actual.height) != (baseline.width, baseline.height) {
+        bail!(
+            "Screenshot {} is {}x{} but baseline {} is {}x{}",
+            actual_ppm.display(),
+            actual.width,
+            actual.height,
+            baseline_png.display(),
+            baseline.width,
+            baseline.height
+        );
+    }
+
+    let (diff, mismatched_pixels) = diff_images(&actual, &baseline);
+    let diff_path = actual_ppm.with_extension("diff.ppm");
+    std::fs::write(&diff_path, diff.to_ppm())
+        .with_context(|| format!("Failed to write diff image {}", diff_path.display()))?;
+
+    let total_pixels = actual.pixel_count();
+    let mismatch_percent = if total_pixels == 0 {
+        0.0
+    } else {
+        mismatched_pixels as f32 * 100.0 / total_pixels as f32
+    };
+    Ok(DiffResult {
+        mismatch_percent,
+        mismatched_pixels,
+        total_pixels,
+        diff_path,
+        passed: mismatch_percent <= tolerance,
+    })
+}
+
+/// Decoded 8-bit RGB image.
+#[derive(Debug, Clone, PartialEq)]
+struct RgbImage {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+impl RgbImage {
+    fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend_from_slice(&self.rgb);
+        out
+    }
+}
+
+/// Build the diff image and count mismatched pixels.
+fn diff_images(actual: &RgbImage, baseline: &RgbImage) -> (RgbImage, usize) {
+    let mut mismatched = 0;
+    let mut rgb = Vec::with_capacity(actual.rgb.len());
+    for (a, b) in actual.rgb.chunks_exact(3).zip(baseline.rgb.chunks_exact(3)) {
+        let differs = a
+            .iter()
+            .zip(b)
+            .any(|(x, y)| x.abs_diff(*y) > PIXEL_TOLERANCE);
+        if differs {
+            mismatched += 1;
+            rgb.extend_from_slice(&[255, 0, 0]);
+        } else {
+            let gray = ((u16::from(a[0]) + u16::from(a[1]) + u16::from(a[2])) / 3 / 3) as u8;
+            rgb.extend_from_slice(&[gray, gray, gray]);
+        }
+    }
+    let diff = RgbImage {
+        width: actual.width,
+        height: actual.height,
+        rgb,
+    };
+    (diff, mismatched)
+}
+
+fn read_image(path: &Path) -> Result<RgbImage> {
+    let data =
+        std::fs::read(path).with_context(|| format!("Failed to read image {}", path.display()))?;
+    let image = if data.starts_with(b"P6") {
+        parse_ppm(&data)
+    } else if data.starts_with(b"\x89PNG") {
+        decode_png(&data)
+    } else {
+        bail!("unsupported image format (expected PPM or PNG)")
+    };
+    image.with_context(|| format!("Failed to decode image {}", path.display()))
+}
+
+/// Parse a binary (`P6`) PPM with maxval 255, as written by QEMU screendump.
+fn parse_ppm(data: &[u8]) -> Result<RgbImage> {
+    let (width, height, offset) = parse_ppm_header(data)?;
+    let pixels = &data[offset..];
+    let expected = width as usize * height as usize * 3;
+    if pixels.len() < expected {
+        bail!(
+            "PPM pixel data truncated ({} of {} bytes)",
+            pixels.len(),
+            expected
+        );
+    }
+    Ok(RgbImage {
+        width,
+        height,
+        rgb: pixels[..expected].to_vec(),
+    })
+}
+
+/// Width, height and pixel data offset of a `P6` PPM header.
+pub(super) fn parse_ppm_header(data: &[u8]) -> Result<(u32, u32, usize)> {
+    // Header: magic, width, height, maxval, each separated by whitespace,
+    // with optional `#` comments; a single whitespace byte precedes pixels.
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while fields.len() < 4 {
+        while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+            if data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < data.len() && !data[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            bail!("truncated PPM header");
+        }
+        fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+    }
+    if fields[0] != "P6" {
+        bail!("not a binary PPM (magic {})", fields[0]);
+    }
+    let width: u32 = fields[1].parse().context("invalid PPM width")?;
+    let height: u32 = fields[2].parse().context("invalid PPM height")?;
+    if fields[3] != "255" {
+        bail!("unsupported PPM maxval {}", fields[3]);
+    }
+    Ok((width, height, (pos + 1).min(data.len())))
+}
+
+fn decode_png(data: &[u8]) -> Result<RgbImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().context("invalid PNG header")?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).context("invalid PNG data")?;
+    let bytes = &buf[..info.buffer_size()];
+    let rgb = match info.color_type {
+        png::ColorType::Rgb => bytes.to_vec(),
+        png::ColorType::Rgba => bytes
+            .chunks_exact(4)
+            .flat_map(|px| [px[0], px[1], px[2]])
+            .collect(),
+        png::ColorType::Grayscale => bytes.iter().flat_map(|&g| [g, g, g]).collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .flat_map(|px| [px[0], px[0], px[0]])
+            .collect(),
+        png::ColorType::Indexed => bail!("indexed PNG was not expanded"),
+    };
+    Ok(RgbImage {
+        width: info.width,
+        height: info.height,
+        rgb,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn solid(width: u32, height: u32, color: [u8; 3]) -> RgbImage {
+        RgbImage {
+            width,
+            height,
+            rgb: color.repeat(width as usize * height as usize),
+        }
+    }
+
+    fn write_ppm(dir: &Path, name: &str, image: &RgbImage) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, image.to_ppm()).expect("write ppm");
+        path
+    }
+
+    #[test]
+    fn test_compare_identical_images_pass() {
+        let dir = temp_dir("capture-identical");
+        let image = solid(4, 4, [10, 20, 30]);
+        let actual = write_ppm(&dir, "actual.ppm", &image);
+        let baseline = write_ppm(&dir, "baseline.ppm", &image);
+
+        let result = compare(&actual, &baseline, 0.0).unwrap();
+        assert_eq!(result.mismatched_pixels, 0);
+        assert!(result.passed);
+        assert_eq!(
+            parse_ppm(&std::fs::read(&result.diff_path).unwrap())
+                .unwrap()
+                .width,
+            4
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_compare_one_pixel_off() {
+        let dir = temp_dir("capture-one-pixel");
+        let base = solid(10, 10, [0, 0, 0]);
+        let mut changed = base.clone();
+        changed.rgb[0] = 200;
+        let actual = write_ppm(&dir, "actual.ppm", &changed);
+        let baseline = write_ppm(&dir, "baseline.ppm", &base);
+
+        let result = compare(&actual, &baseline, 0.5).unwrap();
+        assert_eq!(result.mismatched_pixels, 1);
+        assert!((result.mismatch_percent - 1.0).abs() < f32::EPSILON);
+        assert!(!result.passed);
+        assert!(compare(&actual, &baseline, 1.0).unwrap().passed);
+
+        let diff = parse_ppm(&std::fs::read(&result.diff_path).unwrap()).unwrap();
+        assert_eq!(&diff.rgb[..3], &[255, 0, 0]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_compare_wildly_different_and_within_pixel_tolerance() {
+        let dir = temp_dir("capture-different");
+        let actual = write_ppm(&dir, "actual.ppm", &solid(8, 8, [255, 255, 255]));
+        let baseline = write_ppm(&dir, "baseline.ppm", &solid(8, 8, [0, 0, 0]));
+        let result = compare(&actual, &baseline, 5.0).unwrap();
+        assert!((result.mismatch_percent - 100.0).abs() < f32::EPSILON);
+        assert!(!result.passed);
+
+        let close = write_ppm(&dir, "close.ppm", &solid(8, 8, [PIXEL_TOLERANCE, 0, 0]));
+        assert_eq!(
+            compare(&close, &baseline, 0.0).unwrap().mismatched_pixels,
+            0
+        );
+
+        let small = write_ppm(&dir, "small.ppm", &solid(4, 4, [0, 0, 0]));
+        assert!(compare(&small, &baseline, 100.0).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_ppm_header_skips_comments() {
+        let data = b"P6\n# CREATOR: qemu\n1280 800\n255\n\x00\x00\x00";
+        assert_eq!(parse_ppm_header(data).unwrap(), (1280, 800, data.len() - 3));
+        assert!(parse_ppm_header(b"P3\n1 1\n255\n").is_err());
+    }
+
+    #[test]
+    fn test_ppm_to_png_round_trips() {
+        let dir = temp_dir("capture-png");
+        let mut image = solid(3, 2, [10, 20, 30]);
+        image.rgb[0] = 200;
+        let ppm = write_ppm(&dir, "screen.ppm", &image);
+        let png_path = dir.join("screen.png");
+
+        ppm_to_png(&ppm, &png_path).unwrap();
+        let decoded = read_image(&png_path).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.rgb, image.rgb);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
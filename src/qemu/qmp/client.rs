@@ -9,7 +9,13 @@ use serde_json::{json, Value};
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// How long [`QmpClient::connect`] waits for QEMU to create the socket.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay between connection attempts while the socket is not ready.
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(100);
 
 /// QMP client for communicating with QEMU.
 pub struct QmpClient {
@@ -17,6 +23,8 @@ pub struct QmpClient {
     reader: BufReader<UnixStream>,
     /// Services that failed during boot (tracked for diagnostics).
     failed_services: Vec<String>,
+    /// Display resolution from the last screendump, see `screen_size`.
+    pub(super) screen_size: Option<(u32, u32)>,
 }
 
 /// QMP greeting message sent by QEMU on connection.
@@ -78,16 +86,24 @@ struct QmpCommand {
 impl QmpClient {
     /// Connect to a QMP socket.
     ///
+    /// QEMU creates the socket (`-qmp unix:<path>,server,nowait`) only after
+    /// it starts, so a connect right after spawn can race it. Missing or
+    /// refusing sockets are retried for up to 5 seconds; see
+    /// [`connect_timeout`](Self::connect_timeout).
+    ///
     /// # Arguments
     /// * `socket_path` - Path to the QMP Unix socket
     ///
     /// # Returns
     /// Connected QMP client ready for commands.
     pub fn connect<P: AsRef<Path>>(socket_path: P) -> Result<Self> {
-        let path = socket_path.as_ref();
+        Self::connect_timeout(socket_path, CONNECT_TIMEOUT)
+    }
 
-        // Connect with timeout
-        let stream = UnixStream::connect(path)
+    /// Like [`connect`](Self::connect), waiting up to `timeout` for the socket.
+    pub fn connect_timeout<P: AsRef<Path>>(socket_path: P, timeout: Duration) -> Result<Self> {
+        let path = socket_path.as_ref();
+        let stream = connect_with_retry(path, timeout)
             .with_context(|| format!("Failed to connect to QMP socket: {}", path.display()))?;
 
         stream.set_read_timeout(Some(Duration::from_secs(30)))?;
@@ -99,6 +115,7 @@ impl QmpClient {
             stream,
             reader,
             failed_services: Vec::new(),
+            screen_size: None,
         };
 
         // Wait for QMP greeting
@@ -176,6 +193,15 @@ impl QmpClient {
         }
     }
 
+    /// Ask the guest to shut down via an ACPI power button press.
+    ///
+    /// Returns once QEMU accepts the request; the guest powers off (and QEMU
+    /// exits) asynchronously, or not at all if it ignores ACPI.
+    pub fn system_powerdown(&mut self) -> Result<()> {
+        self.execute("system_powerdown", None)?;
+        Ok(())
+    }
+
     /// Send a key press event.
     ///
     /// # Arguments
@@ -241,14 +267,12 @@ impl QmpClient {
         Ok(())
     }
 
-    /// Send a mouse move + click event.
+    /// Send an absolute mouse move event.
     ///
     /// # Arguments
     /// * `x` - X coordinate (0-32767 absolute)
     /// * `y` - Y coordinate (0-32767 absolute)
-    /// * `button` - Mouse button ("left", "right", "middle")
-    pub fn mouse_click(&mut self, x: i32, y: i32, button: &str) -> Result<()> {
-        // Move mouse to position
+    pub fn mouse_move(&mut self, x: i32, y: i32) -> Result<()> {
         self.execute(
             "input-send-event",
             Some(json!({
@@ -258,6 +282,18 @@ impl QmpClient {
                 ]
             })),
         )?;
+        Ok(())
+    }
+
+    /// Send a mouse move + click event.
+    ///
+    /// # Arguments
+    /// * `x` - X coordinate (0-32767 absolute)
+    /// * `y` - Y coordinate (0-32767 absolute)
+    /// * `button` - Mouse button ("left", "right", "middle")
+    pub fn mouse_click(&mut self, x: i32, y: i32, button: &str) -> Result<()> {
+        // Move mouse to position
+        self.mouse_move(x, y)?;
 
         // Click
         self.execute(
@@ -350,9 +386,58 @@ fn char_to_qcode(ch: char) -> Result<QCodeResult> {
     Ok(QCodeResult { code, needs_shift })
 }
 
+/// Connect to `path`, retrying while QEMU has not yet created or started
+/// listening on the socket.
+fn connect_with_retry(path: &Path, timeout: Duration) -> std::io::Result<UnixStream> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match UnixStream::connect(path) {
+            Ok(stream) => return Ok(stream),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+                ) && Instant::now() < deadline =>
+            {
+                std::thread::sleep(CONNECT_RETRY_INTERVAL);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn connect_waits_for_socket_to_appear() {
+        let path = std::env::temp_dir().join(format!("qmp-retry-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let server_path = path.clone();
+        let server = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            let listener = UnixListener::bind(&server_path).unwrap();
+            let (mut stream, _) = listener.accept().unwrap();
+            writeln!(
+                stream,
+                r#"{{"QMP": {{"version": {{"qemu": {{"micro": 0, "minor": 2, "major": 9}}, "package": ""}}, "capabilities": []}}}}"#
+            )
+            .unwrap();
+            let mut line = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut line)
+                .unwrap();
+            assert!(line.contains("qmp_capabilities"));
+            writeln!(stream, r#"{{"return": {{}}}}"#).unwrap();
+        });
+
+        QmpClient::connect_timeout(&path, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
 
     #[test]
     fn test_char_to_qcode_lowercase() {
@@ -2,10 +2,12 @@
 //!
 //! This module provides higher-level input functions built on QmpClient.
 
+use super::capture::parse_ppm_header;
 use crate::qemu::qmp::QmpClient;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
 /// QMP key codes for common keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyCode {
     // Letters
     A,
@@ -164,6 +166,110 @@ pub fn send_key(client: &mut QmpClient, key: KeyCode) -> Result<()> {
     client.send_key(key.to_qcode())
 }
 
+/// Send a key chord: every modifier plus `key`, pressed together.
+///
+/// Goes through [`QmpClient::send_keys`], so QEMU holds all keys at once,
+/// e.g. `send_key_combo(client, &[KeyCode::Ctrl, KeyCode::Alt], KeyCode::F2)`.
+pub fn send_key_combo(client: &mut QmpClient, modifiers: &[KeyCode], key: KeyCode) -> Result<()> {
+    client.send_keys(&combo_qcodes(modifiers, key))
+}
+
+/// Switch to virtual terminal `n` (1-12) with Ctrl+Alt+F`n`.
+pub fn ctrl_alt_f(client: &mut QmpClient, n: u8) -> Result<()> {
+    send_key_combo(client, &[KeyCode::Ctrl, KeyCode::Alt], function_key(n)?)
+}
+
+/// Send Ctrl+C.
+pub fn ctrl_c(client: &mut QmpClient) -> Result<()> {
+    send_key_combo(client, &[KeyCode::Ctrl], KeyCode::C)
+}
+
+/// QMP key codes with modifiers first, then the key.
+fn combo_qcodes(modifiers: &[KeyCode], key: KeyCode) -> Vec<&'static str> {
+    modifiers
+        .iter()
+        .chain(std::iter::once(&key))
+        .map(KeyCode::to_qcode)
+        .collect()
+}
+
+fn function_key(n: u8) -> Result<KeyCode> {
+    Ok(match n {
+        1 => KeyCode::F1,
+        2 => KeyCode::F2,
+        3 => KeyCode::F3,
+        4 => KeyCode::F4,
+        5 => KeyCode::F5,
+        6 => KeyCode::F6,
+        7 => KeyCode::F7,
+        8 => KeyCode::F8,
+        9 => KeyCode::F9,
+        10 => KeyCode::F10,
+        11 => KeyCode::F11,
+        12 => KeyCode::F12,
+        _ => bail!("No function key F{} (expected 1-12)", n),
+    })
+}
+
+/// Upper bound of QEMU's absolute pointer axes.
+const ABS_AXIS_MAX: i32 = 0x7fff;
+
+/// Pointer helpers in screen pixels, scaled onto the absolute axes of
+/// [`QmpClient::mouse_move`] and [`QmpClient::mouse_click`].
+///
+/// Absolute events need an absolute pointing device in the guest
+/// (e.g. `-device usb-tablet`); a plain PS/2 mouse ignores them.
+impl QmpClient {
+    /// Move the pointer to pixel `(x, y)`.
+    pub fn move_mouse(&mut self, x: u32, y: u32) -> Result<()> {
+        let (abs_x, abs_y) = self.pixel_to_abs(x, y)?;
+        self.mouse_move(abs_x, abs_y)
+    }
+
+    /// Click `button` ("left", "right", "middle") at pixel `(x, y)`.
+    pub fn click(&mut self, x: u32, y: u32, button: &str) -> Result<()> {
+        let (abs_x, abs_y) = self.pixel_to_abs(x, y)?;
+        self.mouse_click(abs_x, abs_y, button)
+    }
+
+    /// Guest display resolution as `(width, height)`.
+    ///
+    /// QMP has no command reporting the console resolution, so the first
+    /// call takes a screendump and reads its PPM header; later calls reuse
+    /// that size until [`forget_screen_size`](Self::forget_screen_size).
+    pub fn screen_size(&mut self) -> Result<(u32, u32)> {
+        if let Some(size) = self.screen_size {
+            return Ok(size);
+        }
+        let path =
+            std::env::temp_dir().join(format!("install-tests-qmp-size-{}.ppm", std::process::id()));
+        self.screendump(&path.to_string_lossy())?;
+        let data = std::fs::read(&path);
+        let _ = std::fs::remove_file(&path);
+        let data = data.with_context(|| format!("Failed to read screendump {}", path.display()))?;
+        let (width, height, _) = parse_ppm_header(&data)
+            .with_context(|| format!("Bad PPM header in {}", path.display()))?;
+        self.screen_size = Some((width, height));
+        Ok((width, height))
+    }
+
+    /// Drop the cached screen size, e.g. after the guest switches video mode.
+    pub fn forget_screen_size(&mut self) {
+        self.screen_size = None;
+    }
+
+    fn pixel_to_abs(&mut self, x: u32, y: u32) -> Result<(i32, i32)> {
+        let (width, height) = self.screen_size()?;
+        Ok((scale_to_abs(x, width), scale_to_abs(y, height)))
+    }
+}
+
+/// Scale a pixel coordinate onto the 0..=0x7fff absolute axis.
+fn scale_to_abs(pixel: u32, extent: u32) -> i32 {
+    let last = i64::from(extent.max(2) - 1);
+    (i64::from(pixel).min(last) * i64::from(ABS_AXIS_MAX) / last) as i32
+}
+
 /// Send text as a series of keystrokes.
 pub fn send_text(client: &mut QmpClient, text: &str) -> Result<()> {
     client.send_text(text)
@@ -179,4 +285,19 @@ mod tests {
         assert_eq!(KeyCode::A.to_qcode(), "a");
         assert_eq!(KeyCode::Num0.to_qcode(), "0");
     }
+
+    #[test]
+    fn test_key_combo_puts_modifiers_first() {
+        let qcodes = combo_qcodes(&[KeyCode::Ctrl, KeyCode::Alt], function_key(2).unwrap());
+        assert_eq!(qcodes, ["ctrl", "alt", "f2"]);
+        assert!(function_key(13).is_err());
+    }
+
+    #[test]
+    fn test_pixel_coordinates_scale_to_abs_axis() {
+        assert_eq!(scale_to_abs(0, 1024), 0);
+        assert_eq!(scale_to_abs(1023, 1024), ABS_AXIS_MAX);
+        assert_eq!(scale_to_abs(5000, 1024), ABS_AXIS_MAX);
+        assert_eq!(scale_to_abs(400, 800), 400 * ABS_AXIS_MAX / 799);
+    }
 }
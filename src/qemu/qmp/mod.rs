@@ -33,6 +33,6 @@ mod capture;
 mod client;
 mod input;
 
-pub use capture::screendump;
+pub use capture::{compare, ppm_to_png, screendump, DiffResult};
 pub use client::QmpClient;
-pub use input::{send_key, send_text, KeyCode};
+pub use input::{ctrl_alt_f, ctrl_c, send_key, send_key_combo, send_text, KeyCode};
@@ -0,0 +1,121 @@
+//! Attach a [`Console`] to a VM the harness did not spawn.
+//!
+//! recqemu's `Console` drives a child's stdin/stdout, so [`ConsoleAttach::attach`]
+//! spawns `socat` to bridge that pipe pair to the VM's serial socket. The VM
+//! must already be running with its serial port on a Unix socket:
+//!
+//! ```text
+//! qemu-system-x86_64 ... -nographic \
+//!     -serial unix:/tmp/levitate-serial.sock,server=on,wait=off
+//! ```
+//!
+//! QEMU serves one serial client at a time; close any `socat`/`minicom`
+//! session on the socket first. The guest must already be at a root shell
+//! (log in over the socket once, then detach) since attaching skips the boot
+//! wait and login.
+
+use super::Console;
+use anyhow::{bail, Context, Result};
+use std::ops::{Deref, DerefMut};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// Serial console over an existing `-serial unix:` socket.
+///
+/// Dereferences to the [`Console`]. Dropping it stops the bridge; the VM
+/// keeps running.
+pub struct AttachedConsole {
+    console: Console,
+    bridge: Child,
+}
+
+/// Adds [`attach`](ConsoleAttach::attach) to recqemu's `Console`.
+pub trait ConsoleAttach {
+    /// Connect to the serial socket of an already-running VM.
+    fn attach(serial_socket: &Path) -> Result<AttachedConsole>;
+}
+
+impl ConsoleAttach for Console {
+    fn attach(serial_socket: &Path) -> Result<AttachedConsole> {
+        ensure_socket(serial_socket)?;
+        let mut bridge = Command::new("socat")
+            .arg("-")
+            .arg(format!("UNIX-CONNECT:{}", serial_socket.display()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .context("spawning socat to bridge the serial socket (is socat installed?)")?;
+        match Console::new(&mut bridge) {
+            Ok(console) => Ok(AttachedConsole { console, bridge }),
+            Err(err) => {
+                let _ = bridge.kill();
+                let _ = bridge.wait();
+                Err(err.context(format!("attaching to '{}'", serial_socket.display())))
+            }
+        }
+    }
+}
+
+impl Deref for AttachedConsole {
+    type Target = Console;
+
+    fn deref(&self) -> &Console {
+        &self.console
+    }
+}
+
+impl DerefMut for AttachedConsole {
+    fn deref_mut(&mut self) -> &mut Console {
+        &mut self.console
+    }
+}
+
+impl Drop for AttachedConsole {
+    fn drop(&mut self) {
+        let _ = self.bridge.kill();
+        let _ = self.bridge.wait();
+    }
+}
+
+/// Fail early, with the QEMU flags to use, unless `path` is a Unix socket.
+fn ensure_socket(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path).with_context(|| {
+        format!(
+            "serial socket '{}' not found (start QEMU with -serial unix:{},server=on,wait=off)",
+            path.display(),
+            path.display()
+        )
+    })?;
+    if !metadata.file_type().is_socket() {
+        bail!("'{}' is not a Unix socket", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn only_unix_sockets_are_attachable() {
+        let dir = temp_dir("attach");
+        let socket = dir.join("serial.sock");
+        let file = dir.join("serial.txt");
+        let _listener = UnixListener::bind(&socket).unwrap();
+        std::fs::write(&file, "not a socket").unwrap();
+
+        assert!(ensure_socket(&socket).is_ok());
+        let err = format!("{:#}", ensure_socket(&file).unwrap_err());
+        assert!(err.contains("not a Unix socket"), "{}", err);
+        let err = format!(
+            "{:#}",
+            ensure_socket(&dir.join("missing.sock")).unwrap_err()
+        );
+        assert!(err.contains("-serial unix:"), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,142 @@
+//! Guest environment that persists across serial commands.
+//!
+//! Every `Console::exec` runs in a fresh command, so `export DESTDIR=...`
+//! in one step is gone by the next. [`EnvExecutor`] keeps the variables
+//! host-side and prefixes each command with `export K=V;`, so a series of
+//! install commands can share `DESTDIR` or a proxy without rewriting each
+//! one. Nothing changes for callers that don't wrap their executor.
+
+use crate::executor::{shell_quote, ExecResult, Executor};
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Executor that exports a host-side variable map before every command.
+///
+/// `write_file`, `login` and the boot waits pass through untouched.
+pub struct EnvExecutor<'a> {
+    inner: &'a mut dyn Executor,
+    env: BTreeMap<String, String>,
+}
+
+impl<'a> EnvExecutor<'a> {
+    /// Wrap `inner` with an empty environment.
+    pub fn new(inner: &'a mut dyn Executor) -> Self {
+        Self {
+            inner,
+            env: BTreeMap::new(),
+        }
+    }
+
+    /// Export `key=value` to every later command, replacing an earlier value.
+    pub fn set_env(&mut self, key: &str, value: &str) -> Result<()> {
+        let valid = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            bail!("'{}' is not a valid shell variable name", key);
+        }
+        self.env.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Stop exporting anything; later commands run as if unwrapped.
+    pub fn clear_env(&mut self) {
+        self.env.clear();
+    }
+
+    fn with_env(&self, cmd: &str) -> String {
+        if self.env.is_empty() {
+            return cmd.to_string();
+        }
+        let exports: Vec<String> = self
+            .env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, shell_quote(value)))
+            .collect();
+        format!("export {}; {}", exports.join(" "), cmd)
+    }
+}
+
+impl Executor for EnvExecutor<'_> {
+    fn exec(&mut self, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        let cmd = self.with_env(cmd);
+        self.inner.exec(&cmd, timeout)
+    }
+
+    fn exec_long(
+        &mut self,
+        cmd: &str,
+        stall_timeout: Duration,
+        error_patterns: &[&str],
+    ) -> Result<ExecResult> {
+        let cmd = self.with_env(cmd);
+        self.inner.exec_long(&cmd, stall_timeout, error_patterns)
+    }
+
+    /// The exports run inside the chroot, after `chroot` itself.
+    fn exec_chroot(&mut self, path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        let cmd = self.with_env(cmd);
+        self.inner.exec_chroot(path, &cmd, timeout)
+    }
+
+    fn write_file(&mut self, path: &str, content: &str) -> Result<()> {
+        self.inner.write_file(path, content)
+    }
+
+    fn login(&mut self, username: &str, password: &str, timeout: Duration) -> Result<()> {
+        self.inner.login(username, password, timeout)
+    }
+
+    fn wait_for_live_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.inner.wait_for_live_boot(stall_timeout)
+    }
+
+    fn wait_for_installed_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.inner.wait_for_installed_boot(stall_timeout)
+    }
+
+    fn failed_services(&self) -> &[String] {
+        self.inner.failed_services()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeExecutor;
+
+    #[test]
+    fn env_persists_across_commands_until_cleared() {
+        let timeout = Duration::from_secs(5);
+        let mut shell = FakeExecutor::host_shell();
+        let mut exec = EnvExecutor::new(&mut shell);
+        exec.set_env("DESTDIR", "/mnt/it's here").unwrap();
+        assert!(exec.set_env("NOT-A-NAME", "x").is_err());
+
+        assert_eq!(
+            exec.exec("echo \"$DESTDIR\"", timeout)
+                .unwrap()
+                .output
+                .trim(),
+            "/mnt/it's here"
+        );
+        assert_eq!(
+            exec.exec("printf '%s' \"$DESTDIR\"", timeout)
+                .unwrap()
+                .output,
+            "/mnt/it's here"
+        );
+
+        exec.clear_env();
+        assert_eq!(
+            exec.exec("echo \"${DESTDIR-unset}\"", timeout)
+                .unwrap()
+                .output
+                .trim(),
+            "unset"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Triage for failed serial logins.
+//!
+//! `Console::login` (recqemu) only recognises a literal "Login incorrect";
+//! when PAM silently re-prompts it just times out. Reading the serial
+//! transcript from a [`SerialTap`] tells the two apart and shows how far the
+//! login got.
+
+use super::{Console, SerialTap};
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+/// Prompt getty prints before the username.
+const LOGIN_PROMPT: &str = "login:";
+
+/// Prompt printed before the password.
+const PASSWORD_PROMPT: &str = "Password:";
+
+/// Explicit rejection messages from login/PAM.
+const REJECTION_MARKERS: &[&str] = &["Login incorrect", "Authentication failure"];
+
+/// How far a serial login got, from the transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginProgress {
+    pub saw_login_prompt: bool,
+    /// The password prompt appeared, so the username was accepted as input.
+    pub sent_username: bool,
+    /// Output followed the password prompt, so the password was sent.
+    pub sent_password: bool,
+    /// A rejection message or a fresh `login:` prompt followed the password.
+    pub rejected: bool,
+}
+
+impl LoginProgress {
+    /// Classify a serial transcript covering one login attempt.
+    pub fn from_transcript(lines: &[String]) -> Self {
+        let mut progress = Self {
+            saw_login_prompt: false,
+            sent_username: false,
+            sent_password: false,
+            rejected: false,
+        };
+        for line in lines {
+            if progress.sent_username {
+                if REJECTION_MARKERS.iter().any(|marker| line.contains(marker))
+                    || line.contains(LOGIN_PROMPT)
+                {
+                    progress.sent_password = true;
+                    progress.rejected = true;
+                } else if !line.contains(PASSWORD_PROMPT) && !line.trim().is_empty() {
+                    progress.sent_password = true;
+                }
+            }
+            if line.contains(LOGIN_PROMPT) {
+                progress.saw_login_prompt = true;
+            }
+            if line.contains(PASSWORD_PROMPT) {
+                progress.sent_username = true;
+            }
+        }
+        progress
+    }
+}
+
+impl std::fmt::Display for LoginProgress {
+    /// e.g. `saw login prompt: yes, sent username: yes, sent password: no`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+        write!(
+            f,
+            "saw login prompt: {}, sent username: {}, sent password: {}",
+            yes_no(self.saw_login_prompt),
+            yes_no(self.sent_username),
+            yes_no(self.sent_password)
+        )
+    }
+}
+
+/// `Console::login`, explaining a failure from the tap's transcript.
+///
+/// Bails with "credentials rejected (re-prompted)" when the guest asked for
+/// the login again after the password, and otherwise adds how far the login
+/// got to the original error.
+pub fn login_observed(
+    console: &mut Console,
+    tap: &SerialTap,
+    username: &str,
+    password: &str,
+    timeout: Duration,
+) -> Result<()> {
+    let seen_before = tap.transcript_since(Duration::ZERO).len();
+    let Err(err) = console.login(username, password, timeout) else {
+        return Ok(());
+    };
+    // The prompt usually arrived during the boot wait, before this attempt.
+    let transcript = tap.transcript_since(Duration::ZERO);
+    let start = transcript[..seen_before.min(transcript.len())]
+        .iter()
+        .rposition(|line| line.contains(LOGIN_PROMPT))
+        .unwrap_or(seen_before.min(transcript.len()));
+    let progress = LoginProgress::from_transcript(&transcript[start..]);
+    if progress.rejected {
+        bail!(
+            "login as {} failed: credentials rejected (re-prompted) ({}): {:#}",
+            username,
+            progress,
+            err
+        );
+    }
+    Err(err.context(format!("login as {} failed ({})", username, progress)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn re_prompt_after_password_counts_as_rejected() {
+        let progress = LoginProgress::from_transcript(&transcript(&[
+            "levitate login: root",
+            "Password: ",
+            "",
+            "levitate login: ",
+        ]));
+        assert!(progress.saw_login_prompt && progress.sent_username && progress.sent_password);
+        assert!(progress.rejected);
+    }
+
+    #[test]
+    fn missing_prompt_is_not_a_rejection() {
+        let progress =
+            LoginProgress::from_transcript(&transcript(&["[  OK  ] Started getty@tty1"]));
+        assert!(!progress.saw_login_prompt && !progress.rejected);
+        assert_eq!(
+            progress.to_string(),
+            "saw login prompt: no, sent username: no, sent password: no"
+        );
+    }
+
+    #[test]
+    fn stuck_at_password_prompt_reports_username_only() {
+        let progress =
+            LoginProgress::from_transcript(&transcript(&["levitate login: root", "Password: "]));
+        assert!(progress.sent_username);
+        assert!(!progress.sent_password && !progress.rejected);
+    }
+}
@@ -13,39 +13,94 @@
 //!
 //! - `impl Executor for Console` - Adapts Console to the test Executor trait
 //! - `SerialExecutorExt` - Context-aware methods for multi-distro support
+//! - `ContextConsole` - Executor whose boot waits use the distro's patterns
+//! - `BootAttempt` - Outcome of a boot wait that may ask the caller to respawn QEMU
+//! - `SerialTap` - Live per-line observers, first-seen pattern timing and `expect`
+//! - `BootTimeline` - UEFI → bootloader → kernel → userspace timings from a tap
+//! - `Heartbeat` - Periodic progress line from a tap during long boot waits
+//! - `login_observed` - Login that tells "no prompt" from "credentials rejected"
+//! - `RecoveringExecutor` - Ctrl-C and resync after a command times out
+//! - `EnvExecutor` - `set_env`/`clear_env` exported before every command
+//! - `ConsoleAttach` - `Console::attach` to a running VM's `-serial unix:` socket
+
+mod attach;
+mod env;
+mod login;
+mod recover;
+mod tap;
 
 // Re-export from recqemu
+pub use attach::{AttachedConsole, ConsoleAttach};
+pub use env::EnvExecutor;
+pub use login::{login_observed, LoginProgress};
+pub use recover::RecoveringExecutor;
 pub use recqemu::serial::{generate_command_markers, is_marker_line, CommandResult, Console};
+pub use tap::{BootTimeline, Heartbeat, LineCallback, SerialTap};
 
 use crate::distro::{load_installed_scenario_facts, DistroContext};
-use crate::executor::{ExecResult, Executor};
-use anyhow::Result;
+use crate::executor::{shell_quote, traced, ExecResult, Executor};
+use crate::qemu::patterns::{
+    dropped_to_emergency_shell, EMERGENCY_SHELL_PATTERNS, FATAL_ERROR_PATTERNS,
+};
+use anyhow::{bail, Result};
 use std::time::Duration;
 
+/// Disconnect message recqemu reports when the QEMU child exits mid-wait.
+const QEMU_DIED_MARKER: &str = "QEMU process died";
+
+/// Appended to the output of a command aborted by an emergency shell.
+const EMERGENCY_SHELL_NOTE: &str = "\n[aborted: guest dropped to emergency shell]";
+
+/// Kernel banner; its presence in the error means QEMU died after the kernel started.
+const KERNEL_OUTPUT_MARKER: &str = "Linux version";
+
+/// Outcome of a live boot wait that distinguishes transient QEMU death.
+#[derive(Debug)]
+pub enum BootAttempt {
+    /// Success patterns were observed.
+    Booted,
+    /// QEMU died before producing any kernel output (e.g. firmware file
+    /// lock contention). The caller should respawn the VM and try again.
+    Respawn(anyhow::Error),
+}
+
+/// True if the boot error is a QEMU death before the kernel printed anything.
+pub fn is_early_qemu_death(err: &anyhow::Error) -> bool {
+    let msg = format!("{:#}", err);
+    msg.contains(QEMU_DIED_MARKER) && !msg.contains(KERNEL_OUTPUT_MARKER)
+}
+
 /// Implementation of Executor trait for serial Console.
 ///
 /// This allows test steps to work with the serial backend through the
 /// abstract Executor interface.
 impl Executor for Console {
+    /// Streams with `timeout` as the stall limit so an emergency shell
+    /// aborts the command at once instead of after the whole timeout.
     fn exec(&mut self, cmd: &str, timeout: Duration) -> Result<ExecResult> {
-        let result = Console::exec(self, cmd, timeout)?;
-        Ok(ExecResult {
-            completed: result.completed,
-            exit_code: result.exit_code,
-            output: result.output,
-            aborted_on_error: result.aborted_on_error,
-            stalled: result.stalled,
-        })
+        traced(cmd, || exec_watched(self, cmd, timeout))
     }
 
+    /// recqemu's chroot always runs `/bin/bash`; [`ContextConsole`] uses the
+    /// distro's `chroot_shell` instead.
     fn exec_chroot(&mut self, path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
-        let result = Console::exec_chroot(self, path, cmd, timeout)?;
-        Ok(ExecResult {
-            completed: result.completed,
-            exit_code: result.exit_code,
-            output: result.output,
-            aborted_on_error: result.aborted_on_error,
-            stalled: result.stalled,
+        traced(&format!("[chroot {}] {}", path, cmd), || {
+            let result = Console::exec_chroot(self, path, cmd, timeout)?;
+            Ok(exec_result(result, &abort_patterns(&[])))
+        })
+    }
+
+    /// Backed by `Console::exec_streaming`: no deadline while output flows.
+    fn exec_long(
+        &mut self,
+        cmd: &str,
+        stall_timeout: Duration,
+        error_patterns: &[&str],
+    ) -> Result<ExecResult> {
+        traced(cmd, || {
+            let patterns = abort_patterns(error_patterns);
+            let result = Console::exec_streaming(self, cmd, stall_timeout, &patterns)?;
+            Ok(exec_result(result, &patterns))
         })
     }
 
@@ -57,19 +112,192 @@ impl Executor for Console {
         Console::login(self, username, password, timeout)
     }
 
+    /// Refuses: recqemu's own boot wait only knows LevitateOS markers and
+    /// would never detect another distro's boot. Use [`ContextConsole`].
+    fn wait_for_live_boot(&mut self, _stall_timeout: Duration) -> Result<()> {
+        bail!("{}", CONTEXT_FREE_WAIT)
+    }
+
+    /// Refuses for the same reason as `wait_for_live_boot`.
+    fn wait_for_installed_boot(&mut self, _stall_timeout: Duration) -> Result<()> {
+        bail!("{}", CONTEXT_FREE_WAIT)
+    }
+
+    fn failed_services(&self) -> &[String] {
+        Console::failed_services(self)
+    }
+}
+
+const CONTEXT_FREE_WAIT: &str = "Console boot waits need a distro context: \
+     wrap the console in ContextConsole or call SerialExecutorExt::*_with_context";
+
+/// What a boot wait watches for.
+#[derive(Debug, PartialEq)]
+struct BootWait {
+    success: Vec<String>,
+    errors: Vec<String>,
+    /// Record service failures for later diagnostics instead of failing.
+    track_service_failures: bool,
+}
+
+impl BootWait {
+    fn live(ctx: &dyn DistroContext) -> Self {
+        Self {
+            success: owned(ctx.live_boot_success_patterns()),
+            errors: owned(ctx.boot_error_patterns()),
+            track_service_failures: false,
+        }
+    }
+
+    fn installed(ctx: &dyn DistroContext) -> Result<Self> {
+        let facts = load_installed_scenario_facts(ctx.id())?;
+        Ok(Self {
+            success: facts.installed_boot.success_patterns,
+            errors: owned(ctx.critical_boot_errors()),
+            track_service_failures: true,
+        })
+    }
+
+    fn run(&self, console: &mut Console, stall_timeout: Duration) -> Result<()> {
+        let success: Vec<&str> = self.success.iter().map(String::as_str).collect();
+        let errors: Vec<&str> = self.errors.iter().map(String::as_str).collect();
+        console.wait_for_boot_with_patterns(
+            stall_timeout,
+            &success,
+            &errors,
+            self.track_service_failures,
+        )
+    }
+}
+
+fn owned(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
+/// Serial console as an [`Executor`] for one distro.
+///
+/// Identical to the `Console` impl except that `wait_for_live_boot` and
+/// `wait_for_installed_boot` use the distro's patterns and `exec_chroot`
+/// runs the distro's `chroot_shell`, like [`SerialExecutorExt`].
+pub struct ContextConsole<'a> {
+    console: &'a mut Console,
+    ctx: &'a dyn DistroContext,
+}
+
+impl<'a> ContextConsole<'a> {
+    pub fn new(console: &'a mut Console, ctx: &'a dyn DistroContext) -> Self {
+        Self { console, ctx }
+    }
+}
+
+impl Executor for ContextConsole<'_> {
+    fn exec(&mut self, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        Executor::exec(self.console, cmd, timeout)
+    }
+
+    fn exec_chroot(&mut self, path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        self.console
+            .exec_chroot_with_context(path, cmd, timeout, self.ctx)
+    }
+
+    fn exec_long(
+        &mut self,
+        cmd: &str,
+        stall_timeout: Duration,
+        error_patterns: &[&str],
+    ) -> Result<ExecResult> {
+        Executor::exec_long(self.console, cmd, stall_timeout, error_patterns)
+    }
+
+    fn write_file(&mut self, path: &str, content: &str) -> Result<()> {
+        Executor::write_file(self.console, path, content)
+    }
+
+    fn login(&mut self, username: &str, password: &str, timeout: Duration) -> Result<()> {
+        Executor::login(self.console, username, password, timeout)
+    }
+
     fn wait_for_live_boot(&mut self, stall_timeout: Duration) -> Result<()> {
-        Console::wait_for_boot(self, stall_timeout)
+        self.console
+            .wait_for_live_boot_with_context(stall_timeout, self.ctx)
     }
 
     fn wait_for_installed_boot(&mut self, stall_timeout: Duration) -> Result<()> {
-        Console::wait_for_installed_boot(self, stall_timeout)
+        self.console
+            .wait_for_installed_boot_with_context(stall_timeout, self.ctx)
     }
 
     fn failed_services(&self) -> &[String] {
-        Console::failed_services(self)
+        Console::failed_services(self.console)
+    }
+}
+
+/// `extra` plus the fatal-error and emergency-shell patterns every command
+/// is watched for.
+fn abort_patterns<'a>(extra: &[&'a str]) -> Vec<&'a str> {
+    FATAL_ERROR_PATTERNS
+        .iter()
+        .chain(EMERGENCY_SHELL_PATTERNS)
+        .chain(extra)
+        .copied()
+        .collect()
+}
+
+/// Stream `cmd` with `timeout` as the stall limit, aborting on the
+/// fatal-error and emergency-shell patterns.
+fn exec_watched(console: &mut Console, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+    let patterns = abort_patterns(&[]);
+    let result = Console::exec_streaming(console, cmd, timeout, &patterns)?;
+    Ok(exec_result(result, &patterns))
+}
+
+/// Convert a recqemu result watched for `patterns`, recording which one
+/// aborted the command and naming an emergency shell when that was the cause.
+fn exec_result(result: CommandResult, patterns: &[&str]) -> ExecResult {
+    let mut output = result.output;
+    let matched_pattern = if result.aborted_on_error {
+        last_match(&output, patterns).map(str::to_string)
+    } else {
+        None
+    };
+    if result.aborted_on_error && dropped_to_emergency_shell(&output) {
+        output.push_str(EMERGENCY_SHELL_NOTE);
+    }
+    ExecResult {
+        completed: result.completed,
+        exit_code: result.exit_code,
+        output,
+        aborted_on_error: result.aborted_on_error,
+        stalled: result.stalled,
+        disconnected: false,
+        matched_pattern,
     }
 }
 
+/// The pattern whose last occurrence ends latest in `output`: the console
+/// stops reading at the match that aborted the command.
+fn last_match<'a>(output: &str, patterns: &[&'a str]) -> Option<&'a str> {
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            output
+                .rfind(pattern)
+                .map(|start| (start + pattern.len(), *pattern))
+        })
+        .max_by_key(|(end, _)| *end)
+        .map(|(_, pattern)| pattern)
+}
+
+/// `recchroot` invocation running `cmd` under `shell` inside `path`.
+fn chroot_command(path: &str, shell: &str, cmd: &str) -> String {
+    format!(
+        "recchroot {} {} -c {}",
+        shell_quote(path),
+        shell_quote(shell),
+        shell_quote(cmd)
+    )
+}
+
 /// Wrapper trait extension for Console to work with DistroContext.
 ///
 /// The Executor trait is generic and doesn't know about DistroContext.
@@ -86,6 +314,27 @@ pub trait SerialExecutorExt {
         stall_timeout: Duration,
         ctx: &dyn DistroContext,
     ) -> Result<()>;
+
+    /// Like `wait_for_live_boot_with_context`, but reports an early QEMU
+    /// death as `BootAttempt::Respawn` instead of an error.
+    ///
+    /// Respawning needs the QEMU builder, so the retry loop itself lives in
+    /// `session::spawn_live_with_retry`.
+    fn wait_for_boot_retrying(
+        &mut self,
+        stall_timeout: Duration,
+        ctx: &dyn DistroContext,
+    ) -> Result<BootAttempt>;
+
+    /// `exec_chroot` with the distro's `chroot_shell`, for live environments
+    /// without bash (busybox `ash`, plain `sh`).
+    fn exec_chroot_with_context(
+        &mut self,
+        path: &str,
+        cmd: &str,
+        timeout: Duration,
+        ctx: &dyn DistroContext,
+    ) -> Result<ExecResult>;
 }
 
 impl SerialExecutorExt for Console {
@@ -94,13 +343,7 @@ impl SerialExecutorExt for Console {
         stall_timeout: Duration,
         ctx: &dyn DistroContext,
     ) -> Result<()> {
-        Console::wait_for_boot_with_patterns(
-            self,
-            stall_timeout,
-            ctx.live_boot_success_patterns(),
-            ctx.boot_error_patterns(),
-            false, // Don't track service failures, fail immediately
-        )
+        BootWait::live(ctx).run(self, stall_timeout)
     }
 
     fn wait_for_installed_boot_with_context(
@@ -108,19 +351,86 @@ impl SerialExecutorExt for Console {
         stall_timeout: Duration,
         ctx: &dyn DistroContext,
     ) -> Result<()> {
-        let facts = load_installed_scenario_facts(ctx.id())?;
-        let success_patterns: Vec<&str> = facts
-            .installed_boot
-            .success_patterns
-            .iter()
-            .map(String::as_str)
-            .collect();
-        Console::wait_for_boot_with_patterns(
-            self,
-            stall_timeout,
-            &success_patterns,
-            ctx.critical_boot_errors(),
-            true, // Track service failures for later diagnostic capture
-        )
+        BootWait::installed(ctx)?.run(self, stall_timeout)
+    }
+
+    fn wait_for_boot_retrying(
+        &mut self,
+        stall_timeout: Duration,
+        ctx: &dyn DistroContext,
+    ) -> Result<BootAttempt> {
+        match self.wait_for_live_boot_with_context(stall_timeout, ctx) {
+            Ok(()) => Ok(BootAttempt::Booted),
+            Err(err) if is_early_qemu_death(&err) => Ok(BootAttempt::Respawn(err)),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn exec_chroot_with_context(
+        &mut self,
+        path: &str,
+        cmd: &str,
+        timeout: Duration,
+        ctx: &dyn DistroContext,
+    ) -> Result<ExecResult> {
+        let chroot = chroot_command(path, ctx.chroot_shell(), cmd);
+        traced(&format!("[chroot {}] {}", path, cmd), || {
+            exec_watched(self, &chroot, timeout)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distro::{context_for_distro, AVAILABLE_DISTROS};
+
+    #[test]
+    fn chroot_command_runs_the_configured_shell() {
+        assert_eq!(
+            chroot_command("/mnt", "/bin/ash", "echo $HOME"),
+            "recchroot '/mnt' '/bin/ash' -c 'echo $HOME'"
+        );
+        for id in AVAILABLE_DISTROS {
+            let ctx = context_for_distro(id).unwrap();
+            let cmd = chroot_command("/mnt", ctx.chroot_shell(), "true");
+            assert!(
+                cmd.contains(&format!(" '{}' -c ", ctx.chroot_shell())),
+                "{}: {}",
+                id,
+                cmd
+            );
+        }
+    }
+
+    #[test]
+    fn last_match_picks_the_pattern_that_stopped_the_console() {
+        let output = "error: disk full\nretrying\nKernel panic - not syncing: VFS";
+        assert_eq!(
+            last_match(output, &["error:", "Kernel panic"]),
+            Some("Kernel panic")
+        );
+        assert_eq!(last_match("all good", &["error:"]), None);
+    }
+
+    #[test]
+    fn live_boot_wait_uses_each_distros_patterns() {
+        for id in AVAILABLE_DISTROS {
+            let ctx = context_for_distro(id).unwrap();
+            let wait = BootWait::live(&*ctx);
+            assert_eq!(
+                wait.success,
+                owned(ctx.live_boot_success_patterns()),
+                "{}",
+                id
+            );
+            assert_eq!(wait.errors, owned(ctx.boot_error_patterns()), "{}", id);
+            assert!(!wait.track_service_failures);
+            assert!(
+                !wait.success.iter().any(|p| p == "LevitateOS Live"),
+                "{} waits on a LevitateOS-only marker",
+                id
+            );
+        }
     }
 }
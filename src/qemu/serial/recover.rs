@@ -0,0 +1,128 @@
+//! Interrupt timed-out serial commands so the next one starts clean.
+//!
+//! When `Console::exec` times out the command keeps running in the guest,
+//! and its late output lands in whatever runs next. [`RecoveringExecutor`]
+//! wraps an executor and, after an incomplete command, has the tap send
+//! Ctrl-C and wait for the shell ([`SerialTap::interrupt`]).
+//!
+//! The installed-system scenarios that run commands over serial (automated
+//! login and daily-driver tools) wrap their console in it. The live and
+//! install scenarios run their checks over SSH instead, where `SshExecutor`
+//! bounds each command with a guest-side `timeout`; their few serial
+//! commands are short setup steps.
+
+use super::SerialTap;
+use crate::executor::{ExecResult, Executor};
+use anyhow::Result;
+use std::time::Duration;
+
+/// How long the shell gets to come back after Ctrl-C.
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Executor that interrupts the guest after a command times out or stalls.
+///
+/// Commands aborted on a fatal pattern are left alone: the guest is already
+/// past saving, and the caller reports that instead.
+pub struct RecoveringExecutor<'a> {
+    inner: &'a mut dyn Executor,
+    tap: &'a SerialTap,
+}
+
+impl<'a> RecoveringExecutor<'a> {
+    /// Wrap `inner`, whose serial stream `tap` observes.
+    pub fn new(inner: &'a mut dyn Executor, tap: &'a SerialTap) -> Self {
+        Self { inner, tap }
+    }
+
+    fn recover_after(&self, result: ExecResult) -> ExecResult {
+        if !result.completed && !result.aborted_on_error {
+            if let Err(err) = self.tap.interrupt(RECOVERY_TIMEOUT) {
+                eprintln!("  could not recover serial shell: {:#}", err);
+            }
+        }
+        result
+    }
+}
+
+impl Executor for RecoveringExecutor<'_> {
+    fn exec(&mut self, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        let result = self.inner.exec(cmd, timeout)?;
+        Ok(self.recover_after(result))
+    }
+
+    fn exec_long(
+        &mut self,
+        cmd: &str,
+        stall_timeout: Duration,
+        error_patterns: &[&str],
+    ) -> Result<ExecResult> {
+        let result = self.inner.exec_long(cmd, stall_timeout, error_patterns)?;
+        Ok(self.recover_after(result))
+    }
+
+    fn exec_chroot(&mut self, path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        let result = self.inner.exec_chroot(path, cmd, timeout)?;
+        Ok(self.recover_after(result))
+    }
+
+    fn write_file(&mut self, path: &str, content: &str) -> Result<()> {
+        self.inner.write_file(path, content)
+    }
+
+    fn login(&mut self, username: &str, password: &str, timeout: Duration) -> Result<()> {
+        self.inner.login(username, password, timeout)
+    }
+
+    fn wait_for_live_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.inner.wait_for_live_boot(stall_timeout)
+    }
+
+    fn wait_for_installed_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.inner.wait_for_installed_boot(stall_timeout)
+    }
+
+    fn failed_services(&self) -> &[String] {
+        self.inner.failed_services()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FakeExecutor, Reply};
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn hung_command_is_interrupted_before_the_next_one() {
+        // Stand-in guest shell that only runs the recovery probe.
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "while read -r line; do line=$(printf '%s' \"$line\" | tr -d '\\003'); \
+                 case \"$line\" in printf*) eval \"$line\";; esac; done",
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let tap = SerialTap::attach(&mut child).expect("attach tap");
+        let mut console_stdout = child.stdout.take().expect("tapped stdout");
+        std::thread::spawn(move || std::io::copy(&mut console_stdout, &mut std::io::sink()));
+
+        let mut inner = FakeExecutor::new()
+            .reply("sleep", Reply::Timeout)
+            .otherwise(Reply::ok("ok"));
+        let mut exec = RecoveringExecutor::new(&mut inner, &tap);
+        let hung = exec.exec("sleep 600", Duration::from_secs(1)).unwrap();
+        assert!(!hung.completed);
+        assert!(tap.time_to_pattern("INTERRUPT_RECOVERED").is_some());
+
+        let next = exec.exec("echo next", Duration::from_secs(1)).unwrap();
+        assert!(next.completed && next.output == "ok");
+        assert_eq!(inner.commands, ["sleep 600", "echo next"]);
+
+        drop(child.stdin.take());
+        let _ = child.kill();
+        child.wait().expect("wait sh");
+    }
+}
@@ -0,0 +1,671 @@
+//! Live observers for the serial stream.
+//!
+//! `Console` drains QEMU's stdout on its own reader thread inside recqemu, so
+//! observers cannot hook its channel directly. Instead, [`SerialTap::attach`]
+//! sits between QEMU and `Console`: it takes the child's stdout, forwards every
+//! byte unchanged through a pipe that `Console::new` then reads, and hands each
+//! line to registered callbacks as it arrives. It does the same for the
+//! child's stdin, so [`SerialTap::send`] can type into the guest alongside
+//! `Console`.
+
+use anyhow::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::os::fd::OwnedFd;
+use std::process::{Child, ChildStdin, ChildStdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Callback invoked with each received serial line (without the newline).
+///
+/// Runs on the tap thread, so it must be `Send` and should return quickly.
+pub type LineCallback = Box<dyn FnMut(&str) + Send>;
+
+/// Lines kept for [`SerialTap::time_to_pattern`]; later lines are still
+/// forwarded and passed to callbacks, just not recorded.
+const HISTORY_LIMIT: usize = 200_000;
+
+/// Printed by the probe [`SerialTap::interrupt`] sends after Ctrl-C.
+const INTERRUPT_MARKER: &str = "INTERRUPT_RECOVERED";
+
+/// Assembles [`INTERRUPT_MARKER`] with `printf` so its echo can't match.
+const INTERRUPT_PROBE: &str = "printf 'INTERRUPT_%s\\n' RECOVERED\n";
+
+/// Lines shown before the deadline when [`SerialTap::expect`] times out.
+const EXPECT_CONTEXT_LINES: usize = 20;
+
+/// How often [`SerialTap::expect`] rechecks the unterminated line for prompts.
+const EXPECT_POLL: Duration = Duration::from_millis(100);
+
+/// Firmware markers printed by OVMF before any bootloader runs.
+const UEFI_MARKERS: &[&str] = &["BdsDxe", "UEFI"];
+
+/// Bootloader markers; the kernel's EFI stub line means the loader handed off.
+const BOOTLOADER_MARKERS: &[&str] = &["systemd-boot", "EFI stub:"];
+
+/// Kernel banner, the first line the kernel itself prints.
+const KERNEL_MARKERS: &[&str] = &["Linux version"];
+
+/// When each boot stage was first observed, relative to QEMU spawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BootTimeline {
+    pub uefi: Option<Duration>,
+    pub bootloader: Option<Duration>,
+    pub kernel: Option<Duration>,
+    pub userspace: Option<Duration>,
+}
+
+impl std::fmt::Display for BootTimeline {
+    /// Renders e.g. `UEFI 1.2s → bootloader 2.0s → kernel 3.8s → userspace 9.1s`,
+    /// with `?` for stages never seen.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stages = [
+            ("UEFI", self.uefi),
+            ("bootloader", self.bootloader),
+            ("kernel", self.kernel),
+            ("userspace", self.userspace),
+        ];
+        for (i, (name, at)) in stages.iter().enumerate() {
+            if i > 0 {
+                write!(f, " → ")?;
+            }
+            match at {
+                Some(at) => write!(f, "{} {:.1}s", name, at.as_secs_f64())?,
+                None => write!(f, "{} ?", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BootTimeline {
+    /// Label for the latest stage reached, e.g. `kernel…`.
+    pub fn current_stage(&self) -> &'static str {
+        if self.userspace.is_some() {
+            "userspace…"
+        } else if self.kernel.is_some() {
+            "kernel…"
+        } else if self.bootloader.is_some() {
+            "bootloader…"
+        } else if self.uefi.is_some() {
+            "UEFI…"
+        } else {
+            "starting…"
+        }
+    }
+}
+
+/// Periodic stderr progress line while a boot wait runs; stops when dropped.
+pub struct Heartbeat {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Handle to the observer thread between QEMU and `Console`.
+///
+/// Cheap to clone; all clones share the same history and callbacks.
+#[derive(Clone)]
+pub struct SerialTap {
+    started: Instant,
+    state: Arc<Mutex<TapState>>,
+    /// QEMU's real stdin, shared with the thread forwarding `Console`'s writes.
+    input: Option<Arc<Mutex<ChildStdin>>>,
+}
+
+impl SerialTap {
+    /// Interpose on `child`'s stdout. Call before `Console::new(&mut child)`.
+    pub fn attach(child: &mut Child) -> Result<Self> {
+        let mut upstream = child
+            .stdout
+            .take()
+            .context("QEMU child has no piped stdout to tap")?;
+        let (reader, mut writer) = std::io::pipe().context("Failed to create serial tap pipe")?;
+        child.stdout = Some(ChildStdout::from(OwnedFd::from(reader)));
+
+        let input = match child.stdin.take() {
+            Some(upstream) => {
+                let (reader, writer) =
+                    std::io::pipe().context("Failed to create serial input pipe")?;
+                child.stdin = Some(ChildStdin::from(OwnedFd::from(writer)));
+                let upstream = Arc::new(Mutex::new(upstream));
+                spawn_input_forwarder(reader, Arc::clone(&upstream))?;
+                Some(upstream)
+            }
+            None => None,
+        };
+
+        let tap = Self {
+            started: Instant::now(),
+            state: Arc::new(Mutex::new(TapState::default())),
+            input,
+        };
+        let thread_tap = tap.clone();
+        std::thread::Builder::new()
+            .name("serial-tap".to_string())
+            .spawn(move || {
+                let mut buf = [0u8; 4096];
+                let mut forwarding = true;
+                loop {
+                    let n = match upstream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => n,
+                    };
+                    // Forward first so observers never delay Console.
+                    if forwarding && writer.write_all(&buf[..n]).is_err() {
+                        // Console hung up; keep observing until QEMU exits.
+                        forwarding = false;
+                    }
+                    thread_tap.feed(&buf[..n]);
+                }
+            })
+            .context("Failed to spawn serial tap thread")?;
+        Ok(tap)
+    }
+
+    /// Write `text` to the guest's serial input as if typed; add the line
+    /// ending yourself.
+    ///
+    /// Interleaves with `Console`'s own writes at write boundaries, so only
+    /// use it while `Console` is not mid-command.
+    pub fn send(&self, text: &str) -> Result<()> {
+        let input = self
+            .input
+            .as_ref()
+            .context("QEMU child has no piped stdin to send through")?;
+        let mut input = input.lock().unwrap_or_else(|e| e.into_inner());
+        input
+            .write_all(text.as_bytes())
+            .and_then(|()| input.flush())
+            .context("Failed to write to QEMU serial input")
+    }
+
+    /// Kill whatever the guest shell is running and wait until it takes
+    /// commands again.
+    ///
+    /// Sends Ctrl-C, then a probe whose marker only appears once the shell
+    /// runs it, so output of the interrupted command cannot leak into the
+    /// next one. Fails if the shell does not come back within `timeout`.
+    pub fn interrupt(&self, timeout: Duration) -> Result<()> {
+        self.send("\x03")?;
+        // Let the shell act on SIGINT before the probe line arrives.
+        std::thread::sleep(Duration::from_millis(200));
+        self.send(INTERRUPT_PROBE)?;
+        self.expect(INTERRUPT_MARKER, timeout)
+            .context("guest shell did not recover after Ctrl-C")
+    }
+
+    /// Register a callback for every line received from now on.
+    pub fn on_line(&self, callback: LineCallback) {
+        self.lock().callbacks.push(callback);
+    }
+
+    /// Time from attach until `pattern` first appeared in the serial stream.
+    ///
+    /// Also matches the current unterminated line, so prompts such as
+    /// `login:` are found before their newline arrives.
+    pub fn time_to_pattern(&self, pattern: &str) -> Option<Duration> {
+        self.lock().first_match(pattern)
+    }
+
+    /// Block until `pattern` appears in serial output received from now on.
+    ///
+    /// For ad-hoc waits that don't fit a boot or command wait. ANSI escapes
+    /// are stripped before matching, and the current unterminated line is
+    /// checked too so prompts match before their newline. On timeout the
+    /// error carries the last lines seen.
+    pub fn expect(&self, pattern: &str, timeout: Duration) -> Result<()> {
+        let (sender, lines) = mpsc::channel();
+        // The callback cannot be unregistered; it goes quiet once `lines` drops.
+        let mut sender = Some(sender);
+        self.on_line(Box::new(move |line| {
+            if let Some(tx) = &sender {
+                if tx.send(line.to_string()).is_err() {
+                    sender = None;
+                }
+            }
+        }));
+        let since = self.started.elapsed();
+        expect_lines(&lines, pattern, timeout, || {
+            let state = self.lock();
+            state
+                .partial
+                .as_ref()
+                .filter(|(at, _)| *at >= since)
+                .map(|(_, raw)| String::from_utf8_lossy(raw).into_owned())
+        })
+    }
+
+    /// Boot stage timings seen so far.
+    ///
+    /// `userspace_patterns` are the distro's boot success patterns (shell
+    /// or login prompt); the earliest match of any of them counts.
+    pub fn boot_timeline(&self, userspace_patterns: &[&str]) -> BootTimeline {
+        let state = self.lock();
+        let earliest = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .filter_map(|pattern| state.first_match(pattern))
+                .min()
+        };
+        BootTimeline {
+            uefi: earliest(UEFI_MARKERS),
+            bootloader: earliest(BOOTLOADER_MARKERS),
+            kernel: earliest(KERNEL_MARKERS),
+            userspace: earliest(userspace_patterns),
+        }
+    }
+
+    /// Lines whose first byte arrived at or after `since` (relative to
+    /// attach), including the current unterminated line.
+    pub fn transcript_since(&self, since: Duration) -> Vec<String> {
+        let state = self.lock();
+        let mut lines: Vec<String> = state
+            .lines
+            .iter()
+            .filter(|(at, _)| *at >= since)
+            .map(|(_, line)| line.clone())
+            .collect();
+        if let Some((at, raw)) = &state.partial {
+            if *at >= since {
+                lines.push(String::from_utf8_lossy(raw).into_owned());
+            }
+        }
+        lines
+    }
+
+    /// Print elapsed time and the detected boot stage to stderr every
+    /// `interval`, but only while serial output keeps arriving.
+    ///
+    /// Keeps a slow but progressing boot from looking hung in CI logs.
+    pub fn heartbeat(&self, interval: Duration) -> Heartbeat {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let tap = self.clone();
+        let thread = std::thread::Builder::new()
+            .name("serial-heartbeat".to_string())
+            .spawn(move || {
+                let mut seen = tap.lock().bytes_received;
+                let mut next = Instant::now() + interval;
+                while !thread_stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(Duration::from_millis(100));
+                    if Instant::now() < next {
+                        continue;
+                    }
+                    next += interval;
+                    let received = tap.lock().bytes_received;
+                    if received != seen {
+                        seen = received;
+                        eprintln!(
+                            "  … {:.0}s, {}",
+                            tap.started.elapsed().as_secs_f64(),
+                            tap.boot_timeline(&[]).current_stage()
+                        );
+                    }
+                }
+            })
+            .ok();
+        Heartbeat { stop, thread }
+    }
+
+    /// When the tap was attached (effectively QEMU spawn time).
+    pub fn started_at(&self) -> Instant {
+        self.started
+    }
+
+    fn feed(&self, bytes: &[u8]) {
+        let at = self.started.elapsed();
+        let (lines, mut callbacks) = {
+            let mut state = self.lock();
+            state.bytes_received += bytes.len() as u64;
+            let lines = state.push_bytes(at, bytes);
+            (lines, std::mem::take(&mut state.callbacks))
+        };
+        // Callbacks run unlocked so they may query the tap themselves.
+        for line in &lines {
+            for callback in &mut callbacks {
+                callback(line);
+            }
+        }
+        let mut state = self.lock();
+        callbacks.append(&mut state.callbacks);
+        state.callbacks = callbacks;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TapState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Copy `Console`'s writes from `reader` to QEMU's stdin until either side closes.
+fn spawn_input_forwarder(
+    mut reader: std::io::PipeReader,
+    upstream: Arc<Mutex<ChildStdin>>,
+) -> Result<()> {
+    std::thread::Builder::new()
+        .name("serial-tap-input".to_string())
+        .spawn(move || {
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let mut upstream = upstream.lock().unwrap_or_else(|e| e.into_inner());
+                if upstream
+                    .write_all(&buf[..n])
+                    .and_then(|()| upstream.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+        .context("Failed to spawn serial input thread")?;
+    Ok(())
+}
+
+/// Consume `lines` until one contains `pattern` or `timeout` passes.
+///
+/// `pending` returns the current unterminated line, if any, and is polled
+/// between lines so prompts without a newline still match.
+fn expect_lines(
+    lines: &Receiver<String>,
+    pattern: &str,
+    timeout: Duration,
+    pending: impl Fn() -> Option<String>,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut recent = VecDeque::with_capacity(EXPECT_CONTEXT_LINES);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match lines.recv_timeout(remaining.min(EXPECT_POLL)) {
+            Ok(line) => {
+                let line = strip_ansi(&line);
+                if line.contains(pattern) {
+                    return Ok(());
+                }
+                if recent.len() == EXPECT_CONTEXT_LINES {
+                    recent.pop_front();
+                }
+                recent.push_back(line);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                bail!(
+                    "Serial stream closed before '{}' appeared{}",
+                    pattern,
+                    format_context(&recent)
+                )
+            }
+        }
+        if pending().is_some_and(|line| strip_ansi(&line).contains(pattern)) {
+            return Ok(());
+        }
+        if remaining.is_zero() {
+            if let Some(line) = pending() {
+                recent.push_back(strip_ansi(&line));
+            }
+            bail!(
+                "Timed out after {:?} waiting for '{}'{}",
+                timeout,
+                pattern,
+                format_context(&recent)
+            );
+        }
+    }
+}
+
+fn format_context(recent: &VecDeque<String>) -> String {
+    if recent.is_empty() {
+        return " (no serial output)".to_string();
+    }
+    let mut context = String::from("; last serial lines:");
+    for line in recent {
+        context.push_str("\n  ");
+        context.push_str(line);
+    }
+    context
+}
+
+/// Remove CSI (`ESC [ … final`) and OSC (`ESC ] … BEL`) sequences plus any
+/// other two-byte escapes, which firmware and getty use for colour and
+/// cursor movement.
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                for c in chars.by_ref() {
+                    if c == '\x07' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+struct TapState {
+    callbacks: Vec<LineCallback>,
+    /// Completed lines with the time their first byte arrived.
+    lines: Vec<(Duration, String)>,
+    /// Bytes of the current unterminated line and when it started.
+    partial: Option<(Duration, Vec<u8>)>,
+    /// Total bytes seen, for [`SerialTap::heartbeat`] activity checks.
+    bytes_received: u64,
+}
+
+impl TapState {
+    /// Append raw bytes; returns the lines they completed.
+    fn push_bytes(&mut self, at: Duration, bytes: &[u8]) -> Vec<String> {
+        let mut completed = Vec::new();
+        for &byte in bytes {
+            let (_, partial) = self.partial.get_or_insert_with(|| (at, Vec::new()));
+            if byte != b'\n' {
+                partial.push(byte);
+                continue;
+            }
+            let (started, raw) = self.partial.take().expect("partial line present");
+            let line = String::from_utf8_lossy(&raw)
+                .trim_end_matches('\r')
+                .to_string();
+            if self.lines.len() < HISTORY_LIMIT {
+                self.lines.push((started, line.clone()));
+            }
+            completed.push(line);
+        }
+        completed
+    }
+
+    fn first_match(&self, pattern: &str) -> Option<Duration> {
+        self.lines
+            .iter()
+            .find(|(_, line)| line.contains(pattern))
+            .map(|(at, _)| *at)
+            .or_else(|| {
+                self.partial
+                    .as_ref()
+                    .filter(|(_, raw)| String::from_utf8_lossy(raw).contains(pattern))
+                    .map(|(at, _)| *at)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    #[test]
+    fn lines_split_across_chunks_keep_first_byte_time() {
+        let mut state = TapState::default();
+        assert!(state
+            .push_bytes(Duration::from_secs(1), b"Linux ver")
+            .is_empty());
+        let lines = state.push_bytes(Duration::from_secs(2), b"sion 6.1\r\nlogin: ");
+        assert_eq!(lines, vec!["Linux version 6.1".to_string()]);
+        assert_eq!(
+            state.first_match("Linux version"),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(state.first_match("login:"), Some(Duration::from_secs(2)));
+        assert_eq!(state.first_match("missing"), None);
+    }
+
+    #[test]
+    fn boot_timeline_uses_earliest_marker_per_stage() {
+        let tap = SerialTap {
+            started: Instant::now(),
+            state: Arc::new(Mutex::new(TapState::default())),
+            input: None,
+        };
+        {
+            let mut state = tap.lock();
+            state.push_bytes(Duration::from_millis(1200), b"BdsDxe: loading Boot0001\n");
+            state.push_bytes(Duration::from_millis(2000), b"EFI stub: Loaded initrd\n");
+            state.push_bytes(Duration::from_millis(3800), b"[0.0] Linux version 6.12\n");
+            state.push_bytes(Duration::from_millis(9100), b"levitate login: ");
+        }
+        let timeline = tap.boot_timeline(&["login:", "# "]);
+        assert_eq!(timeline.kernel, Some(Duration::from_millis(3800)));
+        assert_eq!(
+            timeline.to_string(),
+            "UEFI 1.2s → bootloader 2.0s → kernel 3.8s → userspace 9.1s"
+        );
+        assert_eq!(
+            tap.boot_timeline(&["never"]).to_string(),
+            "UEFI 1.2s → bootloader 2.0s → kernel 3.8s → userspace ?"
+        );
+    }
+
+    #[test]
+    fn current_stage_reports_latest_stage_seen() {
+        let mut timeline = BootTimeline::default();
+        assert_eq!(timeline.current_stage(), "starting…");
+        timeline.uefi = Some(Duration::from_secs(1));
+        timeline.kernel = Some(Duration::from_secs(3));
+        assert_eq!(timeline.current_stage(), "kernel…");
+    }
+
+    #[test]
+    fn expect_matches_through_ansi_and_pending_line() {
+        let (tx, rx) = mpsc::channel();
+        tx.send("\x1b[1;32m[  OK  ]\x1b[0m Reached target Multi-User".to_string())
+            .unwrap();
+        expect_lines(
+            &rx,
+            "[  OK  ] Reached target",
+            Duration::from_secs(1),
+            || None,
+        )
+        .expect("matches colourised line");
+
+        expect_lines(&rx, "login:", Duration::from_secs(1), || {
+            Some("levitate login: ".to_string())
+        })
+        .expect("matches unterminated prompt");
+    }
+
+    #[test]
+    fn expect_timeout_reports_recent_lines() {
+        let (tx, rx) = mpsc::channel();
+        for i in 0..25 {
+            tx.send(format!("line {}", i)).unwrap();
+        }
+        let err = expect_lines(&rx, "never", Duration::from_millis(150), || None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Timed out"), "{}", err);
+        assert!(err.contains("line 24") && err.contains("line 5"), "{}", err);
+        assert!(!err.contains("line 4\n"), "{}", err);
+
+        drop(tx);
+        let err = expect_lines(&rx, "never", Duration::from_secs(5), || None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("closed"), "{}", err);
+    }
+
+    #[test]
+    fn send_types_into_the_child_alongside_console_input() {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("read answer; echo got-$answer")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let tap = SerialTap::attach(&mut child).expect("attach tap");
+        assert!(child.stdin.is_some(), "Console still gets a stdin");
+
+        tap.send("y\n").expect("send answer");
+        let mut forwarded = String::new();
+        child
+            .stdout
+            .take()
+            .expect("tapped stdout")
+            .read_to_string(&mut forwarded)
+            .expect("read forwarded output");
+        child.wait().expect("wait sh");
+        assert_eq!(forwarded, "got-y\n");
+    }
+
+    #[test]
+    fn attach_forwards_output_and_notifies_callbacks() {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("printf 'first\\nready\\n'")
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+        let tap = SerialTap::attach(&mut child).expect("attach tap");
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        tap.on_line(Box::new(move |line| {
+            sink.lock().unwrap().push(line.to_string())
+        }));
+
+        let mut forwarded = String::new();
+        child
+            .stdout
+            .take()
+            .expect("tapped stdout")
+            .read_to_string(&mut forwarded)
+            .expect("read forwarded output");
+        child.wait().expect("wait sh");
+
+        assert_eq!(forwarded, "first\nready\n");
+        assert!(tap.time_to_pattern("ready").is_some());
+        // The callback may register after early lines; it must see no duplicates.
+        let seen = seen.lock().unwrap();
+        assert!(seen.len() <= 2 && seen.iter().all(|l| l == "first" || l == "ready"));
+    }
+}
@@ -2,32 +2,461 @@
 //!
 //! Eliminates duplicated QEMU setup code across scenario runners and install-tests binaries.
 
-use crate::boot_injection::boot_injection_from_env;
+use crate::boot_injection::{
+    boot_injection_from_env, require_kernel_cmdline_applied, ENV_KERNEL_APPEND,
+};
 use crate::distro::DistroContext;
-use crate::qemu::{Console, QemuBuilder};
-use anyhow::{Context, Result};
+use crate::qemu::qmp::{ppm_to_png, QmpClient};
+use crate::qemu::serial::Heartbeat;
+use crate::qemu::watchdog;
+use crate::qemu::{
+    find_ovmf, find_ovmf_vars, Accel, BootAttempt, Console, DiskBus, DiskCache, Display,
+    QemuBuilder, SerialExecutorExt, SerialTap,
+};
+use anyhow::{bail, Context, Result};
 use std::fs;
-use std::net::TcpListener;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::process::Child;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Env var holding a float multiplier applied to boot wait timeouts.
+pub const BOOT_TIMEOUT_SCALE_ENV: &str = "LEVITATE_BOOT_TIMEOUT_SCALE";
+
+/// Env var that, when `1`, boots every VM without outbound networking.
+pub const OFFLINE_ENV: &str = "LEVITATE_OFFLINE";
+
+/// Whether offline mode (`--offline`) was requested.
+pub fn offline_requested() -> bool {
+    std::env::var(OFFLINE_ENV).is_ok_and(|value| value == "1")
+}
+
+/// Env var selecting a guest display (`none`, `gtk`, `vnc:N`) for debugging.
+pub const DISPLAY_ENV: &str = "LEVITATE_DISPLAY";
+
+/// Display requested via `LEVITATE_DISPLAY`, or [`Display::None`].
+///
+/// An unparsable value is reported and ignored, so a typo never turns a
+/// serial-only CI run graphical.
+pub fn display_requested() -> Display {
+    parsed_env(DISPLAY_ENV).unwrap_or_default()
+}
+
+/// Env var that, when `1`, captures boot/login screenshots over QMP.
+pub const SCREENSHOT_ON_BOOT_ENV: &str = "LEVITATE_SCREENSHOT_ON_BOOT";
+
+/// Whether `--screenshot-on-boot` was requested.
+pub fn screenshots_requested() -> bool {
+    std::env::var(SCREENSHOT_ON_BOOT_ENV).is_ok_and(|value| value == "1")
+}
+
+/// Env var selecting the disk cache mode (`none`, `writeback`, `unsafe`).
+pub const DISK_CACHE_ENV: &str = "LEVITATE_DISK_CACHE";
+
+/// Env var selecting the disk bus (`virtio`, `virtio-scsi`, `ide`).
+pub const DISK_BUS_ENV: &str = "LEVITATE_DISK_BUS";
+
+/// Disk cache mode requested via `LEVITATE_DISK_CACHE`, if any.
+pub fn disk_cache_requested() -> Option<DiskCache> {
+    parsed_env(DISK_CACHE_ENV)
+}
+
+/// Disk bus requested via `LEVITATE_DISK_BUS`, if any.
+pub fn disk_bus_requested() -> Option<DiskBus> {
+    parsed_env(DISK_BUS_ENV)
+}
+
+/// Env var selecting the QEMU accelerator (`kvm`, `tcg`, `auto`).
+pub const ACCEL_ENV: &str = "LEVITATE_ACCEL";
+
+/// Accelerator requested via `LEVITATE_ACCEL`; [`Accel::Auto`] if unset.
+pub fn accel_requested() -> Accel {
+    parsed_env(ACCEL_ENV).unwrap_or(Accel::Auto)
+}
+
+/// Accelerator the session's VMs will actually run with.
+pub fn resolved_accel() -> Option<Accel> {
+    accelerated_builder().resolved_accel()
+}
+
+/// Env var overriding the QEMU machine type (e.g. `pc`, `pc-q35-8.2`).
+pub const MACHINE_ENV: &str = "LEVITATE_MACHINE";
+
+/// Env var overriding the QEMU CPU model (e.g. `host`, `Skylake-Client`).
+pub const CPU_ENV: &str = "LEVITATE_CPU";
+
+/// Machine type requested via `LEVITATE_MACHINE`, if any.
+pub fn machine_requested() -> Option<String> {
+    std::env::var(MACHINE_ENV)
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// CPU model requested via `LEVITATE_CPU`, if any.
+pub fn cpu_requested() -> Option<String> {
+    std::env::var(CPU_ENV)
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// `env` parsed as `T`; an unparsable value is reported and ignored.
+fn parsed_env<T>(env: &str) -> Option<T>
+where
+    T: std::str::FromStr<Err = anyhow::Error>,
+{
+    let raw = std::env::var(env).ok()?;
+    raw.parse()
+        .map_err(|err| eprintln!("warning: ignoring {}='{}': {:#}", env, raw, err))
+        .ok()
+}
+
+/// Env var redirecting scratch disks and OVMF vars away from the system temp dir.
+pub const WORK_DIR_ENV: &str = "LEVITATE_WORK_DIR";
+
+/// Directory for scratch disk images and OVMF vars copies, created if missing.
+///
+/// `LEVITATE_WORK_DIR` (`--work-dir`) when set, otherwise the system temp dir,
+/// which is often a small tmpfs that cannot hold a sparse qcow2 growing to
+/// its full size.
+pub fn work_dir() -> Result<PathBuf> {
+    let dir = match std::env::var_os(WORK_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => return Ok(std::env::temp_dir()),
+    };
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("creating {} '{}'", WORK_DIR_ENV, dir.display()))?;
+    Ok(dir)
+}
+
+/// Scratch files for one run, named per process and removed on drop.
+///
+/// Names carry the PID and a per-process sequence number, so concurrent runs
+/// sharing a work dir never clobber each other's disk or vars.
+#[derive(Debug, Default)]
+pub struct ScratchFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl ScratchFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve a fresh `<work_dir>/<stem>-<pid>-<seq>.<extension>`.
+    ///
+    /// Namespace `stem` by distro (e.g. `levitate-disk-vars`) so files stay
+    /// attributable. The file is not created; it is deleted on drop if it
+    /// exists by then.
+    pub fn path(&mut self, stem: &str, extension: &str) -> Result<PathBuf> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let seq = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = work_dir()?.join(scratch_file_name(stem, extension, std::process::id(), seq));
+        self.paths.push(path.clone());
+        Ok(path)
+    }
+}
+
+impl Drop for ScratchFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn scratch_file_name(stem: &str, extension: &str, pid: u32, seq: u64) -> String {
+    format!("{}-{}-{}.{}", stem, pid, seq, extension)
+}
+
+/// Env var forcing boot wait progress lines on (`1`) or off (`0`).
+pub const PROGRESS_ENV: &str = "LEVITATE_PROGRESS";
+
+/// Interval between boot wait progress lines.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Start a boot wait heartbeat on `tap` if progress output is enabled.
+///
+/// Enabled by `--progress` (`LEVITATE_PROGRESS=1`), disabled by
+/// `LEVITATE_PROGRESS=0`, and otherwise on only when stderr is a terminal.
+pub fn boot_heartbeat(tap: &SerialTap) -> Option<Heartbeat> {
+    let enabled = match std::env::var(PROGRESS_ENV).as_deref() {
+        Ok("1") => true,
+        Ok("0") => false,
+        _ => std::io::stderr().is_terminal(),
+    };
+    enabled.then(|| tap.heartbeat(HEARTBEAT_INTERVAL))
+}
+
+/// Boot wait timeout of `secs`, scaled by `LEVITATE_BOOT_TIMEOUT_SCALE`.
+///
+/// Lets slow CI runners stretch every boot wait without per-distro edits.
+/// Unset, unparsable, or non-positive values leave `secs` unchanged.
+pub fn boot_timeout(secs: u64) -> Duration {
+    let scale = match std::env::var(BOOT_TIMEOUT_SCALE_ENV) {
+        Ok(raw) => match raw.trim().parse::<f64>() {
+            Ok(scale) if scale.is_finite() && scale > 0.0 => scale,
+            _ => {
+                eprintln!(
+                    "warning: ignoring invalid {}='{}' (expected a positive number)",
+                    BOOT_TIMEOUT_SCALE_ENV, raw
+                );
+                1.0
+            }
+        },
+        Err(_) => 1.0,
+    };
+    Duration::from_secs_f64(secs as f64 * scale)
+}
+
+/// How long teardown waits for the guest to power off before killing QEMU.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Stop a serial session's VM without risking a half-written disk image.
+///
+/// Sends an ACPI powerdown over the VM's control socket and waits up to
+/// half of `timeout`, so a guest sitting at a login prompt never has
+/// commands typed into it. Guests that ignore the power button then get
+/// `sync; poweroff -f` over the console, and QEMU is killed if it is still
+/// running after the rest of `timeout`. Returns true if the guest powered
+/// off on its own.
+pub fn shutdown(child: &mut Child, console: &mut Console, timeout: Duration) -> bool {
+    if exited(child) {
+        release_vm(child.id());
+        return true;
+    }
+    let socket = take_control_socket(child.id());
+    let powered_down = socket.as_deref().is_some_and(|socket| {
+        QmpClient::connect_timeout(socket, Duration::from_secs(2))
+            .and_then(|mut qmp| qmp.system_powerdown())
+            .is_ok()
+    });
+    let stopped = if powered_down && wait_for_exit(child, timeout / 2) {
+        true
+    } else {
+        // The console dies with the guest, so this exec is expected to fail.
+        let _ = console.exec("sync; poweroff -f", Duration::from_secs(2));
+        wait_or_kill(child, timeout / 2)
+    };
+    if let Some(socket) = socket {
+        let _ = fs::remove_file(socket);
+    }
+    release_vm(child.id());
+    stopped
+}
+
+/// Like [`shutdown`], for VMs controlled over QMP: ACPI powerdown, then kill.
+pub fn shutdown_qmp(child: &mut Child, qmp: &mut QmpClient, timeout: Duration) -> bool {
+    if !exited(child) {
+        if let Err(err) = qmp.system_powerdown() {
+            eprintln!("  QMP system_powerdown failed: {:#}", err);
+        }
+    }
+    let stopped = wait_or_kill(child, timeout);
+    release_vm(child.id());
+    stopped
+}
+
+/// Kill a VM that is being discarded, e.g. after a failed boot attempt.
+pub fn kill_vm(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+    release_vm(child.id());
+}
+
+/// Forget a stopped VM: its watchdog entry and transcript, and its control socket.
+fn release_vm(pid: u32) {
+    watchdog::unwatch_vm(pid);
+    if let Some(socket) = take_control_socket(pid) {
+        let _ = fs::remove_file(socket);
+    }
+}
+
+/// Has QEMU already exited (reaping it if so)?
+fn exited(child: &mut Child) -> bool {
+    matches!(child.try_wait(), Ok(Some(_)))
+}
+
+/// Wait up to `timeout` for QEMU to exit on its own.
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if exited(child) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    exited(child)
+}
+
+/// Wait for QEMU to exit on its own, killing it after `timeout`.
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> bool {
+    if wait_for_exit(child, timeout) {
+        return true;
+    }
+    eprintln!(
+        "  QEMU did not exit within {:?} of powerdown; killing it",
+        timeout
+    );
+    let _ = child.kill();
+    let _ = child.wait();
+    false
+}
+
+/// QMP control sockets of spawned serial-session VMs, by QEMU PID.
+///
+/// The last entry is the most recent VM, which screenshots go to.
+static CONTROL_SOCKETS: Mutex<Vec<(u32, PathBuf)>> = Mutex::new(Vec::new());
+
+/// A fresh control socket path for the next VM.
+///
+/// Stays in the temp dir because socket paths are length-limited.
+fn next_control_socket() -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let socket = std::env::temp_dir().join(format!(
+        "levitate-qmp-{}-{}.sock",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = fs::remove_file(&socket);
+    socket
+}
+
+fn control_sockets() -> std::sync::MutexGuard<'static, Vec<(u32, PathBuf)>> {
+    CONTROL_SOCKETS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Forget and return the control socket of the VM with QEMU PID `pid`.
+fn take_control_socket(pid: u32) -> Option<PathBuf> {
+    let mut sockets = control_sockets();
+    let index = sockets.iter().position(|(owner, _)| *owner == pid)?;
+    Some(sockets.remove(index).1)
+}
+
+/// Screendump the running VM to `dest_png` over its control socket.
+///
+/// Goes to the most recently spawned serial-session VM. QEMU writes a PPM
+/// next to `dest_png`, which is converted and removed.
+pub fn capture_screenshot(dest_png: &Path) -> Result<()> {
+    let Some(socket) = control_sockets().last().map(|(_, socket)| socket.clone()) else {
+        bail!("no running VM to screenshot");
+    };
+    if let Some(parent) = dest_png.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating screenshot dir '{}'", parent.display()))?;
+    }
+    let ppm = dest_png.with_extension("ppm");
+    let mut qmp = QmpClient::connect_timeout(&socket, Duration::from_secs(5))?;
+    qmp.screendump(&ppm.to_string_lossy())?;
+    let converted = ppm_to_png(&ppm, dest_png);
+    let _ = fs::remove_file(&ppm);
+    converted
+}
+
+/// How long [`setup_ovmf_vars_at`] waits for another process to close the
+/// vars file before giving up (e.g. a previous QEMU still shutting down).
+const OVMF_VARS_RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Set up OVMF firmware and writable vars copy at a caller-provided path.
 /// Returns (ovmf_code, ovmf_vars_copy).
+///
+/// A vars file still open in another process, or one that cannot be
+/// replaced (e.g. owned by another user in a shared temp dir), fails with
+/// the holders and what to do instead of a bare IO error.
 pub fn setup_ovmf_vars_at(ovmf_vars_path: &Path) -> Result<(PathBuf, PathBuf)> {
-    let ovmf = recqemu::find_ovmf().context("OVMF not found")?;
-    let ovmf_vars_template = recqemu::find_ovmf_vars().context("OVMF_VARS not found")?;
+    let ovmf = find_ovmf().context("OVMF not found")?;
+    let ovmf_vars_template = find_ovmf_vars().context("OVMF_VARS not found")?;
     if let Some(parent) = ovmf_vars_path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("creating scenario runtime dir '{}'", parent.display()))?;
     }
-    if ovmf_vars_path.exists() {
-        std::fs::remove_file(ovmf_vars_path)?;
-    }
-    std::fs::copy(&ovmf_vars_template, ovmf_vars_path)?;
+    replace_ovmf_vars(
+        &ovmf_vars_template,
+        ovmf_vars_path,
+        OVMF_VARS_RELEASE_TIMEOUT,
+    )?;
     Ok((ovmf, ovmf_vars_path.to_path_buf()))
 }
 
+/// Copy `template` over `dest`, waiting up to `release_timeout` for other
+/// processes to close `dest` first.
+fn replace_ovmf_vars(template: &Path, dest: &Path, release_timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + release_timeout;
+    loop {
+        let holders = processes_holding(dest);
+        if holders.is_empty() {
+            break;
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "OVMF vars '{}' is still open in {} after {:?}. Stop the stale VM \
+                 (kill_stale_qemu_processes, or kill the PID), or give this run its \
+                 own directory with --work-dir / {}",
+                dest.display(),
+                holders.join(", "),
+                release_timeout,
+                WORK_DIR_ENV
+            );
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+
+    let replace_hint = || {
+        format!(
+            "replacing OVMF vars '{}' (owned by another user or run? pass --work-dir or set {})",
+            dest.display(),
+            WORK_DIR_ENV
+        )
+    };
+    match fs::remove_file(dest) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            return Err(err).with_context(replace_hint);
+        }
+        _ => {}
+    }
+    fs::copy(template, dest).with_context(|| {
+        format!(
+            "copying OVMF vars template '{}': {}",
+            template.display(),
+            replace_hint()
+        )
+    })?;
+    Ok(())
+}
+
+/// `PID (comm)` of every process with `path` open, from `/proc/*/fd`.
+///
+/// Processes of other users are invisible without privileges; a file they
+/// hold then surfaces as the replace error instead.
+fn processes_holding(path: &Path) -> Vec<String> {
+    let Ok(target) = path.canonicalize() else {
+        return Vec::new();
+    };
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut holders: Vec<(u32, String)> = procs
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let holds = fs::read_dir(entry.path().join("fd"))
+                .ok()?
+                .flatten()
+                .any(|fd| fs::read_link(fd.path()).is_ok_and(|link| link == target));
+            let comm = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            holds.then(|| (pid, comm.trim().to_string()))
+        })
+        .collect();
+    holders.sort();
+    holders
+        .into_iter()
+        .map(|(pid, comm)| format!("PID {} ({})", pid, comm))
+        .collect()
+}
+
 /// Spawn a QEMU VM booting from a live ISO (no disk attached).
 pub fn spawn_live(_ctx: &dyn DistroContext, iso_path: &Path) -> Result<(Child, Console)> {
     let (child, console, _) = spawn_live_with_ssh(_ctx, iso_path)?;
@@ -41,24 +470,90 @@ pub fn spawn_live_with_ssh(
     _ctx: &dyn DistroContext,
     iso_path: &Path,
 ) -> Result<(Child, Console, u16)> {
-    let ovmf = recqemu::find_ovmf().context("OVMF not found")?;
-    let ssh_host_port = allocate_local_port()?;
-
-    let builder = QemuBuilder::new()
-        .cdrom(iso_path.to_path_buf())
-        .uefi(ovmf)
-        .with_user_network_hostfwd(ssh_host_port, 22)
-        .nographic()
-        .serial_stdio()
-        .no_reboot();
-    let mut cmd = with_boot_injection(builder)?.build_piped();
+    let ovmf = find_ovmf().context("OVMF not found")?;
 
-    let mut child = cmd.spawn().context("Failed to spawn QEMU")?;
+    let builder = live_builder(iso_path, &ovmf, 0)?;
+    let ssh_host_port = ssh_host_port(&builder);
+    let (mut child, _) = spawn_piped(builder)?;
     let console = Console::new(&mut child)?;
     std::thread::sleep(Duration::from_secs(2));
     Ok((child, console, ssh_host_port))
 }
 
+/// Like [`spawn_live`], with a [`SerialTap`] observing the serial stream.
+///
+/// The tap is attached before `Console`, so it sees every line from spawn on.
+pub fn spawn_live_observed(
+    _ctx: &dyn DistroContext,
+    iso_path: &Path,
+) -> Result<(Child, Console, SerialTap)> {
+    let ovmf = find_ovmf().context("OVMF not found")?;
+    let (mut child, tap) = spawn_piped(live_builder(iso_path, &ovmf, 0)?)?;
+    let console = Console::new(&mut child)?;
+    std::thread::sleep(Duration::from_secs(2));
+    Ok((child, console, tap))
+}
+
+/// Spawn a live ISO VM and wait for it to boot, respawning on early QEMU death.
+///
+/// If QEMU dies before producing any kernel output (e.g. it could not grab the
+/// OVMF firmware file), the child and Console are re-created, up to `attempts`
+/// times. Any other boot failure is returned immediately.
+pub fn spawn_live_with_retry(
+    ctx: &dyn DistroContext,
+    iso_path: &Path,
+    attempts: usize,
+) -> Result<(Child, Console)> {
+    let (child, console, _) = spawn_live_with_ssh_retry(ctx, iso_path, attempts)?;
+    Ok((child, console))
+}
+
+/// SSH-forwarding variant of [`spawn_live_with_retry`].
+///
+/// Returns the forwarded host port mapped to guest tcp/22.
+pub fn spawn_live_with_ssh_retry(
+    ctx: &dyn DistroContext,
+    iso_path: &Path,
+    attempts: usize,
+) -> Result<(Child, Console, u16)> {
+    if attempts == 0 {
+        bail!("spawn_live_with_retry requires at least one attempt");
+    }
+    let stall_timeout = boot_timeout(ctx.live_boot_stall_timeout_secs());
+
+    let mut last_err = None;
+    for attempt in 1..=attempts {
+        println!(
+            "  QEMU live boot attempt {}/{} for {}",
+            attempt,
+            attempts,
+            ctx.name()
+        );
+        let (mut child, mut console, ssh_host_port) = spawn_live_with_ssh(ctx, iso_path)?;
+        match console.wait_for_boot_retrying(stall_timeout, ctx) {
+            Ok(BootAttempt::Booted) => return Ok((child, console, ssh_host_port)),
+            Ok(BootAttempt::Respawn(err)) => {
+                kill_vm(&mut child);
+                println!(
+                    "  QEMU died before kernel output (attempt {}/{}): {:#}",
+                    attempt, attempts, err
+                );
+                last_err = Some(err);
+            }
+            Err(err) => {
+                kill_vm(&mut child);
+                return Err(err);
+            }
+        }
+    }
+
+    let err = last_err.expect("at least one attempt was made");
+    Err(err.context(format!(
+        "QEMU died before kernel output on all {} attempts",
+        attempts
+    )))
+}
+
 /// Spawn a QEMU VM booting from a live ISO with a disk attached (for installation).
 pub fn spawn_live_with_disk(
     iso_path: &Path,
@@ -66,19 +561,17 @@ pub fn spawn_live_with_disk(
     ovmf: &Path,
     ovmf_vars: &Path,
 ) -> Result<(Child, Console)> {
-    let builder = QemuBuilder::new()
+    let builder = accelerated_builder()
         .cdrom(iso_path.to_path_buf())
         .disk(disk_path.to_path_buf())
         .uefi(ovmf.to_path_buf())
         .uefi_vars(ovmf_vars.to_path_buf())
-        .boot_order("dc")
-        .with_user_network()
+        .boot_order("dc");
+    let builder = with_network(with_machine(with_disk_options(builder)))
         .nographic()
         .serial_stdio()
         .no_reboot();
-    let mut cmd = with_boot_injection(builder)?.build_piped();
-
-    let mut child = cmd.spawn().context("Failed to spawn QEMU")?;
+    let (mut child, _) = spawn_piped(with_control_socket(with_display(builder)))?;
     let console = Console::new(&mut child)?;
     std::thread::sleep(Duration::from_secs(2));
     Ok((child, console))
@@ -93,21 +586,21 @@ pub fn spawn_live_with_disk_with_ssh(
     ovmf: &Path,
     ovmf_vars: &Path,
 ) -> Result<(Child, Console, u16)> {
-    let ssh_host_port = allocate_local_port()?;
-
-    let builder = QemuBuilder::new()
+    let builder = accelerated_builder()
         .cdrom(iso_path.to_path_buf())
         .disk(disk_path.to_path_buf())
         .uefi(ovmf.to_path_buf())
         .uefi_vars(ovmf_vars.to_path_buf())
         .boot_order("dc")
-        .with_user_network_hostfwd(ssh_host_port, 22)
-        .nographic()
-        .serial_stdio()
-        .no_reboot();
-    let mut cmd = with_boot_injection(builder)?.build_piped();
-
-    let mut child = cmd.spawn().context("Failed to spawn QEMU")?;
+        .forward_port(0, 22)?;
+    let builder = with_control_socket(with_display(
+        with_network_offline_only(with_machine(with_disk_options(builder)))
+            .nographic()
+            .serial_stdio()
+            .no_reboot(),
+    ));
+    let ssh_host_port = ssh_host_port(&builder);
+    let (mut child, _) = spawn_piped(builder)?;
     let console = Console::new(&mut child)?;
     std::thread::sleep(Duration::from_secs(2));
     Ok((child, console, ssh_host_port))
@@ -119,41 +612,215 @@ pub fn spawn_installed(
     ovmf: &Path,
     ovmf_vars: &Path,
 ) -> Result<(Child, Console)> {
-    let builder = QemuBuilder::new()
+    let builder = installed_builder(disk_path, ovmf, ovmf_vars);
+    let (mut child, _) = spawn_piped(builder)?;
+    let console = Console::new(&mut child)?;
+    std::thread::sleep(Duration::from_secs(2));
+    Ok((child, console))
+}
+
+/// Like [`spawn_installed`], with a [`SerialTap`] observing the serial stream.
+pub fn spawn_installed_observed(
+    disk_path: &Path,
+    ovmf: &Path,
+    ovmf_vars: &Path,
+) -> Result<(Child, Console, SerialTap)> {
+    let builder = installed_builder(disk_path, ovmf, ovmf_vars);
+    let (mut child, tap) = spawn_piped(builder)?;
+    let console = Console::new(&mut child)?;
+    std::thread::sleep(Duration::from_secs(2));
+    Ok((child, console, tap))
+}
+
+/// Render the argv `spawn_live_with_ssh` would run, without spawning.
+///
+/// An `ssh_host_port` of 0 allocates an ephemeral port, as a real spawn does.
+pub fn render_live_args(iso_path: &Path, ovmf: &Path, ssh_host_port: u16) -> Result<Vec<String>> {
+    with_boot_injection(live_builder(iso_path, ovmf, ssh_host_port)?)?.render_args()
+}
+
+/// Render the argv `spawn_installed` would run, without spawning.
+pub fn render_installed_args(
+    disk_path: &Path,
+    ovmf: &Path,
+    ovmf_vars: &Path,
+) -> Result<Vec<String>> {
+    with_boot_injection(installed_builder(disk_path, ovmf, ovmf_vars))?.render_args()
+}
+
+fn live_builder(iso_path: &Path, ovmf: &Path, ssh_host_port: u16) -> Result<QemuBuilder> {
+    let builder = accelerated_builder()
+        .cdrom(iso_path.to_path_buf())
+        .uefi(ovmf.to_path_buf())
+        .forward_port(ssh_host_port, 22)?;
+    Ok(with_control_socket(with_display(
+        with_network_offline_only(with_machine(builder))
+            .nographic()
+            .serial_stdio()
+            .no_reboot(),
+    )))
+}
+
+fn installed_builder(disk_path: &Path, ovmf: &Path, ovmf_vars: &Path) -> QemuBuilder {
+    let builder = accelerated_builder()
         .disk(disk_path.to_path_buf())
         .uefi(ovmf.to_path_buf())
         .uefi_vars(ovmf_vars.to_path_buf())
-        .boot_order("c")
-        .with_user_network()
-        .nographic()
-        .serial_stdio()
-        .no_reboot();
-    let mut cmd = with_boot_injection(builder)?.build_piped();
+        .boot_order("c");
+    with_control_socket(with_display(
+        with_network(with_machine(with_disk_options(builder)))
+            .nographic()
+            .serial_stdio()
+            .no_reboot(),
+    ))
+}
 
+/// A builder using the `LEVITATE_ACCEL` accelerator.
+fn accelerated_builder() -> QemuBuilder {
+    QemuBuilder::new().accel(accel_requested())
+}
+
+/// Apply the `LEVITATE_DISPLAY` display, printing where to find it.
+fn with_display(builder: QemuBuilder) -> QemuBuilder {
+    let display = display_requested();
+    match display.vnc_address() {
+        Some(address) => eprintln!("  VNC: {}", address),
+        None if display.is_graphical() => eprintln!("  Display: {}", display),
+        None => {}
+    }
+    builder.display(display)
+}
+
+/// Give the VM a QMP control socket, for powerdown and screenshots.
+fn with_control_socket(builder: QemuBuilder) -> QemuBuilder {
+    builder.qmp_socket(next_control_socket())
+}
+
+/// Spawn `builder` with boot injection, recording its control socket.
+///
+/// A [`SerialTap`] is attached before any `Console`, so it sees every line
+/// from spawn on, and the VM is registered with the run watchdog.
+fn spawn_piped(builder: QemuBuilder) -> Result<(Child, SerialTap)> {
+    let socket = builder.qmp_socket_path().map(Path::to_path_buf);
+    let mut cmd = with_boot_injection(builder)?.build_piped()?;
     let mut child = cmd.spawn().context("Failed to spawn QEMU")?;
-    let console = Console::new(&mut child)?;
-    std::thread::sleep(Duration::from_secs(2));
-    Ok((child, console))
+    if let Some(socket) = socket {
+        control_sockets().push((child.id(), socket));
+    }
+    let tap = SerialTap::attach(&mut child)?;
+    watchdog::watch_vm(&child, &tap);
+    Ok((child, tap))
+}
+
+/// Apply `LEVITATE_MACHINE` / `LEVITATE_CPU`.
+fn with_machine(mut builder: QemuBuilder) -> QemuBuilder {
+    if let Some(machine) = machine_requested() {
+        builder = builder.machine(&machine);
+    }
+    if let Some(cpu) = cpu_requested() {
+        builder = builder.cpu(&cpu);
+    }
+    builder
+}
+
+/// Apply `LEVITATE_DISK_CACHE` / `LEVITATE_DISK_BUS` to a builder with a disk.
+fn with_disk_options(mut builder: QemuBuilder) -> QemuBuilder {
+    if let Some(cache) = disk_cache_requested() {
+        builder = builder.disk_cache(cache);
+    }
+    if let Some(bus) = disk_bus_requested() {
+        builder = builder.disk_bus(bus);
+    }
+    builder
+}
+
+/// User networking, or none at all in offline mode.
+fn with_network(builder: QemuBuilder) -> QemuBuilder {
+    if offline_requested() {
+        builder.no_network()
+    } else {
+        builder.with_user_network()
+    }
+}
+
+/// For builders whose only networking is `forward_port`: restrict it offline.
+fn with_network_offline_only(builder: QemuBuilder) -> QemuBuilder {
+    if offline_requested() {
+        builder.no_network()
+    } else {
+        builder
+    }
 }
 
 fn with_boot_injection(builder: QemuBuilder) -> Result<QemuBuilder> {
-    let Some(injection) = boot_injection_from_env()? else {
-        return Ok(builder);
+    let builder = match std::env::var(ENV_KERNEL_APPEND) {
+        Ok(args) if !args.trim().is_empty() => builder.append_kernel_cmdline(&args),
+        _ => builder,
     };
-    let mut configured = builder.fw_cfg_file(&injection.fw_cfg_name, injection.payload_file);
-    if let Some(media_iso_file) = injection.media_iso_file {
-        configured = configured.extra_cdrom(media_iso_file);
-    }
-    Ok(configured)
-}
-
-fn allocate_local_port() -> Result<u16> {
-    let listener =
-        TcpListener::bind("127.0.0.1:0").context("allocating local tcp port for SSH forward")?;
-    let port = listener
-        .local_addr()
-        .context("reading allocated local tcp port")?
-        .port();
-    drop(listener);
-    Ok(port)
+    match boot_injection_from_env()? {
+        Some(injection) => injection.apply(builder),
+        None => {
+            require_kernel_cmdline_applied(&builder)?;
+            Ok(builder)
+        }
+    }
+}
+
+fn ssh_host_port(builder: &QemuBuilder) -> u16 {
+    builder
+        .forwarded_host_port(22)
+        .expect("builder forwards guest tcp/22")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ovmf_vars_held_open_is_reported_with_its_holder() {
+        let dir =
+            std::env::temp_dir().join(format!("install-tests-ovmf-vars-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let template = dir.join("OVMF_VARS.fd");
+        let dest = dir.join("vars.fd");
+        fs::write(&template, "fresh vars").unwrap();
+        fs::write(&dest, "stale vars").unwrap();
+
+        let held = fs::File::open(&dest).unwrap();
+        let err = format!(
+            "{:#}",
+            replace_ovmf_vars(&template, &dest, Duration::ZERO).unwrap_err()
+        );
+        assert!(
+            err.contains(&format!("PID {} (", std::process::id())),
+            "{}",
+            err
+        );
+        assert!(err.contains("--work-dir"), "{}", err);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "stale vars");
+
+        drop(held);
+        replace_ovmf_vars(&template, &dest, Duration::ZERO).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "fresh vars");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scratch_names_differ_across_processes_and_calls() {
+        assert_ne!(
+            scratch_file_name("levitate-disk", "qcow2", 100, 0),
+            scratch_file_name("levitate-disk", "qcow2", 101, 0)
+        );
+
+        let mut scratch = ScratchFiles::new();
+        let first = scratch.path("levitate-disk", "qcow2").unwrap();
+        let second = scratch.path("levitate-disk", "qcow2").unwrap();
+        assert_ne!(first, second);
+        let name = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with(&format!("levitate-disk-{}-", std::process::id())));
+
+        fs::write(&first, "scratch").unwrap();
+        drop(scratch);
+        assert!(!first.exists());
+    }
 }
@@ -0,0 +1,133 @@
+//! SSH backend for QEMU tests.
+//!
+//! Runs commands over a host-forwarded SSH port (see `QemuBuilder::forward_port`)
+//! using recshuttle's default key. Unlike the serial `Console`, any number of
+//! `SshExecutor`s can talk to the same guest at once, which is what lets
+//! `steps::run_phase_parallel` run independent checks concurrently
+//! (`install-tests run --attach … --parallel-ssh PORT`).
+//!
+//! The guest must accept the recshuttle default key for root.
+
+use crate::executor::{shell_quote, traced, ExecResult, Executor};
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+
+/// Exit code of coreutils/busybox `timeout` when the command is killed.
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Exit code `ssh` uses for its own errors, such as a dropped connection.
+const SSH_ERROR_EXIT_CODE: i32 = 255;
+
+/// Executor backed by host SSH to a forwarded guest port.
+#[derive(Debug, Clone, Copy)]
+pub struct SshExecutor {
+    host_port: u16,
+}
+
+impl SshExecutor {
+    /// Executor for the guest SSH server forwarded to `127.0.0.1:host_port`.
+    pub fn new(host_port: u16) -> Self {
+        Self { host_port }
+    }
+
+    /// Forwarded host port this executor connects to.
+    pub fn host_port(&self) -> u16 {
+        self.host_port
+    }
+
+    /// Poll until the guest answers over SSH or `timeout` elapses.
+    fn wait_for_ssh(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let probe = recshuttle::ssh_exec_default_key(self.host_port, "echo __SSH_READY__");
+            match probe {
+                Ok(out) if out.exit_code == 0 && out.output.contains("__SSH_READY__") => {
+                    return Ok(());
+                }
+                Ok(out) if Instant::now() >= deadline => anyhow::bail!(
+                    "SSH on port {} not ready after {:?}: {}",
+                    self.host_port,
+                    timeout,
+                    out.output.trim()
+                ),
+                Err(err) if Instant::now() >= deadline => {
+                    return Err(err).with_context(|| {
+                        format!(
+                            "SSH on port {} not ready after {:?}",
+                            self.host_port, timeout
+                        )
+                    })
+                }
+                _ => std::thread::sleep(Duration::from_secs(1)),
+            }
+        }
+    }
+}
+
+impl Executor for SshExecutor {
+    fn exec(&mut self, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        // Enforce the timeout guest-side; host SSH has no per-command deadline.
+        let wrapped = format!(
+            "timeout {} sh -c {}",
+            timeout.as_secs().max(1),
+            shell_quote(cmd)
+        );
+        traced(cmd, || {
+            let out = recshuttle::ssh_exec_default_key(self.host_port, &wrapped)
+                .with_context(|| format!("running SSH command on port {}", self.host_port))?;
+            let timed_out = out.exit_code == TIMEOUT_EXIT_CODE;
+            let disconnected = out.exit_code == SSH_ERROR_EXIT_CODE;
+            Ok(ExecResult {
+                completed: !timed_out && !disconnected,
+                exit_code: out.exit_code,
+                output: out.output,
+                aborted_on_error: false,
+                stalled: false,
+                disconnected,
+                matched_pattern: None,
+            })
+        })
+    }
+
+    fn exec_chroot(&mut self, path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        self.exec(
+            &format!(
+                "recchroot {} /bin/sh -c {}",
+                shell_quote(path),
+                shell_quote(cmd)
+            ),
+            timeout,
+        )
+    }
+
+    fn write_file(&mut self, path: &str, content: &str) -> Result<()> {
+        self.exec_ok(
+            &format!(
+                "printf '%s' {} > {}",
+                shell_quote(content),
+                shell_quote(path)
+            ),
+            Duration::from_secs(10),
+        )?;
+        Ok(())
+    }
+
+    /// SSH sessions are already authenticated; this only checks the user exists.
+    fn login(&mut self, username: &str, _password: &str, timeout: Duration) -> Result<()> {
+        self.exec_ok(&format!("id {}", shell_quote(username)), timeout)?;
+        Ok(())
+    }
+
+    fn wait_for_live_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.wait_for_ssh(stall_timeout)
+    }
+
+    fn wait_for_installed_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.wait_for_ssh(stall_timeout)
+    }
+
+    /// Boot-time service failures are only observable on the serial console.
+    fn failed_services(&self) -> &[String] {
+        &[]
+    }
+}
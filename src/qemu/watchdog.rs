@@ -0,0 +1,141 @@
+//! Overall run timeout (`--max-run-time`).
+//!
+//! Per-command and stall timeouts catch a guest that goes quiet, not one
+//! that trickles output forever. [`start`] arms a thread that, once the
+//! whole run exceeds its budget, prints the tail of every watched VM's
+//! serial transcript, kills those VMs and exits with
+//! [`RUN_TIMEOUT_EXIT_CODE`], so CI gets diagnostics instead of a SIGKILL.
+//!
+//! `session`'s spawn functions register each VM and its [`SerialTap`] with
+//! [`watch_vm`]; teardown drops it again with [`unwatch_vm`]. The exit skips
+//! destructors, so scratch files of the timed-out run are left behind.
+
+use super::SerialTap;
+use anyhow::{Context, Result};
+use std::process::Child;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Exit status after an overall run timeout, as `timeout(1)` uses.
+pub const RUN_TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Serial lines shown per VM when the budget runs out.
+const SCROLLBACK_LINES: usize = 40;
+
+struct WatchedVm {
+    pid: u32,
+    tap: SerialTap,
+}
+
+static WATCHED: Mutex<Vec<WatchedVm>> = Mutex::new(Vec::new());
+
+fn watched() -> MutexGuard<'static, Vec<WatchedVm>> {
+    WATCHED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Register a spawned QEMU so a run timeout can report and kill it.
+///
+/// Also forgets VMs that have exited without being unwatched (e.g. on an
+/// error path), so their transcripts do not pile up over a long run.
+pub fn watch_vm(child: &Child, tap: &SerialTap) {
+    let mut watched = watched();
+    watched.retain(|vm| is_qemu(vm.pid));
+    watched.push(WatchedVm {
+        pid: child.id(),
+        tap: tap.clone(),
+    });
+}
+
+/// Forget the VM with QEMU PID `pid`, releasing its transcript.
+pub fn unwatch_vm(pid: u32) {
+    watched().retain(|vm| vm.pid != pid);
+}
+
+/// Exit the process with diagnostics once `budget` has elapsed.
+pub fn start(budget: Duration) -> Result<()> {
+    std::thread::Builder::new()
+        .name("run-watchdog".to_string())
+        .spawn(move || {
+            std::thread::sleep(budget);
+            let vms = running_vms();
+            eprintln!("{}", timeout_report(budget, &vms));
+            for (pid, _) in &vms {
+                // SAFETY: plain kill(2) on a PID we spawned and just saw alive.
+                unsafe {
+                    libc::kill(*pid as libc::pid_t, libc::SIGKILL);
+                }
+            }
+            std::process::exit(RUN_TIMEOUT_EXIT_CODE);
+        })
+        .context("spawning run watchdog thread")?;
+    Ok(())
+}
+
+/// Watched VMs still running QEMU, with their serial transcripts.
+fn running_vms() -> Vec<(u32, Vec<String>)> {
+    watched()
+        .iter()
+        .filter(|vm| is_qemu(vm.pid))
+        .map(|vm| (vm.pid, vm.tap.transcript_since(Duration::ZERO)))
+        .collect()
+}
+
+/// Is `pid` still a QEMU process?
+///
+/// Checking `comm` keeps a PID reused since the VM exited from being killed.
+fn is_qemu(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .is_ok_and(|comm| comm.starts_with("qemu"))
+}
+
+/// The message printed before the watchdog kills `vms` and exits.
+fn timeout_report(budget: Duration, vms: &[(u32, Vec<String>)]) -> String {
+    let mut report = format!(
+        "overall run timeout: run exceeded --max-run-time of {}s; killing {} QEMU process(es)",
+        budget.as_secs(),
+        vms.len()
+    );
+    for (pid, lines) in vms {
+        let shown = &lines[lines.len().saturating_sub(SCROLLBACK_LINES)..];
+        report.push_str(&format!(
+            "\n--- QEMU PID {}: last {} serial lines ---",
+            pid,
+            shown.len()
+        ));
+        for line in shown {
+            report.push_str(&format!("\n  {}", line));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_report_shows_each_vms_tail() {
+        let lines: Vec<String> = (1..=50).map(|n| format!("line {}", n)).collect();
+        let report = timeout_report(Duration::from_secs(3600), &[(41, lines), (42, Vec::new())]);
+
+        assert!(
+            report.starts_with("overall run timeout: run exceeded --max-run-time of 3600s"),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains("QEMU PID 41: last 40 serial lines"),
+            "{}",
+            report
+        );
+        assert!(report.contains("\n  line 11\n  line 12\n"), "{}", report);
+        assert!(!report.contains("line 10\n"), "{}", report);
+        assert!(
+            report.contains("QEMU PID 42: last 0 serial lines"),
+            "{}",
+            report
+        );
+    }
+}
@@ -13,16 +13,22 @@
 //! - `automated-login` — harness can login and run commands
 //! - `runtime` — expected installed-system tools are present
 
+pub mod ssh;
 pub mod state;
 
+use crate::diagnostics;
 use crate::distro::{context_for_distro, load_installed_scenario_facts, DistroContext};
+use crate::executor::{shell_quote, Executor};
 use crate::preflight::require_preflight_with_iso_for_distro;
+use crate::qemu::serial::login_observed;
 use crate::qemu::session;
-use crate::qemu::{Console, SerialExecutorExt};
+use crate::qemu::{find_ovmf, Console, RecoveringExecutor, SerialExecutorExt};
+use crate::steps::report::{RunReport, StepReport};
+use crate::steps::{CheckResult, Step, VerifyLiveOverlay};
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use distro_contract::{load_variant_contract_for_distro_from, RootfsMutability};
-use recshuttle::{InstallLayout, InstallPlanSpec, RemoteInstallerService, SshExecOutput};
+use recshuttle::{InstallLayout, RemoteInstallerService, SshExecOutput};
 use serde::{Deserialize, Serialize};
 use state::ScenarioState;
 use std::fs;
@@ -32,6 +38,32 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 const LIVE_BOOT_SSH_PREFLIGHT_SCRIPT: &str = "/usr/local/bin/live-boot-ssh-preflight.sh";
 const SCENARIO_RUNTIME_RETENTION_COUNT: usize = 5;
+/// Respawn attempts when QEMU dies before kernel output during live boot.
+const LIVE_BOOT_SPAWN_ATTEMPTS: usize = 3;
+/// Set by `--fast-reboot`: kexec into the installed kernel at the end of install.
+pub const FAST_REBOOT_ENV: &str = "LEVITATE_FAST_REBOOT";
+/// Set by `--dump-journal`: `-` prints the installed system's boot journal on
+/// a post-install failure, any other value writes it to the artifact dir.
+pub const DUMP_JOURNAL_ENV: &str = "LEVITATE_DUMP_JOURNAL";
+/// Set by `--journal-lines`: only dump the last N journal entries.
+pub const JOURNAL_LINES_ENV: &str = "LEVITATE_JOURNAL_LINES";
+/// Set by `--preserve-on-failure`: move a failed install's disk and OVMF vars
+/// out of the pruned run history so the image can be re-verified with `--disk`.
+pub const PRESERVE_ON_FAILURE_ENV: &str = "LEVITATE_PRESERVE_ON_FAILURE";
+/// Set by `--extra-pkg`: space-separated packages installed into the live
+/// overlay before the live scenarios run their checks.
+pub const EXTRA_PACKAGES_ENV: &str = "LEVITATE_EXTRA_PACKAGES";
+/// Set by `--extra-pkg-mirror`: host directory served to the guest as the
+/// package mirror for `--extra-pkg`.
+pub const EXTRA_PACKAGES_MIRROR_ENV: &str = "LEVITATE_EXTRA_PACKAGES_MIRROR";
+/// Per-distro directory (beside `scenarios/`) holding preserved failed disks.
+const PRESERVED_DIRNAME: &str = "preserved";
+/// Where the install scenario stages the installed kernel for kexec.
+const KEXEC_STAGING_DIR: &str = "/tmp/fast-reboot";
+/// Serial markers that show the kernel loaded; any one passes `smoke_boot`.
+const SMOKE_BOOT_KERNEL_MARKERS: &[&str] = &["Linux version", "KASLR", "Decompressing Linux"];
+/// How long `smoke_boot` waits for a kernel marker (scaled like boot waits).
+const SMOKE_BOOT_TIMEOUT_SECS: u64 = 90;
 const INSTALL_DISK_FILENAME: &str = "disk.qcow2";
 const INSTALL_OVMF_VARS_FILENAME: &str = "ovmf-vars.fd";
 
@@ -219,9 +251,11 @@ fn run_scenario_impl(distro_id: &str, scenario: ScenarioId, force: bool) -> Resu
         if !state.has_passed(previous) {
             bail!(
                 "{} is blocked: {} has not passed yet.\n\
+                 {}\n\
                  Run: cargo run --bin scenarios -- --distro {} --scenario {}",
                 scenario.display_name(),
                 previous.display_name(),
+                state.explain_block(scenario).unwrap_or_default(),
                 canonical_distro_id,
                 previous.key()
             );
@@ -264,9 +298,10 @@ fn run_scenario_impl(distro_id: &str, scenario: ScenarioId, force: bool) -> Resu
                 .expect("install scenario requires ISO")
                 .path,
         ),
-        ScenarioId::InstalledBoot => run_installed_boot(&*ctx),
-        ScenarioId::AutomatedLogin => run_automated_login(&*ctx),
-        ScenarioId::Runtime => run_daily_driver_tools(&*ctx),
+        ScenarioId::InstalledBoot | ScenarioId::AutomatedLogin | ScenarioId::Runtime => {
+            resolve_latest_install_runtime(canonical_distro_id)
+                .and_then(|runtime| run_installed_scenario(&*ctx, scenario, &runtime))
+        }
     };
 
     match &result {
@@ -290,6 +325,86 @@ fn run_scenario_impl(distro_id: &str, scenario: ScenarioId, force: bool) -> Resu
     }
 }
 
+/// Run a post-install scenario against `install_runtime`'s disk.
+fn run_installed_scenario(
+    ctx: &dyn DistroContext,
+    scenario: ScenarioId,
+    install_runtime: &InstallScenarioRuntime,
+) -> Result<String> {
+    match scenario {
+        ScenarioId::InstalledBoot => run_installed_boot(ctx, install_runtime),
+        ScenarioId::AutomatedLogin => run_automated_login(ctx, install_runtime),
+        ScenarioId::Runtime => run_daily_driver_tools(ctx, install_runtime),
+        other => bail!("{} does not boot an installed disk", other.display_name()),
+    }
+}
+
+/// Run post-install `scenarios` against a disk image built elsewhere.
+///
+/// Skips preflight, the live ISO and the install scenario entirely: the
+/// disk boots as-is. `ovmf_vars` is used when given, otherwise a fresh copy
+/// of the OVMF vars template (the firmware then boots the ESP's removable
+/// fallback path). Results are printed but never recorded in scenario state,
+/// since the disk is not this distro's install output.
+pub fn run_with_disk(
+    distro_id: &str,
+    disk: &Path,
+    ovmf_vars: Option<&Path>,
+    scenarios: &[ScenarioId],
+) -> Result<bool> {
+    let ctx = context_for_distro(distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
+    if let Some(scenario) = scenarios
+        .iter()
+        .find(|s| s.ordinal() < ScenarioId::InstalledBoot.ordinal())
+    {
+        bail!(
+            "--disk only runs post-install scenarios; {} needs the live ISO",
+            scenario.key()
+        );
+    }
+    if !disk.is_file() {
+        bail!("disk image not found: {}", disk.display());
+    }
+    let mut scratch = session::ScratchFiles::new();
+    let ovmf_vars_path = match ovmf_vars {
+        Some(vars) if vars.is_file() => vars.to_path_buf(),
+        Some(vars) => bail!("OVMF vars not found: {}", vars.display()),
+        None => {
+            let fresh = scratch.path(&format!("{}-disk-vars", ctx.id()), "fd")?;
+            session::setup_ovmf_vars_at(&fresh)?.1
+        }
+    };
+    let stem = disk
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let runtime = InstallScenarioRuntime {
+        run_id: format!("external-{}", stem),
+        disk_path: disk.to_path_buf(),
+        ovmf_vars_path,
+    };
+    println!("  disk:  {}", runtime.disk_path.display());
+    println!("  vars:  {}", runtime.ovmf_vars_path.display());
+
+    for &scenario in scenarios {
+        println!("{} {}", ">>".cyan(), scenario.display_name());
+        match run_installed_scenario(&*ctx, scenario, &runtime) {
+            Ok(evidence) => println!(
+                "{} {} passed: {}",
+                "[PASS]".green().bold(),
+                scenario.display_name(),
+                evidence
+            ),
+            Err(e) => {
+                print_failure(scenario, &e);
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
 /// Run all scenarios up to `target` (inclusive).
 pub fn run_up_to_scenario(distro_id: &str, target: ScenarioId) -> Result<bool> {
     for scenario in ScenarioId::ALL {
@@ -303,32 +418,196 @@ pub fn run_up_to_scenario(distro_id: &str, target: ScenarioId) -> Result<bool> {
     Ok(true)
 }
 
-/// Print scenario status for a distro.
-pub fn print_status(distro_id: &str) -> Result<()> {
+/// Re-run scenarios `from` through `to` (inclusive).
+///
+/// The scenario before `from` must already have passed, so gating still
+/// holds. Results from `from` onward are cleared first, so every scenario in
+/// the range actually runs instead of being skipped as already passed.
+pub fn run_scenario_range(distro_id: &str, from: ScenarioId, to: ScenarioId) -> Result<bool> {
+    if from.ordinal() > to.ordinal() {
+        bail!(
+            "--from-scenario {} comes after --up-to-scenario {}",
+            from.key(),
+            to.key()
+        );
+    }
     let ctx = context_for_distro(distro_id)
         .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
-    let canonical_distro_id = ctx.id();
+    if from.ordinal() > 0 {
+        let previous = ScenarioId::ALL[from.ordinal() - 1];
+        if !ScenarioState::load(ctx.id()).has_passed(previous) {
+            bail!(
+                "{} is blocked: {} has not passed yet.\n\
+                 Run: cargo run --bin scenarios -- --distro {} --up-to-scenario {}",
+                from.display_name(),
+                previous.display_name(),
+                ctx.id(),
+                previous.key()
+            );
+        }
+    }
 
-    let state = ScenarioState::load(canonical_distro_id);
-    let valid = ScenarioId::ALL.iter().all(|scenario| {
-        if !state.has_result(*scenario) {
-            return true;
+    for scenario in ScenarioId::ALL {
+        if scenario.ordinal() < from.ordinal() {
+            continue;
         }
-        match scenario_input_fingerprint(
-            canonical_distro_id,
-            *scenario,
-            resolve_iso_artifact_for_scenario(canonical_distro_id, *scenario)
-                .ok()
-                .flatten()
-                .as_ref(),
-        ) {
-            Ok(fingerprint) => state.is_valid_for_scenario_input(*scenario, &fingerprint),
-            Err(_) => false,
+        if scenario.ordinal() > to.ordinal() {
+            break;
         }
-    });
+        let passed = if scenario == from {
+            run_scenario_forced(distro_id, scenario)?
+        } else {
+            run_scenario(distro_id, scenario)?
+        };
+        if !passed {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Print why `scenario` is blocked, down to the lowest scenario that has not passed.
+pub fn explain_block(distro_id: &str, scenario: ScenarioId) -> Result<()> {
+    let ctx = context_for_distro(distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
+    let state = ScenarioState::load(ctx.id());
+    match state.explain_block(scenario) {
+        Some(chain) => println!("{}", chain),
+        None => println!(
+            "{} is not blocked: every scenario below it has passed.",
+            scenario.display_name()
+        ),
+    }
+    Ok(())
+}
+
+/// Machine-readable scenario status, as printed by `--status --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    /// Canonical distro id (aliases resolved).
+    pub distro_id: String,
+    /// ISO the live scenarios currently resolve to, if any.
+    pub iso_path: Option<PathBuf>,
+    /// False if any recorded result was made against different inputs.
+    pub inputs_valid: bool,
+    /// Key of the highest contiguous passed scenario.
+    pub highest_passed: Option<String>,
+    /// One entry per canonical scenario, in ladder order.
+    pub scenarios: Vec<ScenarioStatus>,
+}
+
+/// One scenario's entry in a [`StatusReport`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioStatus {
+    pub key: String,
+    pub name: String,
+    /// `"pass"`, `"fail"` or `"not-run"`.
+    pub status: String,
+    /// Whether the recorded result matches the current inputs (true if none recorded).
+    pub input_valid: bool,
+    pub timestamp: Option<String>,
+    pub evidence: Option<String>,
+}
+
+/// Collect the scenario status for a distro.
+pub fn status_report(distro_id: &str) -> Result<StatusReport> {
+    let ctx = context_for_distro(distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
+    let canonical_distro_id = ctx.id();
+
+    let state = ScenarioState::load(canonical_distro_id);
+    let scenarios: Vec<ScenarioStatus> = ScenarioId::ALL
+        .iter()
+        .map(|&scenario| {
+            let result = state.results.get(scenario.key());
+            ScenarioStatus {
+                key: scenario.key().to_string(),
+                name: scenario.display_name().to_string(),
+                status: match result {
+                    Some(result) if result.passed => "pass",
+                    Some(_) => "fail",
+                    None => "not-run",
+                }
+                .to_string(),
+                input_valid: result.is_none()
+                    || scenario_result_is_current(&state, canonical_distro_id, scenario),
+                timestamp: result.map(|result| result.timestamp.clone()),
+                evidence: result.map(|result| result.evidence.clone()),
+            }
+        })
+        .collect();
+
+    Ok(StatusReport {
+        distro_id: canonical_distro_id.to_string(),
+        iso_path: resolve_iso_artifact_for_scenario(canonical_distro_id, ScenarioId::LiveBoot)
+            .ok()
+            .flatten()
+            .map(|iso| iso.path),
+        inputs_valid: scenarios.iter().all(|scenario| scenario.input_valid),
+        highest_passed: state
+            .highest_passed()
+            .map(|scenario| scenario.key().to_string()),
+        scenarios,
+    })
+}
+
+/// The distro's current scenario results as a run report, the input of
+/// `install-tests compare`.
+///
+/// Each scenario with a result made against the current inputs becomes one
+/// step, numbered by its place in the ladder (live-boot is 1).
+pub fn run_report(distro_id: &str) -> Result<RunReport> {
+    let status = status_report(distro_id)?;
+    let steps = status
+        .scenarios
+        .iter()
+        .enumerate()
+        .filter(|(_, scenario)| scenario.status != "not-run" && scenario.input_valid)
+        .map(|(index, scenario)| StepReport {
+            num: index + 1,
+            name: scenario.name.clone(),
+            passed: scenario.status == "pass",
+            checks: vec![(scenario.key.clone(), scenario.status.clone())],
+            commands: Vec::new(),
+        })
+        .collect();
+    Ok(RunReport { steps })
+}
+
+/// Whether `scenario`'s recorded result was made against its current inputs.
+fn scenario_result_is_current(
+    state: &ScenarioState,
+    distro_id: &str,
+    scenario: ScenarioId,
+) -> bool {
+    match scenario_input_fingerprint(
+        distro_id,
+        scenario,
+        resolve_iso_artifact_for_scenario(distro_id, scenario)
+            .ok()
+            .flatten()
+            .as_ref(),
+    ) {
+        Ok(fingerprint) => state.is_valid_for_scenario_input(scenario, &fingerprint),
+        Err(_) => false,
+    }
+}
+
+/// Print scenario status for a distro as JSON (see [`StatusReport`]).
+pub fn print_status_json(distro_id: &str) -> Result<()> {
+    let report = status_report(distro_id)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Print scenario status for a distro.
+pub fn print_status(distro_id: &str) -> Result<()> {
+    let report = status_report(distro_id)?;
+    let ctx = context_for_distro(&report.distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
 
     println!("{} Scenario Status", ctx.name().bold());
-    if !valid {
+    if !report.inputs_valid {
         println!(
             "{}",
             "  (stale — scenario input changed or is missing, results will reset on next run)"
@@ -337,26 +616,21 @@ pub fn print_status(distro_id: &str) -> Result<()> {
     }
     println!();
 
-    for scenario in ScenarioId::ALL {
-        let status = if state.has_passed(scenario) {
-            "[PASS]".green()
-        } else if state.has_result(scenario) {
-            "[FAIL]".red()
-        } else {
-            "[    ]".dimmed()
+    for scenario in &report.scenarios {
+        let status = match scenario.status.as_str() {
+            "pass" => "[PASS]".green(),
+            "fail" => "[FAIL]".red(),
+            _ => "[    ]".dimmed(),
         };
-        println!(
-            "  {} {:<15} {}",
-            status,
-            scenario.key(),
-            scenario.display_name()
-        );
+        println!("  {} {:<15} {}", status, scenario.key, scenario.name);
     }
     println!();
     println!(
         "  Highest passed: {}",
-        state
-            .highest_passed()
+        report
+            .highest_passed
+            .as_deref()
+            .and_then(ScenarioId::parse_key)
             .map(|scenario| scenario.display_name().to_string())
             .unwrap_or_else(|| "none".to_string())
             .bold()
@@ -364,6 +638,137 @@ pub fn print_status(distro_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Fast "does boot work at all" check: boot the live ISO and wait only for
+/// the kernel to load.
+///
+/// Passes as soon as any of the kernel markers (`Linux version`, `KASLR`,
+/// `Decompressing Linux`) shows up on serial, long before userspace. Cheap
+/// enough to run before the live-boot scenario; records no scenario state.
+pub fn smoke_boot(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
+    let (mut child, _console, tap) = session::spawn_live_observed(ctx, iso_path)?;
+    let timeout = session::boot_timeout(SMOKE_BOOT_TIMEOUT_SECS);
+    let deadline = std::time::Instant::now() + timeout;
+    let found = loop {
+        let found = SMOKE_BOOT_KERNEL_MARKERS
+            .iter()
+            .find_map(|marker| tap.time_to_pattern(marker).map(|at| (*marker, at)));
+        if found.is_some() || std::time::Instant::now() >= deadline {
+            break found;
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            bail!("QEMU exited ({}) before the kernel loaded", status);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    session::kill_vm(&mut child);
+
+    match found {
+        Some((marker, at)) => Ok(format!(
+            "kernel loaded: '{}' after {:.1}s ({})",
+            marker,
+            at.as_secs_f64(),
+            tap.boot_timeline(&[])
+        )),
+        None => bail!(
+            "no kernel marker ({}) within {:?}; boot timeline: {}",
+            SMOKE_BOOT_KERNEL_MARKERS.join(", "),
+            timeout,
+            tap.boot_timeline(&[])
+        ),
+    }
+}
+
+/// Run [`smoke_boot`] against the distro's current live ISO and print the result.
+pub fn run_smoke_boot(distro_id: &str) -> Result<bool> {
+    let ctx = context_for_distro(distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
+    let iso = resolve_iso_artifact_for_scenario(ctx.id(), ScenarioId::LiveBoot)?
+        .ok_or_else(|| anyhow::anyhow!("live-boot scenario resolved no ISO"))?;
+    println!("{} Smoke boot ({})", ">>".cyan(), iso.path.display());
+    match smoke_boot(&*ctx, &iso.path) {
+        Ok(evidence) => {
+            println!("{} {}", "[PASS]".green().bold(), evidence);
+            Ok(true)
+        }
+        Err(err) => {
+            println!("{} {:#}", "[FAIL]".red().bold(), err);
+            Ok(false)
+        }
+    }
+}
+
+/// Print the QEMU command lines the live and installed boots would use, without spawning.
+pub fn print_dry_run(distro_id: &str) -> Result<()> {
+    let ctx = context_for_distro(distro_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown distro '{}'", distro_id))?;
+    let canonical_distro_id = ctx.id();
+    let ovmf = find_ovmf().context("OVMF not found")?;
+
+    println!("{} QEMU Dry Run", ctx.name().bold());
+    println!();
+    println!("  OVMF:  {}", ovmf.display());
+    let requested = session::accel_requested();
+    match session::resolved_accel() {
+        Some(accel) if accel == requested => println!("  accel: {}", accel),
+        Some(accel) => println!("  accel: {} (requested {})", accel, requested),
+        None => println!("  accel: default"),
+    }
+    println!(
+        "  disk:  cache={} bus={}",
+        session::disk_cache_requested().map_or("default".to_string(), |cache| cache.to_string()),
+        session::disk_bus_requested().map_or("default".to_string(), |bus| bus.to_string())
+    );
+    println!(
+        "  machine: {} cpu: {}",
+        session::machine_requested().unwrap_or_else(|| "default".to_string()),
+        session::cpu_requested().unwrap_or_else(|| "default".to_string())
+    );
+
+    println!();
+    println!("{}", "Live boot".blue().bold());
+    match resolve_iso_artifact_for_scenario(canonical_distro_id, ScenarioId::LiveBoot) {
+        Ok(Some(iso)) => {
+            println!("  ISO:   {}", iso.path.display());
+            let args = session::render_live_args(&iso.path, &ovmf, 0)?;
+            println!("  argv:  {}", render_shell_command(&args));
+        }
+        Ok(None) => println!("  {}", "live boot does not use an ISO".dimmed()),
+        Err(e) => println!("  {} {:#}", "ISO unavailable:".yellow(), e),
+    }
+
+    println!();
+    println!("{}", "Installed boot".blue().bold());
+    match resolve_latest_install_runtime(canonical_distro_id) {
+        Ok(runtime) => {
+            println!("  disk:  {}", runtime.disk_path.display());
+            println!("  vars:  {}", runtime.ovmf_vars_path.display());
+            let args =
+                session::render_installed_args(&runtime.disk_path, &ovmf, &runtime.ovmf_vars_path)?;
+            println!("  argv:  {}", render_shell_command(&args));
+        }
+        Err(e) => println!("  {} {:#}", "install runtime unavailable:".yellow(), e),
+    }
+    println!();
+    Ok(())
+}
+
+fn render_shell_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                shell_quote(arg)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Reset all scenario state for a distro.
 pub fn reset_state(distro_id: &str) -> Result<()> {
     let canonical_distro_id = context_for_distro(distro_id)
@@ -377,6 +782,40 @@ pub fn reset_state(distro_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Remove scenario state for every distro.
+///
+/// Covers the built-in distros and anything else with a state file (e.g.
+/// TOML-defined derivatives), since the state directory is scanned directly.
+/// Prints and returns the removed files.
+pub fn reset_all() -> Result<Vec<PathBuf>> {
+    let dir = state::state_dir();
+    let mut removed = Vec::new();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("No scenario state under {}.", dir.display());
+            return Ok(removed);
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("reading {}", dir.display()));
+        }
+    };
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("reading {}", dir.display()))?
+            .path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            println!("Removed {}", path.display());
+            removed.push(path);
+        }
+    }
+    if removed.is_empty() {
+        println!("No scenario state under {}.", dir.display());
+    }
+    Ok(removed)
+}
+
 pub fn parse_scenario_name(value: &str) -> Result<ScenarioId> {
     ScenarioId::parse_key(value).ok_or_else(|| {
         anyhow::anyhow!(
@@ -392,13 +831,17 @@ pub fn parse_scenario_name(value: &str) -> Result<ScenarioId> {
 
 /// Live Boot scenario — ISO boots in QEMU.
 fn run_live_boot(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
+    let (_, public_key) = ssh::ensure_test_key()?;
     let live_boot_evidence = load_live_boot_evidence_spec(ctx.id())?;
-    let (mut child, mut console, ssh_host_port) = spawn_live_qemu_with_ssh(ctx, iso_path)?;
-    let stall_timeout = Duration::from_secs(ctx.live_boot_stall_timeout_secs());
+    let (mut child, mut console, ssh_host_port) =
+        session::spawn_live_with_ssh_retry(ctx, iso_path, LIVE_BOOT_SPAWN_ATTEMPTS)?;
+    screenshot_if_requested(ctx.id(), ScenarioId::LiveBoot, "boot.png");
 
     let result = (|| -> Result<String> {
-        console.wait_for_live_boot_with_context(stall_timeout, ctx)?;
+        ssh::authorize_key(&mut console, &public_key)?;
         verify_live_boot_ssh_login(&mut console, ssh_host_port)?;
+        screenshot_if_requested(ctx.id(), ScenarioId::LiveBoot, "login.png");
+        install_extra_packages(ctx, ssh_host_port)?;
 
         run_stage_script_over_ssh(
             ssh_host_port,
@@ -413,8 +856,7 @@ fn run_live_boot(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
         )
     })();
 
-    let _ = child.kill();
-    let _ = child.wait();
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
     result
 }
 
@@ -427,10 +869,13 @@ fn run_live_boot(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
 /// - Environment is configured (proc/sys/dev available)
 /// - Tool is functional (not broken/corrupted)
 fn run_live_tools(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
+    let (_, public_key) = ssh::ensure_test_key()?;
     let (mut child, mut console, ssh_host_port) = spawn_live_qemu_with_ssh(ctx, iso_path)?;
     let result = (|| -> Result<String> {
         wait_for_live_tools_serial_readiness(&mut console, ctx)?;
+        ssh::authorize_key(&mut console, &public_key)?;
         verify_live_boot_ssh_login(&mut console, ssh_host_port)?;
+        install_extra_packages(ctx, ssh_host_port)?;
 
         // Check for key tools expected in the live environment
         let tools: Vec<&str> = ctx.live_tools().to_vec();
@@ -458,7 +903,7 @@ fn run_live_tools(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
             }
         }
 
-        let overlay_evidence = verify_live_overlay_behavior(&mut console)?;
+        let overlay_evidence = verify_live_overlay_behavior(&mut console, ctx)?;
 
         // Report failures
         if !missing.is_empty() || !broken.is_empty() {
@@ -560,8 +1005,7 @@ fn run_live_tools(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
         ))
     })();
 
-    let _ = child.kill();
-    let _ = child.wait();
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
     result
 }
 
@@ -569,7 +1013,7 @@ fn wait_for_live_tools_serial_readiness(
     console: &mut Console,
     ctx: &dyn DistroContext,
 ) -> Result<()> {
-    let stall_timeout = Duration::from_secs(ctx.live_boot_stall_timeout_secs());
+    let stall_timeout = session::boot_timeout(ctx.live_boot_stall_timeout_secs());
     // Live-tools is validated over SSH. Serial readiness can be either the explicit
     // shell marker or a stable login prompt on ttyS0.
     let live_tools_success_patterns = [
@@ -588,6 +1032,7 @@ fn wait_for_live_tools_serial_readiness(
 }
 
 fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String> {
+    let (_, public_key) = ssh::ensure_test_key()?;
     let scenario_run = ScenarioRun::start(ctx.id(), ScenarioId::Install, None)?;
     let disk_path = scenario_run.output_dir.join(INSTALL_DISK_FILENAME);
     if disk_path.exists() {
@@ -607,38 +1052,20 @@ fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String>
     let installer = RemoteInstallerService::new(ssh_host_port);
     let install_result = (|| -> Result<usize> {
         console.wait_for_live_boot_with_context(
-            Duration::from_secs(ctx.live_boot_stall_timeout_secs()),
+            session::boot_timeout(ctx.live_boot_stall_timeout_secs()),
             ctx,
         )?;
-        installer.wait_ready(Duration::from_secs(ctx.live_boot_stall_timeout_secs()))?;
+        ssh::authorize_key(&mut console, &public_key)?;
+        installer.wait_ready(session::boot_timeout(ctx.live_boot_timeout_secs()))?;
+        install_extra_packages(ctx, ssh_host_port)?;
 
         let install_disk = installer.resolve_install_disk()?;
-        let installed_facts = load_installed_scenario_facts(ctx.id())?;
         let install_layout = install_layout_for_distro(ctx.id())?;
-        let install_spec = InstallPlanSpec {
-            distro_id: ctx.id().to_string(),
-            os_name: ctx.name().to_string(),
-            default_hostname: ctx.default_hostname().to_string(),
-            default_password: installed_facts
-                .automated_login
-                .default_password
-                .clone()
-                .ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "missing canonical automated-login default_password for '{}'",
-                        ctx.id()
-                    )
-                })?,
-            install_bootloader_cmd: ctx.install_bootloader_cmd().to_string(),
-            enable_serial_getty_cmd: ctx.enable_serial_getty_cmd(),
-            include_initramfs: ctx.init_system_name() != "OpenRC",
-        };
-        let install_cmds =
-            recshuttle::install_commands_for(&install_spec, &install_disk, install_layout);
+        let install_cmds = ctx.install_recipe(&install_disk, install_layout)?;
         let step_count = installer.run_install_plan(&install_cmds)?;
 
         // Verify key artifacts exist
-        let include_initramfs = ctx.init_system_name() != "OpenRC";
+        let include_initramfs = ctx.install_includes_initramfs();
         let mut verify_cmds = vec![
             ("Root filesystem", "ls /mnt/sysroot/bin/busybox".to_string()),
             ("Boot partition", "ls /mnt/sysroot/boot/EFI".to_string()),
@@ -679,6 +1106,16 @@ fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String>
             &install_disk,
         ));
         installer.verify_checks(&verify_cmds)?;
+        let staged_kexec = if fast_reboot_requested() {
+            Some(stage_fast_reboot(
+                &mut console,
+                ctx,
+                install_layout,
+                include_initramfs,
+            )?)
+        } else {
+            None
+        };
         installer.run_install_plan(&[
             ("Sync filesystem", "sync".to_string()),
             (
@@ -687,19 +1124,34 @@ fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String>
             ),
         ])?;
 
+        if let Some(staged) = staged_kexec {
+            warn_fast_reboot();
+            console
+                .kexec_into(
+                    &staged.kernel,
+                    staged.initrd.as_deref(),
+                    &staged.cmdline,
+                    session::boot_timeout(ctx.installed_boot_timeout_secs()),
+                )
+                .context("fast reboot (kexec) into installed kernel")?;
+        }
+
         Ok(step_count)
     })();
 
     let _ = installer.shutdown();
-    let _ = child.kill();
-    let _ = child.wait();
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
 
     match install_result {
         Ok(step_count) => {
-            let evidence = format!(
+            let mut evidence = format!(
                 "{} install steps completed + verified via remote installer service",
                 step_count
             );
+            if fast_reboot_requested() {
+                evidence
+                    .push_str(" + kexec fast reboot reached installed boot (UEFI not exercised)");
+            }
             scenario_run.finish_success(
                 &evidence,
                 Some(disk_path.as_path()),
@@ -709,6 +1161,11 @@ fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String>
         }
         Err(err) => {
             let failure = format!("FAIL: {:#}", err);
+            let (disk_path, ovmf_vars_path) = if preserve_on_failure_requested() {
+                preserve_failed_install(ctx.id(), &disk_path, &ovmf_vars_path)
+            } else {
+                (disk_path, ovmf_vars_path)
+            };
             let _ = scenario_run.finish_failed(
                 &failure,
                 Some(disk_path.as_path()),
@@ -719,27 +1176,267 @@ fn run_installation(ctx: &dyn DistroContext, iso_path: &Path) -> Result<String>
     }
 }
 
-fn run_installed_boot(ctx: &dyn DistroContext) -> Result<String> {
-    let install_runtime = resolve_latest_install_runtime(ctx.id())?;
+fn preserve_on_failure_requested() -> bool {
+    std::env::var(PRESERVE_ON_FAILURE_ENV).is_ok_and(|value| value == "1")
+}
+
+/// Move a failed install's disk and OVMF vars to a timestamped directory that
+/// run-history pruning never touches, and print how to boot them again.
+///
+/// Returns the paths the files now live at (the originals if the move failed).
+fn preserve_failed_install(
+    distro_id: &str,
+    disk_path: &Path,
+    ovmf_vars_path: &Path,
+) -> (PathBuf, PathBuf) {
+    let unchanged = (disk_path.to_path_buf(), ovmf_vars_path.to_path_buf());
+    let preserved = now_utc_sortable().and_then(|stamp| {
+        let dir = workspace_root()
+            .join(".artifacts/out")
+            .join(distro_id)
+            .join(PRESERVED_DIRNAME)
+            .join(format!("install-{}", stamp));
+        fs::create_dir_all(&dir).with_context(|| format!("creating '{}'", dir.display()))?;
+        let disk = move_into(disk_path, &dir)?;
+        let vars = move_into(ovmf_vars_path, &dir)?;
+        Ok((disk, vars))
+    });
+    match preserved {
+        Ok((disk, vars)) => {
+            println!("  Preserved failed install disk: {}", disk.display());
+            println!("  Preserved OVMF vars:           {}", vars.display());
+            println!(
+                "  Re-verify with: --distro {} --disk {} --ovmf-vars {}",
+                distro_id,
+                disk.display(),
+                vars.display()
+            );
+            (disk, vars)
+        }
+        Err(err) => {
+            eprintln!("  could not preserve failed install disk: {:#}", err);
+            unchanged
+        }
+    }
+}
+
+fn move_into(path: &Path, dir: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("'{}' has no file name", path.display()))?;
+    let target = dir.join(file_name);
+    let context = || format!("moving '{}' to '{}'", path.display(), target.display());
+    match fs::rename(path, &target) {
+        // rename(2) cannot cross filesystems: copy, then drop the original.
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(err) = fs::copy(path, &target) {
+                let _ = fs::remove_file(&target);
+                return Err(err).with_context(context);
+            }
+            fs::remove_file(path)
+                .with_context(|| format!("removing '{}' after copying it", path.display()))?;
+        }
+        result => result.with_context(context)?,
+    }
+    Ok(target)
+}
+
+/// Installed kernel staged outside the install target for `kexec_into`.
+struct StagedKexec {
+    kernel: String,
+    initrd: Option<String>,
+    cmdline: String,
+}
+
+fn fast_reboot_requested() -> bool {
+    std::env::var(FAST_REBOOT_ENV).is_ok_and(|value| value == "1")
+}
+
+fn extra_packages_requested() -> Vec<String> {
+    parse_extra_packages(&std::env::var(EXTRA_PACKAGES_ENV).unwrap_or_default())
+}
+
+/// Package names from `--extra-pkg`, which may be repeated or comma-separated.
+fn parse_extra_packages(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|package| !package.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Install the `--extra-pkg` packages into the live system over SSH.
+///
+/// The live root is an overlay, so the packages are gone on the next boot.
+/// With `--extra-pkg-mirror` the packages come from a host directory served
+/// through [`crate::fixtures::serve_dir`] (not reachable with `--offline`);
+/// otherwise from the distro's configured repositories.
+fn install_extra_packages(ctx: &dyn DistroContext, ssh_host_port: u16) -> Result<()> {
+    let packages = extra_packages_requested();
+    if packages.is_empty() {
+        return Ok(());
+    }
+    let packages: Vec<&str> = packages.iter().map(String::as_str).collect();
+    let install_cmd = ctx.install_packages_cmd(&packages).ok_or_else(|| {
+        anyhow::anyhow!(
+            "--extra-pkg is not supported for {}: no package manager command",
+            ctx.name()
+        )
+    })?;
+
+    let _mirror = match std::env::var_os(EXTRA_PACKAGES_MIRROR_ENV) {
+        Some(dir) => {
+            let (server, _) = crate::fixtures::serve_dir(Path::new(&dir))?;
+            let mirror_cmd = ctx.package_mirror_cmd(&server.guest_url()).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "--extra-pkg-mirror is not supported for {}: no package mirror command",
+                    ctx.name()
+                )
+            })?;
+            let result = ssh_exec(ssh_host_port, &mirror_cmd)?;
+            if result.exit_code != 0 {
+                bail!(
+                    "pointing the live package manager at the mirror failed (exit {}): {}",
+                    result.exit_code,
+                    result.output.trim()
+                );
+            }
+            Some(server)
+        }
+        None => None,
+    };
+
+    println!(
+        "{}",
+        format!(
+            "  Installing extra packages into the live overlay: {} (results do not reflect the stock image)",
+            packages.join(", ")
+        )
+        .yellow()
+    );
+    let result = ssh_exec(ssh_host_port, &install_cmd)?;
+    if result.exit_code != 0 {
+        bail!(
+            "installing extra packages failed (exit {}): {}\n{}",
+            result.exit_code,
+            install_cmd,
+            result.output.trim()
+        );
+    }
+    Ok(())
+}
+
+fn warn_fast_reboot() {
+    let border = "!".repeat(60);
+    eprintln!("{}", border.red().bold());
+    eprintln!(
+        "{}",
+        "FAST REBOOT: kexec into the installed kernel, BYPASSING UEFI + systemd-boot."
+            .red()
+            .bold()
+    );
+    eprintln!(
+        "{}",
+        "This does not verify the boot chain. Run the installed-boot scenario for that.".red()
+    );
+    eprintln!("{}", border.red().bold());
+}
+
+/// Copy the installed kernel/initramfs out of the install target and read its
+/// command line from the systemd-boot entry, before the target is unmounted.
+fn stage_fast_reboot(
+    console: &mut Console,
+    ctx: &dyn DistroContext,
+    install_layout: InstallLayout,
+    include_initramfs: bool,
+) -> Result<StagedKexec> {
+    if !console
+        .exec("command -v kexec", Duration::from_secs(5))?
+        .success()
+    {
+        bail!("--fast-reboot requires kexec in the live environment");
+    }
+
+    let entry = match install_layout {
+        InstallLayout::MutableSingleRoot => format!("{}.conf", ctx.id()),
+        InstallLayout::ImmutableAb => format!("{}-a.conf", ctx.id()),
+    };
+    let cmdline = console.exec_ok(
+        &format!(
+            "sed -n 's/^options[[:space:]]*//p' /mnt/sysroot/boot/loader/entries/{}",
+            entry
+        ),
+        Duration::from_secs(5),
+    )?;
+    let cmdline = cmdline.trim().to_string();
+    if cmdline.is_empty() {
+        bail!("systemd-boot entry '{}' has no options line", entry);
+    }
+
+    let kernel = format!("{}/vmlinuz", KEXEC_STAGING_DIR);
+    let initrd = include_initramfs.then(|| format!("{}/initramfs.img", KEXEC_STAGING_DIR));
+    let mut staging = vec![
+        format!("mkdir -p {}", KEXEC_STAGING_DIR),
+        format!("cp /mnt/sysroot/boot/vmlinuz {}", kernel),
+    ];
+    if let Some(initrd) = &initrd {
+        staging.push(format!("cp /mnt/sysroot/boot/initramfs.img {}", initrd));
+    }
+    let staging: Vec<&str> = staging.iter().map(String::as_str).collect();
+    let results = console.exec_chain(&staging, Duration::from_secs(30), true)?;
+    if let Some(failed) = results.last().filter(|result| !result.success()) {
+        bail!(
+            "staging kexec kernel failed at `{}` (exit {}): {}",
+            staging[results.len() - 1],
+            failed.exit_code,
+            failed.output.trim()
+        );
+    }
+
+    Ok(StagedKexec {
+        kernel,
+        initrd,
+        cmdline,
+    })
+}
+
+fn run_installed_boot(
+    ctx: &dyn DistroContext,
+    install_runtime: &InstallScenarioRuntime,
+) -> Result<String> {
     let scenario_run = ScenarioRun::start(
         ctx.id(),
         ScenarioId::InstalledBoot,
         Some(install_runtime.run_id.clone()),
     )?;
-    let ovmf = recqemu::find_ovmf().context("OVMF not found")?;
-    let (mut child, mut console) = session::spawn_installed(
+    let ovmf = find_ovmf().context("OVMF not found")?;
+    let (mut child, mut console, tap) = session::spawn_installed_observed(
         &install_runtime.disk_path,
         &ovmf,
         &install_runtime.ovmf_vars_path,
     )?;
 
-    let result = console.wait_for_installed_boot_with_context(Duration::from_secs(90), ctx);
-    let _ = child.kill();
-    let _ = child.wait();
+    let heartbeat = session::boot_heartbeat(&tap);
+    let result = console.wait_for_installed_boot_with_context(
+        session::boot_timeout(ctx.installed_boot_timeout_secs()),
+        ctx,
+    );
+    drop(heartbeat);
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
+
+    let facts = load_installed_scenario_facts(ctx.id())?;
+    let success_patterns: Vec<&str> = facts
+        .installed_boot
+        .success_patterns
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let timeline = tap.boot_timeline(&success_patterns);
+    println!("  Boot timeline: {}", timeline);
 
     match result {
         Ok(()) => {
-            let evidence = "Installed system boot markers detected".to_string();
+            let evidence = format!("Installed system boot markers detected ({})", timeline);
             scenario_run.finish_success(
                 &evidence,
                 Some(install_runtime.disk_path.as_path()),
@@ -759,70 +1456,83 @@ fn run_installed_boot(ctx: &dyn DistroContext) -> Result<String> {
     }
 }
 
-fn run_automated_login(ctx: &dyn DistroContext) -> Result<String> {
-    let install_runtime = resolve_latest_install_runtime(ctx.id())?;
-    let ovmf = recqemu::find_ovmf().context("OVMF not found")?;
+fn run_automated_login(
+    ctx: &dyn DistroContext,
+    install_runtime: &InstallScenarioRuntime,
+) -> Result<String> {
+    let ovmf = find_ovmf().context("OVMF not found")?;
 
-    let (mut child, mut console) = session::spawn_installed(
+    let (mut child, mut console, tap) = session::spawn_installed_observed(
         &install_runtime.disk_path,
         &ovmf,
         &install_runtime.ovmf_vars_path,
     )?;
 
-    console.wait_for_installed_boot_with_context(Duration::from_secs(90), ctx)?;
+    let heartbeat = session::boot_heartbeat(&tap);
+    console.wait_for_installed_boot_with_context(
+        session::boot_timeout(ctx.installed_boot_timeout_secs()),
+        ctx,
+    )?;
+    drop(heartbeat);
+    screenshot_if_requested(ctx.id(), ScenarioId::AutomatedLogin, "boot.png");
 
     // Attempt login
-    let facts = load_installed_scenario_facts(ctx.id())?;
-    let default_password = facts
-        .automated_login
-        .default_password
-        .as_deref()
-        .ok_or_else(|| {
-            anyhow::anyhow!(
-                "missing canonical automated-login default_password for '{}'",
-                ctx.id()
-            )
-        })?;
-    console.login("root", default_password, Duration::from_secs(15))?;
+    let default_password = ctx.default_password()?;
+    login_observed(
+        &mut console,
+        &tap,
+        "root",
+        &default_password,
+        Duration::from_secs(15),
+    )?;
+    screenshot_if_requested(ctx.id(), ScenarioId::AutomatedLogin, "login.png");
+
+    let failed = Executor::failed_services(&console).to_vec();
+    // A hung diagnostic must not garble the shell check or journal after it.
+    let mut recovering = RecoveringExecutor::new(&mut console, &tap);
+    if !failed.is_empty() {
+        let report = diagnostics::collect_service_failures(&mut recovering, ctx, &failed);
+        println!("{}", report.render("Service failure diagnostics:"));
+    }
 
     // Verify shell works
-    let result = console.exec("echo STAGE_LOGIN_OK", Duration::from_secs(5))?;
-    let _ = child.kill();
-    let _ = child.wait();
-
-    if result.output.contains("STAGE_LOGIN_OK") {
-        Ok("Login succeeded, shell functional".to_string())
-    } else {
-        bail!(
-            "Login succeeded but shell not functional. Got: {}",
-            result.output.trim()
-        );
+    let result = recovering
+        .wait_for_shell_ready(Duration::from_secs(5))
+        .map(|()| "Login succeeded, shell functional".to_string());
+    if result.is_err() {
+        dump_journal_on_failure(&mut recovering, ctx, ScenarioId::AutomatedLogin);
     }
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
+    result
 }
 
-fn run_daily_driver_tools(ctx: &dyn DistroContext) -> Result<String> {
-    let install_runtime = resolve_latest_install_runtime(ctx.id())?;
-    let ovmf = recqemu::find_ovmf().context("OVMF not found")?;
+fn run_daily_driver_tools(
+    ctx: &dyn DistroContext,
+    install_runtime: &InstallScenarioRuntime,
+) -> Result<String> {
+    let ovmf = find_ovmf().context("OVMF not found")?;
 
-    let (mut child, mut console) = session::spawn_installed(
+    let (mut child, mut console, tap) = session::spawn_installed_observed(
         &install_runtime.disk_path,
         &ovmf,
         &install_runtime.ovmf_vars_path,
     )?;
 
-    console.wait_for_installed_boot_with_context(Duration::from_secs(90), ctx)?;
+    let heartbeat = session::boot_heartbeat(&tap);
+    console.wait_for_installed_boot_with_context(
+        session::boot_timeout(ctx.installed_boot_timeout_secs()),
+        ctx,
+    )?;
+    drop(heartbeat);
     let facts = load_installed_scenario_facts(ctx.id())?;
-    let default_password = facts
-        .automated_login
-        .default_password
-        .as_deref()
-        .ok_or_else(|| {
-            anyhow::anyhow!(
-                "missing canonical automated-login default_password for '{}'",
-                ctx.id()
-            )
-        })?;
-    console.login("root", default_password, Duration::from_secs(15))?;
+    let default_password = ctx.default_password()?;
+    login_observed(
+        &mut console,
+        &tap,
+        "root",
+        &default_password,
+        Duration::from_secs(15),
+    )?;
 
     let tools: Vec<&str> = facts
         .installed_tools
@@ -830,10 +1540,23 @@ fn run_daily_driver_tools(ctx: &dyn DistroContext) -> Result<String> {
         .iter()
         .map(String::as_str)
         .collect();
+    // One hung `which` must not garble the checks after it.
+    let mut recovering = RecoveringExecutor::new(&mut console, &tap);
+    let result = recovering
+        .wait_for_shell_ready(Duration::from_secs(5))
+        .and_then(|()| check_daily_driver_tools(&mut recovering, &tools));
+    if result.is_err() {
+        dump_journal_on_failure(&mut recovering, ctx, ScenarioId::Runtime);
+    }
+    session::shutdown(&mut child, &mut console, session::SHUTDOWN_TIMEOUT);
+    result
+}
+
+fn check_daily_driver_tools(console: &mut dyn Executor, tools: &[&str]) -> Result<String> {
     let mut missing = Vec::new();
     let mut found = Vec::new();
 
-    for tool in &tools {
+    for tool in tools {
         let result = console.exec(
             &format!("which {} 2>/dev/null && echo FOUND", tool),
             Duration::from_secs(5),
@@ -845,9 +1568,6 @@ fn run_daily_driver_tools(ctx: &dyn DistroContext) -> Result<String> {
         }
     }
 
-    let _ = child.kill();
-    let _ = child.wait();
-
     if !missing.is_empty() {
         bail!(
             "Missing daily driver tools: {}\nFound: {}",
@@ -859,6 +1579,61 @@ fn run_daily_driver_tools(ctx: &dyn DistroContext) -> Result<String> {
     Ok(format!("All {} daily driver tools present", found.len()))
 }
 
+/// With `--dump-journal`, save the installed system's boot log (the journal,
+/// or the init system's equivalent) after a failure. Only called once logged
+/// in: before that there is no shell.
+fn dump_journal_on_failure(
+    console: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    scenario: ScenarioId,
+) {
+    let Ok(destination) = std::env::var(DUMP_JOURNAL_ENV) else {
+        return;
+    };
+    let lines = std::env::var(JOURNAL_LINES_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok());
+    let journal = diagnostics::collect_journal(console, ctx, lines);
+    if let Some(err) = &journal.error {
+        eprintln!("  could not collect boot journal: {}", err);
+        return;
+    }
+    if destination == "-" {
+        println!("--- {} ---\n{}", journal.command, journal.output);
+        return;
+    }
+    match write_journal(ctx.id(), scenario, &journal.output) {
+        Ok(path) => println!("  Boot journal: {}", path.display()),
+        Err(err) => eprintln!("  could not save boot journal: {:#}", err),
+    }
+}
+
+fn write_journal(distro_id: &str, scenario: ScenarioId, journal: &str) -> Result<PathBuf> {
+    let dir = scenario_runtime_root_dir(distro_id, scenario);
+    fs::create_dir_all(&dir).with_context(|| format!("creating '{}'", dir.display()))?;
+    let path = dir.join(format!("journal-{}.log", now_utc_sortable()?));
+    fs::write(&path, format!("{}\n", journal))
+        .with_context(|| format!("writing boot journal '{}'", path.display()))?;
+    Ok(path)
+}
+
+/// With `--screenshot-on-boot`, save the VM's screen as
+/// `<scenario runtime dir>/screenshots/<name>`.
+///
+/// A failed capture is reported but never fails the scenario.
+fn screenshot_if_requested(distro_id: &str, scenario: ScenarioId, name: &str) {
+    if !session::screenshots_requested() {
+        return;
+    }
+    let path = scenario_runtime_root_dir(distro_id, scenario)
+        .join("screenshots")
+        .join(name);
+    match session::capture_screenshot(&path) {
+        Ok(()) => println!("  Screenshot: {}", path.display()),
+        Err(err) => eprintln!("  could not capture screenshot: {:#}", err),
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Helpers
 // ═══════════════════════════════════════════════════════════════════════════
@@ -928,6 +1703,11 @@ pub fn resolve_iso_artifact_for_scenario(
         .and_then(|part| part.to_str())
         .ok_or_else(|| anyhow::anyhow!("invalid ISO filename '{}'", iso_path.display()))?
         .to_string();
+    if let Some(warning) =
+        crate::preflight::stale_legacy_iso_warning(&release_root.join(&filename), &iso_path)
+    {
+        eprintln!("  warning: {}", warning);
+    }
 
     Ok(Some(ScenarioIsoArtifact {
         scenario,
@@ -950,12 +1730,14 @@ fn scenario_input_fingerprint(
             .duration_since(UNIX_EPOCH)
             .with_context(|| format!("mtime before UNIX_EPOCH for '{}'", iso.path.display()))?
             .as_secs();
-        return Ok(format!(
-            "iso:{}:{}:{}",
-            iso.product_name,
-            iso.path.display(),
-            mtime
-        ));
+        let mut fingerprint = format!("iso:{}:{}:{}", iso.product_name, iso.path.display(), mtime);
+        // A pass with extra packages says nothing about the stock image, so
+        // the next run without them must not reuse it.
+        let extra_packages = extra_packages_requested();
+        if !extra_packages.is_empty() {
+            fingerprint.push_str(&format!(":extra-pkg:{}", extra_packages.join(",")));
+        }
+        return Ok(fingerprint);
     }
 
     let install_runtime = resolve_latest_install_runtime(distro_id)?;
@@ -1065,25 +1847,8 @@ fn collect_live_boot_ssh_diagnostics(console: &mut Console) -> String {
         ),
     ];
 
-    let mut report = String::from("live-boot SSH diagnostics from live shell:\n");
-    for (title, cmd) in checks {
-        report.push_str(&format!("\n--- {} ---\n$ {}\n", title, cmd));
-        match console.exec(cmd, Duration::from_secs(15)) {
-            Ok(result) => {
-                let output = result.output.trim();
-                if output.is_empty() {
-                    report.push_str("(no output)\n");
-                } else {
-                    report.push_str(output);
-                    report.push('\n');
-                }
-            }
-            Err(err) => {
-                report.push_str(&format!("(failed to collect: {:#})\n", err));
-            }
-        }
-    }
-    report
+    diagnostics::collect_checks(console, &checks)
+        .render("live-boot SSH diagnostics from live shell:")
 }
 
 fn ssh_exec(ssh_host_port: u16, remote_cmd: &str) -> Result<SshExecOutput> {
@@ -1103,10 +1868,10 @@ fn run_stage_script_over_ssh(
     let remote_cmd = match pass_marker {
         Some(pass_marker) => format!(
             "SCENARIO_PASS_MARKER={} {}",
-            shell_single_quote(pass_marker),
-            shell_single_quote(script_path)
+            shell_quote(pass_marker),
+            shell_quote(script_path)
         ),
-        None => shell_single_quote(script_path),
+        None => shell_quote(script_path),
     };
     let result = ssh_exec(ssh_host_port, &remote_cmd)?;
     if result.exit_code == 0 {
@@ -1121,10 +1886,6 @@ fn run_stage_script_over_ssh(
     );
 }
 
-fn shell_single_quote(value: &str) -> String {
-    format!("'{}'", value.replace('\'', "'\"'\"'"))
-}
-
 fn verify_install_ux_split_behavior(ssh_host_port: u16) -> Result<String> {
     let probe = ssh_exec(
         ssh_host_port,
@@ -1427,21 +2188,18 @@ fn now_unix_nanos() -> Result<u128> {
         .as_nanos())
 }
 
-fn verify_live_overlay_behavior(console: &mut Console) -> Result<String> {
-    let marker = console.exec("test -f /live-boot-marker", Duration::from_secs(5))?;
-    if !marker.success() {
-        bail!("Live overlay marker missing: /live-boot-marker");
-    }
-
-    let overlay_mount = console.exec(
-        "mount | grep ' type overlay ' | grep 'lowerdir=/live-overlay:/rootfs'",
-        Duration::from_secs(5),
-    )?;
-    if !overlay_mount.success() {
-        bail!("Overlay root mount is missing required lowerdir=/live-overlay:/rootfs chain");
-    }
-
-    Ok("overlayfs lowerdir chain verified".to_string())
+fn verify_live_overlay_behavior(console: &mut Console, ctx: &dyn DistroContext) -> Result<String> {
+    let result = VerifyLiveOverlay.execute(console, ctx)?;
+    Ok(result
+        .checks
+        .iter()
+        .map(|(_, check)| match check {
+            CheckResult::Pass { evidence, .. } => evidence.clone(),
+            CheckResult::Skip(reason) => reason.clone(),
+            other => format!("{:?}", other),
+        })
+        .collect::<Vec<_>>()
+        .join("; "))
 }
 
 fn install_layout_for_distro(distro_id: &str) -> Result<InstallLayout> {
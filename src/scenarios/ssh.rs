@@ -0,0 +1,197 @@
+//! Host SSH key for the live-ISO scenarios.
+//!
+//! recshuttle authenticates with `LEVITATE_SSH_PRIVATE_KEY`, falling back to
+//! `~/.ssh/id_ed25519`. Without either the SSH scenarios fail, so
+//! [`ensure_test_key`] generates a keypair under `.artifacts/out/ssh/`, and
+//! [`authorize_key`] appends its public half to root's `authorized_keys`
+//! over the serial console once the live shell is up. The images have no
+//! boot-time hook for installing keys, so this is done from the host.
+//!
+//! # Security
+//!
+//! The generated key is a throwaway: unencrypted, stored in the work tree,
+//! and authorized only on test VMs. Never add it to a real machine's
+//! `authorized_keys`, and never configure a personal key here expecting it
+//! to be kept secret from the guest.
+
+use crate::executor::{shell_quote, Executor};
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Private key recshuttle uses instead of `~/.ssh/id_ed25519`.
+pub const ENV_SSH_PRIVATE_KEY: &str = "LEVITATE_SSH_PRIVATE_KEY";
+
+const TEST_KEY_FILENAME: &str = "install-tests_ed25519";
+
+/// Private and public key paths for the scenario SSH flow.
+///
+/// A configured key (`LEVITATE_SSH_PRIVATE_KEY`, then `~/.ssh/id_ed25519`)
+/// is used as-is; the image is expected to trust it already. Otherwise a
+/// throwaway key is generated (or reused) in the work dir and
+/// `LEVITATE_SSH_PRIVATE_KEY` is set so recshuttle uses it.
+pub fn ensure_test_key() -> Result<(PathBuf, PathBuf)> {
+    let configured = configured_key(
+        std::env::var_os(ENV_SSH_PRIVATE_KEY),
+        std::env::var_os("HOME"),
+    );
+    if let Some(private) = configured {
+        let public = public_key_path(&private);
+        return Ok((private, public));
+    }
+
+    let (private, public) = ensure_key_in(
+        &super::workspace_root().join(".artifacts/out/ssh"),
+        run_ssh_keygen,
+    )?;
+    std::env::set_var(ENV_SSH_PRIVATE_KEY, &private);
+    Ok((private, public))
+}
+
+/// Add the public key at `public` to root's `authorized_keys` in the guest.
+///
+/// `exec` must be a root shell (the live serial console). A key already
+/// present is not appended again. A configured key without a `.pub` file
+/// is assumed to be trusted by the image and skipped.
+pub fn authorize_key(exec: &mut dyn Executor, public: &Path) -> Result<()> {
+    if !public.is_file() {
+        return Ok(());
+    }
+    let key = std::fs::read_to_string(public)
+        .with_context(|| format!("reading public key '{}'", public.display()))?;
+    let key = key.trim();
+    if key.is_empty() || key.contains('\n') {
+        bail!(
+            "public key '{}' must hold exactly one key line",
+            public.display()
+        );
+    }
+    exec.exec_ok(&authorize_command(key), Duration::from_secs(10))
+        .context("adding the test key to /root/.ssh/authorized_keys")?;
+    Ok(())
+}
+
+fn authorize_command(key: &str) -> String {
+    let key = shell_quote(key);
+    format!(
+        "mkdir -p /root/.ssh && chmod 700 /root/.ssh && \
+         touch /root/.ssh/authorized_keys && chmod 600 /root/.ssh/authorized_keys && \
+         (grep -qxF {key} /root/.ssh/authorized_keys || printf '%s\\n' {key} >> /root/.ssh/authorized_keys)"
+    )
+}
+
+/// `LEVITATE_SSH_PRIVATE_KEY` if set, else `~/.ssh/id_ed25519` if it exists.
+///
+/// An explicit `LEVITATE_SSH_PRIVATE_KEY` wins even when missing, so a
+/// typo is reported instead of silently replaced by a generated key.
+fn configured_key(env_key: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    if let Some(path) = env_key.filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let default = PathBuf::from(home?).join(".ssh/id_ed25519");
+    default.is_file().then_some(default)
+}
+
+fn public_key_path(private: &Path) -> PathBuf {
+    let mut public = private.as_os_str().to_owned();
+    public.push(".pub");
+    PathBuf::from(public)
+}
+
+/// Reuse the test keypair in `dir`, generating it with `keygen` on first use.
+fn ensure_key_in(dir: &Path, keygen: fn(&Path) -> Result<()>) -> Result<(PathBuf, PathBuf)> {
+    let private = dir.join(TEST_KEY_FILENAME);
+    let public = public_key_path(&private);
+    if private.is_file() && public.is_file() {
+        return Ok((private, public));
+    }
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("creating SSH key dir '{}'", dir.display()))?;
+    // ssh-keygen prompts before overwriting; clear a half-written pair.
+    let _ = std::fs::remove_file(&private);
+    let _ = std::fs::remove_file(&public);
+    keygen(&private)?;
+    if !public.is_file() {
+        bail!("key generation did not write '{}'", public.display());
+    }
+    Ok((private, public))
+}
+
+/// Write an unencrypted ed25519 keypair to `private` and `private.pub`.
+fn run_ssh_keygen(private: &Path) -> Result<()> {
+    let output = Command::new("ssh-keygen")
+        .args([
+            "-q",
+            "-t",
+            "ed25519",
+            "-N",
+            "",
+            "-C",
+            "install-tests-throwaway",
+            "-f",
+        ])
+        .arg(private)
+        .output()
+        .context("running ssh-keygen (install openssh or set LEVITATE_SSH_PRIVATE_KEY)")?;
+    if !output.status.success() {
+        bail!(
+            "ssh-keygen failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static KEYGEN_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn fake_keygen(private: &Path) -> Result<()> {
+        let n = KEYGEN_CALLS.fetch_add(1, Ordering::SeqCst);
+        std::fs::write(private, "private")?;
+        std::fs::write(
+            public_key_path(private),
+            format!("ssh-ed25519 AAAA{} test\n", n),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn generated_key_is_reused() {
+        let dir = temp_dir("ssh");
+        let (private, public) = ensure_key_in(&dir, fake_keygen).expect("generate key");
+        let first = std::fs::read_to_string(&public).unwrap();
+        assert!(first.starts_with("ssh-ed25519 "), "{}", first);
+
+        let (again, _) = ensure_key_in(&dir, fake_keygen).expect("reuse key");
+        assert_eq!(again, private);
+        assert_eq!(std::fs::read_to_string(&public).unwrap(), first);
+        assert_eq!(KEYGEN_CALLS.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            configured_key(Some("/keys/ci".into()), None),
+            Some(PathBuf::from("/keys/ci"))
+        );
+        assert_eq!(configured_key(None, Some(dir.clone().into())), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn authorize_command_quotes_the_key_and_is_idempotent() {
+        let cmd = authorize_command("ssh-ed25519 AAAA it's");
+        assert!(
+            cmd.contains("grep -qxF 'ssh-ed25519 AAAA it'\\''s' /root/.ssh/authorized_keys ||"),
+            "{}",
+            cmd
+        );
+        assert!(cmd.contains(">> /root/.ssh/authorized_keys"), "{}", cmd);
+    }
+}
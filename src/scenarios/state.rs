@@ -117,12 +117,40 @@ impl ScenarioState {
     pub fn has_result(&self, scenario: ScenarioId) -> bool {
         self.results.contains_key(scenario.key())
     }
+
+    /// Why `scenario` cannot run yet, traced to the lowest scenario that has
+    /// not passed, e.g. `Install blocked ← Live Tools not run ← Live Boot failed: <evidence>`.
+    ///
+    /// Returns `None` if every scenario below `scenario` has passed.
+    pub fn explain_block(&self, scenario: ScenarioId) -> Option<String> {
+        let root = ScenarioId::ALL[..scenario.ordinal()]
+            .iter()
+            .copied()
+            .find(|lower| !self.has_passed(*lower))?;
+        let mut chain = format!("{} blocked", scenario.display_name());
+        for lower in ScenarioId::ALL[root.ordinal()..scenario.ordinal()]
+            .iter()
+            .rev()
+        {
+            let reason = match self.results.get(lower.key()) {
+                Some(result) if *lower == root => format!("failed: {}", result.evidence),
+                Some(_) => "failed".to_string(),
+                None if *lower == root => "has never run".to_string(),
+                None => "not run".to_string(),
+            };
+            chain.push_str(&format!(" ← {} {}", lower.display_name(), reason));
+        }
+        Some(chain)
+    }
+}
+
+/// Directory holding one `<distro>.json` state file per distro.
+pub(crate) fn state_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../.scenarios")
 }
 
 pub(crate) fn state_path(distro_id: &str) -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("../../.scenarios")
-        .join(format!("{}.json", distro_id))
+    state_dir().join(format!("{}.json", distro_id))
 }
 
 fn unix_timestamp_string() -> String {
@@ -154,6 +182,30 @@ mod tests {
         assert!(!state.has_result(ScenarioId::Install));
     }
 
+    #[test]
+    fn explain_block_traces_to_lowest_unpassed_scenario() {
+        let mut state = ScenarioState::default();
+        state.record(ScenarioId::BuildPreflight, true, "ok");
+        state.record(ScenarioId::LiveBoot, false, "FAIL: no shell prompt");
+
+        let install = ScenarioId::Install;
+        let live_tools = ScenarioId::LiveTools;
+        let live_boot = ScenarioId::LiveBoot;
+        assert_eq!(
+            state.explain_block(install).as_deref(),
+            Some(
+                format!(
+                    "{} blocked ← {} not run ← {} failed: FAIL: no shell prompt",
+                    install.display_name(),
+                    live_tools.display_name(),
+                    live_boot.display_name()
+                )
+                .as_str()
+            )
+        );
+        assert_eq!(state.explain_block(live_boot), None);
+    }
+
     #[test]
     fn canonical_state_path_uses_scenarios_dir() {
         let path = state_path("levitate");
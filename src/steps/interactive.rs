@@ -0,0 +1,138 @@
+//! Expect-style scripts for guided (interactive) installers.
+//!
+//! The scripted install drives the guest through `Executor::exec`, which
+//! cannot answer prompts. A guided installer instead waits on the serial
+//! console: "Select disk:", then "Confirm? [y/N]". [`run_script`] plays a
+//! list of [`PromptStep`]s against anything implementing [`PromptConsole`],
+//! which [`SerialTap`] does.
+
+use crate::qemu::SerialTap;
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+/// Wait for `expect`, then type `send` followed by a newline.
+#[derive(Debug, Clone, Copy)]
+pub struct PromptStep<'a> {
+    pub expect: &'a str,
+    pub send: &'a str,
+    pub timeout: Duration,
+}
+
+impl<'a> PromptStep<'a> {
+    pub fn new(expect: &'a str, send: &'a str, timeout: Duration) -> Self {
+        Self {
+            expect,
+            send,
+            timeout,
+        }
+    }
+}
+
+/// Serial I/O an expect script needs.
+pub trait PromptConsole {
+    /// Block until `pattern` appears in output received from now on.
+    fn expect(&self, pattern: &str, timeout: Duration) -> Result<()>;
+
+    /// Type `text` into the guest.
+    fn send(&self, text: &str) -> Result<()>;
+}
+
+impl PromptConsole for SerialTap {
+    fn expect(&self, pattern: &str, timeout: Duration) -> Result<()> {
+        SerialTap::expect(self, pattern, timeout)
+    }
+
+    fn send(&self, text: &str) -> Result<()> {
+        SerialTap::send(self, text)
+    }
+}
+
+/// Answer each prompt of `script` in order, stopping at the first one that
+/// never appears.
+///
+/// Errors name the step (1-based) and prompt so a changed installer
+/// dialogue is easy to spot.
+pub fn run_script(console: &dyn PromptConsole, script: &[PromptStep]) -> Result<()> {
+    for (i, step) in script.iter().enumerate() {
+        console
+            .expect(step.expect, step.timeout)
+            .with_context(|| format!("prompt {} '{}' never appeared", i + 1, step.expect))?;
+        console
+            .send(&format!("{}\n", step.send))
+            .with_context(|| format!("answering prompt {} '{}'", i + 1, step.expect))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::cell::RefCell;
+
+    /// Shows each prompt of `dialogue` in turn, advancing when it is answered.
+    struct FakeInstaller {
+        dialogue: Vec<&'static str>,
+        shown: RefCell<usize>,
+        sent: RefCell<Vec<String>>,
+    }
+
+    impl PromptConsole for FakeInstaller {
+        fn expect(&self, pattern: &str, _timeout: Duration) -> Result<()> {
+            match self.dialogue.get(*self.shown.borrow()) {
+                Some(prompt) if prompt.contains(pattern) => Ok(()),
+                shown => bail!("Timed out waiting for '{}' (showing {:?})", pattern, shown),
+            }
+        }
+
+        fn send(&self, text: &str) -> Result<()> {
+            self.sent.borrow_mut().push(text.to_string());
+            *self.shown.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    fn installer(dialogue: Vec<&'static str>) -> FakeInstaller {
+        FakeInstaller {
+            dialogue,
+            shown: RefCell::new(0),
+            sent: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn answers_prompts_in_order() {
+        let console = installer(vec!["Select disk: ", "Confirm? [y/N] "]);
+        let timeout = Duration::from_secs(5);
+        run_script(
+            &console,
+            &[
+                PromptStep::new("Select disk:", "1", timeout),
+                PromptStep::new("Confirm?", "y", timeout),
+            ],
+        )
+        .expect("script completes");
+        assert_eq!(*console.sent.borrow(), vec!["1\n", "y\n"]);
+    }
+
+    #[test]
+    fn stops_at_a_prompt_that_never_appears() {
+        let console = installer(vec!["Select disk: ", "Encrypt? [y/N] "]);
+        let timeout = Duration::from_secs(5);
+        let err = run_script(
+            &console,
+            &[
+                PromptStep::new("Select disk:", "1", timeout),
+                PromptStep::new("Confirm?", "y", timeout),
+                PromptStep::new("Done", "", timeout),
+            ],
+        )
+        .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("prompt 2 'Confirm?'"),
+            "{:#}",
+            err
+        );
+        assert_eq!(console.sent.borrow().len(), 1);
+    }
+}
@@ -24,17 +24,38 @@
 //!
 //! 4. **Each step has an "ensures" statement** - Documents what the step
 //!    guarantees for the user when it passes
+//!
+//! ## Backends
+//!
+//! Steps only use the `exec` family of [`Executor`] methods, so any backend
+//! that can run a command and return its output and exit code runs every phase:
+//!
+//! | Backend | Phases | Notes |
+//! |---------|--------|-------|
+//! | serial `Console` | 1-6 | Default. Sees boot output, so it also reports failed services. |
+//! | `SshExecutor` | 1-6 | Needs the recshuttle key accepted by root. `exec_long` falls back to a fixed ceiling. `run --attach --parallel-ssh PORT` gives each parallel-safe step its own one via `run_phase_parallel`. |
+//! | QMP | none | Keystrokes and screenshots only. It cannot read output, so it has no `Executor` (see `qemu::qmp`). |
+//!
+//! Guided installers that prompt on the serial console are driven with
+//! [`interactive::run_script`] instead of an `Executor`.
 
+pub mod interactive;
 mod phase1_boot;
 mod phase2_disk;
 mod phase3_base;
 mod phase4_config;
 mod phase5_boot;
 mod phase6_verify;
+pub mod report;
+pub mod util;
+
+pub use phase1_boot::VerifyLiveOverlay;
 
 use crate::distro::DistroContext;
 use crate::executor::Executor;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::ops::RangeInclusive;
+use std::thread::ScopedJoinHandle;
 use std::time::Duration;
 
 /// Log entry for a command execution
@@ -76,7 +97,13 @@ pub enum CheckResult {
     /// The evidence string should contain ACTUAL VALUES, not just "ok"
     /// Good: "45MB initramfs at /boot/initramfs.img"
     /// Bad:  "file exists" (skeptic asks: "but is it empty?")
-    Pass { evidence: String },
+    ///
+    /// `data` optionally carries the same values machine-readably (sizes,
+    /// UUIDs, versions) for the JSON check export.
+    Pass {
+        evidence: String,
+        data: Option<serde_json::Value>,
+    },
     /// Check failed - the feature is broken
     Fail { expected: String, actual: String },
     /// Check skipped - feature not available (e.g., missing from tarball)
@@ -93,6 +120,16 @@ impl CheckResult {
     pub fn pass(evidence: impl Into<String>) -> Self {
         CheckResult::Pass {
             evidence: evidence.into(),
+            data: None,
+        }
+    }
+
+    /// Create a passing check whose evidence values are also attached as
+    /// structured `data`, e.g. `json!({ "size_bytes": 47185920 })`
+    pub fn pass_with_data(evidence: impl Into<String>, data: serde_json::Value) -> Self {
+        CheckResult::Pass {
+            evidence: evidence.into(),
+            data: Some(data),
         }
     }
 
@@ -123,6 +160,9 @@ pub struct StepResult {
     pub fix_suggestion: Option<String>,
     /// Commands executed during this step with their results
     pub commands: Vec<CommandLog>,
+    /// Times the runner re-executed the step before this result (see
+    /// [`Step::max_retries`]).
+    pub retries: u32,
 }
 
 impl StepResult {
@@ -137,6 +177,7 @@ impl StepResult {
             checks: Vec::new(),
             fix_suggestion: None,
             commands: Vec::new(),
+            retries: 0,
         }
     }
 
@@ -155,12 +196,8 @@ impl StepResult {
     /// Add a passing check with evidence
     /// Evidence should be ACTUAL VALUES that prove the check passed
     pub fn pass(&mut self, name: &str, evidence: impl Into<String>) {
-        self.checks.push((
-            name.to_string(),
-            CheckResult::Pass {
-                evidence: evidence.into(),
-            },
-        ));
+        self.checks
+            .push((name.to_string(), CheckResult::pass(evidence)));
     }
 
     /// Add a failing check
@@ -195,6 +232,11 @@ impl StepResult {
         self.checks.push((name.to_string(), result));
     }
 
+    /// Sum of the durations of all logged commands.
+    pub fn total_command_time(&self) -> Duration {
+        self.commands.iter().map(|cmd| cmd.duration).sum()
+    }
+
     /// Count of skipped checks
     pub fn skip_count(&self) -> usize {
         self.checks.iter().filter(|(_, r)| r.skipped()).count()
@@ -207,8 +249,8 @@ impl StepResult {
 }
 
 /// A single installation step
-pub trait Step {
-    /// Step number (1-24)
+pub trait Step: Send + Sync {
+    /// Step number (1-30)
     fn num(&self) -> usize;
 
     /// Step name for display
@@ -221,6 +263,46 @@ pub trait Step {
     /// Execute the step with distro context.
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult>;
 
+    /// Whether this step is a read-only check that can run concurrently with
+    /// other parallel-safe steps on its own executor.
+    ///
+    /// Steps that change guest state (writing files, creating users) must keep
+    /// the default `false`.
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
+    /// Step numbers that must pass before this step is worth running.
+    ///
+    /// If any of them fails, the runner skips this step instead of producing
+    /// a cascading failure.
+    fn depends_on(&self) -> &[usize] {
+        &[]
+    }
+
+    /// Whether the guest already has the state this step would produce.
+    ///
+    /// On targeted reruns (`--step`/`--step-range`) the runner skips the step
+    /// with `Skip("already satisfied")` when this returns true, so they don't
+    /// redo destructive work such as repartitioning. Full runs never consult
+    /// it. Probes must be read-only.
+    fn already_satisfied(
+        &self,
+        _executor: &mut dyn Executor,
+        _ctx: &dyn DistroContext,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// How many more times the runner re-executes this step after a failure,
+    /// for checks that can flake while the guest settles (e.g. DHCP).
+    ///
+    /// Only honoured for [`parallel_safe`](Self::parallel_safe) steps; a step
+    /// that changes guest state is never re-run.
+    fn max_retries(&self) -> u32 {
+        0
+    }
+
     /// Phase this step belongs to
     fn phase(&self) -> usize {
         match self.num() {
@@ -229,7 +311,7 @@ pub trait Step {
             7..=10 => 3,  // Base system (mount media, extract, fstab, chroot)
             11..=15 => 4, // Configuration (timezone, locale, hostname, passwords, users)
             16..=18 => 5, // Bootloader (initramfs, bootloader, services)
-            19..=24 => 6, // Post-reboot verification (systemd, user, network, sudo)
+            19..=25 => 6, // Post-reboot verification (systemd, user, network, sudo, memory)
             _ => 0,
         }
     }
@@ -244,6 +326,7 @@ pub fn all_steps() -> Vec<Box<dyn Step>> {
         // Phase 1: Boot
         Box::new(phase1_boot::VerifyUefi),
         Box::new(phase1_boot::SyncClock),
+        Box::new(phase1_boot::VerifyLiveOverlay),
         // Phase 2: Disk
         Box::new(phase2_disk::IdentifyDisk),
         Box::new(phase2_disk::PartitionDisk),
@@ -254,6 +337,7 @@ pub fn all_steps() -> Vec<Box<dyn Step>> {
         Box::new(phase3_base::ExtractRootfs),
         Box::new(phase3_base::GenerateFstab),
         Box::new(phase3_base::VerifyChroot),
+        Box::new(phase3_base::VerifyInstalledRootfs),
         // Phase 4: Configuration
         Box::new(phase4_config::SetTimezone),
         Box::new(phase4_config::ConfigureLocale),
@@ -264,6 +348,7 @@ pub fn all_steps() -> Vec<Box<dyn Step>> {
         Box::new(phase5_boot::GenerateInitramfs),
         Box::new(phase5_boot::InstallBootloader),
         Box::new(phase5_boot::EnableServices),
+        Box::new(phase5_boot::VerifyFilesystemIntegrity),
     ]
 }
 
@@ -279,10 +364,247 @@ pub fn all_steps_with_experimental() -> Vec<Box<dyn Step>> {
         Box::new(phase6_verify::VerifyNetworking),
         Box::new(phase6_verify::VerifySudo),
         Box::new(phase6_verify::VerifyEssentialCommands),
+        Box::new(phase6_verify::VerifyMemoryPressure),
+        Box::new(phase6_verify::VerifyFstabMounts),
     ]);
     steps
 }
 
+/// Whether `step` only runs with `--experimental` (absent from [`all_steps`]).
+pub fn is_experimental(step: &dyn Step) -> bool {
+    !all_steps().iter().any(|s| s.num() == step.num())
+}
+
+/// Pause between attempts of a step with [`Step::max_retries`].
+const STEP_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Run `steps` in order on `executor`, skipping steps whose prerequisites failed.
+///
+/// A step is skipped (with a `Skip("dependency N failed")` check) when any
+/// step it lists in `depends_on` errored, failed, or was itself skipped for
+/// a dependency. Prerequisites that are not part of `steps` are ignored.
+///
+/// With `skip_satisfied`, steps whose [`Step::already_satisfied`] probe
+/// returns true are skipped instead of executed; set it only for targeted
+/// reruns of individual steps.
+pub fn run_steps(
+    steps: &[Box<dyn Step>],
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    skip_satisfied: bool,
+) -> Vec<Result<StepResult>> {
+    let mut broken = Vec::new();
+    steps
+        .iter()
+        .map(|step| run_unless_blocked(step.as_ref(), executor, ctx, skip_satisfied, &mut broken))
+        .collect()
+}
+
+/// Run `steps` in order, stopping after the first pre-reboot step (Phases
+/// 1-5) that errors or fails.
+///
+/// The pre-reboot default: a broken partition table makes every later
+/// install step noise. Phase 6 verification steps are independent checks
+/// of the booted system, so every one of them runs regardless. Only the
+/// steps that ran have results; pass `fail_fast = false` to run everything
+/// as [`run_steps`] does. `skip_satisfied` is as for [`run_steps`].
+pub fn run_steps_until_failure(
+    steps: &[Box<dyn Step>],
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    fail_fast: bool,
+    skip_satisfied: bool,
+) -> Vec<Result<StepResult>> {
+    if !fail_fast {
+        return run_steps(steps, executor, ctx, skip_satisfied);
+    }
+    let mut broken = Vec::new();
+    let mut results = Vec::new();
+    for step in steps {
+        let result = run_unless_blocked(step.as_ref(), executor, ctx, skip_satisfied, &mut broken);
+        let stop = step.phase() < 6 && !step_passed(&result);
+        results.push(result);
+        if stop {
+            break;
+        }
+    }
+    results
+}
+
+/// Run `steps`, executing parallel-safe ones concurrently.
+///
+/// Each parallel-safe step without dependencies gets its own executor from
+/// `connect` (e.g. a fresh `SshExecutor`) and runs on its own thread. The
+/// remaining steps run sequentially on `executor`, waiting for any parallel
+/// prerequisite first. Dependency skipping and `skip_satisfied` work as in
+/// [`run_steps`]. Results are returned in the order of `steps`.
+pub fn run_phase_parallel<E, F>(
+    steps: &[Box<dyn Step>],
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    skip_satisfied: bool,
+    connect: F,
+) -> Vec<Result<StepResult>>
+where
+    E: Executor,
+    F: Fn() -> Result<E> + Sync,
+{
+    let runs_in_parallel = |step: &dyn Step| step.parallel_safe() && step.depends_on().is_empty();
+    let mut results: Vec<Option<Result<StepResult>>> = steps.iter().map(|_| None).collect();
+
+    std::thread::scope(|scope| {
+        let mut handles: Vec<_> = steps
+            .iter()
+            .map(|step| {
+                if !runs_in_parallel(step.as_ref()) {
+                    return None;
+                }
+                let connect = &connect;
+                Some(scope.spawn(move || {
+                    let mut step_executor = connect()?;
+                    execute_unless_satisfied(step.as_ref(), &mut step_executor, ctx, skip_satisfied)
+                }))
+            })
+            .collect();
+
+        let join = |index: usize, handles: &mut [Option<ScopedJoinHandle<'_, _>>]| {
+            let handle = handles[index].take().expect("parallel step joined once");
+            handle.join().unwrap_or_else(|_| {
+                Err(anyhow::anyhow!(
+                    "step {} panicked while running in parallel",
+                    steps[index].num()
+                ))
+            })
+        };
+
+        let mut broken = Vec::new();
+        for (index, step) in steps.iter().enumerate() {
+            if runs_in_parallel(step.as_ref()) {
+                continue;
+            }
+            for dep in step.depends_on() {
+                let Some(dep_index) = steps.iter().position(|s| s.num() == *dep) else {
+                    continue;
+                };
+                if results[dep_index].is_none() && handles[dep_index].is_some() {
+                    let result = join(dep_index, &mut handles);
+                    if !step_passed(&result) {
+                        broken.push(*dep);
+                    }
+                    results[dep_index] = Some(result);
+                }
+            }
+            results[index] = Some(run_unless_blocked(
+                step.as_ref(),
+                executor,
+                ctx,
+                skip_satisfied,
+                &mut broken,
+            ));
+        }
+
+        for index in 0..steps.len() {
+            if handles[index].is_some() {
+                results[index] = Some(join(index, &mut handles));
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every step produces a result"))
+        .collect()
+}
+
+/// Execute `step`, or skip it if a prerequisite is in `broken`.
+///
+/// Records the step in `broken` if it fails or is skipped, so its own
+/// dependents are skipped in turn.
+fn run_unless_blocked(
+    step: &dyn Step,
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    skip_satisfied: bool,
+    broken: &mut Vec<usize>,
+) -> Result<StepResult> {
+    if let Some(dep) = step.depends_on().iter().find(|dep| broken.contains(dep)) {
+        let mut skipped = StepResult::new(step.num(), step.name());
+        skipped.add_check(
+            "Prerequisites",
+            CheckResult::Skip(format!("dependency {} failed", dep)),
+        );
+        broken.push(step.num());
+        return Ok(skipped);
+    }
+    let result = execute_unless_satisfied(step, executor, ctx, skip_satisfied);
+    if !step_passed(&result) {
+        broken.push(step.num());
+    }
+    result
+}
+
+/// Execute `step`, or, with `skip_satisfied`, skip it if `already_satisfied`
+/// says its work is done.
+fn execute_unless_satisfied(
+    step: &dyn Step,
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    skip_satisfied: bool,
+) -> Result<StepResult> {
+    if skip_satisfied && step.already_satisfied(executor, ctx)? {
+        let mut skipped = StepResult::new(step.num(), step.name());
+        skipped.add_check(
+            "Existing state",
+            CheckResult::Skip("already satisfied".to_string()),
+        );
+        return Ok(skipped);
+    }
+    execute_with_retries(step, executor, ctx, STEP_RETRY_DELAY)
+}
+
+/// Execute `step`, re-running a failed read-only step up to `max_retries`
+/// times with `delay` in between. The last attempt's result is kept.
+fn execute_with_retries(
+    step: &dyn Step,
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    delay: Duration,
+) -> Result<StepResult> {
+    let max_retries = if step.parallel_safe() {
+        step.max_retries()
+    } else {
+        0
+    };
+    let mut result = step.execute(executor, ctx);
+    let mut retries = 0;
+    while retries < max_retries && !step_passed(&result) {
+        std::thread::sleep(delay);
+        retries += 1;
+        result = step.execute(executor, ctx);
+    }
+    result.map(|mut step_result| {
+        step_result.retries = retries;
+        step_result
+    })
+}
+
+fn step_passed(result: &Result<StepResult>) -> bool {
+    matches!(result, Ok(step) if step.passed)
+}
+
+/// Short name of a phase as returned by [`Step::phase`], e.g. "Disk Setup".
+pub fn phase_name(phase: usize) -> &'static str {
+    match phase {
+        1 => "Boot Verification",
+        2 => "Disk Setup",
+        3 => "Base System",
+        4 => "Configuration",
+        5 => "Bootloader",
+        6 => "Post-Reboot Verification",
+        _ => "Unknown Phase",
+    }
+}
+
 /// Get steps for a specific phase.
 ///
 /// Returns empty for phase 6 unless `experimental` is true.
@@ -303,3 +625,490 @@ pub fn steps_for_phase_experimental(phase: usize) -> Vec<Box<dyn Step>> {
         .filter(|s| s.phase() == phase)
         .collect()
 }
+
+/// Parse an inclusive `START..END` (or `START-END`) step range.
+///
+/// Both ends must be existing step numbers and START must not exceed END.
+pub fn parse_step_range(value: &str) -> Result<RangeInclusive<usize>> {
+    let (start, end) = value
+        .split_once("..")
+        .or_else(|| value.split_once('-'))
+        .ok_or_else(|| anyhow::anyhow!("step range '{}' is not START..END", value))?;
+    let parse = |bound: &str| {
+        bound
+            .trim()
+            .parse::<usize>()
+            .with_context(|| format!("step range '{}': '{}' is not a step number", value, bound))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    let last = all_steps_with_experimental()
+        .iter()
+        .map(|s| s.num())
+        .max()
+        .unwrap_or(0);
+    if start > end {
+        bail!("step range '{}': START must not exceed END", value);
+    }
+    if start < 1 || end > last {
+        bail!("step range '{}' must lie within 1..{}", value, last);
+    }
+    Ok(start..=end)
+}
+
+/// Steps numbered within `range`, in run order.
+///
+/// A range may not span the reboot: pre-reboot steps run on the live ISO
+/// and Phase 6 on the installed system, so one run cannot do both.
+pub fn steps_in_range(range: RangeInclusive<usize>) -> Result<Vec<Box<dyn Step>>> {
+    let steps: Vec<Box<dyn Step>> = all_steps_with_experimental()
+        .into_iter()
+        .filter(|s| range.contains(&s.num()))
+        .collect();
+    let post_reboot = steps.iter().filter(|s| s.phase() == 6).count();
+    if post_reboot != 0 && post_reboot != steps.len() {
+        bail!(
+            "step range {}..{} spans the reboot into the installed system; \
+             run the pre-reboot and Phase 6 steps separately",
+            range.start(),
+            range.end()
+        );
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FakeExecutor, Reply};
+
+    /// Executor for fake steps, which never run commands.
+    fn null_executor() -> FakeExecutor {
+        FakeExecutor::new().otherwise(Reply::Error("fake steps do not run commands"))
+    }
+
+    struct FakeStep {
+        num: usize,
+        passes: bool,
+        depends_on: &'static [usize],
+        satisfied: bool,
+    }
+
+    /// Fails its first `failures` attempts, then passes.
+    struct FlakyStep {
+        read_only: bool,
+        failures: u32,
+        attempts: std::sync::atomic::AtomicU32,
+    }
+
+    impl Step for FlakyStep {
+        fn num(&self) -> usize {
+            23
+        }
+        fn name(&self) -> &str {
+            "Flaky"
+        }
+        fn ensures(&self) -> &str {
+            "nothing"
+        }
+        fn parallel_safe(&self) -> bool {
+            self.read_only
+        }
+        fn max_retries(&self) -> u32 {
+            2
+        }
+
+        fn execute(
+            &self,
+            _executor: &mut dyn Executor,
+            _ctx: &dyn DistroContext,
+        ) -> Result<StepResult> {
+            let attempt = self
+                .attempts
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut result = StepResult::new(self.num(), self.name());
+            if attempt < self.failures {
+                result.fail("flaky", "pass", "fail");
+            }
+            Ok(result)
+        }
+    }
+
+    fn flaky(read_only: bool, failures: u32) -> FlakyStep {
+        FlakyStep {
+            read_only,
+            failures,
+            attempts: std::sync::atomic::AtomicU32::new(0),
+        }
+    }
+
+    #[test]
+    fn read_only_steps_are_retried_until_they_pass() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let step = flaky(true, 2);
+        let result =
+            execute_with_retries(&step, &mut null_executor(), &*ctx, Duration::ZERO).unwrap();
+        assert!(result.passed);
+        assert_eq!(result.retries, 2);
+
+        let step = flaky(true, 3);
+        let result =
+            execute_with_retries(&step, &mut null_executor(), &*ctx, Duration::ZERO).unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.retries, 2);
+    }
+
+    #[test]
+    fn parallel_safe_steps_get_their_own_executor() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let steps: Vec<Box<dyn Step>> = vec![
+            Box::new(flaky(true, 0)),
+            Box::new(FakeStep {
+                num: 24,
+                passes: false,
+                depends_on: &[],
+                satisfied: false,
+            }),
+        ];
+        let connects = std::sync::atomic::AtomicUsize::new(0);
+
+        let results = run_phase_parallel(&steps, &mut null_executor(), &*ctx, false, || {
+            connects.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(null_executor())
+        });
+        assert_eq!(connects.into_inner(), 1);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].as_ref().unwrap().passed);
+        assert!(!results[1].as_ref().unwrap().passed);
+    }
+
+    #[test]
+    fn parallel_step_reports_a_failed_connect() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let steps: Vec<Box<dyn Step>> = vec![Box::new(flaky(true, 0))];
+
+        let results = run_phase_parallel(&steps, &mut null_executor(), &*ctx, false, || {
+            Err::<FakeExecutor, _>(anyhow::anyhow!("connection refused"))
+        });
+        assert!(format!("{:#}", results[0].as_ref().unwrap_err()).contains("connection refused"));
+    }
+
+    #[test]
+    fn mutating_steps_are_never_retried() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let step = flaky(false, 1);
+        let result =
+            execute_with_retries(&step, &mut null_executor(), &*ctx, Duration::ZERO).unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.retries, 0);
+    }
+
+    impl Step for FakeStep {
+        fn num(&self) -> usize {
+            self.num
+        }
+        fn name(&self) -> &str {
+            "Fake"
+        }
+        fn ensures(&self) -> &str {
+            "nothing"
+        }
+        fn depends_on(&self) -> &[usize] {
+            self.depends_on
+        }
+        fn already_satisfied(
+            &self,
+            _executor: &mut dyn Executor,
+            _ctx: &dyn DistroContext,
+        ) -> Result<bool> {
+            Ok(self.satisfied)
+        }
+
+        fn execute(
+            &self,
+            _executor: &mut dyn Executor,
+            _ctx: &dyn DistroContext,
+        ) -> Result<StepResult> {
+            let mut result = StepResult::new(self.num, self.name());
+            if !self.passes {
+                result.fail("fake", "pass", "fail");
+            }
+            Ok(result)
+        }
+    }
+
+    #[test]
+    fn step_ranges_are_inclusive_and_stay_on_one_side_of_the_reboot() {
+        assert_eq!(parse_step_range("16..18").unwrap(), 16..=18);
+        assert_eq!(parse_step_range("16-18").unwrap(), 16..=18);
+        assert!(parse_step_range("18..16").is_err());
+        assert!(parse_step_range("0..3").is_err());
+        assert!(parse_step_range("1..99").is_err());
+        assert!(parse_step_range("16").is_err());
+
+        let nums: Vec<usize> = steps_in_range(16..=18)
+            .unwrap()
+            .iter()
+            .map(|s| s.num())
+            .collect();
+        assert_eq!(nums, [16, 17, 18]);
+        let err = steps_in_range(17..=20).err().expect("crosses the reboot");
+        assert!(err.to_string().contains("spans the reboot"), "{}", err);
+    }
+
+    #[test]
+    fn run_steps_skips_transitive_dependents_of_a_failure() {
+        let steps: Vec<Box<dyn Step>> = vec![
+            Box::new(FakeStep {
+                num: 21,
+                passes: false,
+                depends_on: &[],
+                satisfied: false,
+            }),
+            Box::new(FakeStep {
+                num: 22,
+                passes: true,
+                depends_on: &[],
+                satisfied: false,
+            }),
+            Box::new(FakeStep {
+                num: 23,
+                passes: true,
+                depends_on: &[21],
+                satisfied: false,
+            }),
+            Box::new(FakeStep {
+                num: 24,
+                passes: true,
+                depends_on: &[23],
+                satisfied: false,
+            }),
+        ];
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let results: Vec<StepResult> = run_steps(&steps, &mut null_executor(), &*ctx, false)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert!(!results[0].passed);
+        assert!(results[1].passed && !results[1].has_skips);
+        assert_eq!(results[2].skip_count(), 1);
+        assert!(matches!(
+            &results[2].checks[0].1,
+            CheckResult::Skip(reason) if reason == "dependency 21 failed"
+        ));
+        assert!(matches!(
+            &results[3].checks[0].1,
+            CheckResult::Skip(reason) if reason == "dependency 23 failed"
+        ));
+    }
+
+    #[test]
+    fn fail_fast_stops_at_the_first_failure() {
+        let steps: Vec<Box<dyn Step>> = [(3, true), (4, false), (5, true)]
+            .into_iter()
+            .map(|(num, passes)| -> Box<dyn Step> {
+                Box::new(FakeStep {
+                    num,
+                    passes,
+                    depends_on: &[],
+                    satisfied: false,
+                })
+            })
+            .collect();
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let stopped = run_steps_until_failure(&steps, &mut null_executor(), &*ctx, true, false);
+        assert_eq!(stopped.len(), 2);
+        assert!(!stopped[1].as_ref().unwrap().passed);
+
+        let all = run_steps_until_failure(&steps, &mut null_executor(), &*ctx, false, false);
+        assert_eq!(all.len(), 3);
+        assert!(all[2].as_ref().unwrap().passed);
+    }
+
+    #[test]
+    fn fail_fast_runs_every_phase_6_step() {
+        let steps: Vec<Box<dyn Step>> = [(21, false), (22, true)]
+            .into_iter()
+            .map(|(num, passes)| -> Box<dyn Step> {
+                Box::new(FakeStep {
+                    num,
+                    passes,
+                    depends_on: &[],
+                    satisfied: false,
+                })
+            })
+            .collect();
+        assert!(steps.iter().all(|step| step.phase() == 6));
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let results = run_steps_until_failure(&steps, &mut null_executor(), &*ctx, true, false);
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].as_ref().unwrap().passed);
+        assert!(results[1].as_ref().unwrap().passed);
+    }
+
+    /// A real step that fails without touching the guest.
+    struct FailingStep(Box<dyn Step>);
+
+    impl Step for FailingStep {
+        fn num(&self) -> usize {
+            self.0.num()
+        }
+        fn name(&self) -> &str {
+            self.0.name()
+        }
+        fn ensures(&self) -> &str {
+            self.0.ensures()
+        }
+        fn phase(&self) -> usize {
+            self.0.phase()
+        }
+        fn depends_on(&self) -> &[usize] {
+            self.0.depends_on()
+        }
+
+        fn execute(
+            &self,
+            _executor: &mut dyn Executor,
+            _ctx: &dyn DistroContext,
+        ) -> Result<StepResult> {
+            let mut result = StepResult::new(self.num(), self.name());
+            result.fail("fake", "pass", "fail");
+            Ok(result)
+        }
+    }
+
+    /// `run --phase 6 --experimental --attach` with the default fail-fast.
+    #[test]
+    fn phase_6_attach_selection_reports_every_step_under_fail_fast() {
+        let steps: Vec<Box<dyn Step>> = steps_for_phase_experimental(6)
+            .into_iter()
+            .map(|step| -> Box<dyn Step> { Box::new(FailingStep(step)) })
+            .collect();
+        assert!(steps.len() > 1);
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let results = run_steps_until_failure(&steps, &mut null_executor(), &*ctx, true, false);
+        assert_eq!(results.len(), steps.len());
+        assert!(!step_passed(&results[0]));
+    }
+
+    #[test]
+    fn targeted_runs_skip_satisfied_steps_without_blocking_dependents() {
+        let steps: Vec<Box<dyn Step>> = vec![
+            Box::new(FakeStep {
+                num: 4,
+                passes: false,
+                depends_on: &[],
+                satisfied: true,
+            }),
+            Box::new(FakeStep {
+                num: 5,
+                passes: true,
+                depends_on: &[4],
+                satisfied: false,
+            }),
+        ];
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+
+        let results: Vec<StepResult> = run_steps(&steps, &mut null_executor(), &*ctx, true)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert!(results[0].passed);
+        assert!(matches!(
+            &results[0].checks[0].1,
+            CheckResult::Skip(reason) if reason == "already satisfied"
+        ));
+        assert!(results[1].passed && !results[1].has_skips);
+
+        // A full run executes the step regardless of the probe.
+        let full = run_steps(&steps, &mut null_executor(), &*ctx, false);
+        assert!(!full[0].as_ref().unwrap().passed);
+    }
+
+    /// Block device names (`sda2`, `vda`, `nvme0n1p1`) referenced as `/dev/...`.
+    fn disk_devices<'a>(commands: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut devices = Vec::new();
+        for command in commands {
+            for (index, prefix) in command.match_indices("/dev/") {
+                let rest = &command[index + prefix.len()..];
+                let name: String = rest
+                    .chars()
+                    .take_while(char::is_ascii_alphanumeric)
+                    .collect();
+                let is_disk = ["sd", "vd", "hd", "xvd", "nvme", "mmcblk"]
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix));
+                if is_disk && !devices.contains(&name) {
+                    devices.push(name);
+                }
+            }
+        }
+        devices
+    }
+
+    #[test]
+    fn install_recipe_and_disk_steps_agree_on_device_paths() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let mut executor = FakeExecutor::new().on(
+            "lsblk -d",
+            "NAME=\"sda\" SIZE=\"21474836480\" TYPE=\"disk\" RM=\"0\" RO=\"0\"\n",
+        );
+        for step in steps_for_phase(2).into_iter().chain(steps_for_phase(5)) {
+            // Only the commands matter; canned output fails later checks.
+            let _ = step.execute(&mut executor, &*ctx);
+        }
+        let step_devices = disk_devices(executor.commands.iter().map(String::as_str));
+        assert!(
+            step_devices.iter().any(|name| name.starts_with("sda")),
+            "phase 2/5 steps never touched the detected disk: {:?}",
+            executor.commands
+        );
+        let stray: Vec<&String> = step_devices
+            .iter()
+            .filter(|name| !name.starts_with("sda"))
+            .collect();
+        assert!(
+            stray.is_empty(),
+            "steps ignore the detected disk: {:?}",
+            stray
+        );
+
+        // A stand-in password keeps the host's variant contracts out of it.
+        let plan = ctx.install_plan_spec_with_password("test-password".to_string());
+        let recipe = ctx
+            .install_recipe_for(
+                &plan,
+                "/dev/sda",
+                recshuttle::InstallLayout::MutableSingleRoot,
+            )
+            .unwrap();
+        let recipe_devices = disk_devices(recipe.iter().map(|(_, cmd)| cmd.as_str()));
+        let stray: Vec<&String> = recipe_devices
+            .iter()
+            .filter(|name| !name.starts_with("sda"))
+            .collect();
+        assert!(
+            stray.is_empty(),
+            "install recipe hardcodes a different disk: {:?}",
+            stray
+        );
+    }
+
+    #[test]
+    fn only_post_reboot_steps_are_experimental() {
+        for step in all_steps_with_experimental() {
+            assert_eq!(
+                is_experimental(&*step),
+                step.phase() == 6,
+                "{}",
+                step.name()
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Phase 1: Boot verification steps.
 //!
-//! Steps 1-2: Verify UEFI mode, sync clock, identify target disk.
+//! Steps 1-2 and 27: Verify UEFI mode, check and correct clock skew, verify
+//! the live overlay root.
 //!
 //! # Anti-Cheat
 //!
@@ -14,7 +15,13 @@ use crate::distro::DistroContext;
 use crate::executor::Executor;
 use anyhow::Result;
 use leviso_cheat_guard::cheat_ensure;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Guest/host clock difference above which `SyncClock` corrects the guest.
+///
+/// Certificate validity windows tolerate a few minutes; anything beyond this
+/// is an RTC reset (common after OVMF NVRAM resets), not drift.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(300);
 
 /// Step 1: Verify UEFI boot mode
 pub struct VerifyUefi;
@@ -113,6 +120,12 @@ impl Step for SyncClock {
             CheckResult::pass(format!("year={}", year)),
         );
 
+        let host_now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        result.add_check("Clock skew vs host", check_clock_skew(executor, host_now)?);
+
         // Add a small delay to let any async output settle
         // This prevents cross-contamination with the next step
         let _ = executor.exec("sleep 0.5", Duration::from_secs(2))?;
@@ -121,3 +134,181 @@ impl Step for SyncClock {
         Ok(result)
     }
 }
+
+/// Step 27: Verify the live root is the expected overlay
+///
+/// Numbered after the existing steps so numbers in saved run reports keep
+/// their meaning; [`Step::phase`] is overridden to place it in Phase 1.
+pub struct VerifyLiveOverlay;
+
+impl Step for VerifyLiveOverlay {
+    fn num(&self) -> usize {
+        27
+    }
+    fn name(&self) -> &str {
+        "Verify Live Overlay"
+    }
+    fn ensures(&self) -> &str {
+        "Live ISO root is an overlay on the shipped rootfs, not an installed disk"
+    }
+    fn phase(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
+        let start = Instant::now();
+        let mut result = StepResult::new(self.num(), self.name());
+
+        let Some(expected) = ctx.live_overlay_lowerdir() else {
+            result.add_check(
+                "Live overlay root",
+                CheckResult::Skip(format!("{} live root is not an overlay", ctx.name())),
+            );
+            result.duration = start.elapsed();
+            return Ok(result);
+        };
+
+        let marker = executor.exec("test -f /live-boot-marker", Duration::from_secs(5))?;
+        cheat_ensure!(
+            marker.success(),
+            protects = "Install steps run inside the live ISO environment",
+            severity = "CRITICAL",
+            cheats = [
+                "Run install steps on an already-installed disk",
+                "Skip the live environment check"
+            ],
+            consequence = "Install is tested against a system that never came from the ISO",
+            "Live overlay marker missing: /live-boot-marker"
+        );
+        result.add_check(
+            "Live boot marker",
+            CheckResult::pass("/live-boot-marker exists"),
+        );
+
+        let root = executor.exec("findmnt -no FSTYPE,OPTIONS /", Duration::from_secs(5))?;
+        let lowerdir = root_overlay_lowerdir(&root.output);
+        cheat_ensure!(
+            lowerdir == Some(expected),
+            protects = "Live root is the ISO's overlay chain",
+            severity = "CRITICAL",
+            cheats = ["Accept any overlay mount", "Only check the marker file"],
+            consequence = "Live rootfs provenance unverified, install may copy from the wrong tree",
+            "Root mount is not an overlay with lowerdir={} (found: {})",
+            expected,
+            root.output.trim()
+        );
+        result.add_check(
+            "Overlay lowerdir chain",
+            CheckResult::pass(format!("lowerdir={}", expected)),
+        );
+
+        result.duration = start.elapsed();
+        Ok(result)
+    }
+}
+
+/// `lowerdir=` of the root mount in `findmnt -no FSTYPE,OPTIONS /` output,
+/// if it is an overlay.
+fn root_overlay_lowerdir(findmnt: &str) -> Option<&str> {
+    findmnt.lines().find_map(|line| {
+        let (fstype, options) = line.trim().split_once(char::is_whitespace)?;
+        if fstype != "overlay" {
+            return None;
+        }
+        options
+            .trim()
+            .split(',')
+            .find_map(|opt| opt.strip_prefix("lowerdir="))
+    })
+}
+
+/// Compare the guest clock with `host_now` (unix seconds) and correct it when
+/// the skew exceeds [`MAX_CLOCK_SKEW`].
+///
+/// A correction is reported as a Warning carrying the measured skew and the
+/// guest time after the fix, so the RTC problem stays visible in the results.
+fn check_clock_skew(executor: &mut dyn Executor, host_now: i64) -> Result<CheckResult> {
+    let guest = executor.exec("date -u +%s", Duration::from_secs(5))?;
+    let Some(guest_now) = parse_epoch(&guest.output) else {
+        return Ok(CheckResult::Warning(format!(
+            "could not read guest clock: {}",
+            guest.output.trim()
+        )));
+    };
+
+    let skew = guest_now - host_now;
+    if skew.unsigned_abs() <= MAX_CLOCK_SKEW.as_secs() {
+        return Ok(CheckResult::pass(format!("skew={}s", skew)));
+    }
+
+    // Set the system clock from the host, then persist it to the RTC if the
+    // guest has hwclock; a missing hwclock must not hide a successful `date`.
+    let fix = executor.exec(
+        &format!(
+            "date -u -s @{} >/dev/null && (hwclock --systohc --utc 2>/dev/null || true) && date -u '+%Y-%m-%dT%H:%M:%SZ'",
+            host_now
+        ),
+        Duration::from_secs(10),
+    )?;
+    if !fix.success() {
+        return Ok(CheckResult::Warning(format!(
+            "guest clock off by {}s; correction failed: {}",
+            skew,
+            fix.output.trim()
+        )));
+    }
+    Ok(CheckResult::Warning(format!(
+        "guest clock off by {}s; corrected to {}",
+        skew,
+        fix.output.trim()
+    )))
+}
+
+/// Last line of `output` that parses as unix seconds (serial output can carry
+/// stray console lines before the value).
+fn parse_epoch(output: &str) -> Option<i64> {
+    output
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{FakeExecutor, Reply};
+
+    #[test]
+    fn root_overlay_lowerdir_reads_the_root_mount() {
+        let live = "overlay rw,relatime,lowerdir=/live-overlay:/rootfs,upperdir=/run/upper,workdir=/run/work\n";
+        assert_eq!(root_overlay_lowerdir(live), Some("/live-overlay:/rootfs"));
+        assert_eq!(root_overlay_lowerdir("ext4 rw,relatime\n"), None);
+        assert_eq!(root_overlay_lowerdir(""), None);
+    }
+
+    fn executor(guest_now: &str) -> FakeExecutor {
+        FakeExecutor::new()
+            .on("date -u +%s", guest_now)
+            .otherwise(Reply::ok("2026-10-14T12:00:00Z"))
+    }
+
+    #[test]
+    fn small_skew_passes_without_correction() {
+        let mut exec = executor("1000030\n");
+        let check = check_clock_skew(&mut exec, 1_000_000).unwrap();
+        assert!(matches!(check, CheckResult::Pass { ref evidence, .. } if evidence == "skew=30s"));
+        assert_eq!(exec.commands.len(), 1);
+    }
+
+    #[test]
+    fn large_skew_is_corrected_and_warned() {
+        let mut exec = executor("noise\n1000\n");
+        let check = check_clock_skew(&mut exec, 1_000_000).unwrap();
+        let CheckResult::Warning(msg) = check else {
+            panic!("expected warning, got {:?}", check);
+        };
+        assert!(msg.contains("off by -999000s"), "{}", msg);
+        assert!(msg.contains("corrected to 2026-10-14T12:00:00Z"), "{}", msg);
+        assert!(exec.commands[1].starts_with("date -u -s @1000000"));
+    }
+}
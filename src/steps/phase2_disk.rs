@@ -10,10 +10,12 @@
 //! - Not waiting for kernel to create device nodes
 
 use super::{CheckResult, Step, StepResult};
-use crate::distro::DistroContext;
-use crate::executor::Executor;
-use anyhow::Result;
-use distro_spec::PartitionLayout;
+use crate::distro::{
+    partition_devices, partition_mounts, sfdisk_script, validate_partition_layout, DistroContext,
+    PartitionSpec,
+};
+use crate::executor::{ExecResult, Executor};
+use anyhow::{Context, Result};
 use leviso_cheat_guard::cheat_ensure;
 use std::time::{Duration, Instant};
 
@@ -39,13 +41,13 @@ impl Step for IdentifyDisk {
         // This ensures previous steps' async output is cleared
         let _ = executor.exec("true", Duration::from_secs(2))?;
 
-        // Check for /dev/vda (virtio disk)
-        // First, list all block devices for diagnostics
+        // List all block devices for diagnostics, then pick the install disk
         let lsblk_all = executor.exec("lsblk -dn -o NAME,TYPE,SIZE", Duration::from_secs(5))?;
+        let disk = executor.detect_install_disk();
 
         // CHEAT GUARD: Target disk MUST be detected
         cheat_ensure!(
-            lsblk_all.output.contains("vda"),
+            disk.is_ok(),
             protects = "Target disk is detected for installation",
             severity = "CRITICAL",
             cheats = [
@@ -54,19 +56,21 @@ impl Step for IdentifyDisk {
                 "Accept any output"
             ],
             consequence = "No disk to install to, all subsequent steps fail",
-            "Target disk /dev/vda not found. lsblk output: {}",
+            "Target disk not found. lsblk output: {}",
             lsblk_all.output.trim()
         );
+        let disk = disk?;
 
         // Extract disk size from lsblk output for evidence
+        let name = disk.trim_start_matches("/dev/");
         let disk_info = lsblk_all
             .output
             .lines()
-            .find(|l| l.contains("vda"))
-            .unwrap_or("vda found");
+            .find(|l| l.split_whitespace().next() == Some(name))
+            .unwrap_or("found");
         result.add_check(
             "Target disk found",
-            CheckResult::pass(format!("/dev/vda: {}", disk_info.trim())),
+            CheckResult::pass(format!("{}: {}", disk, disk_info.trim())),
         );
 
         result.duration = start.elapsed();
@@ -74,6 +78,61 @@ impl Step for IdentifyDisk {
     }
 }
 
+/// One block device row from `lsblk -P`: name, partition table type, fs type.
+struct BlockDevice {
+    name: String,
+    pttype: String,
+    fstype: String,
+}
+
+/// Read-only snapshot of `disk` and its partitions, for `already_satisfied`.
+fn probe_target_disk(executor: &mut dyn Executor, disk: &str) -> Result<Vec<BlockDevice>> {
+    let probe = executor.exec(
+        &format!("lsblk -nP -o NAME,PTTYPE,FSTYPE {}", disk),
+        Duration::from_secs(5),
+    )?;
+    if !probe.success() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_lsblk_pairs(&probe.output))
+}
+
+/// Parse `lsblk -P` output (`NAME="vda1" PTTYPE="gpt" FSTYPE="vfat"` lines).
+fn parse_lsblk_pairs(output: &str) -> Vec<BlockDevice> {
+    output
+        .lines()
+        .filter(|line| line.contains("NAME="))
+        .map(|line| {
+            let field = |key: &str| {
+                line.split_once(&format!("{}=\"", key))
+                    .and_then(|(_, rest)| rest.split_once('"'))
+                    .map(|(value, _)| value.to_string())
+                    .unwrap_or_default()
+            };
+            BlockDevice {
+                name: field("NAME"),
+                pttype: field("PTTYPE"),
+                fstype: field("FSTYPE"),
+            }
+        })
+        .collect()
+}
+
+/// Find the row for `device` (a `/dev/...` path) in `probe_target_disk` output.
+fn find_device<'a>(devices: &'a [BlockDevice], device: &str) -> Option<&'a BlockDevice> {
+    let name = device.trim_start_matches("/dev/");
+    devices.iter().find(|row| row.name == name)
+}
+
+/// The context's partition layout, rejected early if the later steps can't
+/// install and boot from it.
+fn target_layout(ctx: &dyn DistroContext) -> Result<Vec<PartitionSpec>> {
+    let layout = ctx.partition_layout();
+    validate_partition_layout(&layout)
+        .with_context(|| format!("invalid partition layout for {}", ctx.id()))?;
+    Ok(layout)
+}
+
 /// Step 4: Partition the disk with GPT layout
 ///
 /// # Cheat Vectors
@@ -84,6 +143,8 @@ impl Step for IdentifyDisk {
 /// # User Consequence if Cheated
 /// Installation fails at format step ("device not found") or boot fails
 /// because EFI partition is wrong size/type.
+///
+/// The partitions come from [`DistroContext::partition_layout`].
 pub struct PartitionDisk;
 
 impl Step for PartitionDisk {
@@ -94,21 +155,40 @@ impl Step for PartitionDisk {
         "Partition Disk (GPT)"
     }
     fn ensures(&self) -> &str {
-        "Disk has GPT layout with EFI and root partitions"
+        "Disk has GPT layout with every partition of the distro's layout"
     }
 
-    fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
+    /// Every partition of the layout already exists on a GPT disk.
+    fn already_satisfied(
+        &self,
+        executor: &mut dyn Executor,
+        ctx: &dyn DistroContext,
+    ) -> Result<bool> {
+        let layout = target_layout(ctx)?;
+        let disk = executor.detect_install_disk()?;
+        let devices = probe_target_disk(executor, &disk)?;
+        Ok(partition_devices(&disk, &layout).iter().all(|(part, _)| {
+            find_device(&devices, part).is_some_and(|device| device.pttype == "gpt")
+        }))
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
+        let layout = target_layout(ctx)?;
+        let disk = executor.detect_install_disk()?;
+        let parts: Vec<String> = partition_devices(&disk, &layout)
+            .into_iter()
+            .map(|(device, _)| device)
+            .collect();
+
         // Use sfdisk for non-interactive partitioning
-        // Layout from levitate-spec
-        let layout = PartitionLayout::default();
-        let partition_script = layout.to_sfdisk_script();
+        let partition_script = sfdisk_script(&layout);
 
         // Write partition table
         let sfdisk_result = executor.exec(
-            &format!("echo '{}' | sfdisk /dev/vda", partition_script),
+            &format!("echo '{}' | sfdisk {}", partition_script, disk),
             Duration::from_secs(30),
         )?;
 
@@ -135,36 +215,61 @@ impl Step for PartitionDisk {
         // udevadm settle waits for udev to process device events
         // Wait for udevd to be ready before settle (ping with retry)
         // udevd startup can take time on slow systems (TCG emulation without KVM)
-        let mut udev_ready = false;
-        for _ in 0..30 {
-            // 15 seconds total
-            let ping = executor.exec("udevadm control --ping", Duration::from_secs(2))?;
-            if ping.success() {
-                udev_ready = true;
-                break;
-            }
-            std::thread::sleep(Duration::from_millis(500));
-        }
-        if !udev_ready {
+        let ping = executor.exec_until(
+            "udevadm control --ping",
+            Duration::from_secs(2),
+            &ExecResult::success,
+            Duration::from_millis(500),
+            30, // ~15 seconds total
+        )?;
+        if !ping.success() {
             anyhow::bail!("udevd not responding after 15 seconds of retries. Check systemd-udevd.service status.");
         }
         executor.exec_ok("udevadm settle --timeout=10", Duration::from_secs(15))?;
 
+        // settle only covers events udev has already seen; poll for the nodes
+        // themselves rather than sleeping and hoping.
+        let node_test: Vec<String> = parts
+            .iter()
+            .map(|part| format!("test -b {}", part))
+            .collect();
+        let nodes = executor.exec_until(
+            &node_test.join(" && "),
+            Duration::from_secs(2),
+            &ExecResult::success,
+            Duration::from_millis(500),
+            20,
+        )?;
+        if !nodes.success() {
+            anyhow::bail!(
+                "partition device nodes {} did not appear after 10 seconds",
+                parts.join(", ")
+            );
+        }
+
         // CRITICAL: Verify partitions actually exist - don't trust sfdisk exit code alone
-        let verify = executor.exec("lsblk /dev/vda -o NAME,SIZE,TYPE", Duration::from_secs(5))?;
+        let verify = executor.exec(
+            &format!("lsblk {} -o NAME,SIZE,TYPE", disk),
+            Duration::from_secs(5),
+        )?;
+        let part_names: Vec<&str> = parts
+            .iter()
+            .map(|part| part.trim_start_matches("/dev/"))
+            .collect();
 
-        // CHEAT GUARD: Must verify BOTH partitions exist
+        // CHEAT GUARD: Must verify ALL partitions exist
         cheat_ensure!(
-            verify.output.contains("vda1") && verify.output.contains("vda2"),
-            protects = "Both partitions were actually created",
+            part_names.iter().all(|name| verify.output.contains(name)),
+            protects = "Every partition of the layout was actually created",
             severity = "CRITICAL",
             cheats = [
                 "Only check exit code",
-                "Check for vda1 OR vda2 instead of AND",
+                "Check for any one partition instead of all of them",
                 "Skip this verification entirely"
             ],
             consequence = "Missing partition causes format/mount failure, user cannot install",
-            "Partitions not found. Expected vda1 AND vda2, got:\n{}",
+            "Partitions not found. Expected {}, got:\n{}",
+            part_names.join(" AND "),
             verify.output
         );
 
@@ -172,7 +277,7 @@ impl Step for PartitionDisk {
         let part_lines: Vec<&str> = verify
             .output
             .lines()
-            .filter(|l| l.contains("vda1") || l.contains("vda2"))
+            .filter(|l| part_names.iter().any(|name| l.contains(name)))
             .collect();
         result.add_check(
             "Partitions created",
@@ -195,66 +300,149 @@ impl Step for FormatPartitions {
         "Format Partitions"
     }
     fn ensures(&self) -> &str {
-        "Partitions have proper filesystems (FAT32 for EFI, ext4 for root)"
+        "Every partition has its layout's filesystem (FAT32 for EFI, ext4 for root)"
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[4]
     }
 
-    fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
+    /// Every partition already carries the filesystem the layout asks for.
+    fn already_satisfied(
+        &self,
+        executor: &mut dyn Executor,
+        ctx: &dyn DistroContext,
+    ) -> Result<bool> {
+        let layout = target_layout(ctx)?;
+        let disk = executor.detect_install_disk()?;
+        let devices = probe_target_disk(executor, &disk)?;
+        Ok(partition_devices(&disk, &layout)
+            .iter()
+            .all(|(part, spec)| {
+                find_device(&devices, part)
+                    .is_some_and(|device| device.fstype == spec.filesystem.lsblk_fstype())
+            }))
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
-        // Format EFI partition as FAT32
-        let fat_result = executor.exec("mkfs.fat -F32 /dev/vda1", Duration::from_secs(30))?;
-
-        // CHEAT GUARD: EFI partition MUST be formatted as FAT32
-        cheat_ensure!(
-            fat_result.success(),
-            protects = "EFI partition has FAT32 filesystem for UEFI boot",
-            severity = "CRITICAL",
-            cheats = [
-                "Skip format step",
-                "Accept any exit code",
-                "Format wrong partition"
-            ],
-            consequence = "EFI partition unreadable by UEFI firmware, system won't boot",
-            "mkfs.fat failed (exit {}): {}",
-            fat_result.exit_code,
-            fat_result.output
-        );
-
-        result.add_check(
-            "EFI partition formatted",
-            CheckResult::pass("mkfs.fat -F32 /dev/vda1 exit 0"),
-        );
-
-        // Format root partition as ext4
-        let ext4_result = executor.exec("mkfs.ext4 -F /dev/vda2", Duration::from_secs(60))?;
-
-        // CHEAT GUARD: Root partition MUST be formatted as ext4
-        cheat_ensure!(
-            ext4_result.success(),
-            protects = "Root partition has ext4 filesystem for system files",
-            severity = "CRITICAL",
-            cheats = [
-                "Skip format step",
-                "Accept any exit code",
-                "Format wrong partition"
-            ],
-            consequence = "Root partition unreadable, system cannot mount rootfs, VFS panic",
-            "mkfs.ext4 failed (exit {}): {}",
-            ext4_result.exit_code,
-            ext4_result.output
-        );
-
-        result.add_check(
-            "Root partition formatted",
-            CheckResult::pass("mkfs.ext4 /dev/vda2 exit 0"),
-        );
+        let layout = target_layout(ctx)?;
+        let disk = executor.detect_install_disk()?;
+
+        for (part, spec) in partition_devices(&disk, &layout) {
+            let mkfs = spec.filesystem.mkfs_command(&part);
+            let mkfs_result = executor.exec(&mkfs, Duration::from_secs(60))?;
+
+            match spec.mountpoint.as_deref() {
+                Some("/boot") => {
+                    // CHEAT GUARD: EFI partition MUST be formatted as FAT32
+                    cheat_ensure!(
+                        mkfs_result.success(),
+                        protects = "EFI partition has FAT32 filesystem for UEFI boot",
+                        severity = "CRITICAL",
+                        cheats = [
+                            "Skip format step",
+                            "Accept any exit code",
+                            "Format wrong partition"
+                        ],
+                        consequence =
+                            "EFI partition unreadable by UEFI firmware, system won't boot",
+                        "{} failed (exit {}): {}",
+                        mkfs,
+                        mkfs_result.exit_code,
+                        mkfs_result.output
+                    );
+                    result.add_check(
+                        "EFI partition formatted",
+                        CheckResult::pass(format!("{} exit 0", mkfs)),
+                    );
+                }
+                Some("/") => {
+                    // CHEAT GUARD: Root partition MUST be formatted as ext4
+                    cheat_ensure!(
+                        mkfs_result.success(),
+                        protects = "Root partition has ext4 filesystem for system files",
+                        severity = "CRITICAL",
+                        cheats = [
+                            "Skip format step",
+                            "Accept any exit code",
+                            "Format wrong partition"
+                        ],
+                        consequence =
+                            "Root partition unreadable, system cannot mount rootfs, VFS panic",
+                        "{} failed (exit {}): {}",
+                        mkfs,
+                        mkfs_result.exit_code,
+                        mkfs_result.output
+                    );
+                    result.add_check(
+                        "Root partition formatted",
+                        CheckResult::pass(format!("{} exit 0", mkfs)),
+                    );
+                }
+                _ => {
+                    // CHEAT GUARD: Extra partitions (/home, swap) MUST be formatted too
+                    cheat_ensure!(
+                        mkfs_result.success(),
+                        protects = "Every partition of the layout has its filesystem",
+                        severity = "HIGH",
+                        cheats = [
+                            "Only format the EFI and root partitions",
+                            "Accept any exit code"
+                        ],
+                        consequence = "Partition cannot be mounted or swapped on, boot drops to emergency shell",
+                        "{} failed (exit {}): {}",
+                        mkfs,
+                        mkfs_result.exit_code,
+                        mkfs_result.output
+                    );
+                    result.add_check(
+                        &format!("Partition {} formatted", part),
+                        CheckResult::pass(format!("{} exit 0", mkfs)),
+                    );
+                }
+            }
+        }
 
         result.duration = start.elapsed();
         Ok(result)
     }
 }
 
+/// A mount from a `mount_in_order` list that failed.
+struct MountFailure {
+    /// Position of the failing entry in the mount list.
+    index: usize,
+    result: ExecResult,
+}
+
+/// Mount `(device, target)` pairs in order, creating each target first.
+///
+/// If a mount fails, the mounts completed before it are unmounted in reverse
+/// order so a retry starts clean instead of hitting "device busy", and the
+/// failing entry is returned.
+fn mount_in_order(
+    executor: &mut dyn Executor,
+    mounts: &[(&str, &str)],
+) -> Result<Option<MountFailure>> {
+    for (index, (device, target)) in mounts.iter().enumerate() {
+        executor.exec(&format!("mkdir -p {}", target), Duration::from_secs(5))?;
+        let result = executor.exec(
+            &format!("mount {} {}", device, target),
+            Duration::from_secs(10),
+        )?;
+        if !result.success() {
+            for (_, mounted) in mounts[..index].iter().rev() {
+                // Best effort: the failing mount is what gets reported.
+                let _ = executor.exec(&format!("umount {}", mounted), Duration::from_secs(10));
+            }
+            return Ok(Some(MountFailure { index, result }));
+        }
+    }
+    Ok(None)
+}
+
 /// Step 6: Mount partitions
 ///
 /// IMPORTANT: ESP is mounted at /mnt/boot, NOT /mnt/boot/efi
@@ -277,20 +465,60 @@ impl Step for MountPartitions {
         "Mount Partitions"
     }
     fn ensures(&self) -> &str {
-        "Root partition at /mnt, EFI partition at /mnt/boot"
+        "Root partition at /mnt, EFI partition at /mnt/boot, other mounts of the layout below them"
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[5]
     }
 
-    fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
+    /// Every entry in `partition_mounts` is already mounted where expected.
+    fn already_satisfied(
+        &self,
+        executor: &mut dyn Executor,
+        ctx: &dyn DistroContext,
+    ) -> Result<bool> {
+        let layout = target_layout(ctx)?;
+        let mounts = partition_mounts(&executor.detect_install_disk()?, &layout, "/mnt");
+        let probe = executor.exec("findmnt -nr -o SOURCE,TARGET", Duration::from_secs(5))?;
+        if !probe.success() {
+            return Ok(false);
+        }
+        Ok(mounts.iter().all(|(device, target)| {
+            probe.output.lines().any(|line| {
+                let mut fields = line.split_whitespace();
+                fields.next() == Some(device.as_str()) && fields.next() == Some(target.as_str())
+            })
+        }))
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
-        // Mount root partition
-        executor.exec("mkdir -p /mnt", Duration::from_secs(5))?;
-        let mount_root = executor.exec("mount /dev/vda2 /mnt", Duration::from_secs(10))?;
+        // Root first, then the ESP and any other mounts on top of it.
+        // NOTE: ESP is at /boot, NOT /boot/efi
+        // systemd-boot can ONLY read from FAT partitions, so kernel must be on ESP
+        let layout = target_layout(ctx)?;
+        let mounts = partition_mounts(&executor.detect_install_disk()?, &layout, "/mnt");
+        let device_at = |target: &str| {
+            mounts
+                .iter()
+                .find(|(_, t)| t == target)
+                .map_or("", |(device, _)| device.as_str())
+        };
+        let (root, efi) = (device_at("/mnt"), device_at("/mnt/boot"));
+        let mount_list: Vec<(&str, &str)> = mounts
+            .iter()
+            .map(|(device, target)| (device.as_str(), target.as_str()))
+            .collect();
+        let failure = mount_in_order(executor, &mount_list)?;
+        let failed_at = |target: &str| failure.as_ref().filter(|f| mount_list[f.index].1 == target);
+        let root_failure = failed_at("/mnt");
+        let boot_failure = failed_at("/mnt/boot");
 
         // CHEAT GUARD: Root partition MUST be mounted for installation
         cheat_ensure!(
-            mount_root.success(),
+            root_failure.is_none(),
             protects = "Root partition is mounted for file extraction",
             severity = "CRITICAL",
             cheats = [
@@ -299,22 +527,20 @@ impl Step for MountPartitions {
                 "Accept mount failure"
             ],
             consequence = "Files extracted to wrong location, installed system empty",
-            "Failed to mount /dev/vda2 to /mnt (exit {}): {}",
-            mount_root.exit_code,
-            mount_root.output
+            "Failed to mount {} to /mnt (exit {}): {}",
+            root,
+            root_failure.map_or(0, |f| f.result.exit_code),
+            root_failure.map_or("", |f| f.result.output.as_str())
         );
 
-        result.add_check("Root mounted", CheckResult::pass("/dev/vda2 → /mnt"));
-
-        // Create and mount EFI partition at /mnt/boot
-        // NOTE: ESP is at /boot, NOT /boot/efi
-        // systemd-boot can ONLY read from FAT partitions, so kernel must be on ESP
-        executor.exec("mkdir -p /mnt/boot", Duration::from_secs(5))?;
-        let mount_boot = executor.exec("mount /dev/vda1 /mnt/boot", Duration::from_secs(10))?;
+        result.add_check(
+            "Root mounted",
+            CheckResult::pass(format!("{} → /mnt", root)),
+        );
 
         // CHEAT GUARD: EFI partition MUST be mounted for bootloader
         cheat_ensure!(
-            mount_boot.success(),
+            boot_failure.is_none(),
             protects = "EFI partition is mounted at /boot for bootloader and kernel",
             severity = "CRITICAL",
             cheats = [
@@ -323,17 +549,52 @@ impl Step for MountPartitions {
                 "Accept mount failure"
             ],
             consequence = "Kernel not on FAT32, systemd-boot can't find it, system won't boot",
-            "Failed to mount /dev/vda1 to /mnt/boot (exit {}): {}",
-            mount_boot.exit_code,
-            mount_boot.output
+            "Failed to mount {} to /mnt/boot (exit {}): {}",
+            efi,
+            boot_failure.map_or(0, |f| f.result.exit_code),
+            boot_failure.map_or("", |f| f.result.output.as_str())
+        );
+
+        result.add_check(
+            "EFI mounted",
+            CheckResult::pass(format!("{} → /mnt/boot", efi)),
         );
 
-        result.add_check("EFI mounted", CheckResult::pass("/dev/vda1 → /mnt/boot"));
+        // CHEAT GUARD: Extra mounts (/home, ...) MUST land on the target disk
+        let other_failure = failure.as_ref().map(|f| mount_list[f.index]);
+        cheat_ensure!(
+            other_failure.is_none(),
+            protects = "Every mount of the partition layout is in place before extraction",
+            severity = "HIGH",
+            cheats = [
+                "Only mount root and EFI",
+                "Accept mount failure"
+            ],
+            consequence = "Files for that mount land on the root partition and are hidden once it mounts at boot",
+            "Failed to mount {} to {} (exit {}): {}",
+            other_failure.map_or("", |(device, _)| device),
+            other_failure.map_or("", |(_, target)| target),
+            failure.as_ref().map_or(0, |f| f.result.exit_code),
+            failure.as_ref().map_or("", |f| f.result.output.as_str())
+        );
+        for (device, target) in mount_list
+            .iter()
+            .filter(|(_, target)| !matches!(*target, "/mnt" | "/mnt/boot"))
+        {
+            result.add_check(
+                &format!("{} mounted", target),
+                CheckResult::pass(format!("{} → {}", device, target)),
+            );
+        }
 
         // Verify mounts - show actual mount output as evidence
-        let mounts = executor.exec("mount | grep /mnt", Duration::from_secs(5))?;
-        if mounts.output.contains("/mnt ") && mounts.output.contains("/mnt/boot ") {
-            let mount_lines: Vec<&str> = mounts.output.lines().take(2).collect();
+        let mount_output = executor.exec("mount | grep /mnt", Duration::from_secs(5))?;
+        if mount_list
+            .iter()
+            .all(|(_, target)| mount_output.output.contains(&format!("{} ", target)))
+        {
+            let mount_lines: Vec<&str> =
+                mount_output.output.lines().take(mount_list.len()).collect();
             result.add_check(
                 "Mounts verified",
                 CheckResult::pass(mount_lines.join(" | ")),
@@ -344,3 +605,92 @@ impl Step for MountPartitions {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distro::default_partition_layout;
+    use crate::test_support::{FakeExecutor, Reply};
+
+    #[test]
+    fn mount_in_order_unwinds_completed_mounts_on_failure() {
+        let mut executor =
+            FakeExecutor::new().reply("mount /dev/vdc1 ", Reply::Exit(32, String::new()));
+        let mounts = [
+            ("/dev/vda2", "/mnt"),
+            ("/dev/vda1", "/mnt/boot"),
+            ("/dev/vdc1", "/mnt/data"),
+        ];
+
+        let failure = mount_in_order(&mut executor, &mounts)
+            .unwrap()
+            .expect("third mount fails");
+
+        assert_eq!(failure.index, 2);
+        assert_eq!(failure.result.exit_code, 32);
+        let unmounts: Vec<&str> = executor
+            .commands
+            .iter()
+            .map(String::as_str)
+            .filter(|cmd| cmd.starts_with("umount "))
+            .collect();
+        assert_eq!(unmounts, ["umount /mnt/boot", "umount /mnt"]);
+    }
+
+    #[test]
+    fn mount_in_order_leaves_mounts_on_success() {
+        let mut executor = FakeExecutor::new();
+        let mounts = partition_mounts("/dev/vda", &default_partition_layout(), "/mnt");
+        let mounts: Vec<(&str, &str)> = mounts
+            .iter()
+            .map(|(d, t)| (d.as_str(), t.as_str()))
+            .collect();
+        assert!(mount_in_order(&mut executor, &mounts).unwrap().is_none());
+        assert!(!executor
+            .commands
+            .iter()
+            .any(|cmd| cmd.starts_with("umount")));
+    }
+
+    /// `detect_install_disk` listing for a single 20G virtio disk plus the ISO.
+    const VIRTIO_DISK: &str = "NAME=\"vda\" SIZE=\"21474836480\" TYPE=\"disk\" RM=\"0\" RO=\"0\"\n\
+                               NAME=\"sr0\" SIZE=\"1073741824\" TYPE=\"rom\" RM=\"1\" RO=\"0\"\n";
+
+    #[test]
+    fn disk_steps_detect_already_applied_state() {
+        let ctx = crate::distro::context_for_distro("levitate").unwrap();
+        let ctx = &*ctx;
+        let mut fresh = FakeExecutor::new()
+            .on("lsblk -d", VIRTIO_DISK)
+            .on("lsblk", "NAME=\"vda\" PTTYPE=\"\" FSTYPE=\"\"\n");
+        assert!(!PartitionDisk.already_satisfied(&mut fresh, ctx).unwrap());
+        assert!(!FormatPartitions.already_satisfied(&mut fresh, ctx).unwrap());
+        assert!(!MountPartitions.already_satisfied(&mut fresh, ctx).unwrap());
+
+        let mut installed = FakeExecutor::new()
+            .on("lsblk -d", VIRTIO_DISK)
+            .on(
+                "lsblk",
+                "NAME=\"vda\" PTTYPE=\"gpt\" FSTYPE=\"\"\n\
+                 NAME=\"vda1\" PTTYPE=\"gpt\" FSTYPE=\"vfat\"\n\
+                 NAME=\"vda2\" PTTYPE=\"gpt\" FSTYPE=\"ext4\"\n",
+            )
+            .on(
+                "findmnt",
+                "/dev/sr0 /media\n/dev/vda2 /mnt\n/dev/vda1 /mnt/boot\n",
+            );
+        assert!(PartitionDisk
+            .already_satisfied(&mut installed, ctx)
+            .unwrap());
+        assert!(FormatPartitions
+            .already_satisfied(&mut installed, ctx)
+            .unwrap());
+        assert!(MountPartitions
+            .already_satisfied(&mut installed, ctx)
+            .unwrap());
+        assert!(installed
+            .commands
+            .iter()
+            .all(|cmd| cmd.starts_with("lsblk") || cmd.starts_with("findmnt")));
+    }
+}
@@ -1,6 +1,7 @@
 //! Phase 3: Base system installation steps.
 //!
-//! Steps 7-10: Mount install media, run recstrap, generate fstab, verify chroot.
+//! Steps 7-10 and 28: Mount install media, run recstrap, generate fstab,
+//! verify chroot, check the extracted rootfs against fsdbg's checklist.
 //!
 //! Uses the LevitateOS installation tools:
 //! - recstrap (like pacstrap) - extracts rootfs to target
@@ -17,11 +18,23 @@
 use super::{CheckResult, Step, StepResult};
 use crate::distro::DistroContext;
 use crate::executor::Executor;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use distro_spec::levitate::{ROOTFS_CDROM_PATH, ROOTFS_NAME};
+use fsdbg::cpio::CpioReader;
 use leviso_cheat_guard::cheat_ensure;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+/// How long recstrap may go without output before extraction counts as hung.
+const RECSTRAP_STALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// recstrap output that means extraction failed, whatever the exit code.
+const RECSTRAP_ERROR_PATTERNS: &[&str] = &["No space left on device", "Input/output error"];
+
 /// Step 7: Mount installation media (CDROM)
 pub struct MountInstallMedia;
 
@@ -113,6 +126,9 @@ impl Step for ExtractRootfs {
     fn ensures(&self) -> &str {
         "Base system is extracted with all essential directories present"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[6, 7]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -142,10 +158,12 @@ impl Step for ExtractRootfs {
 
         // Run recstrap to extract base system
         // recstrap handles rootfs location automatically (/run/live-rootfs.erofs)
-        // Use --force because the freshly formatted ext4 contains lost+found
-        let extract = executor.exec(
+        // Use --force because the freshly formatted ext4 contains lost+found.
+        // Extraction time scales with the image, so fail on silence, not a deadline.
+        let extract = executor.exec_long(
             "recstrap --force /mnt",
-            Duration::from_secs(300), // 5 minutes for extraction
+            RECSTRAP_STALL_TIMEOUT,
+            RECSTRAP_ERROR_PATTERNS,
         )?;
 
         // CHEAT GUARD: recstrap MUST succeed
@@ -159,8 +177,8 @@ impl Step for ExtractRootfs {
                 "Ignore extraction errors"
             ],
             consequence = "Empty /mnt, no system installed, boot fails",
-            "recstrap failed (exit {}): {}",
-            extract.exit_code,
+            "recstrap failed ({}): {}",
+            extract.outcome(),
             extract.output
         );
 
@@ -211,6 +229,9 @@ impl Step for GenerateFstab {
     fn ensures(&self) -> &str {
         "System has valid /etc/fstab with correct UUIDs for automatic mounting"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[8]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -285,13 +306,31 @@ impl Step for GenerateFstab {
             .lines()
             .find(|l| l.contains("UUID="))
             .unwrap_or("UUID= found");
-        result.add_check("fstab contains UUIDs", CheckResult::pass(uuid_line.trim()));
+        let check = match root_uuid(&verify.output) {
+            Some(uuid) => {
+                CheckResult::pass_with_data(uuid_line.trim(), json!({ "root_uuid": uuid }))
+            }
+            None => CheckResult::pass(uuid_line.trim()),
+        };
+        result.add_check("fstab contains UUIDs", check);
 
         result.duration = start.elapsed();
         Ok(result)
     }
 }
 
+/// UUID of the `/` entry in fstab `content`, if it is mounted by UUID.
+fn root_uuid(content: &str) -> Option<&str> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let uuid = fields.next()?.strip_prefix("UUID=")?;
+            (fields.next()? == "/").then_some(uuid)
+        })
+}
+
 /// Step 10: Verify chroot environment works
 ///
 /// recchroot is like arch-chroot - handles bind mounts automatically.
@@ -308,6 +347,9 @@ impl Step for VerifyChroot {
     fn ensures(&self) -> &str {
         "recchroot can execute commands in the installed system"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[8]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -362,3 +404,394 @@ impl Step for VerifyChroot {
         Ok(result)
     }
 }
+
+/// Where step 28 bind-mounts the extracted root read-only.
+const ROOTFS_VIEW_DIR: &str = "/run/install-tests-rootfs";
+
+/// Distros whose rootfs fsdbg's rootfs checklist describes; preflight gates
+/// the install initramfs checklist the same way.
+const ROOTFS_CHECKLIST_DISTROS: &[&str] = &["levitate"];
+
+/// Lists a tree as `M <hex st_mode> <size> <path>` rows, then its symlinks
+/// as `L <path>\t<target>` rows. Only `find -exec … {} +`, `stat -c` and
+/// `readlink`, so busybox runs it as well as coreutils/findutils.
+const LISTING_CMD: &str = "find {root} -xdev -exec stat -c 'M %f %s %n' {} + && \
+     find {root} -xdev -type l -exec sh -c 'for l; do printf \"L %s\\t%s\\n\" \"$l\" \"$(readlink \"$l\")\"; done' sh {} +";
+
+/// Step 28: Check the installed rootfs against fsdbg's rootfs checklist
+///
+/// No other check runs the rootfs checklist. After extraction this lists
+/// the root partition (mounted at /mnt) through a read-only bind mount,
+/// plus the ESP at /mnt/boot, rebuilds the listing on the host as a
+/// metadata-only cpio (file contents are holes) and runs
+/// `fsdbg::checklist::rootfs::verify` on it, so a binary, library, unit or
+/// boot file that never reached the disk fails here. The checklist
+/// describes LevitateOS, so other distros skip the step.
+///
+/// Numbered after the existing steps so numbers in saved run reports keep
+/// their meaning; [`Step::phase`] is overridden to place it in Phase 3.
+pub struct VerifyInstalledRootfs;
+
+impl Step for VerifyInstalledRootfs {
+    fn num(&self) -> usize {
+        28
+    }
+    fn name(&self) -> &str {
+        "Verify Installed Rootfs"
+    }
+    fn ensures(&self) -> &str {
+        "Installed root partition has everything the ISO's rootfs checklist requires"
+    }
+    fn phase(&self) -> usize {
+        3
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[8]
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
+        let start = Instant::now();
+        let mut result = StepResult::new(self.num(), self.name());
+        if !ROOTFS_CHECKLIST_DISTROS.contains(&ctx.id()) {
+            result.add_check(
+                "Rootfs checklist",
+                CheckResult::Skip(format!("fsdbg has no rootfs checklist for {}", ctx.name())),
+            );
+            return Ok(result);
+        }
+
+        // A plain bind does not carry submounts; the ESP at /mnt/boot is
+        // listed from its own mount below.
+        let mount = executor.exec(
+            &format!(
+                "mkdir -p {dir} && mount --bind /mnt {dir} && mount -o remount,bind,ro {dir}",
+                dir = ROOTFS_VIEW_DIR
+            ),
+            Duration::from_secs(10),
+        )?;
+        cheat_ensure!(
+            mount.success(),
+            protects = "Rootfs checklist runs against the installed disk, read-only",
+            severity = "HIGH",
+            cheats = [
+                "Check the live ISO's rootfs instead",
+                "Skip the checklist when the mount fails"
+            ],
+            consequence =
+                "Files missing from the installed disk go unnoticed until the user needs them",
+            "Read-only bind mount of /mnt at {} failed (exit {}): {}",
+            ROOTFS_VIEW_DIR,
+            mount.exit_code,
+            mount.output.trim()
+        );
+
+        let listing_cmd = format!(
+            "cd {} && {} && if mountpoint -q /mnt/boot; then cd /mnt && {}; fi",
+            ROOTFS_VIEW_DIR,
+            LISTING_CMD.replace("{root}", "."),
+            LISTING_CMD.replace("{root}", "./boot")
+        );
+        let listing = executor.exec_long(&listing_cmd, Duration::from_secs(60), &[]);
+        // A leftover view keeps the root device mounted past step 18's
+        // unmount, and step 30's fsck would refuse it.
+        let unmount = executor.exec(
+            &format!("umount {dir} && rmdir {dir}", dir = ROOTFS_VIEW_DIR),
+            Duration::from_secs(10),
+        )?;
+        if !unmount.success() {
+            result.fail(
+                "Read-only view unmounted",
+                format!("umount {} exit 0", ROOTFS_VIEW_DIR),
+                format!("exit {}: {}", unmount.exit_code, unmount.output.trim()),
+            );
+        }
+        let listing = listing?;
+        let entries = parse_rootfs_listing(&listing.output);
+        if !listing.success() || entries.is_empty() {
+            result.add_check(
+                "Installed rootfs listed",
+                CheckResult::Fail {
+                    expected: "file listing of the installed root".to_string(),
+                    actual: format!("exit {}: {}", listing.exit_code, listing.output.trim()),
+                },
+            );
+            result.duration = start.elapsed();
+            return Ok(result);
+        }
+        result.add_check(
+            "Installed rootfs listed",
+            CheckResult::pass(format!("{} entries", entries.len())),
+        );
+
+        let archive = std::env::temp_dir().join(format!(
+            "install-tests-installed-rootfs-{}.cpio",
+            std::process::id()
+        ));
+        write_listing_cpio(&entries, &archive)?;
+        let report = CpioReader::open(&archive)
+            .with_context(|| format!("opening rebuilt rootfs listing {}", archive.display()))
+            .map(|reader| fsdbg::checklist::rootfs::verify(&reader));
+        let _ = std::fs::remove_file(&archive);
+        let report = report?;
+
+        for item in report.results.iter().filter(|item| !item.passed) {
+            result.add_check(
+                &item.item,
+                CheckResult::Fail {
+                    expected: "present on the installed disk".to_string(),
+                    actual: item.message.as_deref().unwrap_or("Missing").to_string(),
+                },
+            );
+        }
+        if report.is_success() {
+            result.add_check(
+                "Rootfs checklist",
+                CheckResult::pass(format!(
+                    "{}/{} items on the installed disk",
+                    report.passed(),
+                    report.total()
+                )),
+            );
+        }
+
+        result.duration = start.elapsed();
+        Ok(result)
+    }
+}
+
+/// One entry of the step 28 listing of the installed root.
+#[derive(Debug, PartialEq)]
+struct ListedEntry {
+    path: String,
+    /// Full `st_mode`: file type bits plus permissions.
+    mode: u32,
+    size: u64,
+    link_target: String,
+}
+
+/// Entries of the step 28 listing ([`LISTING_CMD`] output), in listing
+/// order. The root itself, sockets, devices and unparseable lines (prompt
+/// noise) are dropped; a path listed twice (the ESP mountpoint) keeps its
+/// last metadata.
+fn parse_rootfs_listing(output: &str) -> Vec<ListedEntry> {
+    let mut entries: Vec<ListedEntry> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut links = Vec::new();
+    for line in output.lines().map(|line| line.trim_end_matches('\r')) {
+        if let Some(link) = line.strip_prefix("L ") {
+            if let Some((path, target)) = link.split_once('\t') {
+                links.push((relative_path(path), target));
+            }
+            continue;
+        }
+        let Some(row) = line.strip_prefix("M ") else {
+            continue;
+        };
+        let mut fields = row.splitn(3, ' ');
+        let (Some(mode), Some(size), Some(path)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let (Ok(mode), Ok(size)) = (u32::from_str_radix(mode, 16), size.parse::<u64>()) else {
+            continue;
+        };
+        let path = relative_path(path);
+        let file_type = mode & 0o170000;
+        if path.is_empty() || !matches!(file_type, 0o100000 | 0o040000 | 0o120000) {
+            continue;
+        }
+        let entry = ListedEntry {
+            path: path.to_string(),
+            mode,
+            size: if file_type == 0o100000 { size } else { 0 },
+            link_target: String::new(),
+        };
+        match index.get(path) {
+            Some(&seen) => entries[seen] = entry,
+            None => {
+                index.insert(path.to_string(), entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+    for (path, target) in links {
+        if let Some(&at) = index.get(path) {
+            entries[at].link_target = target.to_string();
+        }
+    }
+    entries
+}
+
+/// `./usr/bin` → `usr/bin`; the root `.` becomes empty.
+fn relative_path(path: &str) -> &str {
+    path.strip_prefix("./")
+        .unwrap_or(path.trim_start_matches('.'))
+}
+
+/// Write `entries` as a newc cpio that fsdbg can read.
+///
+/// Symlink targets are stored as their data, as in a real archive; regular
+/// file data is left as a sparse hole of the listed size, so the archive
+/// takes almost no disk space but reports true sizes.
+fn write_listing_cpio(entries: &[ListedEntry], path: &Path) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut offset: u64 = 0;
+    let trailer = ListedEntry {
+        path: "TRAILER!!!".to_string(),
+        mode: 0,
+        size: 0,
+        link_target: String::new(),
+    };
+    for (ino, entry) in entries.iter().chain([&trailer]).enumerate() {
+        let is_link = entry.mode & 0o170000 == 0o120000;
+        let data_size = if is_link {
+            entry.link_target.len() as u64
+        } else {
+            entry.size
+        };
+        let header = format!(
+            "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+            ino,
+            entry.mode,
+            0,
+            0,
+            1,
+            0,
+            data_size,
+            0,
+            0,
+            0,
+            0,
+            entry.path.len() + 1,
+            0
+        );
+        out.write_all(header.as_bytes())?;
+        out.write_all(entry.path.as_bytes())?;
+        out.write_all(&[0])?;
+        offset += header.len() as u64 + entry.path.len() as u64 + 1;
+        pad_to_4(&mut out, &mut offset)?;
+        if is_link {
+            out.write_all(entry.link_target.as_bytes())?;
+        } else {
+            out.seek(SeekFrom::Current(data_size as i64))?;
+        }
+        offset += data_size;
+        pad_to_4(&mut out, &mut offset)?;
+    }
+    // Extend over a hole left by a trailing seek.
+    out.into_inner()
+        .map_err(|err| err.into_error())?
+        .set_len(offset)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// Zero-fill `out` up to the next 4-byte boundary, as newc requires.
+fn pad_to_4(out: &mut impl Write, offset: &mut u64) -> std::io::Result<()> {
+    let padding = (4 - *offset % 4) % 4;
+    out.write_all(&[0; 3][..padding as usize])?;
+    *offset += padding;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rootfs_listing_rebuilds_as_newc_cpio() {
+        let listing = "M 41ed 4096 .\r\n\
+                       M 41ed 4096 ./usr\r\n\
+                       M 81ed 1234567 ./usr/bin/bash\r\n\
+                       M a1ff 7 ./bin\r\n\
+                       M c1ed 0 ./run/socket\r\n\
+                       M 41c0 4096 ./boot\r\n\
+                       L ./bin\tusr/bin\r\n\
+                       M 41ed 4096 ./boot\r\n\
+                       M 81ed 9000 ./boot/vmlinuz\r\n\
+                       [root@levitate ~]# \r\n";
+        let entries = parse_rootfs_listing(listing);
+        assert_eq!(
+            entries,
+            [
+                ListedEntry {
+                    path: "usr".to_string(),
+                    mode: 0o040755,
+                    size: 0,
+                    link_target: String::new(),
+                },
+                ListedEntry {
+                    path: "usr/bin/bash".to_string(),
+                    mode: 0o100755,
+                    size: 1234567,
+                    link_target: String::new(),
+                },
+                ListedEntry {
+                    path: "bin".to_string(),
+                    mode: 0o120777,
+                    size: 0,
+                    link_target: "usr/bin".to_string(),
+                },
+                ListedEntry {
+                    path: "boot".to_string(),
+                    mode: 0o040755,
+                    size: 0,
+                    link_target: String::new(),
+                },
+                ListedEntry {
+                    path: "boot/vmlinuz".to_string(),
+                    mode: 0o100755,
+                    size: 9000,
+                    link_target: String::new(),
+                },
+            ]
+        );
+
+        let archive =
+            std::env::temp_dir().join(format!("install-tests-listing-{}.cpio", std::process::id()));
+        write_listing_cpio(&entries, &archive).unwrap();
+        let bytes = std::fs::read(&archive).unwrap();
+        std::fs::remove_file(&archive).unwrap();
+
+        // Walk the archive back: names, modes and sizes survive.
+        let mut offset = 0;
+        let mut seen = Vec::new();
+        loop {
+            let field = |index: usize| {
+                let start = offset + 6 + index * 8;
+                let hex = std::str::from_utf8(&bytes[start..start + 8]).unwrap();
+                usize::from_str_radix(hex, 16).unwrap()
+            };
+            assert_eq!(&bytes[offset..offset + 6], b"070701");
+            let (mode, size, namesize) = (field(1), field(6), field(11));
+            let name_start = offset + 110;
+            let name = std::str::from_utf8(&bytes[name_start..name_start + namesize - 1]).unwrap();
+            let data_start = (name_start + namesize + 3) & !3;
+            if name == "TRAILER!!!" {
+                break;
+            }
+            seen.push((name.to_string(), mode as u32, size));
+            if mode as u32 & 0o170000 == 0o120000 {
+                assert_eq!(&bytes[data_start..data_start + size], b"usr/bin");
+            }
+            offset = (data_start + size + 3) & !3;
+        }
+        assert_eq!(
+            seen,
+            [
+                ("usr".to_string(), 0o040755, 0),
+                ("usr/bin/bash".to_string(), 0o100755, 1234567),
+                ("bin".to_string(), 0o120777, 7),
+                ("boot".to_string(), 0o040755, 0),
+                ("boot/vmlinuz".to_string(), 0o100755, 9000),
+            ]
+        );
+        assert_eq!(bytes.len() % 4, 0);
+    }
+
+    #[test]
+    fn rootfs_listing_avoids_gnu_only_find() {
+        assert!(!LISTING_CMD.contains("-printf"));
+        assert!(LISTING_CMD.contains("-exec stat -c 'M %f %s %n' {} +"));
+    }
+}
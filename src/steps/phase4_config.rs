@@ -8,19 +8,12 @@
 //! User creation MUST include password - empty passwords = security hole.
 
 use super::{CheckResult, Step, StepResult};
-use crate::distro::{load_installed_scenario_facts, DistroContext};
-use crate::executor::Executor;
+use crate::distro::DistroContext;
+use crate::executor::{shell_quote, Executor};
 use anyhow::Result;
 use leviso_cheat_guard::cheat_ensure;
 use std::time::{Duration, Instant};
 
-/// Escape a string for use in single-quoted shell context.
-/// Handles single quotes by ending the quote, adding escaped quote, and resuming.
-/// Example: "it's" -> "it'\''s" (in shell: 'it'\''s' = it's)
-fn shell_escape(s: &str) -> String {
-    s.replace('\'', "'\\''")
-}
-
 /// Escape a string for use in sed replacement pattern.
 /// SHA-512 hashes contain base64 chars (A-Za-z0-9./) plus $ delimiters.
 /// We use | as the sed delimiter, so we only need to escape $ and backslashes.
@@ -43,6 +36,9 @@ impl Step for SetTimezone {
     fn ensures(&self) -> &str {
         "System timezone is configured for correct local time display"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -101,6 +97,9 @@ impl Step for ConfigureLocale {
     fn ensures(&self) -> &str {
         "System locale is set for proper character encoding and language"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -110,9 +109,10 @@ impl Step for ConfigureLocale {
         let locale = "en_US.UTF-8";
 
         // OPTIMIZATION: Check if locale is already set correctly (rootfs default)
-        let check = executor.exec("cat /mnt/etc/locale.conf", Duration::from_secs(5))?;
+        let expected = format!("LANG={}\n", locale);
+        let current = executor.read_file("/mnt/etc/locale.conf").ok();
 
-        if check.success() && check.output.contains(locale) {
+        if current.as_deref() == Some(expected.as_str()) {
             // Already correct, skip the write
             result.add_check(
                 "locale.conf already correct (skipped)",
@@ -120,12 +120,12 @@ impl Step for ConfigureLocale {
             );
         } else {
             // Write locale.conf
-            executor.write_file("/mnt/etc/locale.conf", &format!("LANG={}\n", locale))?;
+            executor.write_file("/mnt/etc/locale.conf", &expected)?;
 
             // Verify
-            let verify = executor.exec("cat /mnt/etc/locale.conf", Duration::from_secs(5))?;
+            let verify = executor.read_file("/mnt/etc/locale.conf")?;
 
-            if verify.output.contains(locale) {
+            if verify == expected {
                 result.add_check(
                     "locale.conf written",
                     CheckResult::pass(format!("LANG={}", locale)),
@@ -135,7 +135,7 @@ impl Step for ConfigureLocale {
                     "locale.conf written",
                     CheckResult::Fail {
                         expected: format!("LANG={}", locale),
-                        actual: verify.output.clone(),
+                        actual: verify,
                     },
                 );
             }
@@ -159,6 +159,9 @@ impl Step for SetHostname {
     fn ensures(&self) -> &str {
         "System has a hostname configured for network identification"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -180,29 +183,23 @@ impl Step for SetHostname {
         );
         executor.write_file("/mnt/etc/hosts", &hosts)?;
 
-        // Verify (use contains since output may include command echo)
-        let verify_hostname = executor.exec("cat /mnt/etc/hostname", Duration::from_secs(5))?;
-        let verify_hosts = executor.exec("cat /mnt/etc/hosts", Duration::from_secs(5))?;
-
-        // Check if hostname appears as a separate line in output
-        let hostname_found = verify_hostname
-            .output
-            .lines()
-            .any(|line| line.trim() == hostname);
+        // Verify
+        let verify_hostname = executor.read_file("/mnt/etc/hostname")?;
+        let verify_hosts = executor.read_file("/mnt/etc/hosts")?;
 
-        if hostname_found {
+        if verify_hostname.trim_end() == hostname {
             result.add_check("Hostname set", CheckResult::pass(hostname));
         } else {
             result.add_check(
                 "Hostname set",
                 CheckResult::Fail {
                     expected: hostname.to_string(),
-                    actual: verify_hostname.output.trim().to_string(),
+                    actual: verify_hostname.trim().to_string(),
                 },
             );
         }
 
-        if verify_hosts.output.contains(hostname) {
+        if verify_hosts.contains(hostname) {
             result.add_check(
                 "Hosts file updated",
                 CheckResult::pass(format!("127.0.1.1 → {}", hostname)),
@@ -227,6 +224,9 @@ impl Step for SetRootPassword {
     fn ensures(&self) -> &str {
         "Root account has a password for emergency system recovery"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -238,24 +238,14 @@ impl Step for SetRootPassword {
         // is now codified in the build system.
         //
         // See: https://github.com/systemd/systemd/issues/9197
-        let facts = load_installed_scenario_facts(ctx.id())?;
-        let password = facts
-            .automated_login
-            .default_password
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_password for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let password = ctx.default_password()?;
 
         // Generate SHA-512 password hash using openssl (available on all systems)
         // The -6 option uses SHA-512 (same as yescrypt in terms of security)
         // Use -stdin to avoid shell escaping issues with special characters in password
         let hash_cmd = format!(
-            "printf '%s' '{}' | openssl passwd -6 -stdin",
-            shell_escape(password)
+            "printf '%s' {} | openssl passwd -6 -stdin",
+            shell_quote(&password)
         );
         let hash_result = executor.exec(&hash_cmd, Duration::from_secs(10))?;
 
@@ -350,23 +340,16 @@ impl Step for CreateUser {
     fn ensures(&self) -> &str {
         "Primary user account exists with proper groups for daily use"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
         // Get user details from distro context
-        let facts = load_installed_scenario_facts(ctx.id())?;
-        let username = facts
-            .automated_login
-            .default_username
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_username for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let username = ctx.default_username()?;
         let user_shell = ctx.chroot_shell(); // Use chroot shell as default user shell
 
         // Default groups for sudo/admin access
@@ -422,21 +405,12 @@ impl Step for CreateUser {
 
         // Set user password using direct shadow manipulation (same workaround as root password)
         // chpasswd via PAM silently fails in chroot environments
-        let password = facts
-            .automated_login
-            .default_password
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_password for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let password = ctx.default_password()?;
 
         // Generate SHA-512 password hash using stdin to avoid shell escaping issues
         let hash_cmd = format!(
-            "printf '%s' '{}' | openssl passwd -6 -stdin",
-            shell_escape(password)
+            "printf '%s' {} | openssl passwd -6 -stdin",
+            shell_quote(&password)
         );
         let hash_result = executor.exec(&hash_cmd, Duration::from_secs(10))?;
 
@@ -490,7 +464,7 @@ impl Step for CreateUser {
         let verify =
             executor.exec_chroot("/mnt", &format!("id {}", username), Duration::from_secs(5))?;
 
-        if verify.success() && verify.output.contains(username) {
+        if verify.success() && verify.output.contains(username.as_str()) {
             // Show actual id output as evidence
             result.add_check("User verified", CheckResult::pass(verify.output.trim()));
         }
@@ -1,6 +1,7 @@
 //! Phase 5: Bootloader installation steps.
 //!
-//! Steps 16-18: Copy/install initramfs, install bootloader, enable services.
+//! Steps 16-18 and 30: Copy/install initramfs, install bootloader, enable services,
+//! check the unmounted filesystems.
 //!
 //! # Cheat Prevention
 //!
@@ -8,14 +9,16 @@
 //! - initramfs MUST be copied from ISO (no initramfs = kernel panic)
 //! - boot entry MUST have correct root UUID (wrong UUID = VFS panic)
 //! - Essential services MUST be enabled (no getty = no login prompt)
+//! - Installed filesystems MUST be clean before the first boot
 
-use super::{CheckResult, Step, StepResult};
-use crate::distro::DistroContext;
+use super::{util, CheckResult, Step, StepResult};
+use crate::distro::{device_mounted_at, partition_devices, DistroContext};
 use crate::executor::Executor;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use distro_contract::load_variant_contract_bundle_for_distro_from;
 use distro_spec::shared::boot::{BootEntry, LoaderConfig};
 use leviso_cheat_guard::cheat_ensure;
+use serde_json::json;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
@@ -35,6 +38,9 @@ impl Step for GenerateInitramfs {
     fn ensures(&self) -> &str {
         "Initramfs exists at /boot/initramfs.img with drivers for installed hardware"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let step_start = Instant::now();
@@ -86,9 +92,12 @@ impl Step for GenerateInitramfs {
 
         // SKEPTIC-PROOF: Show actual size, not just "exists"
         if kernel_bytes > 1_000_000 {
-            result.pass(
+            result.add_check(
                 "kernel on ESP",
-                format!("{:.1}MB at /mnt/boot/vmlinuz", kernel_mb),
+                CheckResult::pass_with_data(
+                    format!("{:.1}MB at /mnt/boot/vmlinuz", kernel_mb),
+                    json!({ "path": "/mnt/boot/vmlinuz", "size_bytes": kernel_bytes }),
+                ),
             );
         } else {
             result.fail(
@@ -142,9 +151,12 @@ impl Step for GenerateInitramfs {
 
         // SKEPTIC-PROOF: An initramfs under 10MB is suspiciously small
         if initramfs_bytes > 10_000_000 {
-            result.pass(
+            result.add_check(
                 "initramfs on ESP",
-                format!("{:.1}MB at /mnt/boot/initramfs.img", initramfs_mb),
+                CheckResult::pass_with_data(
+                    format!("{:.1}MB at /mnt/boot/initramfs.img", initramfs_mb),
+                    json!({ "path": "/mnt/boot/initramfs.img", "size_bytes": initramfs_bytes }),
+                ),
             );
         } else {
             result.fail(
@@ -188,10 +200,14 @@ impl Step for InstallBootloader {
     fn ensures(&self) -> &str {
         "System is bootable via systemd-boot with correct kernel and root"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
+        let disk = executor.detect_install_disk()?;
 
         // Check if systemd-boot EFI files exist in the tarball
         let efi_check = executor.exec_chroot(
@@ -256,7 +272,7 @@ impl Step for InstallBootloader {
             )?;
             let efi_label = ctx.efi_entry_label();
             let efi_entry = executor.exec(
-                &format!("efibootmgr --create --disk /dev/vda --part 1 --label '{}' --loader '\\EFI\\systemd\\systemd-bootx64.efi' 2>&1", efi_label),
+                &format!("efibootmgr --create --disk {} --part 1 --label '{}' --loader '\\EFI\\systemd\\systemd-bootx64.efi' 2>&1", disk, efi_label),
                 Duration::from_secs(10),
             )?;
 
@@ -282,10 +298,11 @@ impl Step for InstallBootloader {
             );
         }
 
-        // Get root partition UUID for boot entry
-        let uuid_result =
-            executor.exec("blkid -s UUID -o value /dev/vda2", Duration::from_secs(5))?;
-        let root_uuid = uuid_result.output.trim();
+        // Get root partition UUID for boot entry (validated: empty = broken entry)
+        let root_device = device_mounted_at(&disk, &ctx.partition_layout(), "/")
+            .context("partition layout has no root partition")?;
+        let root_uuid = util::root_uuid(executor, &root_device)?;
+        let root_uuid = root_uuid.as_str();
 
         // Create loader.conf (goes in ESP at /boot)
         let loader_config = LoaderConfig::with_defaults(ctx.id())
@@ -385,6 +402,9 @@ impl Step for EnableServices {
     fn ensures(&self) -> &str {
         "Essential services (networkd, sshd, getty) start automatically on boot"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[10]
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -534,13 +554,13 @@ impl Step for EnableServices {
         // This enables ___SHELL_READY___ markers after reboot
         // Without this, the installed system won't have the markers that install-tests requires
         let test_script = ctx.test_instrumentation_source();
-        let script_name = format!("00-{}-test.sh", ctx.id());
-        let script_path = format!("/mnt/etc/profile.d/{}", script_name);
+        let installed_path = util::instrumentation_script_path(ctx);
+        let script_path = format!("/mnt{}", installed_path);
         executor.write_file(&script_path, test_script)?;
         executor.exec_ok(&format!("chmod +x {}", script_path), Duration::from_secs(5))?;
         result.add_check(
             "Test instrumentation installed",
-            CheckResult::pass(format!("/etc/profile.d/{}", script_name)),
+            CheckResult::pass(installed_path),
         );
 
         // Unmount partitions (EFI first, then root)
@@ -556,3 +576,79 @@ impl Step for EnableServices {
         Ok(result)
     }
 }
+
+/// Step 30: Check installed filesystems before the first boot
+///
+/// Runs read-only `e2fsck -fn` on the root partition and `fsck.fat -n` on the
+/// ESP from the live environment, after Step 18 has unmounted them. A
+/// truncated write shows up here as an fsck error instead of as a confusing
+/// boot failure several minutes later.
+pub struct VerifyFilesystemIntegrity;
+
+impl Step for VerifyFilesystemIntegrity {
+    fn num(&self) -> usize {
+        30
+    }
+    fn name(&self) -> &str {
+        "Verify Filesystem Integrity"
+    }
+    fn ensures(&self) -> &str {
+        "Installed filesystems pass a consistency check before first boot"
+    }
+    fn phase(&self) -> usize {
+        5
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[18]
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
+        let start = Instant::now();
+        let mut result = StepResult::new(self.num(), self.name());
+
+        let disk = executor.detect_install_disk()?;
+        let layout = ctx.partition_layout();
+        let checks: Vec<(String, String, &str)> = partition_devices(&disk, &layout)
+            .into_iter()
+            .filter_map(|(device, spec)| {
+                let fsck = spec.filesystem.fsck_command()?;
+                let name = match spec.mountpoint.as_deref() {
+                    Some("/") => "Root filesystem clean".to_string(),
+                    Some("/boot") => "ESP filesystem clean".to_string(),
+                    Some(mountpoint) => format!("{} filesystem clean", mountpoint),
+                    None => format!("{} filesystem clean", device),
+                };
+                Some((name, device, fsck))
+            })
+            .collect();
+        for (name, device, fsck) in checks {
+            let name = name.as_str();
+            // fsck of a mounted filesystem reports spurious errors.
+            let mounted = executor.exec(
+                &format!("grep -q '^{} ' /proc/mounts", device),
+                Duration::from_secs(5),
+            )?;
+            if mounted.success() {
+                result.fail(name, format!("{} unmounted", device), "still mounted");
+                continue;
+            }
+
+            let cmd = format!("{} {}", fsck, device);
+            let check = executor.exec(&cmd, Duration::from_secs(120))?;
+            // Both tools exit 0 only when the filesystem is clean; with -n any
+            // inconsistency is left in place and reported as nonzero.
+            if check.success() {
+                result.add_check(name, CheckResult::pass(format!("{} exit 0", cmd)));
+            } else {
+                result.fail(
+                    name,
+                    format!("{} exit 0", cmd),
+                    format!("exit {}: {}", check.exit_code, check.output.trim()),
+                );
+            }
+        }
+
+        result.duration = start.elapsed();
+        Ok(result)
+    }
+}
@@ -1,6 +1,6 @@
 //! Phase 6: Post-reboot verification steps.
 //!
-//! Steps 19-24: Verify the installed system actually works.
+//! Steps 19-25 and 29: Verify the installed system actually works.
 //!
 //! # CRITICAL
 //!
@@ -16,9 +16,11 @@
 //! - User login proves authentication works
 //! - Essential commands prove base system is complete
 
-use super::{CheckResult, Step, StepResult};
-use crate::distro::{load_installed_scenario_facts, DistroContext};
+use super::{util, CheckResult, Step, StepResult};
+use crate::diagnostics::parse_failed_units;
+use crate::distro::{DistroContext, InitSystem};
 use crate::executor::Executor;
+use crate::qemu::session;
 use anyhow::Result;
 use leviso_cheat_guard::cheat_ensure;
 use std::time::{Duration, Instant};
@@ -37,6 +39,10 @@ impl Step for VerifySystemdBoot {
         "Installed system boots to multi-user target with systemd running"
     }
 
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
@@ -99,6 +105,26 @@ impl Step for VerifySystemdBoot {
             CheckResult::pass(format!("/proc/1/comm = {}", expected_pid1)),
         );
 
+        // Check the installed kernel is the one the ISO shipped, not a stale copy
+        let kernel = match crate::preflight::expected_kernel_release(ctx.id()) {
+            Ok(expected) => {
+                let running = executor.exec("uname -r", Duration::from_secs(5))?;
+                kernel_release_check(&running.output, &expected)
+            }
+            Err(err) => {
+                CheckResult::Skip(format!("expected kernel release unavailable: {:#}", err))
+            }
+        };
+        result.add_check("Kernel matches ISO", kernel);
+
+        let tainted = executor.exec("cat /proc/sys/kernel/tainted", Duration::from_secs(5))?;
+        result.add_check("Kernel not tainted", taint_check(&tainted.output));
+
+        let entry = boot_entry_check(executor)?;
+        result.add_check("Single boot entry for installed kernel", entry);
+        let bootctl = bootctl_check(executor)?;
+        result.add_check("bootctl default entry", bootctl);
+
         // Check we reached boot target using distro-specific command
         let target_cmd = ctx.check_target_reached_cmd();
         let target_expected = ctx.target_reached_expected();
@@ -150,6 +176,228 @@ impl Step for VerifySystemdBoot {
     }
 }
 
+/// Meaning of each bit of `/proc/sys/kernel/tainted`, by bit number.
+const TAINT_FLAGS: &[&str] = &[
+    "proprietary module loaded",
+    "module force-loaded",
+    "out-of-spec system (SMP on unsupported CPU)",
+    "module force-unloaded",
+    "machine check exception",
+    "bad page referenced",
+    "taint requested by userspace",
+    "kernel died recently (OOPS or BUG)",
+    "ACPI table overridden",
+    "kernel warning issued",
+    "staging driver loaded",
+    "platform firmware bug workaround",
+    "out-of-tree module loaded",
+    "unsigned module loaded",
+    "soft lockup",
+    "kernel live-patched",
+    "auxiliary taint",
+    "struct randomization plugin",
+    "in-kernel test run",
+];
+
+/// Names of the taint flags set in `value`; unknown bits are listed by number.
+fn decode_taint(value: u64) -> Vec<String> {
+    (0..64)
+        .filter(|bit| value & (1u64 << bit) != 0)
+        .map(|bit| match TAINT_FLAGS.get(bit as usize) {
+            Some(name) => format!("{} (bit {})", name, bit),
+            None => format!("unknown (bit {})", bit),
+        })
+        .collect()
+}
+
+/// Warn with the decoded flags when the kernel is tainted; taint is worth
+/// knowing about but does not by itself break the installed system.
+fn taint_check(output: &str) -> CheckResult {
+    match output.trim().parse::<u64>() {
+        Ok(0) => CheckResult::pass("kernel not tainted"),
+        Ok(value) => CheckResult::Warning(format!(
+            "kernel tainted ({}): {}",
+            value,
+            decode_taint(value).join(", ")
+        )),
+        Err(_) => CheckResult::Skip(format!(
+            "could not read /proc/sys/kernel/tainted: {}",
+            output.trim()
+        )),
+    }
+}
+
+/// The `linux`, `initrd` and `root=` values of a systemd-boot entry file.
+#[derive(Debug, Default, PartialEq)]
+struct LoaderEntry {
+    linux: Option<String>,
+    initrd: Vec<String>,
+    root: Option<String>,
+}
+
+fn parse_loader_entry(text: &str) -> LoaderEntry {
+    let mut entry = LoaderEntry::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "linux" => entry.linux = Some(value.to_string()),
+            "initrd" => entry.initrd.push(value.to_string()),
+            "options" => {
+                entry.root = value
+                    .split_whitespace()
+                    .find_map(|opt| opt.strip_prefix("root="))
+                    .map(str::to_string)
+            }
+            _ => {}
+        }
+    }
+    entry
+}
+
+/// Everything wrong with `entry` for an install whose root is `root_uuid`.
+fn loader_entry_problems(entry: &LoaderEntry, root_uuid: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if entry.linux.as_deref() != Some("/vmlinuz") {
+        problems.push(format!("linux is {:?}, expected /vmlinuz", entry.linux));
+    }
+    if entry.initrd != ["/initramfs.img"] {
+        problems.push(format!(
+            "initrd is {:?}, expected [/initramfs.img]",
+            entry.initrd
+        ));
+    }
+    let expected_root = format!("UUID={}", root_uuid);
+    if entry.root.as_deref() != Some(expected_root.as_str()) {
+        problems.push(format!(
+            "root is {:?}, expected {}",
+            entry.root, expected_root
+        ));
+    }
+    problems
+}
+
+/// Is `uname_output`'s release the `expected` one from the contract?
+fn kernel_release_check(uname_output: &str, expected: &str) -> CheckResult {
+    let running = uname_output.lines().map(str::trim).find(|l| !l.is_empty());
+    match running {
+        Some(release) if release == expected => {
+            CheckResult::pass(format!("uname -r = {}", release))
+        }
+        _ => CheckResult::Fail {
+            expected: format!("uname -r = {}", expected),
+            actual: format!("uname -r = {}", running.unwrap_or("<none>")),
+        },
+    }
+}
+
+/// Phase 5 writes exactly one entry; a second one (or none) means the
+/// bootloader may pick a config nobody tested.
+fn boot_entry_check(executor: &mut dyn Executor) -> Result<CheckResult> {
+    let listing = executor.exec(
+        "ls -1 /boot/loader/entries/*.conf 2>/dev/null",
+        Duration::from_secs(5),
+    )?;
+    let entries: Vec<&str> = listing
+        .output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("/boot/loader/entries/") && line.ends_with(".conf"))
+        .collect();
+    let path = match entries.as_slice() {
+        [path] => *path,
+        [] => {
+            return Ok(CheckResult::Fail {
+                expected: "one entry in /boot/loader/entries".to_string(),
+                actual: "no entries".to_string(),
+            })
+        }
+        _ => {
+            return Ok(CheckResult::Fail {
+                expected: "one entry in /boot/loader/entries".to_string(),
+                actual: format!("stale or duplicate entries: {}", entries.join(", ")),
+            })
+        }
+    };
+
+    // Re-derive the UUID from the device actually mounted at /, not from
+    // anything Phase 5 recorded.
+    let source = executor.exec("findmnt -no SOURCE /", Duration::from_secs(5))?;
+    let root_uuid = util::root_uuid(executor, source.output.trim())?;
+
+    let entry = parse_loader_entry(&executor.read_file(path)?);
+    let mut problems = loader_entry_problems(&entry, &root_uuid);
+    for file in entry.linux.iter().chain(&entry.initrd) {
+        let exists = executor.exec(&format!("test -f /boot{}", file), Duration::from_secs(5))?;
+        if !exists.success() {
+            problems.push(format!("/boot{} does not exist", file));
+        }
+    }
+
+    Ok(if problems.is_empty() {
+        CheckResult::pass(format!("{} -> /vmlinuz, root=UUID={}", path, root_uuid))
+    } else {
+        CheckResult::Fail {
+            expected: format!("{} boots /vmlinuz with root=UUID={}", path, root_uuid),
+            actual: problems.join("; "),
+        }
+    })
+}
+
+/// `(id, is_default)` for each `.conf` entry in `bootctl list` output.
+///
+/// Auto-detected entries (`auto-reboot-to-firmware-setup`, ...) are ignored;
+/// only installer-written files can be stale.
+fn bootctl_entries(output: &str) -> Vec<(String, bool)> {
+    let mut entries = Vec::new();
+    let mut default = false;
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "title" => default = value.ends_with("(default)"),
+            "id" if value.ends_with(".conf") => entries.push((value.to_string(), default)),
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// Cross-check what systemd-boot itself will offer, where `bootctl` exists.
+fn bootctl_check(executor: &mut dyn Executor) -> Result<CheckResult> {
+    let listed = executor.exec(
+        "command -v bootctl >/dev/null && bootctl list --no-pager 2>&1",
+        Duration::from_secs(10),
+    )?;
+    if !listed.success() {
+        return Ok(CheckResult::Skip(format!(
+            "bootctl list unavailable: {}",
+            listed.output.trim()
+        )));
+    }
+    let entries = bootctl_entries(&listed.output);
+    let defaults: Vec<&str> = entries
+        .iter()
+        .filter(|(_, default)| *default)
+        .map(|(id, _)| id.as_str())
+        .collect();
+    Ok(match (entries.len(), defaults.as_slice()) {
+        (1, [id]) => CheckResult::pass(format!("bootctl default entry is {}", id)),
+        _ => CheckResult::Fail {
+            expected: "exactly one entry, marked (default)".to_string(),
+            actual: format!(
+                "entries {:?}, default {:?}",
+                entries.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+                defaults
+            ),
+        },
+    })
+}
+
 /// Step 20: Verify hostname persisted
 pub struct VerifyHostname;
 
@@ -164,6 +412,10 @@ impl Step for VerifyHostname {
         "Configured hostname persisted across reboot"
     }
 
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
@@ -203,7 +455,10 @@ impl Step for VerifyUserLogin {
         "Verify User Login"
     }
     fn ensures(&self) -> &str {
-        "Created user account can authenticate and access home directory"
+        "Created user account can authenticate and access home directory; login shells emit the test instrumentation markers"
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[15]
     }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
@@ -211,17 +466,7 @@ impl Step for VerifyUserLogin {
         let mut result = StepResult::new(self.num(), self.name());
 
         // Check user exists
-        let facts = load_installed_scenario_facts(ctx.id())?;
-        let username = facts
-            .automated_login
-            .default_username
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_username for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let username = ctx.default_username()?;
         let user_check = executor.exec(&format!("id {}", username), Duration::from_secs(5))?;
 
         // CHEAT GUARD: User account MUST exist after reboot
@@ -286,11 +531,94 @@ impl Step for VerifyUserLogin {
             );
         }
 
+        check_test_instrumentation(executor, ctx, &mut result)?;
+
         result.duration = start.elapsed();
         Ok(result)
     }
 }
 
+/// Confirm the instrumentation script from Phase 5 survived the reboot,
+/// parses, and makes the distro's login shell emit its markers.
+///
+/// Every post-reboot serial login waits on those markers, so a missing or
+/// broken script fails here instead of as a mysterious login timeout.
+fn check_test_instrumentation(
+    executor: &mut dyn Executor,
+    ctx: &dyn DistroContext,
+    result: &mut StepResult,
+) -> Result<()> {
+    let path = util::instrumentation_script_path(ctx);
+    let present = executor.exec(
+        &format!("test -f {0} && test -x {0}", path),
+        Duration::from_secs(5),
+    )?;
+    cheat_ensure!(
+        present.success(),
+        protects = "Test instrumentation installed in Phase 5 survived reboot",
+        severity = "CRITICAL",
+        cheats = [
+            "Trust the Phase 5 copy",
+            "Match any shell prompt instead of the markers"
+        ],
+        consequence = "Post-reboot login detection has no markers to wait for",
+        "{} is missing or not executable after reboot",
+        path
+    );
+    result.add_check(
+        "Test instrumentation present",
+        CheckResult::pass(format!("{} (executable)", path)),
+    );
+
+    let shell = ctx.chroot_shell();
+    let syntax = executor.exec(
+        &format!("{} -n {} 2>&1", shell, path),
+        Duration::from_secs(5),
+    )?;
+    if syntax.success() {
+        result.add_check(
+            "Test instrumentation parses",
+            CheckResult::pass(format!("{} -n {}: no syntax errors", shell, path)),
+        );
+    } else {
+        result.add_check(
+            "Test instrumentation parses",
+            CheckResult::Fail {
+                expected: "script parses as shell".to_string(),
+                actual: syntax.output.trim().to_string(),
+            },
+        );
+    }
+
+    // An interactive login shell on an empty stdin sources profile.d, prints
+    // its prompt once, and exits.
+    let markers = util::instrumentation_markers(ctx);
+    let login = executor.exec(
+        &format!("timeout 10 {} -li </dev/null 2>&1", shell),
+        Duration::from_secs(15),
+    )?;
+    let missing: Vec<&str> = markers
+        .iter()
+        .copied()
+        .filter(|marker| !login.output.contains(marker))
+        .collect();
+    if missing.is_empty() {
+        result.add_check(
+            "Login shell emits instrumentation markers",
+            CheckResult::pass(format!("{} -li printed {}", shell, markers.join(", "))),
+        );
+    } else {
+        result.add_check(
+            "Login shell emits instrumentation markers",
+            CheckResult::Fail {
+                expected: format!("{} -li to print {}", shell, markers.join(", ")),
+                actual: format!("missing {}: {}", missing.join(", "), login.output.trim()),
+            },
+        );
+    }
+    Ok(())
+}
+
 /// Step 22: Verify networking works
 pub struct VerifyNetworking;
 
@@ -305,10 +633,26 @@ impl Step for VerifyNetworking {
         "Network interface is up and has IP address (DHCP or static)"
     }
 
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+
+    /// DHCP may still be completing right after login.
+    fn max_retries(&self) -> u32 {
+        2
+    }
+
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
+        // Offline runs boot with `-nic none`; there is nothing to verify.
+        if session::offline_requested() {
+            result.add_check("Networking", CheckResult::Skip("offline mode".to_string()));
+            result.duration = start.elapsed();
+            return Ok(result);
+        }
+
         // Check network service is running using distro-specific command
         let network_cmd = ctx.check_network_service_cmd();
         let networkd = executor.exec(network_cmd, Duration::from_secs(10))?;
@@ -392,6 +736,13 @@ impl Step for VerifySudo {
     fn ensures(&self) -> &str {
         "User can elevate privileges with sudo for system administration"
     }
+    fn depends_on(&self) -> &[usize] {
+        &[21]
+    }
+
+    fn parallel_safe(&self) -> bool {
+        true
+    }
 
     fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
@@ -422,17 +773,7 @@ impl Step for VerifySudo {
 
         // Check if wheel group exists and user is in it
         // This is the standard sudo configuration on most Linux systems
-        let facts = load_installed_scenario_facts(ctx.id())?;
-        let username = facts
-            .automated_login
-            .default_username
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_username for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let username = ctx.default_username()?;
         let wheel_check = executor.exec(
             &format!(
                 "getent group wheel && id {} | grep -q wheel && echo WHEEL_OK",
@@ -463,16 +804,7 @@ impl Step for VerifySudo {
         );
 
         // Test sudo actually works (with password from stdin)
-        let password = facts
-            .automated_login
-            .default_password
-            .as_deref()
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "missing canonical automated-login default_password for '{}'",
-                    ctx.id()
-                )
-            })?;
+        let password = ctx.default_password()?;
         let sudo_test = executor.exec(
             &format!(
                 "echo '{}' | su - {} -c 'sudo -S whoami'",
@@ -517,29 +849,30 @@ impl Step for VerifyEssentialCommands {
         "Verify Essential Commands"
     }
     fn ensures(&self) -> &str {
-        "Core system utilities (coreutils, systemd tools) are functional"
+        "The distro's essential commands are functional"
     }
 
-    fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
         let start = Instant::now();
         let mut result = StepResult::new(self.num(), self.name());
 
         // Essential commands that MUST work on a daily driver OS
-        let essential_commands = [
-            ("ls --version", "coreutils"),
-            ("cat --version", "coreutils"),
-            ("grep --version", "grep"),
-            ("find --version", "findutils"),
-            ("tar --version", "tar"),
-            ("systemctl --version", "systemd"),
-            ("journalctl --version", "systemd"),
-            ("ip --version", "iproute2"),
-            ("bash --version", "bash"),
-        ];
+        let essential_commands = ctx.essential_commands();
+
+        // CHEAT GUARD: an empty list would make the check below vacuous
+        cheat_ensure!(
+            !essential_commands.is_empty(),
+            protects = "Core system utilities are functional",
+            severity = "CRITICAL",
+            cheats = ["Declare no essential commands"],
+            consequence = "Core utilities never verified on the installed system",
+            "{} declares no essential commands",
+            ctx.name()
+        );
 
         let mut failed = 0;
 
-        for (cmd, package) in essential_commands {
+        for &(cmd, package) in essential_commands {
             let check =
                 executor.exec(&format!("{} 2>&1 | head -1", cmd), Duration::from_secs(5))?;
 
@@ -572,7 +905,11 @@ impl Step for VerifyEssentialCommands {
 
         result.add_check(
             "All essential commands",
-            CheckResult::pass("9/9 commands working"),
+            CheckResult::pass(format!(
+                "{}/{} commands working",
+                essential_commands.len(),
+                essential_commands.len()
+            )),
         );
 
         // Test file operations work
@@ -621,3 +958,452 @@ impl Step for VerifyEssentialCommands {
         Ok(result)
     }
 }
+
+/// Upper bound for the memory-pressure allocation, whatever the guest has.
+const MEMORY_PRESSURE_CAP_MIB: u64 = 1024;
+
+/// Step 25: Memory-pressure smoke test
+pub struct VerifyMemoryPressure;
+
+impl Step for VerifyMemoryPressure {
+    fn num(&self) -> usize {
+        25
+    }
+    fn name(&self) -> &str {
+        "Verify Memory Pressure"
+    }
+    fn ensures(&self) -> &str {
+        "System has working swap/zram and survives light memory pressure"
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, _ctx: &dyn DistroContext) -> Result<StepResult> {
+        let start = Instant::now();
+        let mut result = StepResult::new(self.num(), self.name());
+
+        let swaps = executor.exec("cat /proc/swaps", Duration::from_secs(5))?;
+        let devices = active_swap_devices(&swaps.output);
+        if devices.is_empty() {
+            result.add_check(
+                "Swap active",
+                CheckResult::Fail {
+                    expected: "a swap device or zram in /proc/swaps".to_string(),
+                    actual: "no active swap".to_string(),
+                },
+            );
+        } else {
+            result.add_check("Swap active", CheckResult::pass(devices.join(", ")));
+        }
+
+        let meminfo = executor.exec("cat /proc/meminfo", Duration::from_secs(5))?;
+        let Some(available_kib) = meminfo_kib(&meminfo.output, "MemAvailable") else {
+            result.add_check(
+                "Survives memory pressure",
+                CheckResult::Skip("MemAvailable missing from /proc/meminfo".to_string()),
+            );
+            result.duration = start.elapsed();
+            return Ok(result);
+        };
+        // Most of what is free, so the kernel has to reclaim, but bounded.
+        let mib = (available_kib / 1024 * 4 / 5).clamp(1, MEMORY_PRESSURE_CAP_MIB);
+
+        let Some(load) = memory_stress_command(executor, mib)? else {
+            result.add_check(
+                "Survives memory pressure",
+                CheckResult::Skip("no memory-stress tool (stress-ng or python3)".to_string()),
+            );
+            result.duration = start.elapsed();
+            return Ok(result);
+        };
+        let cmd_start = Instant::now();
+        let stress = executor.exec(&load, Duration::from_secs(90))?;
+        result.log_command(&load, stress.exit_code, &stress.output, cmd_start.elapsed());
+
+        let alive = executor.exec("echo STILL_RESPONSIVE", Duration::from_secs(15))?;
+        cheat_ensure!(
+            alive.output.contains("STILL_RESPONSIVE"),
+            protects = "System stays usable under memory pressure",
+            severity = "HIGH",
+            cheats = [
+                "Skip the responsiveness probe",
+                "Shrink the allocation to nothing"
+            ],
+            consequence = "Installed system locks up as soon as memory runs low",
+            "Shell unresponsive after allocating {} MiB",
+            mib
+        );
+
+        let oom = executor.exec(
+            "dmesg 2>/dev/null | grep -c 'Out of memory' || true",
+            Duration::from_secs(5),
+        )?;
+        let oom_kills: u32 = oom.output.trim().parse().unwrap_or(0);
+        if !stress.success() {
+            result.add_check(
+                "Survives memory pressure",
+                CheckResult::Fail {
+                    expected: format!("{} MiB allocation completes", mib),
+                    actual: format!("exit {}: {}", stress.exit_code, stress.output.trim()),
+                },
+            );
+        } else if oom_kills > 0 {
+            result.add_check(
+                "Survives memory pressure",
+                CheckResult::Warning(format!(
+                    "{} MiB allocated, but the OOM killer fired {} time(s)",
+                    mib, oom_kills
+                )),
+            );
+        } else {
+            result.add_check(
+                "Survives memory pressure",
+                CheckResult::pass(format!(
+                    "{} MiB allocated, shell responsive, no OOM kills",
+                    mib
+                )),
+            );
+        }
+
+        result.duration = start.elapsed();
+        Ok(result)
+    }
+}
+
+/// Device names from `/proc/swaps` (header line skipped).
+fn active_swap_devices(proc_swaps: &str) -> Vec<&str> {
+    proc_swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .collect()
+}
+
+/// Value in KiB of `key` from `/proc/meminfo` output.
+fn meminfo_kib(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let rest = line.strip_prefix(key)?.strip_prefix(':')?;
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
+/// Command holding `mib` MiB for a few seconds, with whichever tool the
+/// guest has; `None` when neither is installed.
+fn memory_stress_command(executor: &mut dyn Executor, mib: u64) -> Result<Option<String>> {
+    let tools = executor.exec(
+        "for t in stress-ng python3; do command -v $t >/dev/null 2>&1 && echo $t; done",
+        Duration::from_secs(5),
+    )?;
+    let available: Vec<&str> = tools.output.lines().map(str::trim).collect();
+    Ok(if available.contains(&"stress-ng") {
+        Some(format!(
+            "stress-ng --vm 1 --vm-bytes {}M --vm-keep --timeout 20s --quiet",
+            mib
+        ))
+    } else if available.contains(&"python3") {
+        Some(format!(
+            "python3 -c 'import time; b = bytearray({} << 20); b[::4096] = b\"x\" * len(b[::4096]); time.sleep(5)'",
+            mib
+        ))
+    } else {
+        None
+    })
+}
+
+/// Step 29: Verify every fstab entry is mounted after reboot
+///
+/// Step 9 only proves `/etc/fstab` was written. A wrong UUID or option can
+/// still boot, with the filesystem silently left unmounted; this compares
+/// each persistent entry against the live mount table.
+///
+/// Numbered after the existing steps so numbers in saved run reports keep
+/// their meaning; [`Step::phase`] is overridden to place it in Phase 6.
+pub struct VerifyFstabMounts;
+
+impl Step for VerifyFstabMounts {
+    fn num(&self) -> usize {
+        29
+    }
+    fn name(&self) -> &str {
+        "Verify fstab Mounts"
+    }
+    fn ensures(&self) -> &str {
+        "Every persistent /etc/fstab entry is mounted with its filesystem after reboot"
+    }
+    fn phase(&self) -> usize {
+        6
+    }
+    fn depends_on(&self) -> &[usize] {
+        &[19]
+    }
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, executor: &mut dyn Executor, ctx: &dyn DistroContext) -> Result<StepResult> {
+        let start = Instant::now();
+        let mut result = StepResult::new(self.num(), self.name());
+
+        let verify = executor.exec("findmnt --fstab --verify 2>&1", Duration::from_secs(10))?;
+        if verify.success() {
+            result.add_check("findmnt --fstab --verify", CheckResult::pass("no errors"));
+        } else {
+            result.fail(
+                "findmnt --fstab --verify",
+                "exit 0",
+                format!("exit {}: {}", verify.exit_code, verify.output.trim()),
+            );
+        }
+
+        let fstab = executor.exec("cat /etc/fstab", Duration::from_secs(5))?;
+        // Sources as the kernel sees them: UUID=... resolved to /dev/...
+        let evaluated = executor.exec(
+            "findmnt --fstab --evaluate -rn -o TARGET,SOURCE",
+            Duration::from_secs(10),
+        )?;
+        let live = executor.exec(
+            "findmnt -rn -o TARGET,SOURCE,FSTYPE",
+            Duration::from_secs(5),
+        )?;
+        let entries = persistent_fstab_entries(&fstab.output);
+        cheat_ensure!(
+            fstab.success() && !entries.is_empty(),
+            protects = "Installed system has fstab entries to mount at boot",
+            severity = "HIGH",
+            cheats = [
+                "Treat an empty fstab as nothing to verify",
+                "Skip the check when fstab is unreadable"
+            ],
+            consequence = "Nothing beyond what the kernel mounts comes up; /boot is missing and kernel updates land on the root",
+            "No persistent entries in /etc/fstab: {}",
+            fstab.output.trim()
+        );
+        for entry in &entries {
+            let name = format!("fstab {}", entry.target);
+            match fstab_entry_problem(entry, &evaluated.output, &live.output) {
+                None => result.add_check(
+                    &name,
+                    CheckResult::pass(format!(
+                        "{} mounted at {} ({})",
+                        entry.source, entry.target, entry.fstype
+                    )),
+                ),
+                Some(problem) => result.fail(
+                    &name,
+                    format!(
+                        "{} mounted at {} as {}",
+                        entry.source, entry.target, entry.fstype
+                    ),
+                    problem,
+                ),
+            }
+        }
+
+        if ctx.init_system() == InitSystem::Systemd {
+            let failed = executor.exec(
+                "systemctl --failed --no-legend --plain --type=mount",
+                Duration::from_secs(10),
+            )?;
+            let units: Vec<String> = parse_failed_units(&failed.output)
+                .into_iter()
+                .filter(|unit| unit.ends_with(".mount"))
+                .collect();
+            if units.is_empty() {
+                result.add_check("No failed mount units", CheckResult::pass("none"));
+            } else {
+                result.fail("No failed mount units", "none", units.join(", "));
+            }
+        } else {
+            result.add_check(
+                "No failed mount units",
+                CheckResult::Skip(format!("{} has no mount units", ctx.init_system_name())),
+            );
+        }
+
+        result.duration = start.elapsed();
+        Ok(result)
+    }
+}
+
+/// One `/etc/fstab` line that should be mounted at boot.
+#[derive(Debug, PartialEq)]
+struct FstabEntry {
+    source: String,
+    target: String,
+    fstype: String,
+}
+
+/// Entries of `fstab` mounted at boot: swap, `noauto` and pseudo-filesystem
+/// placeholders (`none` targets) are left out.
+fn persistent_fstab_entries(fstab: &str) -> Vec<FstabEntry> {
+    fstab
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [source, target, fstype, rest @ ..] = fields.as_slice() else {
+                return None;
+            };
+            let noauto = rest
+                .first()
+                .is_some_and(|options| options.split(',').any(|option| option == "noauto"));
+            (*fstype != "swap" && target.starts_with('/') && !noauto).then(|| FstabEntry {
+                source: source.to_string(),
+                target: target.to_string(),
+                fstype: fstype.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Why `entry` is not mounted as declared, given `findmnt --fstab
+/// --evaluate -rn -o TARGET,SOURCE` and `findmnt -rn -o TARGET,SOURCE,FSTYPE`
+/// output; `None` when it is.
+fn fstab_entry_problem(entry: &FstabEntry, evaluated: &str, live: &str) -> Option<String> {
+    let row = |output: &str| -> Option<Vec<String>> {
+        output.lines().find_map(|line| {
+            let fields: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+            (fields.first()? == &entry.target).then_some(fields)
+        })
+    };
+    let Some(mounted) = row(live) else {
+        return Some(format!("nothing mounted at {}", entry.target));
+    };
+    let (source, fstype) = (&mounted[1], mounted.get(2).map_or("", String::as_str));
+    if entry.fstype != "auto" && fstype != entry.fstype {
+        return Some(format!("{} mounted as {}", source, fstype));
+    }
+    let expected = row(evaluated).and_then(|fields| fields.get(1).cloned());
+    match expected {
+        Some(device) if &device != source => Some(format!(
+            "{} resolves to {}, but {} is mounted",
+            entry.source, device, source
+        )),
+        None if entry.source.contains('=') => {
+            Some(format!("{} does not resolve to a device", entry.source))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taint_bits_decode_to_named_flags() {
+        assert!(matches!(taint_check("0\n"), CheckResult::Pass { .. }));
+        // Out-of-tree (O, bit 12) plus unsigned (E, bit 13) module.
+        match taint_check("12288\n") {
+            CheckResult::Warning(msg) => {
+                assert!(
+                    msg.contains("out-of-tree module loaded (bit 12)"),
+                    "{}",
+                    msg
+                );
+                assert!(msg.contains("unsigned module loaded (bit 13)"), "{}", msg);
+            }
+            other => panic!("expected warning, got {:?}", other),
+        }
+        assert_eq!(decode_taint(1 << 40), vec!["unknown (bit 40)"]);
+        assert!(taint_check("cat: no such file").skipped());
+    }
+
+    #[test]
+    fn running_kernel_must_be_the_contract_release() {
+        assert!(matches!(
+            kernel_release_check("6.12.9-levitate\n", "6.12.9-levitate"),
+            CheckResult::Pass { .. }
+        ));
+        match kernel_release_check("6.12.8-levitate\n", "6.12.9-levitate") {
+            CheckResult::Fail { expected, actual } => {
+                assert_eq!(expected, "uname -r = 6.12.9-levitate");
+                assert_eq!(actual, "uname -r = 6.12.8-levitate");
+            }
+            other => panic!("expected fail, got {:?}", other),
+        }
+        assert!(matches!(
+            kernel_release_check("", "6.12.9-levitate"),
+            CheckResult::Fail { .. }
+        ));
+    }
+
+    #[test]
+    fn loader_entry_must_boot_the_installed_root() {
+        let uuid = "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0";
+        let entry = parse_loader_entry(&format!(
+            "title   LevitateOS\nlinux   /vmlinuz\ninitrd  /initramfs.img\n\
+             options root=UUID={} rw console=ttyS0,115200n8\n",
+            uuid
+        ));
+        assert!(loader_entry_problems(&entry, uuid).is_empty());
+
+        let problems = loader_entry_problems(&entry, "11111111-2222-3333-4444-555555555555");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("root is"), "{:?}", problems);
+
+        let stale = parse_loader_entry("linux /vmlinuz-old\noptions rw\n");
+        assert_eq!(loader_entry_problems(&stale, uuid).len(), 3);
+    }
+
+    #[test]
+    fn bootctl_list_entries_and_default() {
+        let output = [
+            "Boot Loader Entries:",
+            "         type: Boot Loader Specification Type #1 (.conf)",
+            "        title: LevitateOS (default)",
+            "           id: levitate.conf",
+            "",
+            "         type: Automatic",
+            "        title: Reboot Into Firmware Interface",
+            "           id: auto-reboot-to-firmware-setup",
+        ]
+        .join("\n");
+        assert_eq!(
+            bootctl_entries(&output),
+            vec![("levitate.conf".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn swap_and_meminfo_parsing() {
+        let swaps = "Filename\tType\tSize\tUsed\tPriority\n/dev/zram0 partition 4046844 0 100\n";
+        assert_eq!(active_swap_devices(swaps), vec!["/dev/zram0"]);
+        assert!(active_swap_devices("Filename\tType\tSize\tUsed\tPriority\n").is_empty());
+
+        let meminfo = "MemTotal:        4015556 kB\nMemAvailable:    3520104 kB\n";
+        assert_eq!(meminfo_kib(meminfo, "MemAvailable"), Some(3520104));
+        assert_eq!(meminfo_kib(meminfo, "SwapTotal"), None);
+    }
+
+    #[test]
+    fn fstab_entries_must_match_the_live_mounts() {
+        let fstab = "# /etc/fstab\n\
+                     UUID=aaaa-root / ext4 rw,relatime 0 1\n\
+                     UUID=BBBB-EFI /boot vfat rw,umask=0077 0 2\n\
+                     UUID=cccc-home /home ext4 defaults 0 2\n\
+                     UUID=dddd-data /data ext4 noauto 0 0\n\
+                     /dev/vda3 none swap defaults 0 0\n";
+        let entries = persistent_fstab_entries(fstab);
+        let targets: Vec<&str> = entries.iter().map(|e| e.target.as_str()).collect();
+        assert_eq!(targets, ["/", "/boot", "/home"]);
+
+        let evaluated = "/ /dev/vda2\n/boot /dev/vda1\n/home /dev/vda4\n";
+        let live = "/ /dev/vda2 ext4\n/boot /dev/vda1 ext4\n/proc proc proc\n";
+        assert_eq!(fstab_entry_problem(&entries[0], evaluated, live), None);
+        assert_eq!(
+            fstab_entry_problem(&entries[1], evaluated, live).as_deref(),
+            Some("/dev/vda1 mounted as ext4")
+        );
+        assert_eq!(
+            fstab_entry_problem(&entries[2], evaluated, live).as_deref(),
+            Some("nothing mounted at /home")
+        );
+
+        let stale_uuid = "/ /dev/vda2\n";
+        let live = "/ /dev/vda2 ext4\n/boot /dev/vda1 vfat\n";
+        assert_eq!(
+            fstab_entry_problem(&entries[1], stale_uuid, live).as_deref(),
+            Some("UUID=BBBB-EFI does not resolve to a device")
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Aggregate reporting over step results.
+//!
+//! Pure aggregation over the `CommandLog`s each step already records, for
+//! spotting slow install commands (e.g. rootfs extraction regressing), plus a
+//! JSON export of every check with its structured evidence.
+//!
+//! [`RunReport`] is the saved form of a whole run; [`RunReport::compare`]
+//! diffs two of them for a "no regressions" gate.
+
+use super::{CheckResult, Step, StepResult};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// One command timing row in the `--timing-json` export.
+#[derive(Debug, Serialize)]
+struct CommandTiming<'a> {
+    step_num: usize,
+    step_name: &'a str,
+    command: &'a str,
+    exit_code: i32,
+    duration_ms: u128,
+}
+
+/// One check row in the `write_checks_json` export.
+#[derive(Debug, Serialize)]
+struct CheckRecord<'a> {
+    step_num: usize,
+    step_name: &'a str,
+    check: &'a str,
+    /// `"pass"`, `"fail"`, `"skip"` or `"warning"`.
+    status: &'static str,
+    /// Evidence for passes, `expected …, got …` for failures, the reason otherwise.
+    detail: String,
+    /// Structured evidence from [`CheckResult::pass_with_data`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<&'a serde_json::Value>,
+}
+
+/// Label of a check outcome as used in the JSON exports.
+fn status_label(outcome: &CheckResult) -> &'static str {
+    match outcome {
+        CheckResult::Pass { .. } => "pass",
+        CheckResult::Fail { .. } => "fail",
+        CheckResult::Skip(_) => "skip",
+        CheckResult::Warning(_) => "warning",
+    }
+}
+
+/// Saved outcome of a run, loaded again as a `--baseline`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub steps: Vec<StepReport>,
+}
+
+/// One step in a [`RunReport`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReport {
+    pub num: usize,
+    pub name: String,
+    pub passed: bool,
+    /// `(check name, "pass" | "fail" | "skip" | "warning")`, in check order.
+    pub checks: Vec<(String, String)>,
+    /// `(command, duration in ms)`, in execution order.
+    pub commands: Vec<(String, u64)>,
+}
+
+/// Differences of a run against its baseline; see [`RunReport::compare`].
+#[derive(Debug, Default)]
+pub struct RunDiff {
+    /// Steps that passed in the baseline and fail now.
+    pub newly_failed: Vec<String>,
+    /// Steps that failed in the baseline and pass now.
+    pub newly_passed: Vec<String>,
+    /// `step: check` labels that are warnings now but were not before.
+    pub new_warnings: Vec<String>,
+    /// `step: check` labels that are skipped now but were not before.
+    pub new_skips: Vec<String>,
+    /// `(step: command, baseline, current)` beyond the threshold, either way.
+    pub timing_changes: Vec<(String, Duration, Duration)>,
+}
+
+impl RunReport {
+    /// Summarize `results` for saving.
+    pub fn from_results(results: &[StepResult]) -> Self {
+        Self {
+            steps: results.iter().map(step_report).collect(),
+        }
+    }
+
+    /// Summarize the outcome of running `steps`, as returned by the runner.
+    ///
+    /// A step that errored is recorded as failed with an `error` check, so
+    /// it still regresses against a baseline where it passed. Steps the
+    /// runner never reached (fail-fast) are left out.
+    pub fn from_run(steps: &[Box<dyn Step>], results: &[Result<StepResult>]) -> Self {
+        let steps = steps
+            .iter()
+            .zip(results)
+            .map(|(step, result)| match result {
+                Ok(result) => step_report(result),
+                Err(err) => {
+                    let mut failed = StepResult::new(step.num(), step.name());
+                    failed.fail("error", "step runs", format!("{:#}", err));
+                    step_report(&failed)
+                }
+            })
+            .collect();
+        Self { steps }
+    }
+
+    /// Write the report to `path` as JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("serializing run report")?;
+        std::fs::write(path, json)
+            .with_context(|| format!("writing run report to '{}'", path.display()))
+    }
+
+    /// Load a report written by [`save`](Self::save).
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read(path)
+            .with_context(|| format!("reading run report '{}'", path.display()))?;
+        serde_json::from_slice(&raw)
+            .with_context(|| format!("parsing run report '{}'", path.display()))
+    }
+
+    /// Load the report at `baseline`, print this run's diff against it and
+    /// fail if anything regressed (see [`RunDiff::regressed`]).
+    pub fn check_against(&self, baseline: &Path, timing_threshold: Duration) -> Result<()> {
+        let diff = self.compare(&Self::load(baseline)?, timing_threshold);
+        diff.print();
+        if diff.regressed() {
+            anyhow::bail!("Run regressed against the baseline");
+        }
+        Ok(())
+    }
+
+    /// Diff this run against `baseline`, matching steps by number and
+    /// commands by text.
+    ///
+    /// Timing changes count only when a command's duration moved by more
+    /// than `timing_threshold`. Steps absent from the baseline are compared
+    /// as if they had passed cleanly there, so a new failing step regresses.
+    pub fn compare(&self, baseline: &RunReport, timing_threshold: Duration) -> RunDiff {
+        let baseline_steps: HashMap<usize, &StepReport> =
+            baseline.steps.iter().map(|step| (step.num, step)).collect();
+        let mut diff = RunDiff::default();
+
+        for step in &self.steps {
+            let label = format!("{}. {}", step.num, step.name);
+            let before = baseline_steps.get(&step.num);
+            match (before.is_none_or(|b| b.passed), step.passed) {
+                (true, false) => diff.newly_failed.push(label.clone()),
+                (false, true) => diff.newly_passed.push(label.clone()),
+                _ => {}
+            }
+
+            let status_before = |check: &str| {
+                before.and_then(|b| {
+                    b.checks
+                        .iter()
+                        .find(|(name, _)| name == check)
+                        .map(|(_, status)| status.as_str())
+                })
+            };
+            for (check, status) in &step.checks {
+                if status_before(check) == Some(status.as_str()) {
+                    continue;
+                }
+                match status.as_str() {
+                    "warning" => diff.new_warnings.push(format!("{}: {}", label, check)),
+                    "skip" => diff.new_skips.push(format!("{}: {}", label, check)),
+                    _ => {}
+                }
+            }
+
+            let Some(before) = before else { continue };
+            for (command, ms) in &step.commands {
+                let Some((_, before_ms)) = before.commands.iter().find(|(c, _)| c == command)
+                else {
+                    continue;
+                };
+                let (then, now) = (
+                    Duration::from_millis(*before_ms),
+                    Duration::from_millis(*ms),
+                );
+                if then.abs_diff(now) > timing_threshold {
+                    diff.timing_changes
+                        .push((format!("{}: {}", label, command), then, now));
+                }
+            }
+        }
+        diff
+    }
+}
+
+fn step_report(result: &StepResult) -> StepReport {
+    StepReport {
+        num: result.step_num,
+        name: result.name.clone(),
+        passed: result.passed,
+        checks: result
+            .checks
+            .iter()
+            .map(|(name, outcome)| (name.clone(), status_label(outcome).to_string()))
+            .collect(),
+        commands: result
+            .commands
+            .iter()
+            .map(|cmd| (cmd.command.clone(), cmd.duration.as_millis() as u64))
+            .collect(),
+    }
+}
+
+impl RunDiff {
+    /// Whether anything got worse: a new failure, warning or skip, or a
+    /// command slowing down past the threshold.
+    pub fn regressed(&self) -> bool {
+        !self.newly_failed.is_empty()
+            || !self.new_warnings.is_empty()
+            || !self.new_skips.is_empty()
+            || self.timing_changes.iter().any(|(_, then, now)| now > then)
+    }
+
+    /// Print the diff; prints a single line when nothing changed.
+    pub fn print(&self) {
+        let sections = [
+            ("Newly failed", &self.newly_failed),
+            ("Newly passed", &self.newly_passed),
+            ("New warnings", &self.new_warnings),
+            ("New skips", &self.new_skips),
+        ];
+        let mut changed = false;
+        for (title, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            changed = true;
+            println!("{}", format!("{}:", title).bold());
+            for entry in entries {
+                println!("  {}", entry);
+            }
+        }
+        if !self.timing_changes.is_empty() {
+            changed = true;
+            println!("{}", "Timing changes:".bold());
+            for (label, then, now) in &self.timing_changes {
+                println!(
+                    "  {:>8.1}s → {:>8.1}s  {}",
+                    then.as_secs_f64(),
+                    now.as_secs_f64(),
+                    label
+                );
+            }
+        }
+        if !changed {
+            println!("No changes against baseline.");
+        }
+    }
+}
+
+/// Every logged command as `("<step>. <name>: <command>", duration)`, slowest first.
+pub fn timing_summary(results: &[StepResult]) -> Vec<(String, Duration)> {
+    let mut timings: Vec<(String, Duration)> = results
+        .iter()
+        .flat_map(|result| {
+            result.commands.iter().map(move |cmd| {
+                (
+                    format!("{}. {}: {}", result.step_num, result.name, cmd.command),
+                    cmd.duration,
+                )
+            })
+        })
+        .collect();
+    timings.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+    timings
+}
+
+/// Print the `limit` slowest commands across `results`.
+pub fn print_slowest_commands(results: &[StepResult], limit: usize) {
+    let timings = timing_summary(results);
+    if timings.is_empty() {
+        return;
+    }
+    println!("{}", format!("Slowest {} commands:", limit).bold());
+    for (label, duration) in timings.iter().take(limit) {
+        println!("  {:>8.1}s  {}", duration.as_secs_f64(), label);
+    }
+}
+
+/// Write all command timings to `path` as a JSON array.
+pub fn write_timing_json(results: &[StepResult], path: &Path) -> Result<()> {
+    let rows: Vec<CommandTiming> = results
+        .iter()
+        .flat_map(|result| {
+            result.commands.iter().map(move |cmd| CommandTiming {
+                step_num: result.step_num,
+                step_name: &result.name,
+                command: &cmd.command,
+                exit_code: cmd.exit_code,
+                duration_ms: cmd.duration.as_millis(),
+            })
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&rows).context("serializing command timings")?;
+    std::fs::write(path, json)
+        .with_context(|| format!("writing command timings to '{}'", path.display()))
+}
+
+/// Write every check of `results` to `path` as a JSON array, including any
+/// structured evidence, for trend analysis without scraping evidence prose.
+pub fn write_checks_json(results: &[StepResult], path: &Path) -> Result<()> {
+    let rows: Vec<CheckRecord> = results
+        .iter()
+        .flat_map(|result| {
+            result.checks.iter().map(move |(check, outcome)| {
+                let (detail, data) = match outcome {
+                    CheckResult::Pass { evidence, data } => (evidence.clone(), data.as_ref()),
+                    CheckResult::Fail { expected, actual } => {
+                        (format!("expected {}, got {}", expected, actual), None)
+                    }
+                    CheckResult::Skip(reason) | CheckResult::Warning(reason) => {
+                        (reason.clone(), None)
+                    }
+                };
+                CheckRecord {
+                    step_num: result.step_num,
+                    step_name: &result.name,
+                    check,
+                    status: status_label(outcome),
+                    detail,
+                    data,
+                }
+            })
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&rows).context("serializing check results")?;
+    std::fs::write(path, json)
+        .with_context(|| format!("writing check results to '{}'", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(
+        num: usize,
+        passed: bool,
+        checks: &[(&str, &str)],
+        commands: &[(&str, u64)],
+    ) -> StepReport {
+        StepReport {
+            num,
+            name: format!("Step {}", num),
+            passed,
+            checks: checks
+                .iter()
+                .map(|(name, status)| (name.to_string(), status.to_string()))
+                .collect(),
+            commands: commands
+                .iter()
+                .map(|(command, ms)| (command.to_string(), *ms))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compare_flags_new_failures_warnings_and_slowdowns() {
+        let baseline = RunReport {
+            steps: vec![
+                step(
+                    1,
+                    true,
+                    &[("uefi", "pass")],
+                    &[("ls /sys/firmware/efi", 100)],
+                ),
+                step(2, false, &[("clock", "fail")], &[]),
+                step(8, true, &[("rootfs", "pass")], &[("unsquashfs", 40_000)]),
+            ],
+        };
+        let current = RunReport {
+            steps: vec![
+                step(
+                    1,
+                    false,
+                    &[("uefi", "fail")],
+                    &[("ls /sys/firmware/efi", 120)],
+                ),
+                step(2, true, &[("clock", "warning")], &[]),
+                step(8, true, &[("rootfs", "pass")], &[("unsquashfs", 95_000)]),
+            ],
+        };
+
+        let diff = current.compare(&baseline, Duration::from_secs(10));
+        assert_eq!(diff.newly_failed, vec!["1. Step 1"]);
+        assert_eq!(diff.newly_passed, vec!["2. Step 2"]);
+        assert_eq!(diff.new_warnings, vec!["2. Step 2: clock"]);
+        assert_eq!(diff.timing_changes.len(), 1);
+        assert!(diff.timing_changes[0].0.contains("unsquashfs"));
+        assert!(diff.regressed());
+    }
+
+    #[test]
+    fn from_run_records_errored_steps_as_failed() {
+        let steps = crate::steps::steps_in_range(1..=2).unwrap();
+        let mut passed = StepResult::new(1, steps[0].name());
+        passed.pass("uefi", "efivars present");
+        let results = vec![Ok(passed), Err(anyhow::anyhow!("console closed"))];
+
+        let report = RunReport::from_run(&steps, &results);
+        assert_eq!(report.steps.len(), 2);
+        assert!(report.steps[0].passed);
+        assert!(!report.steps[1].passed);
+        assert_eq!(report.steps[1].num, 2);
+        assert_eq!(
+            report.steps[1].checks,
+            vec![("error".to_string(), "fail".to_string())]
+        );
+    }
+
+    #[test]
+    fn unchanged_or_faster_run_does_not_regress() {
+        let baseline = RunReport {
+            steps: vec![step(
+                8,
+                true,
+                &[("rootfs", "skip")],
+                &[("unsquashfs", 95_000)],
+            )],
+        };
+        let current = RunReport {
+            steps: vec![step(
+                8,
+                true,
+                &[("rootfs", "skip")],
+                &[("unsquashfs", 40_000)],
+            )],
+        };
+        let diff = current.compare(&baseline, Duration::from_secs(10));
+        assert_eq!(diff.timing_changes.len(), 1);
+        assert!(diff.new_skips.is_empty());
+        assert!(!diff.regressed());
+    }
+}
@@ -0,0 +1,87 @@
+//! Helpers shared by install steps.
+
+use crate::distro::DistroContext;
+use crate::executor::Executor;
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+/// Filesystem UUID of `device`, validated before anyone builds on it.
+///
+/// An empty or mangled `blkid` result would otherwise end up in the boot
+/// entry as `root=UUID=` and only surface as a VFS panic on first boot.
+/// Fetch it once per step and reuse the value.
+pub fn root_uuid(executor: &mut dyn Executor, device: &str) -> Result<String> {
+    let result = executor.exec(
+        &format!("blkid -s UUID -o value {}", device),
+        Duration::from_secs(5),
+    )?;
+    let uuid = result.output.trim();
+    if !result.success() || !is_valid_uuid(uuid) {
+        bail!(
+            "blkid returned no valid UUID for {} (exit {}): '{}'",
+            device,
+            result.exit_code,
+            uuid
+        );
+    }
+    Ok(uuid.to_string())
+}
+
+/// True for the canonical 8-4-4-4-12 hex form, e.g. `0f1e2d3c-...`.
+pub fn is_valid_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Installed-system path of the test instrumentation script
+/// (`/etc/profile.d/00-<distro>-test.sh`), without the `/mnt` prefix.
+pub fn instrumentation_script_path(ctx: &dyn DistroContext) -> String {
+    format!("/etc/profile.d/00-{}-test.sh", ctx.id())
+}
+
+/// Markers a login shell must print once the instrumentation is sourced.
+///
+/// `___SHELL_READY___` plus any `___X___` markers among the distro's
+/// declared live boot success patterns. Markers the script only prints from
+/// `PROMPT_COMMAND` or similar hooks are not required, since a shell with
+/// no terminal may never run them.
+pub fn instrumentation_markers(ctx: &dyn DistroContext) -> Vec<&str> {
+    let mut markers = vec!["___SHELL_READY___"];
+    for pattern in ctx.live_boot_success_patterns() {
+        let inner = pattern.trim_matches('_');
+        if pattern.len() > 6
+            && pattern.starts_with("___")
+            && pattern.ends_with("___")
+            && !inner.is_empty()
+            && !markers.contains(pattern)
+        {
+            markers.push(pattern);
+        }
+    }
+    markers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_valid_uuid_accepts_only_canonical_form() {
+        assert!(is_valid_uuid("3f2a9c1e-7b4d-4e8a-9c0f-1a2b3c4d5e6f"));
+        assert!(is_valid_uuid("3F2A9C1E-7B4D-4E8A-9C0F-1A2B3C4D5E6F"));
+        assert!(!is_valid_uuid(""));
+        assert!(!is_valid_uuid("1234-ABCD"));
+        assert!(!is_valid_uuid("3f2a9c1e-7b4d-4e8a-9c0f-1a2b3c4d5e6"));
+        assert!(!is_valid_uuid("3f2a9c1e-7b4d-4e8a-9c0f-1a2b3c4d5e6g"));
+    }
+
+    #[test]
+    fn instrumentation_markers_are_shell_ready_plus_declared_markers() {
+        let ctx = crate::distro::context_for_distro("levitate").expect("levitate context");
+        assert_eq!(instrumentation_markers(ctx.as_ref()), ["___SHELL_READY___"]);
+    }
+}
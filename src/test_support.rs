@@ -0,0 +1,181 @@
+//! Helpers shared by the unit tests.
+
+use crate::executor::{ExecResult, Executor};
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Create a fresh, empty directory under the system temp dir.
+///
+/// The name includes the pid and a timestamp, so parallel test threads and
+/// reruns never share a directory. Callers remove it when done.
+pub(crate) fn temp_dir(test_name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock before epoch")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!(
+        "install-tests-{test_name}-{}-{nanos}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&path).expect("create temp dir");
+    path
+}
+
+/// How [`FakeExecutor`] answers a command.
+#[derive(Debug, Clone)]
+pub(crate) enum Reply {
+    /// Completed with this exit code and output.
+    Exit(i32, String),
+    /// Ran past its timeout without output.
+    Timeout,
+    /// `exec` itself failed, as when the console is gone.
+    Error(&'static str),
+    /// Run by the host `sh -c`, for tests of generated shell scripts.
+    HostShell,
+}
+
+impl Reply {
+    /// Successful completion printing `output`.
+    pub(crate) fn ok(output: &str) -> Self {
+        Reply::Exit(0, output.to_string())
+    }
+}
+
+/// Scripted [`Executor`] standing in for a guest shell.
+///
+/// Every command is recorded in `commands`, then answered by the first rule
+/// whose prefix it starts with, or by the fallback (an empty success unless
+/// set with [`otherwise`](Self::otherwise)). Rules added with
+/// [`once`](Self::once) are used up by their first match.
+pub(crate) struct FakeExecutor {
+    rules: Vec<(String, Reply, bool)>,
+    fallback: Reply,
+    boot_error: Option<&'static str>,
+    latency: Duration,
+    pub(crate) commands: Vec<String>,
+}
+
+impl FakeExecutor {
+    pub(crate) fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            fallback: Reply::ok(""),
+            boot_error: None,
+            latency: Duration::ZERO,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Run every command with the host `sh -c`.
+    pub(crate) fn host_shell() -> Self {
+        Self::new().otherwise(Reply::HostShell)
+    }
+
+    /// Answer commands starting with `prefix` with a success printing `output`.
+    pub(crate) fn on(self, prefix: &str, output: &str) -> Self {
+        self.reply(prefix, Reply::ok(output))
+    }
+
+    /// Answer commands starting with `prefix` with `reply`.
+    pub(crate) fn reply(mut self, prefix: &str, reply: Reply) -> Self {
+        self.rules.push((prefix.to_string(), reply, false));
+        self
+    }
+
+    /// Answer the next command starting with `prefix` with `reply`.
+    pub(crate) fn once(mut self, prefix: &str, reply: Reply) -> Self {
+        self.rules.push((prefix.to_string(), reply, true));
+        self
+    }
+
+    /// Answer commands no rule matches with `reply`.
+    pub(crate) fn otherwise(mut self, reply: Reply) -> Self {
+        self.fallback = reply;
+        self
+    }
+
+    /// Fail every boot wait with `message`.
+    pub(crate) fn boot_fails(mut self, message: &'static str) -> Self {
+        self.boot_error = Some(message);
+        self
+    }
+
+    /// Sleep this long per command, like a serial round trip.
+    pub(crate) fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    fn next_reply(&mut self, cmd: &str) -> Reply {
+        let Some(index) = self
+            .rules
+            .iter()
+            .position(|(prefix, _, _)| cmd.starts_with(prefix.as_str()))
+        else {
+            return self.fallback.clone();
+        };
+        if self.rules[index].2 {
+            self.rules.remove(index).1
+        } else {
+            self.rules[index].1.clone()
+        }
+    }
+}
+
+impl Executor for FakeExecutor {
+    fn exec(&mut self, cmd: &str, _timeout: Duration) -> Result<ExecResult> {
+        self.commands.push(cmd.to_string());
+        std::thread::sleep(self.latency);
+        let (completed, exit_code, output) = match self.next_reply(cmd) {
+            Reply::Exit(code, output) => (true, code, output),
+            Reply::Timeout => (false, -1, String::new()),
+            Reply::Error(message) => bail!("{}", message),
+            Reply::HostShell => {
+                let out = Command::new("sh").arg("-c").arg(cmd).output()?;
+                (
+                    true,
+                    out.status.code().unwrap_or(-1),
+                    String::from_utf8_lossy(&out.stdout).into_owned(),
+                )
+            }
+        };
+        Ok(ExecResult {
+            completed,
+            exit_code,
+            output,
+            aborted_on_error: false,
+            stalled: false,
+            disconnected: false,
+            matched_pattern: None,
+        })
+    }
+
+    fn exec_chroot(&mut self, _path: &str, cmd: &str, timeout: Duration) -> Result<ExecResult> {
+        self.exec(cmd, timeout)
+    }
+
+    fn write_file(&mut self, _path: &str, _content: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn login(&mut self, _username: &str, _password: &str, _timeout: Duration) -> Result<()> {
+        Ok(())
+    }
+
+    fn wait_for_live_boot(&mut self, _stall_timeout: Duration) -> Result<()> {
+        match self.boot_error {
+            Some(message) => bail!("{}", message),
+            None => Ok(()),
+        }
+    }
+
+    fn wait_for_installed_boot(&mut self, stall_timeout: Duration) -> Result<()> {
+        self.wait_for_live_boot(stall_timeout)
+    }
+
+    fn failed_services(&self) -> &[String] {
+        &[]
+    }
+}